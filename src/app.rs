@@ -2,22 +2,211 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use crate::backup::{self, Backup};
+use crate::bitcoin_conf::discover::Discovery;
+use crate::bitcoin_conf::include::{self, Includes};
+use crate::bitcoin_conf::validate;
+use crate::bitcoin_conf::{BitcoinConf, Network};
+use crate::components::conf_picker::ConfPicker;
+use crate::components::config_editor::ConfigEditor;
+use crate::components::effective_view::EffectiveView;
+use crate::components::error_dialog::ErrorDialog;
 use crate::components::file_explorer::FileExplorer;
-use std::path::PathBuf;
+use crate::components::history_view::HistoryView;
+use crate::components::log_view::{self, LogView};
+use crate::components::mempool_view::{self, MempoolView, TxDetail};
+use crate::components::miner_list::MinerList;
+use crate::components::p2pool_editor::P2poolEditor;
+use crate::components::peer_list::{self, PeerAction, PeerList};
+use crate::components::readiness_report::ReadinessReport;
+use crate::components::rpcauth_dialog::RpcAuthDialog;
+use crate::components::save_preview::SavePreview;
+use crate::components::setup_wizard::SetupWizard;
+use crate::components::template_view::TemplateView;
+use crate::event::{self, AppEvent};
+use crate::node_status::{self, NodeStatus, StatusPoller};
+use crate::p2pool_conf::{P2poolConf, crosscheck};
+use crate::process::{ProcessEvent, Supervisor};
+use crate::rpc::{self, RpcConfig};
+use crate::state::PdmState;
+use crate::stratum::{self, StratumEvent, StratumMonitor, StratumStatus};
+use crate::tasks::{RpcReply, RpcTasks};
+use crate::zmq::{self, LinkState, ZmqActivity, ZmqEvent, ZmqSubscriber};
+use std::env;
+use std::io;
+use std::path::{self, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+/// Shown by screens that need the node while none is configured.
+const NO_NODE: &str = "No node to ask: load a bitcoin.conf first";
+
+/// How long bitcoind gets to act on the `stop` RPC before it is sent
+/// SIGTERM. Flushing a large UTXO cache can take a while.
+pub const BITCOIND_STOP_GRACE: Duration = Duration::from_secs(60);
+
+/// How long pdm waits on exit for the bitcoind and p2poolv2 it started to
+/// stop.
+pub const EXIT_GRACE: Duration = Duration::from_secs(30);
+
+/// Entries of the sidebar, in order; `sidebar_index` points into this.
+pub const SIDEBAR_ITEMS: [&str; 11] = [
+    "Home",
+    "Bitcoin Config",
+    "Peers",
+    "Mempool",
+    "Template",
+    "Debug Log",
+    "bitcoind",
+    "p2pool Config",
+    "p2poolv2",
+    "Stratum",
+    "Miners",
+];
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum CurrentScreen {
     Home,
     BitcoinConfig,
     FileExplorer,
+    Wizard,
+    Peers,
+    Mempool,
+    Template,
+    Log,
+    Bitcoind,
+    P2poolConfig,
+    P2pool,
+    Stratum,
+    Miners,
     Exiting,
 }
 
+/// Which config the file explorer picks a file for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfKind {
+    Bitcoin,
+    P2pool,
+}
+
+/// An action that would throw away unsaved config edits, held in
+/// [`App::discard_prompt`] until the user confirms it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Discard {
+    Quit,
+    /// Open another config of this kind.
+    Open(ConfKind),
+    /// Restore the backup selected in [`App::history_view`].
+    Restore(ConfKind),
+}
+
+/// Which part of the window receives navigation keys.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Focus {
+    Sidebar,
+    Content,
+}
+
+/// What the Bitcoin Config screen shows for a loaded config.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ConfigView {
+    Options,
+    Readiness,
+    /// Final values across `bitcoin_conf` and its included files.
+    Effective,
+    /// Backups of `bitcoin_conf_path`.
+    History,
+}
+
+/// What the p2pool Config screen shows for a loaded config.
+#[derive(Debug, PartialEq, Eq)]
+pub enum P2poolView {
+    Settings,
+    /// Agreement with the loaded bitcoin.conf.
+    CrossCheck,
+    /// Backups of `p2pool_conf_path`.
+    History,
+}
+
 pub struct App {
     pub current_screen: CurrentScreen,
     pub sidebar_index: usize,
+    pub focus: Focus,
     pub bitcoin_conf_path: Option<PathBuf>,
+    /// Parsed contents of `bitcoin_conf_path`, if it could be read.
+    pub bitcoin_conf: Option<BitcoinConf>,
+    /// Why `bitcoin_conf_path` could not be loaded, if it failed.
+    pub bitcoin_conf_error: Option<String>,
+    /// Files pulled in by `includeconf=` lines of `bitcoin_conf`.
+    pub bitcoin_includes: Includes,
     pub explorer: FileExplorer,
+    pub explorer_target: ConfKind,
+    /// Where to look for existing bitcoin.conf files.
+    pub discovery: Discovery,
+    /// Discovered configs to choose from, shown before the file explorer.
+    pub conf_picker: Option<ConfPicker>,
+    pub config_editor: ConfigEditor,
+    pub config_view: ConfigView,
+    pub readiness_report: ReadinessReport,
+    pub effective_view: EffectiveView,
+    pub history_view: HistoryView,
+    /// How many backups to keep per config file; 0 disables backups.
+    pub backup_history: usize,
+    /// Open rpcauth generator dialog, shown over the current screen.
+    pub rpcauth_dialog: Option<RpcAuthDialog>,
+    /// Pending save waiting for confirmation, shown over the current screen.
+    pub save_preview: Option<SavePreview>,
+    /// Failure to report, shown over everything else.
+    pub error_dialog: Option<ErrorDialog>,
+    /// Action waiting for confirmation because it would lose unsaved edits.
+    pub discard_prompt: Option<Discard>,
+    pub p2pool_conf_path: Option<PathBuf>,
+    /// Parsed contents of `p2pool_conf_path`, if it could be read.
+    pub p2pool_conf: Option<P2poolConf>,
+    /// Why `p2pool_conf_path` could not be loaded, if it failed.
+    pub p2pool_conf_error: Option<String>,
+    pub p2pool_editor: P2poolEditor,
+    pub p2pool_view: P2poolView,
+    pub crosscheck_report: ReadinessReport,
+    /// Where selected config paths are remembered between runs.
+    pub state_path: Option<PathBuf>,
+    /// First-run setup wizard, while `current_screen` is `Wizard`.
+    pub wizard: Option<SetupWizard>,
+    /// Latest snapshot of the node configured by `bitcoin_conf`.
+    pub node_status: Option<NodeStatus>,
+    /// Why the last attempt to reach the node failed.
+    pub node_error: Option<String>,
+    pub status_poller: Option<StatusPoller>,
+    /// Runs RPC calls against the node configured by `bitcoin_conf`.
+    pub rpc_tasks: Option<RpcTasks>,
+    pub peer_list: PeerList,
+    pub mempool_view: MempoolView,
+    pub template_view: TemplateView,
+    pub log_view: LogView,
+    /// Listens for bitcoind's ZMQ notifications, if bitcoin.conf enables
+    /// them.
+    pub zmq_subscriber: Option<ZmqSubscriber>,
+    pub zmq: ZmqActivity,
+    /// The bitcoind started from pdm, if any.
+    pub bitcoind: Supervisor,
+    /// The bitcoind executable to start.
+    pub bitcoind_path: PathBuf,
+    /// The p2poolv2 started from pdm, if any.
+    pub p2pool: Supervisor,
+    /// The p2poolv2 executable to start.
+    pub p2pool_path: PathBuf,
+    /// Checks the stratum server of the loaded p2poolv2 config serves
+    /// work, once the Stratum screen has been opened.
+    pub stratum_monitor: Option<StratumMonitor>,
+    pub stratum: Option<StratumStatus>,
+    /// Workers seen in the output of `p2pool`.
+    pub miner_list: MinerList,
+    /// Where background tasks deliver their results. Without it, as in
+    /// tests, the node is never contacted.
+    pub events: Option<Sender<AppEvent>>,
+    /// Redraws per second.
+    pub frame_rate: u32,
 }
 
 impl App {
@@ -25,9 +214,145 @@ impl App {
         App {
             current_screen: CurrentScreen::Home,
             sidebar_index: 0,
+            focus: Focus::Sidebar,
             bitcoin_conf_path: None,
+            bitcoin_conf: None,
+            bitcoin_conf_error: None,
+            bitcoin_includes: Includes::default(),
             explorer: FileExplorer::new(),
+            explorer_target: ConfKind::Bitcoin,
+            discovery: Discovery::default(),
+            conf_picker: None,
+            config_editor: ConfigEditor::new(),
+            config_view: ConfigView::Options,
+            readiness_report: ReadinessReport::new(),
+            effective_view: EffectiveView::new(),
+            history_view: HistoryView::new(),
+            backup_history: backup::DEFAULT_HISTORY,
+            rpcauth_dialog: None,
+            save_preview: None,
+            error_dialog: None,
+            discard_prompt: None,
+            p2pool_conf_path: None,
+            p2pool_conf: None,
+            p2pool_conf_error: None,
+            p2pool_editor: P2poolEditor::new(),
+            p2pool_view: P2poolView::Settings,
+            crosscheck_report: ReadinessReport::new(),
+            state_path: None,
+            wizard: None,
+            node_status: None,
+            node_error: None,
+            status_poller: None,
+            rpc_tasks: None,
+            peer_list: PeerList::new(),
+            mempool_view: MempoolView::new(),
+            template_view: TemplateView::new(),
+            log_view: LogView::new(),
+            zmq_subscriber: None,
+            zmq: ZmqActivity::default(),
+            bitcoind: Supervisor::new("bitcoind"),
+            bitcoind_path: PathBuf::from("bitcoind"),
+            p2pool: Supervisor::new("p2poolv2"),
+            p2pool_path: PathBuf::from("p2poolv2"),
+            stratum_monitor: None,
+            stratum: None,
+            miner_list: MinerList::new(),
+            events: None,
+            frame_rate: event::DEFAULT_FRAME_RATE,
+        }
+    }
+
+    /// Restores the configs remembered in the state file at `state_path`.
+    ///
+    /// Without any saved state this is a fresh deployment, so the setup
+    /// wizard is opened. A state file that cannot be read is reported and
+    /// left alone: nothing is remembered for the rest of the session.
+    pub fn from_state(state_path: Option<PathBuf>) -> App {
+        let mut app = App::new();
+        let state = match &state_path {
+            Some(path) => match PdmState::load(path) {
+                Ok(state) => {
+                    app.state_path = state_path.clone();
+                    state
+                }
+                Err(err) => {
+                    app.show_error(
+                        "Could not read pdm state",
+                        format!("{}: {}", path.display(), err),
+                    );
+                    return app;
+                }
+            },
+            None => None,
+        };
+        let Some(state) = state else {
+            app.start_wizard();
+            return app;
+        };
+
+        if let Some(path) = state.p2pool_conf_path {
+            app.load_p2pool_conf(path);
+        }
+        app.backup_history = state.backup_history.unwrap_or(backup::DEFAULT_HISTORY);
+        app.frame_rate = state.frame_rate.unwrap_or(event::DEFAULT_FRAME_RATE);
+        if let Some(path) = state.bitcoind_path {
+            app.bitcoind_path = path;
+        }
+        if let Some(path) = state.p2pool_path {
+            app.p2pool_path = path;
         }
+        app.p2pool.auto_restart = state.p2pool_auto_restart.unwrap_or(false);
+        if let Some(path) = state.bitcoin_conf_path {
+            app.load_bitcoin_conf(path);
+        }
+        app
+    }
+
+    /// Opens the setup wizard with defaults under the user's home directory.
+    pub fn start_wizard(&mut self) {
+        let home = env::var_os("HOME").map(PathBuf::from).unwrap_or_default();
+        self.wizard = Some(SetupWizard::new(&home));
+        self.current_screen = CurrentScreen::Wizard;
+    }
+
+    /// Leaves the wizard without writing anything.
+    pub fn cancel_wizard(&mut self) {
+        self.wizard = None;
+        self.sidebar_index = 0;
+        self.toggle_menu();
+    }
+
+    /// Writes the files described by the wizard, loads them and remembers
+    /// their paths.
+    pub fn finish_wizard(&mut self) -> io::Result<()> {
+        let Some(wizard) = &self.wizard else {
+            return Ok(());
+        };
+        let (bitcoin_path, p2pool_path) = wizard.write()?;
+        self.wizard = None;
+        self.load_bitcoin_conf(bitcoin_path);
+        self.load_p2pool_conf(p2pool_path);
+        self.sidebar_index = 1;
+        self.toggle_menu();
+        self.persist_state()
+    }
+
+    /// Saves the selected config paths to `state_path`, if set.
+    pub fn persist_state(&self) -> io::Result<()> {
+        let Some(path) = &self.state_path else {
+            return Ok(());
+        };
+        PdmState {
+            bitcoin_conf_path: self.bitcoin_conf_path.clone(),
+            p2pool_conf_path: self.p2pool_conf_path.clone(),
+            backup_history: Some(self.backup_history),
+            frame_rate: Some(self.frame_rate),
+            bitcoind_path: Some(self.bitcoind_path.clone()),
+            p2pool_path: Some(self.p2pool_path.clone()),
+            p2pool_auto_restart: Some(self.p2pool.auto_restart),
+        }
+        .save(path)
     }
 
     pub fn toggle_menu(&mut self) {
@@ -35,9 +360,815 @@ impl App {
         match self.sidebar_index {
             0 => self.current_screen = CurrentScreen::Home,
             1 => self.current_screen = CurrentScreen::BitcoinConfig,
+            2 => {
+                self.current_screen = CurrentScreen::Peers;
+                self.refresh_peers_if_stale();
+            }
+            3 => {
+                self.current_screen = CurrentScreen::Mempool;
+                self.refresh_mempool_if_stale();
+            }
+            4 => {
+                self.current_screen = CurrentScreen::Template;
+                self.refresh_template_if_stale();
+            }
+            5 => {
+                self.current_screen = CurrentScreen::Log;
+                self.open_debug_log();
+            }
+            6 => self.current_screen = CurrentScreen::Bitcoind,
+            7 => self.current_screen = CurrentScreen::P2poolConfig,
+            8 => self.current_screen = CurrentScreen::P2pool,
+            9 => {
+                self.current_screen = CurrentScreen::Stratum;
+                if self.stratum_monitor.is_none() {
+                    self.watch_stratum();
+                }
+            }
+            10 => self.current_screen = CurrentScreen::Miners,
+            _ => {}
+        }
+    }
+
+    /// Reports a failure in a dialog.
+    pub fn show_error(&mut self, title: &str, err: impl std::fmt::Display) {
+        self.error_dialog = Some(ErrorDialog::new(title, err));
+    }
+
+    /// Whether keystrokes are going into a text field, so that shortcuts
+    /// such as `q` must not be interpreted.
+    pub fn is_typing(&self) -> bool {
+        self.config_editor.is_editing()
+            || self.p2pool_editor.is_editing()
+            || self.rpcauth_dialog.is_some()
+            || self.save_preview.is_some()
+            || self.discard_prompt.is_some()
+            || self.wizard.is_some()
+            || self.peer_list.add_input.is_some()
+            || self.peer_list.pending.is_some()
+            || self.mempool_view.txid_input.is_some()
+            || self.log_view.search_input.is_some()
+            || self.bitcoind.pending.is_some()
+            || self.p2pool.pending.is_some()
+            || self.miner_list.filter_input.is_some()
+    }
+
+    /// Names of the configs `action` would discard unsaved edits of.
+    pub fn unsaved(&self, action: Discard) -> Vec<&'static str> {
+        let bitcoin = self.config_editor.modified.then_some("bitcoin.conf");
+        let p2pool = self.p2pool_editor.modified.then_some("p2poolv2 config");
+        match action {
+            Discard::Quit => bitcoin.into_iter().chain(p2pool).collect(),
+            Discard::Open(ConfKind::Bitcoin) | Discard::Restore(ConfKind::Bitcoin) => {
+                bitcoin.into_iter().collect()
+            }
+            Discard::Open(ConfKind::P2pool) | Discard::Restore(ConfKind::P2pool) => {
+                p2pool.into_iter().collect()
+            }
+        }
+    }
+
+    /// Runs `action`, or asks for confirmation first if it would throw
+    /// away unsaved edits.
+    pub fn discard(&mut self, action: Discard) {
+        if self.unsaved(action).is_empty() {
+            self.confirm_discard(action);
+        } else {
+            self.discard_prompt = Some(action);
+        }
+    }
+
+    /// Runs `action` regardless of unsaved edits. Quitting is left to
+    /// `run_app`, which owns the event loop.
+    pub fn confirm_discard(&mut self, action: Discard) {
+        match action {
+            Discard::Quit => {}
+            Discard::Open(ConfKind::Bitcoin) => self.choose_bitcoin_conf(),
+            Discard::Open(ConfKind::P2pool) => self.choose_p2pool_conf(),
+            Discard::Restore(kind) => self.restore_selected_backup(kind),
+        }
+    }
+
+    /// Offers the discovered bitcoin.conf files, or goes straight to the
+    /// file explorer if none were found.
+    pub fn choose_bitcoin_conf(&mut self) {
+        let candidates = self.discovery.candidates();
+        self.explorer_target = ConfKind::Bitcoin;
+        if candidates.is_empty() {
+            self.conf_picker = None;
+            self.focus = Focus::Sidebar;
+            self.current_screen = CurrentScreen::FileExplorer;
+        } else {
+            self.conf_picker = Some(ConfPicker::new(candidates));
+            self.focus = Focus::Content;
+            self.current_screen = CurrentScreen::BitcoinConfig;
+        }
+    }
+
+    /// Opens the file explorer to pick the p2poolv2 config, next to the
+    /// current one if there is one.
+    pub fn choose_p2pool_conf(&mut self) {
+        if let Some(dir) = self.p2pool_conf_path.as_ref().and_then(|p| p.parent()) {
+            self.explorer.open_dir(dir.to_path_buf());
+        }
+        self.explorer_target = ConfKind::P2pool;
+        self.focus = Focus::Sidebar;
+        self.current_screen = CurrentScreen::FileExplorer;
+    }
+
+    /// Remembers `path` as the selected p2poolv2 config and parses it.
+    ///
+    /// As with bitcoin.conf, the path is kept even if reading fails; the
+    /// error is recorded in `p2pool_conf_error`.
+    pub fn load_p2pool_conf(&mut self, path: PathBuf) {
+        match P2poolConf::load(&path) {
+            Ok(conf) => {
+                self.p2pool_conf = Some(conf);
+                self.p2pool_conf_error = None;
+            }
+            Err(err) => {
+                self.p2pool_conf = None;
+                self.p2pool_conf_error = Some(err.to_string());
+            }
+        }
+        self.p2pool_conf_path = Some(path);
+        self.p2pool_editor = P2poolEditor::new();
+        self.p2pool_view = P2poolView::Settings;
+        self.crosscheck_report = ReadinessReport::new();
+        self.stratum_monitor = None;
+        self.stratum = None;
+    }
+
+    /// Remembers `path` as the selected bitcoin.conf and parses it,
+    /// together with the files it includes.
+    ///
+    /// The path is kept even if reading fails so the user can see which
+    /// file was chosen; the error is recorded in `bitcoin_conf_error`.
+    pub fn load_bitcoin_conf(&mut self, path: PathBuf) {
+        match BitcoinConf::load(&path) {
+            Ok(conf) => {
+                self.bitcoin_includes = include::resolve(&path, &conf);
+                self.bitcoin_conf = Some(conf);
+                self.bitcoin_conf_error = None;
+            }
+            Err(err) => {
+                self.bitcoin_includes = Includes::default();
+                self.bitcoin_conf = None;
+                self.bitcoin_conf_error = Some(err.to_string());
+            }
+        }
+        self.bitcoin_conf_path = Some(path);
+        self.conf_picker = None;
+        self.config_editor = ConfigEditor::new();
+        self.config_view = ConfigView::Options;
+        self.readiness_report = ReadinessReport::new();
+        self.effective_view = EffectiveView::new();
+        self.history_view = HistoryView::new();
+        self.rpcauth_dialog = None;
+        self.node_status = None;
+        self.node_error = None;
+        self.peer_list = PeerList::new();
+        self.mempool_view = MempoolView::new();
+        self.template_view = TemplateView::new();
+        self.log_view = LogView::new();
+        self.zmq = ZmqActivity::default();
+        self.watch_node();
+
+        let problems = self.bitcoin_includes.problems.len();
+        if problems > 0 {
+            self.config_editor.message = Some(format!(
+                "{} includeconf problem(s), press [e] for details",
+                problems
+            ));
+        }
+    }
+
+    /// Re-reads the included files, picking up `includeconf=` lines added
+    /// in the editor, and shows the effective configuration.
+    pub fn show_effective_config(&mut self) {
+        if let (Some(path), Some(conf)) = (&self.bitcoin_conf_path, &self.bitcoin_conf) {
+            self.bitcoin_includes = include::resolve(path, conf);
+        }
+        self.effective_view = EffectiveView::new();
+        self.config_view = ConfigView::Effective;
+    }
+
+    /// Lists the backups of the loaded config of `kind`.
+    pub fn show_history(&mut self, kind: ConfKind) {
+        self.history_view = HistoryView::new();
+        let path = match kind {
+            ConfKind::Bitcoin => {
+                self.config_view = ConfigView::History;
+                &self.bitcoin_conf_path
+            }
+            ConfKind::P2pool => {
+                self.p2pool_view = P2poolView::History;
+                &self.p2pool_conf_path
+            }
+        };
+        if let Some(path) = path {
+            self.history_view.load(path);
+        }
+    }
+
+    /// Puts `backup` back in place of the loaded config of `kind` and
+    /// reloads it. The file being replaced is backed up first.
+    pub fn restore_backup(&mut self, kind: ConfKind, backup: &Backup) -> io::Result<()> {
+        let path = match kind {
+            ConfKind::Bitcoin => self.bitcoin_conf_path.clone(),
+            ConfKind::P2pool => self.p2pool_conf_path.clone(),
+        };
+        let Some(path) = path else {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "no config loaded"));
+        };
+        backup::restore(&path, backup, self.backup_history)?;
+        match kind {
+            ConfKind::Bitcoin => self.load_bitcoin_conf(path),
+            ConfKind::P2pool => self.load_p2pool_conf(path),
+        }
+        self.show_history(kind);
+        Ok(())
+    }
+
+    /// Restores the backup selected in the history view, reporting the
+    /// outcome there.
+    fn restore_selected_backup(&mut self, kind: ConfKind) {
+        let Some(backup) = self.history_view.selected_backup().cloned() else {
+            return;
+        };
+        match self.restore_backup(kind, &backup) {
+            Ok(()) => {
+                self.history_view.message = Some(format!("Restored backup from {}", backup.label()))
+            }
+            Err(err) => self.show_error("Restore failed", err),
+        }
+    }
+
+    /// The loaded bitcoin.conf and its path, if it may be written.
+    ///
+    /// A config that has validation errors may not.
+    fn savable_bitcoin_conf(&self) -> io::Result<(&PathBuf, &BitcoinConf)> {
+        let (Some(path), Some(conf)) = (&self.bitcoin_conf_path, &self.bitcoin_conf) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no bitcoin.conf loaded",
+            ));
+        };
+        let errors = validate::error_count(&validate::validate(conf));
+        if errors > 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("config has {} error(s), fix them first", errors),
+            ));
+        }
+        Ok((path, conf))
+    }
+
+    /// Opens a diff of the pending bitcoin.conf changes for confirmation.
+    ///
+    /// Returns `false` if saving would not change the file.
+    pub fn preview_bitcoin_conf_save(&mut self) -> io::Result<bool> {
+        let (path, conf) = self.savable_bitcoin_conf()?;
+        self.save_preview = SavePreview::new(path, &conf.to_string())?;
+        Ok(self.save_preview.is_some())
+    }
+
+    /// Writes the loaded bitcoin.conf back to `bitcoin_conf_path`, keeping
+    /// a backup of the previous version.
+    ///
+    /// Refuses to write a config that has validation errors.
+    pub fn save_bitcoin_conf(&mut self) -> io::Result<()> {
+        let (path, conf) = self.savable_bitcoin_conf()?;
+        backup::create(path, self.backup_history)?;
+        conf.save(path)?;
+        self.config_editor.modified = false;
+        // The RPC settings may have changed.
+        self.watch_node();
+        if self.bitcoind.is_running() {
+            self.bitcoind.message =
+                Some("bitcoin.conf changed: restart bitcoind to apply it".to_string());
+        }
+        Ok(())
+    }
+
+    fn loaded_p2pool_conf(&self) -> io::Result<(&PathBuf, &P2poolConf)> {
+        let (Some(path), Some(conf)) = (&self.p2pool_conf_path, &self.p2pool_conf) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no p2pool config loaded",
+            ));
+        };
+        Ok((path, conf))
+    }
+
+    /// Opens a diff of the pending p2poolv2 config changes for
+    /// confirmation.
+    ///
+    /// Returns `false` if saving would not change the file.
+    pub fn preview_p2pool_conf_save(&mut self) -> io::Result<bool> {
+        let (path, conf) = self.loaded_p2pool_conf()?;
+        self.save_preview = SavePreview::new(path, &conf.to_string())?;
+        Ok(self.save_preview.is_some())
+    }
+
+    /// Writes the loaded p2poolv2 config back to `p2pool_conf_path`,
+    /// keeping a backup of the previous version.
+    pub fn save_p2pool_conf(&mut self) -> io::Result<()> {
+        let (path, conf) = self.loaded_p2pool_conf()?;
+        backup::create(path, self.backup_history)?;
+        conf.save(path)?;
+        self.p2pool_editor.modified = false;
+        if self.p2pool.is_running() {
+            self.p2pool.message =
+                Some("p2poolv2 config changed: restart p2poolv2 to apply it".to_string());
+        }
+        Ok(())
+    }
+
+    /// The network the loaded p2poolv2 config mines on, if it sets one.
+    pub fn p2pool_network(&self) -> Option<Network> {
+        self.p2pool_conf.as_ref()?.settings().network
+    }
+
+    /// Checks the loaded p2poolv2 config against the loaded bitcoin.conf,
+    /// included files taken into account. `None` unless both are loaded.
+    pub fn p2pool_crosscheck(&self) -> Option<Vec<crosscheck::Check>> {
+        let (path, conf) = (
+            self.bitcoin_conf_path.as_ref()?,
+            self.bitcoin_conf.as_ref()?,
+        );
+        let merged = self.bitcoin_includes.merged(path, conf);
+        let cookie = match RpcConfig::from_conf(&merged, path).auth {
+            auth @ rpc::Auth::Cookie(_) => auth.credentials().ok().map(|(_, password)| password),
+            rpc::Auth::UserPass { .. } => None,
+        };
+        Some(crosscheck::check(
+            &merged,
+            self.p2pool_conf.as_ref()?,
+            cookie.as_deref(),
+        ))
+    }
+
+    /// How to reach the node `bitcoin_conf` configures, included files
+    /// taken into account.
+    pub fn rpc_config(&self) -> Option<RpcConfig> {
+        let (path, conf) = (
+            self.bitcoin_conf_path.as_ref()?,
+            self.bitcoin_conf.as_ref()?,
+        );
+        let merged = self.bitcoin_includes.merged(path, conf);
+        Some(RpcConfig::from_conf(&merged, path))
+    }
+
+    /// The ZMQ endpoints `bitcoin_conf` publishes on, included files taken
+    /// into account.
+    pub fn zmq_endpoints(&self) -> Vec<zmq::Endpoint> {
+        let (Some(path), Some(conf)) = (&self.bitcoin_conf_path, &self.bitcoin_conf) else {
+            return Vec::new();
+        };
+        zmq::endpoints(&self.bitcoin_includes.merged(path, conf))
+    }
+
+    /// Starts tailing the debug.log of the node `bitcoin_conf` configures,
+    /// unless it is already shown.
+    pub fn open_debug_log(&mut self) {
+        let (Some(path), Some(conf)) = (&self.bitcoin_conf_path, &self.bitcoin_conf) else {
+            self.log_view.error = Some("No debug.log to show: load a bitcoin.conf first".into());
+            return;
+        };
+        let merged = self.bitcoin_includes.merged(path, conf);
+        match log_view::debug_log_path(&merged, path) {
+            Some(log) if self.log_view.path.as_ref() != Some(&log) => self.log_view.open(log),
+            Some(_) => {}
+            None => self.log_view.error = Some("bitcoind is set not to write a debug.log".into()),
+        }
+    }
+
+    /// (Re)connects background tasks to the node and starts polling it and
+    /// listening to its ZMQ notifications, if background tasks are enabled.
+    pub fn watch_node(&mut self) {
+        self.status_poller = None;
+        self.zmq_subscriber = None;
+        self.rpc_tasks = None;
+        let (Some(events), Some(config)) = (&self.events, self.rpc_config()) else {
+            return;
+        };
+        match RpcTasks::start(config, events.clone()) {
+            Ok(tasks) => {
+                self.status_poller = Some(StatusPoller::start(&tasks, node_status::POLL_INTERVAL));
+                let endpoints = self.zmq_endpoints();
+                self.zmq = ZmqActivity::new(&endpoints);
+                if !endpoints.is_empty() {
+                    self.zmq_subscriber =
+                        Some(ZmqSubscriber::start(&tasks, endpoints, zmq::RETRY_INTERVAL));
+                }
+                self.rpc_tasks = Some(tasks);
+            }
+            Err(err) => self.node_error = Some(format!("cannot start RPC tasks: {}", err)),
+        }
+    }
+
+    /// Where the stratum monitor connects for the loaded p2poolv2 config.
+    pub fn stratum_target(&self) -> Result<stratum::Target, String> {
+        match &self.p2pool_conf {
+            Some(conf) => stratum::Target::from_settings(&conf.settings()),
+            None => Err("select a p2poolv2 config first".to_string()),
+        }
+    }
+
+    /// (Re)connects to the stratum server of the loaded p2poolv2 config,
+    /// if background tasks are enabled.
+    pub fn watch_stratum(&mut self) {
+        self.stratum_monitor = None;
+        self.stratum = None;
+        let (Some(events), Ok(target)) = (&self.events, self.stratum_target()) else {
+            return;
+        };
+        match StratumMonitor::start(target.clone(), events.clone(), stratum::RETRY_INTERVAL) {
+            Ok(monitor) => {
+                self.stratum_monitor = Some(monitor);
+                self.stratum = Some(StratumStatus::new(target));
+            }
+            Err(err) => self.show_error("Cannot monitor stratum", err),
+        }
+    }
+
+    /// Disconnects from the stratum server, keeping what was heard.
+    pub fn stop_stratum(&mut self) {
+        if self.stratum_monitor.take().is_some()
+            && let Some(status) = &mut self.stratum
+        {
+            status.link = LinkState::Down("disconnected by pdm".to_string());
+        }
+    }
+
+    /// Called on every tick of the event loop.
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        if self.bitcoind.tick(now) {
+            self.start_bitcoind();
+        }
+        if self.p2pool.tick(now) {
+            self.start_p2pool();
+        }
+        self.refresh_screen();
+    }
+
+    /// Fetches what the current screen shows once it is out of date.
+    fn refresh_screen(&mut self) {
+        match self.current_screen {
+            CurrentScreen::Peers => self.refresh_peers_if_stale(),
+            CurrentScreen::Mempool => self.refresh_mempool_if_stale(),
+            CurrentScreen::Template => self.refresh_template_if_stale(),
+            CurrentScreen::Log => self.log_view.poll(),
             _ => {}
         }
     }
+
+    fn refresh_peers_if_stale(&mut self) {
+        if self.peer_list.is_stale(Instant::now()) {
+            self.refresh_peers();
+        }
+    }
+
+    /// Asks the node for its peers.
+    pub fn refresh_peers(&mut self) {
+        self.peer_list.last_fetch = Some(Instant::now());
+        let Some(tasks) = &self.rpc_tasks else {
+            self.peer_list.error = Some(NO_NODE.to_string());
+            return;
+        };
+        tasks.spawn(|client| async move {
+            RpcReply::Peers(client.get_peer_info().await.map_err(|e| e.to_string()))
+        });
+    }
+
+    fn refresh_mempool_if_stale(&mut self) {
+        if self.mempool_view.is_stale(Instant::now()) {
+            self.refresh_mempool();
+        }
+    }
+
+    /// Asks the node for mempool statistics and every transaction's fee
+    /// rate. The histogram is built on the RPC runtime so the full mempool
+    /// never reaches the UI thread.
+    pub fn refresh_mempool(&mut self) {
+        self.mempool_view.last_fetch = Some(Instant::now());
+        let Some(tasks) = &self.rpc_tasks else {
+            self.mempool_view.error = Some(NO_NODE.to_string());
+            return;
+        };
+        tasks.spawn(|client| async move {
+            let stats = async {
+                let info = client.get_mempool_info().await?;
+                let entries = client.get_raw_mempool_verbose().await?;
+                Ok::<_, rpc::Error>((info, mempool_view::histogram(entries.values())))
+            };
+            RpcReply::Mempool(stats.await.map_err(|e| e.to_string()))
+        });
+    }
+
+    fn refresh_template_if_stale(&mut self) {
+        if self.template_view.is_stale(Instant::now()) {
+            self.refresh_template();
+        }
+    }
+
+    /// Asks the node for the block template it would have the pool mine.
+    pub fn refresh_template(&mut self) {
+        self.template_view.last_fetch = Some(Instant::now());
+        let Some(tasks) = &self.rpc_tasks else {
+            self.template_view.error = Some(NO_NODE.to_string());
+            return;
+        };
+        tasks.spawn(|client| async move {
+            RpcReply::Template(client.get_block_template().await.map_err(|e| e.to_string()))
+        });
+    }
+
+    /// Looks up a mempool transaction with its ancestors and descendants.
+    pub fn lookup_transaction(&mut self, txid: String) {
+        let Some(tasks) = &self.rpc_tasks else {
+            self.mempool_view.message = Some(NO_NODE.to_string());
+            return;
+        };
+        tasks.spawn(|client| async move {
+            let detail = async {
+                let (entry, ancestors, descendants) = tokio::try_join!(
+                    client.get_mempool_entry(&txid),
+                    client.get_mempool_ancestors(&txid),
+                    client.get_mempool_descendants(&txid),
+                )?;
+                Ok::<_, rpc::Error>(TxDetail {
+                    txid,
+                    entry,
+                    ancestors,
+                    descendants,
+                })
+            };
+            RpcReply::Transaction(detail.await.map_err(|e| e.to_string()))
+        });
+    }
+
+    /// Runs the confirmed peer action.
+    pub fn run_peer_action(&mut self, action: PeerAction) {
+        let Some(tasks) = &self.rpc_tasks else {
+            self.peer_list.message = Some(NO_NODE.to_string());
+            return;
+        };
+        tasks.spawn(|client| async move {
+            let result = match &action {
+                PeerAction::Disconnect { id, .. } => client.disconnect_node(*id).await,
+                PeerAction::Ban { subnet } => client.set_ban(subnet, peer_list::BAN_SECONDS).await,
+                PeerAction::Add { address } => client.add_node(address).await,
+            };
+            RpcReply::PeerAction(result.map(|()| action.done()).map_err(|e| e.to_string()))
+        });
+    }
+
+    /// Takes in the result of a background RPC task.
+    pub fn handle_rpc_reply(&mut self, reply: RpcReply) {
+        match reply {
+            RpcReply::Peers(Ok(peers)) => self.peer_list.set_peers(peers),
+            RpcReply::Peers(Err(err)) => self.peer_list.error = Some(err),
+            RpcReply::Mempool(Ok((info, histogram))) => {
+                self.mempool_view.set_stats(info, histogram)
+            }
+            RpcReply::Mempool(Err(err)) => self.mempool_view.error = Some(err),
+            RpcReply::Transaction(Ok(detail)) => self.mempool_view.show_detail(detail),
+            RpcReply::Transaction(Err(err)) => {
+                self.mempool_view.message = Some(format!("Lookup failed: {}", err))
+            }
+            RpcReply::Template(Ok(template)) => self.template_view.set_template(template),
+            RpcReply::Template(Err(err)) => self.template_view.error = Some(err),
+            RpcReply::Stop(Ok(_)) => {
+                self.bitcoind.message = Some("bitcoind is shutting down".to_string())
+            }
+            RpcReply::Stop(Err(err)) if self.bitcoind.is_running() => {
+                self.bitcoind.message = Some(match self.bitcoind.terminate() {
+                    Ok(()) => format!("stop RPC failed ({}), sent SIGTERM", err),
+                    Err(signal) => format!("stop RPC failed ({}), SIGTERM failed: {}", err, signal),
+                })
+            }
+            RpcReply::Stop(Err(err)) => {
+                self.bitcoind.message = Some(format!("Stop failed: {}", err))
+            }
+            RpcReply::PeerAction(result) => {
+                self.peer_list.message = Some(match result {
+                    Ok(done) => done,
+                    Err(err) => format!("Failed: {}", err),
+                });
+                self.refresh_peers();
+            }
+        }
+    }
+
+    /// The command that starts bitcoind in the foreground with the loaded
+    /// bitcoin.conf and the data directory it names, if any.
+    pub fn bitcoind_command(&self) -> Option<Command> {
+        let (path, conf) = (
+            self.bitcoin_conf_path.as_ref()?,
+            self.bitcoin_conf.as_ref()?,
+        );
+        // bitcoind would look for a relative -conf inside the datadir
+        let path = path::absolute(path).unwrap_or_else(|_| path.clone());
+        let merged = self.bitcoin_includes.merged(&path, conf);
+        let mut command = Command::new(&self.bitcoind_path);
+        command.arg(format!("-conf={}", path.display()));
+        // Without datadir= bitcoind uses its default, not the conf's directory
+        if merged.effective("datadir").is_some() {
+            command.arg(format!("-datadir={}", merged.datadir(&path).display()));
+        }
+        // A forking bitcoind would look like it exited to the supervisor
+        command.arg("-daemon=0");
+        Some(command)
+    }
+
+    /// Starts bitcoind with the loaded bitcoin.conf.
+    pub fn start_bitcoind(&mut self) {
+        let Some(command) = self.bitcoind_command() else {
+            self.bitcoind.message = Some("Cannot start bitcoind: load a bitcoin.conf first".into());
+            return;
+        };
+        let Some(events) = &self.events else {
+            self.bitcoind.message = Some("Cannot start bitcoind: no event loop".into());
+            return;
+        };
+        if let Err(err) = self.bitcoind.start(command, events.clone()) {
+            self.bitcoind.message = Some(format!("Cannot start bitcoind: {}", err));
+        }
+    }
+
+    /// Asks bitcoind to shut down over RPC. A bitcoind started from pdm is
+    /// sent SIGTERM if the RPC fails or it takes too long to exit.
+    pub fn stop_bitcoind(&mut self) {
+        self.bitcoind.begin_stop(BITCOIND_STOP_GRACE);
+        match &self.rpc_tasks {
+            Some(tasks) => {
+                self.bitcoind.message = Some("Asked bitcoind to stop".to_string());
+                tasks.spawn(|client| async move {
+                    RpcReply::Stop(client.stop().await.map_err(|e| e.to_string()))
+                });
+            }
+            None if self.bitcoind.is_running() => {
+                self.handle_rpc_reply(RpcReply::Stop(Err("no RPC settings".into())))
+            }
+            None => self.bitcoind.message = Some(NO_NODE.to_string()),
+        }
+    }
+
+    /// Stops the bitcoind started from pdm and starts it again once it has
+    /// exited, picking up bitcoin.conf changes. Starts it if not running.
+    pub fn restart_bitcoind(&mut self) {
+        if self.bitcoind.is_running() {
+            self.bitcoind.restart_pending = true;
+            self.stop_bitcoind();
+        } else {
+            self.start_bitcoind();
+        }
+    }
+
+    /// The command that starts p2poolv2 with the loaded config. It runs in
+    /// the config's directory, so relative paths in the config are taken
+    /// from there.
+    pub fn p2pool_command(&self) -> Option<Command> {
+        let path = self.p2pool_conf_path.as_ref()?;
+        let path = path::absolute(path).unwrap_or_else(|_| path.clone());
+        let mut command = Command::new(&self.p2pool_path);
+        command.arg("--config").arg(&path);
+        // Colour codes would get in the way of reading worker fields
+        command.env("NO_COLOR", "1");
+        if let Some(dir) = path.parent() {
+            command.current_dir(dir);
+        }
+        Some(command)
+    }
+
+    /// Starts p2poolv2 with the selected config.
+    pub fn start_p2pool(&mut self) {
+        let Some(command) = self.p2pool_command() else {
+            self.p2pool.message =
+                Some("Cannot start p2poolv2: select a p2poolv2 config first".into());
+            return;
+        };
+        let Some(events) = &self.events else {
+            self.p2pool.message = Some("Cannot start p2poolv2: no event loop".into());
+            return;
+        };
+        if let Err(err) = self.p2pool.start(command, events.clone()) {
+            self.p2pool.message = Some(format!("Cannot start p2poolv2: {}", err));
+        }
+    }
+
+    /// Sends SIGTERM to the p2poolv2 started from pdm, or cancels its
+    /// pending automatic restart.
+    pub fn stop_p2pool(&mut self) {
+        let running = self.p2pool.is_running();
+        self.p2pool.message = Some(match self.p2pool.terminate() {
+            Ok(()) if running => "Sent SIGTERM to p2poolv2".to_string(),
+            Ok(()) => "p2poolv2 is not running".to_string(),
+            Err(err) => format!("Could not signal p2poolv2: {}", err),
+        });
+    }
+
+    /// Stops the p2poolv2 started from pdm and starts it again once it has
+    /// exited. Starts it if not running.
+    pub fn restart_p2pool(&mut self) {
+        if self.p2pool.is_running() {
+            self.p2pool.restart_pending = true;
+            self.stop_p2pool();
+        } else {
+            self.start_p2pool();
+        }
+    }
+
+    /// Turns automatic restarts of p2poolv2 on or off and remembers the
+    /// choice.
+    pub fn toggle_p2pool_auto_restart(&mut self) {
+        self.p2pool.set_auto_restart(!self.p2pool.auto_restart);
+        if let Err(err) = self.persist_state() {
+            self.show_error("Could not remember setting", err);
+        }
+    }
+
+    /// Sends SIGTERM to the bitcoind and p2poolv2 started from pdm as pdm
+    /// exits, cancelling pending restarts, and returns the names of those
+    /// it was sent to.
+    pub fn stop_children(&mut self) -> Vec<&'static str> {
+        let mut stopping = Vec::new();
+        for supervisor in [&mut self.bitcoind, &mut self.p2pool] {
+            supervisor.restart_pending = false;
+            supervisor.restart_at = None;
+            if supervisor.is_running() && supervisor.terminate().is_ok() {
+                stopping.push(supervisor.name);
+            }
+        }
+        stopping
+    }
+
+    /// Takes in output or the exit of the child process with id `process`.
+    /// p2poolv2's output also feeds the worker statistics.
+    pub fn handle_process_event(&mut self, process: u64, event: ProcessEvent) {
+        if self.bitcoind.owns(process) {
+            if self.bitcoind.handle(event) {
+                self.start_bitcoind();
+            }
+        } else if self.p2pool.owns(process) {
+            match &event {
+                ProcessEvent::Output(line) => self.miner_list.observe(line, Instant::now()),
+                ProcessEvent::Exited(_) => self.miner_list.disconnect_all(),
+            }
+            if self.p2pool.handle(event) {
+                self.start_p2pool();
+            }
+        }
+    }
+
+    /// Takes in a status update from the poller with id `poller`.
+    pub fn handle_node_status(&mut self, poller: u64, status: Result<NodeStatus, String>) {
+        // Left over from a poller that has since been replaced
+        if self.status_poller.as_ref().map(|p| p.id) != Some(poller) {
+            return;
+        }
+        match status {
+            Ok(status) => {
+                self.node_status = Some(status);
+                self.node_error = None;
+            }
+            Err(err) => self.node_error = Some(err),
+        }
+    }
+
+    /// Takes in a notification from the ZMQ subscriber with id
+    /// `subscriber`. A new block refreshes the node status and the screen
+    /// shown right away rather than at the next poll, and is announced on
+    /// every screen for [`zmq::BLOCK_NOTICE`].
+    pub fn handle_zmq(&mut self, subscriber: u64, event: ZmqEvent) {
+        if self.zmq_subscriber.as_ref().map(|s| s.id) != Some(subscriber) {
+            return;
+        }
+        let new_block = matches!(event, ZmqEvent::Block { .. });
+        self.zmq.record(event);
+        if new_block {
+            if let Some(poller) = &self.status_poller {
+                poller.poll_now();
+            }
+            self.peer_list.last_fetch = None;
+            self.mempool_view.last_fetch = None;
+            self.template_view.last_fetch = None;
+            self.refresh_screen();
+        }
+    }
+
+    /// Takes in what the stratum monitor with id `monitor` heard.
+    pub fn handle_stratum(&mut self, monitor: u64, event: StratumEvent) {
+        if self.stratum_monitor.as_ref().map(|m| m.id) != Some(monitor) {
+            return;
+        }
+        if let Some(status) = &mut self.stratum {
+            status.record(event);
+        }
+    }
 }
 impl Default for App {
     fn default() -> Self {
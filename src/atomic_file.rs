@@ -0,0 +1,196 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Crash-safe file replacement.
+//!
+//! The new contents are written to a temporary file next to the target,
+//! synced to disk, given the target's owner and mode and then renamed over
+//! it. A reader, or bitcoind restarting after a power loss, sees either the
+//! old file or the new one, never a partial write.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Mode for files that do not exist yet: configs hold credentials.
+#[cfg(unix)]
+const NEW_FILE_MODE: u32 = 0o600;
+
+/// Atomically replaces the file at `path` with `contents`.
+///
+/// An existing file keeps its owner, group and permissions, and a symlink
+/// keeps pointing at the same file. New files are created readable by the
+/// owner only. The directory must already exist.
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let target = resolve_symlink(path)?;
+    let dir = match target.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let name = target
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let temp = dir.join(format!(
+        ".{}.pdm-{}.tmp",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let existing = match fs::metadata(&target) {
+        Ok(metadata) => Some(metadata),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err),
+    };
+
+    let result = write_temp(&temp, contents.as_ref(), existing.as_ref())
+        .and_then(|()| fs::rename(&temp, &target))
+        .and_then(|()| sync_dir(&dir));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// The file a symlink at `path` points to, or `path` itself.
+fn resolve_symlink(path: &Path) -> io::Result<PathBuf> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => fs::canonicalize(path),
+        _ => Ok(path.to_path_buf()),
+    }
+}
+
+fn write_temp(temp: &Path, contents: &[u8], existing: Option<&fs::Metadata>) -> io::Result<()> {
+    // A leftover from an earlier crash of this process id.
+    let _ = fs::remove_file(temp);
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(NEW_FILE_MODE);
+    }
+    let mut file = options.open(temp)?;
+    file.write_all(contents)?;
+
+    if let Some(metadata) = existing {
+        preserve_metadata(&file, metadata)?;
+    }
+    file.sync_all()
+}
+
+#[cfg(unix)]
+fn preserve_metadata(file: &File, metadata: &fs::Metadata) -> io::Result<()> {
+    use std::os::unix::fs::{MetadataExt, fchown};
+
+    let current = file.metadata()?;
+    if (current.uid(), current.gid()) != (metadata.uid(), metadata.gid()) {
+        fchown(file, Some(metadata.uid()), Some(metadata.gid())).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!(
+                    "cannot keep owner {}:{} ({}); run pdm as that user or root",
+                    metadata.uid(),
+                    metadata.gid(),
+                    err
+                ),
+            )
+        })?;
+    }
+    // After chown, which may clear set-id bits.
+    file.set_permissions(metadata.permissions())
+}
+
+#[cfg(not(unix))]
+fn preserve_metadata(file: &File, metadata: &fs::Metadata) -> io::Result<()> {
+    file.set_permissions(metadata.permissions())
+}
+
+/// Makes the rename itself durable.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("pdm_atomic_file_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn replaces_contents_without_leftovers() {
+        let dir = scratch("replace");
+        let path = dir.join("bitcoin.conf");
+        fs::write(&path, "old\n").unwrap();
+
+        write(&path, "new\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new\n");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_mode_of_existing_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = scratch("mode");
+        let path = dir.join("bitcoin.conf");
+        fs::write(&path, "old\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+        write(&path, "new\n").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn new_files_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = scratch("new");
+        let path = dir.join("p2pool.toml");
+        write(&path, "x").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn writes_through_symlinks() {
+        let dir = scratch("symlink");
+        let real = dir.join("real.conf");
+        let link = dir.join("bitcoin.conf");
+        fs::write(&real, "old\n").unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        write(&link, "new\n").unwrap();
+        assert!(
+            fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert_eq!(fs::read_to_string(&real).unwrap(), "new\n");
+    }
+
+    #[test]
+    fn missing_directory_is_an_error() {
+        let dir = scratch("missing");
+        let err = write(&dir.join("nope/bitcoin.conf"), "x").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
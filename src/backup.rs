@@ -0,0 +1,269 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Timestamped copies of config files, taken before pdm overwrites them.
+//!
+//! Backups of `<dir>/<name>` live in `<dir>/.pdm-backups/` as
+//! `<name>.<YYYYMMDD-HHMMSS>`, in UTC, so they sort by age.
+
+use crate::atomic_file;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Backups kept per file unless configured otherwise.
+pub const DEFAULT_HISTORY: usize = 10;
+
+const BACKUP_DIR: &str = ".pdm-backups";
+
+/// A saved copy of a config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub path: PathBuf,
+    /// `YYYYMMDD-HHMMSS`, optionally followed by `-N` when several
+    /// backups were taken in the same second.
+    pub stamp: String,
+}
+
+impl Backup {
+    /// The time the backup was taken, for display.
+    pub fn label(&self) -> String {
+        let s = &self.stamp;
+        if s.len() < 15 {
+            return s.clone();
+        }
+        format!(
+            "{}-{}-{} {}:{}:{} UTC{}",
+            &s[0..4],
+            &s[4..6],
+            &s[6..8],
+            &s[9..11],
+            &s[11..13],
+            &s[13..15],
+            &s[15..]
+        )
+    }
+
+    /// Sort key: timestamp, then same-second counter.
+    fn order(&self) -> (&str, u32) {
+        let (time, counter) = self.stamp.split_at(self.stamp.len().min(15));
+        (time, counter.trim_start_matches('-').parse().unwrap_or(0))
+    }
+}
+
+/// The directory backups of `path` are written to.
+pub fn backup_dir(path: &Path) -> PathBuf {
+    path.parent().unwrap_or(Path::new("")).join(BACKUP_DIR)
+}
+
+fn file_name(path: &Path) -> io::Result<String> {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))
+}
+
+/// Copies `path` into its backup directory and deletes all but the newest
+/// `keep` backups. Does nothing if `path` does not exist yet or `keep` is 0.
+pub fn create(path: &Path, keep: usize) -> io::Result<Option<Backup>> {
+    if keep == 0 || !path.exists() {
+        return Ok(None);
+    }
+    let dir = backup_dir(path);
+    fs::create_dir_all(&dir)?;
+
+    let name = file_name(path)?;
+    let time = timestamp(SystemTime::now());
+    // Number backups taken within the same second after the newest one,
+    // even if older ones have been pruned.
+    let stamp = match list(path)?
+        .iter()
+        .filter(|b| b.order().0 == time)
+        .map(|b| b.order().1)
+        .max()
+    {
+        Some(counter) => format!("{}-{}", time, counter + 1),
+        None => time,
+    };
+
+    let backup = Backup {
+        path: dir.join(format!("{}.{}", name, stamp)),
+        stamp,
+    };
+    fs::copy(path, &backup.path)?;
+
+    for old in list(path)?.into_iter().skip(keep) {
+        fs::remove_file(old.path)?;
+    }
+    Ok(Some(backup))
+}
+
+/// Backups of `path`, newest first.
+pub fn list(path: &Path) -> io::Result<Vec<Backup>> {
+    let prefix = format!("{}.", file_name(path)?);
+    let entries = match fs::read_dir(backup_dir(path)) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    let mut backups: Vec<Backup> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let stamp = name.strip_prefix(&prefix)?;
+            is_stamp(stamp).then(|| Backup {
+                path: entry.path(),
+                stamp: stamp.to_string(),
+            })
+        })
+        .collect();
+    backups.sort_by(|a, b| b.order().cmp(&a.order()));
+    Ok(backups)
+}
+
+/// Replaces `path` with the contents of `backup`, backing up the current
+/// file first so the restore can be undone, even if backups are disabled.
+/// With backups disabled, existing backups are not pruned.
+pub fn restore(path: &Path, backup: &Backup, keep: usize) -> io::Result<()> {
+    let contents = fs::read(&backup.path)?;
+    let keep = match keep {
+        0 => list(path)?.len() + 1,
+        keep => keep,
+    };
+    create(path, keep)?;
+    atomic_file::write(path, contents)
+}
+
+fn is_stamp(s: &str) -> bool {
+    let b = s.as_bytes();
+    b.len() >= 15
+        && b[..8].iter().all(u8::is_ascii_digit)
+        && b[8] == b'-'
+        && b[9..15].iter().all(u8::is_ascii_digit)
+        && (b.len() == 15 || (b[15] == b'-' && b[16..].iter().all(u8::is_ascii_digit)))
+}
+
+/// Formats `time` as `YYYYMMDD-HHMMSS` in UTC.
+fn timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Converts days since 1970-01-01 to a (year, month, day) date.
+///
+/// Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::time::Duration;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("pdm_backup_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("bitcoin.conf")
+    }
+
+    #[test]
+    fn formats_utc_timestamps() {
+        assert_eq!(timestamp(UNIX_EPOCH), "19700101-000000");
+        let leap_day = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+        assert_eq!(timestamp(leap_day), "20240229-123456");
+    }
+
+    #[test]
+    fn labels_are_readable() {
+        let backup = Backup {
+            path: PathBuf::new(),
+            stamp: "20240229-123456-2".into(),
+        };
+        assert_eq!(backup.label(), "2024-02-29 12:34:56 UTC-2");
+    }
+
+    #[test]
+    fn missing_file_is_not_backed_up() {
+        let path = scratch("missing");
+        assert_eq!(create(&path, 5).unwrap(), None);
+        assert!(list(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn keeps_newest_backups_only() {
+        let path = scratch("prune");
+        for i in 0..4 {
+            fs::write(&path, format!("v{}\n", i)).unwrap();
+            create(&path, 2).unwrap().unwrap();
+        }
+        let backups = list(&path).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(fs::read_to_string(&backups[0].path).unwrap(), "v3\n");
+        assert_eq!(fs::read_to_string(&backups[1].path).unwrap(), "v2\n");
+    }
+
+    #[test]
+    fn unrelated_files_are_ignored() {
+        let path = scratch("unrelated");
+        fs::write(&path, "x\n").unwrap();
+        create(&path, 5).unwrap();
+        fs::write(backup_dir(&path).join("bitcoin.conf.notes"), "").unwrap();
+        fs::write(backup_dir(&path).join("other.conf.20240101-000000"), "").unwrap();
+        assert_eq!(list(&path).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn restore_replaces_and_backs_up_current() {
+        let path = scratch("restore");
+        fs::write(&path, "old\n").unwrap();
+        let backup = create(&path, 5).unwrap().unwrap();
+        fs::write(&path, "new\n").unwrap();
+
+        restore(&path, &backup, 5).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old\n");
+        let backups = list(&path).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(fs::read_to_string(&backups[0].path).unwrap(), "new\n");
+    }
+
+    #[test]
+    fn restore_with_backups_disabled_keeps_history() {
+        let path = scratch("restore_disabled");
+        fs::write(&path, "one\n").unwrap();
+        let first = create(&path, 5).unwrap().unwrap();
+        fs::write(&path, "two\n").unwrap();
+        create(&path, 5).unwrap();
+        fs::write(&path, "three\n").unwrap();
+
+        restore(&path, &first, 0).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "one\n");
+        assert_eq!(list(&path).unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,357 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Finds bitcoin.conf files in the places nodes usually keep them: the
+//! command line of a running bitcoind, `$BITCOIN_DATADIR`, `~/.bitcoin` and
+//! the layouts used by node distributions.
+
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Config locations used by node distributions, relative to `/`.
+const PLATFORM_PATHS: &[(&str, &str)] = &[
+    (
+        "Umbrel",
+        "home/umbrel/umbrel/app-data/bitcoin/data/bitcoin/bitcoin.conf",
+    ),
+    ("Umbrel", "home/umbrel/umbrel/bitcoin/bitcoin.conf"),
+    ("RaspiBlitz", "mnt/hdd/bitcoin/bitcoin.conf"),
+    ("RaspiBlitz", "home/bitcoin/.bitcoin/bitcoin.conf"),
+    (
+        "Start9",
+        "embassy-data/package-data/volumes/bitcoind/data/main/bitcoin.conf",
+    ),
+    ("myNode", "mnt/hdd/mynode/bitcoin/bitcoin.conf"),
+];
+
+/// A bitcoin.conf that exists, and why it was suggested.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub path: PathBuf,
+    pub source: String,
+}
+
+/// Where to look for configs. Built from the environment with
+/// [`Discovery::from_env`]; tests point `root` at a scratch directory. The
+/// default looks nowhere.
+#[derive(Debug, Clone, Default)]
+pub struct Discovery {
+    /// Prefix for absolute paths such as `/proc` and the platform layouts.
+    pub root: PathBuf,
+    /// Our own home, for `~/.bitcoin`.
+    pub home: Option<PathBuf>,
+    /// Value of `$BITCOIN_DATADIR`.
+    pub datadir: Option<PathBuf>,
+}
+
+impl Discovery {
+    pub fn from_env() -> Self {
+        Self {
+            root: PathBuf::from("/"),
+            home: env::var_os("HOME").map(PathBuf::from),
+            datadir: env::var_os("BITCOIN_DATADIR")
+                .filter(|d| !d.is_empty())
+                .map(PathBuf::from),
+        }
+    }
+
+    /// Every existing config found, most reliable first, without duplicates.
+    pub fn candidates(&self) -> Vec<Candidate> {
+        if self.root.as_os_str().is_empty() {
+            return Vec::new();
+        }
+        let mut found: Vec<(PathBuf, String)> = self.running_bitcoind();
+        if let Some(datadir) = &self.datadir {
+            found.push((datadir.join("bitcoin.conf"), "$BITCOIN_DATADIR".into()));
+        }
+        if let Some(home) = &self.home {
+            found.push((
+                home.join(".bitcoin").join("bitcoin.conf"),
+                "default location".into(),
+            ));
+        }
+        for (platform, path) in PLATFORM_PATHS {
+            found.push((self.root.join(path), platform.to_string()));
+        }
+
+        let mut out: Vec<Candidate> = Vec::new();
+        let mut seen = Vec::new();
+        for (path, source) in found {
+            if !path.is_file() {
+                continue;
+            }
+            let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+            if seen.contains(&canonical) {
+                continue;
+            }
+            seen.push(canonical);
+            out.push(Candidate { path, source });
+        }
+        out
+    }
+
+    /// Configs named on the command line of running bitcoind processes.
+    fn running_bitcoind(&self) -> Vec<(PathBuf, String)> {
+        let proc = self.root.join("proc");
+        let Ok(entries) = fs::read_dir(&proc) else {
+            return Vec::new();
+        };
+
+        let mut pids: Vec<u32> = entries
+            .flatten()
+            .filter_map(|e| e.file_name().to_str()?.parse().ok())
+            .collect();
+        pids.sort_unstable();
+
+        let mut out = Vec::new();
+        for pid in pids {
+            let dir = proc.join(pid.to_string());
+            let Ok(cmdline) = fs::read(dir.join("cmdline")) else {
+                continue;
+            };
+            let cwd = fs::read_link(dir.join("cwd")).ok();
+            let home = self.owner_home(&dir);
+            if let Some(path) = conf_from_cmdline(&cmdline, cwd.as_deref(), home.as_deref()) {
+                out.push((path, format!("running bitcoind (pid {})", pid)));
+            }
+        }
+        out
+    }
+
+    /// The home directory of the user running the process whose `/proc`
+    /// entry is `dir`, which is often not ours: nodes usually run as a
+    /// service user. Taken from the passwd entry of its real uid, or else
+    /// from its `$HOME`.
+    fn owner_home(&self, dir: &Path) -> Option<PathBuf> {
+        let status = fs::read_to_string(dir.join("status")).ok();
+        let uid = status.as_deref().and_then(|status| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix("Uid:"))?
+                .split_whitespace()
+                .next()
+        });
+        let passwd = fs::read_to_string(self.root.join("etc/passwd")).unwrap_or_default();
+        let from_passwd = uid.and_then(|uid| {
+            passwd.lines().find_map(|entry| {
+                let fields: Vec<&str> = entry.split(':').collect();
+                (fields.len() >= 6 && fields[2] == uid).then(|| fields[5].to_string())
+            })
+        });
+        let home = from_passwd.or_else(|| {
+            let environ = fs::read(dir.join("environ")).ok()?;
+            environ
+                .split(|b| *b == 0)
+                .find_map(|var| var.strip_prefix(b"HOME="))
+                .map(|home| String::from_utf8_lossy(home).into_owned())
+        })?;
+        let home = Path::new(&home);
+        home.is_absolute()
+            .then(|| self.root.join(home.strip_prefix("/").unwrap_or(home)))
+    }
+}
+
+/// The config a bitcoind started with the NUL separated `cmdline` reads.
+///
+/// `home` is that of the user running it. Returns `None` if the process is
+/// not bitcoind, or if its datadir depends on a home that is not known.
+/// Relative paths are
+/// resolved the way bitcoind does: `-datadir` against the working
+/// directory and `-conf` against the data directory.
+fn conf_from_cmdline(cmdline: &[u8], cwd: Option<&Path>, home: Option<&Path>) -> Option<PathBuf> {
+    let mut args = cmdline
+        .split(|b| *b == 0)
+        .filter(|a| !a.is_empty())
+        .map(String::from_utf8_lossy);
+
+    let program = args.next()?;
+    if Path::new(program.as_ref()).file_name() != Some(OsStr::new("bitcoind")) {
+        return None;
+    }
+
+    let mut datadir = None;
+    let mut conf = None;
+    for arg in args {
+        let arg = arg.trim_start_matches('-');
+        if let Some(value) = arg.strip_prefix("datadir=") {
+            datadir = Some(PathBuf::from(value));
+        } else if let Some(value) = arg.strip_prefix("conf=") {
+            conf = Some(PathBuf::from(value));
+        }
+    }
+
+    let datadir = match datadir {
+        Some(dir) if dir.is_relative() => cwd?.join(dir),
+        Some(dir) => dir,
+        None => home?.join(".bitcoin"),
+    };
+    Some(match conf {
+        Some(conf) if conf.is_relative() => datadir.join(conf),
+        Some(conf) => conf,
+        None => datadir.join("bitcoin.conf"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("pdm_discover_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn touch(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "").unwrap();
+    }
+
+    #[test]
+    fn parses_bitcoind_command_lines() {
+        let home = Path::new("/home/satoshi");
+        let parse = |cmdline: &str| conf_from_cmdline(cmdline.as_bytes(), None, Some(home));
+
+        assert_eq!(
+            parse("/usr/bin/bitcoind\0-datadir=/data\0"),
+            Some(PathBuf::from("/data/bitcoin.conf"))
+        );
+        assert_eq!(
+            parse("bitcoind\0--datadir=/data\0-conf=node.conf\0"),
+            Some(PathBuf::from("/data/node.conf"))
+        );
+        assert_eq!(
+            parse("bitcoind\0-conf=/etc/bitcoin/bitcoin.conf\0"),
+            Some(PathBuf::from("/etc/bitcoin/bitcoin.conf"))
+        );
+        assert_eq!(
+            parse("bitcoind\0-daemon\0"),
+            Some(PathBuf::from("/home/satoshi/.bitcoin/bitcoin.conf"))
+        );
+        assert_eq!(parse("/usr/bin/bitcoin-qt\0-datadir=/data\0"), None);
+        assert_eq!(parse(""), None);
+    }
+
+    #[test]
+    fn relative_datadir_uses_process_cwd() {
+        let path = conf_from_cmdline(b"bitcoind\0-datadir=node\0", Some(Path::new("/srv")), None);
+        assert_eq!(path, Some(PathBuf::from("/srv/node/bitcoin.conf")));
+        assert_eq!(
+            conf_from_cmdline(b"bitcoind\0-datadir=node\0", None, None),
+            None
+        );
+    }
+
+    #[test]
+    fn finds_existing_configs_in_order() {
+        let root = scratch("order");
+        let home = root.join("home/alice");
+        let datadir = root.join("data");
+
+        fs::create_dir_all(root.join("proc/42")).unwrap();
+        fs::write(
+            root.join("proc/42/cmdline"),
+            format!("bitcoind\0-datadir={}\0", datadir.display()),
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("proc/self")).unwrap();
+        touch(&datadir.join("bitcoin.conf"));
+        touch(&home.join(".bitcoin/bitcoin.conf"));
+        touch(&root.join("mnt/hdd/bitcoin/bitcoin.conf"));
+
+        let discovery = Discovery {
+            root: root.clone(),
+            home: Some(home.clone()),
+            // Same file as the running bitcoind; listed once.
+            datadir: Some(datadir.clone()),
+        };
+        let found = discovery.candidates();
+        let sources: Vec<&str> = found.iter().map(|c| c.source.as_str()).collect();
+        assert_eq!(
+            sources,
+            vec![
+                "running bitcoind (pid 42)",
+                "default location",
+                "RaspiBlitz"
+            ]
+        );
+        assert_eq!(found[0].path, datadir.join("bitcoin.conf"));
+    }
+
+    #[test]
+    fn running_bitcoind_uses_its_owners_home() {
+        let root = scratch("owner");
+        let ours = root.join("home/alice");
+        touch(&ours.join(".bitcoin/bitcoin.conf"));
+        touch(&root.join("home/bitcoin/.bitcoin/bitcoin.conf"));
+        touch(&root.join("srv/node/.bitcoin/bitcoin.conf"));
+        fs::create_dir_all(root.join("etc")).unwrap();
+        fs::write(
+            root.join("etc/passwd"),
+            "root:x:0:0:root:/root:/bin/sh\nbitcoin:x:1001:1001::/home/bitcoin:/usr/sbin/nologin\n",
+        )
+        .unwrap();
+        let process = |pid: &str, uid: &str, environ: Option<&str>| {
+            let dir = root.join("proc").join(pid);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("cmdline"), "bitcoind\0-daemon\0").unwrap();
+            fs::write(
+                dir.join("status"),
+                format!("Name:\tbitcoind\nUid:\t{0}\t{0}\t{0}\t{0}\n", uid),
+            )
+            .unwrap();
+            if let Some(environ) = environ {
+                fs::write(dir.join("environ"), environ).unwrap();
+            }
+        };
+        process("10", "1001", None);
+        process("11", "1002", Some("LANG=C\0HOME=/srv/node\0"));
+        // Neither in passwd nor with a readable environment
+        process("12", "1003", None);
+
+        let discovery = Discovery {
+            root: root.clone(),
+            home: None,
+            datadir: None,
+        };
+        assert_eq!(
+            discovery.running_bitcoind(),
+            vec![
+                (
+                    root.join("home/bitcoin/.bitcoin/bitcoin.conf"),
+                    "running bitcoind (pid 10)".to_string()
+                ),
+                (
+                    root.join("srv/node/.bitcoin/bitcoin.conf"),
+                    "running bitcoind (pid 11)".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn default_looks_nowhere() {
+        let root = scratch("default");
+        touch(&root.join("home/.bitcoin/bitcoin.conf"));
+        let discovery = Discovery {
+            home: Some(root.join("home")),
+            ..Discovery::default()
+        };
+        assert!(discovery.candidates().is_empty());
+    }
+
+    #[test]
+    fn nothing_found_is_empty() {
+        let root = scratch("empty");
+        let discovery = Discovery {
+            root: root.clone(),
+            home: Some(root.join("home")),
+            datadir: None,
+        };
+        assert!(discovery.candidates().is_empty());
+    }
+}
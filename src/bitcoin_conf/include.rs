@@ -0,0 +1,390 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! `includeconf=` resolution and the effective configuration across all
+//! files.
+//!
+//! Bitcoin Core reads included files after the file that includes them.
+//! Since the first value of an option wins, an included file can add
+//! options but never override one already set in the main config. Relative
+//! include paths are taken relative to the data directory, and
+//! `includeconf=` inside an included file is ignored.
+
+use super::{BitcoinConf, ConfOption, Line, LineKind, Network, options};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A file pulled in through `includeconf=`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfFile {
+    pub path: PathBuf,
+    pub conf: BitcoinConf,
+}
+
+/// A line in one of the config files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Origin {
+    pub path: PathBuf,
+    /// Zero-based line index.
+    pub line: usize,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.path.file_name().unwrap_or(self.path.as_os_str());
+        write!(f, "{}:{}", name.to_string_lossy(), self.line + 1)
+    }
+}
+
+/// An `includeconf=` line that could not be followed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub origin: Origin,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.origin, self.message)
+    }
+}
+
+/// Every file included from a config, in the order they are read.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Includes {
+    pub files: Vec<ConfFile>,
+    pub problems: Vec<Problem>,
+}
+
+/// A value as written in one of the files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub value: String,
+    pub section: Option<String>,
+    pub origin: Origin,
+}
+
+/// The value an option ends up with and where it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting {
+    pub key: String,
+    pub entry: Entry,
+    /// Later values of a single-valued option that bitcoind ignores.
+    pub ignored: Vec<Entry>,
+}
+
+/// Reads the files included from `conf`, which was loaded from `path`.
+///
+/// Unreadable files, files included twice and `includeconf=` lines inside
+/// included files, which bitcoind ignores, are reported in
+/// [`Includes::problems`] and skipped.
+pub fn resolve(path: &Path, conf: &BitcoinConf) -> Includes {
+    let base = conf.datadir(path);
+    let chain = conf.chain();
+    let mut seen = vec![canonical(path)];
+    let mut out = Includes::default();
+
+    for (line, value) in includes(conf, chain) {
+        let origin = Origin {
+            path: path.to_path_buf(),
+            line,
+        };
+        let target = base.join(&value);
+        let key = canonical(&target);
+        if seen.contains(&key) {
+            out.problem(origin, format!("{} is already included", value));
+            continue;
+        }
+        match BitcoinConf::load(&target) {
+            Ok(included) => {
+                seen.push(key);
+                for (line, nested) in includes(&included, chain) {
+                    let origin = Origin {
+                        path: target.clone(),
+                        line,
+                    };
+                    out.problem(
+                        origin,
+                        format!(
+                            "includeconf={} is ignored, bitcoind only follows includes in the main config",
+                            nested
+                        ),
+                    );
+                }
+                out.files.push(ConfFile {
+                    path: target,
+                    conf: included,
+                });
+            }
+            Err(err) => out.problem(origin, format!("cannot read {}: {}", value, err)),
+        }
+    }
+    out
+}
+
+/// The `includeconf=` lines of `conf` that apply to `chain`.
+fn includes(conf: &BitcoinConf, chain: Network) -> Vec<(usize, String)> {
+    conf.options()
+        .filter(|o| o.option.key == "includeconf")
+        .filter(|o| o.section.is_none() || o.section == Some(chain.name()))
+        .map(|o| (o.line, o.option.value.clone()))
+        .collect()
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+impl Includes {
+    fn problem(&mut self, origin: Origin, message: String) {
+        self.problems.push(Problem { origin, message });
+    }
+
+    /// Every option of `main` and the included files, in reading order.
+    fn entries(&self, main_path: &Path, main: &BitcoinConf) -> Vec<(String, Entry)> {
+        let files = std::iter::once((main_path, main))
+            .chain(self.files.iter().map(|f| (f.path.as_path(), &f.conf)));
+        let mut out = Vec::new();
+        for (path, conf) in files {
+            for o in conf.options() {
+                out.push((
+                    o.option.key.clone(),
+                    Entry {
+                        value: o.option.value.clone(),
+                        section: o.section.map(str::to_string),
+                        origin: Origin {
+                            path: path.to_path_buf(),
+                            line: o.line,
+                        },
+                    },
+                ));
+            }
+        }
+        out
+    }
+
+    /// `main` and the included files flattened into a single config, so the
+    /// usual lookups ([`BitcoinConf::effective`] and friends) see every file.
+    ///
+    /// Options from sections are written with a network prefix.
+    pub fn merged(&self, main_path: &Path, main: &BitcoinConf) -> BitcoinConf {
+        let lines = self
+            .entries(main_path, main)
+            .into_iter()
+            .map(|(key, entry)| {
+                Line::new(LineKind::Option(ConfOption {
+                    prefix: entry.section,
+                    key,
+                    value: entry.value,
+                    comment: None,
+                }))
+            })
+            .collect();
+        BitcoinConf { lines }
+    }
+
+    /// The options that apply to the configured chain, in the order they
+    /// first appear, with where each value came from.
+    ///
+    /// Repeatable options such as `addnode` produce one setting per value.
+    pub fn settings(&self, main_path: &Path, main: &BitcoinConf) -> Vec<Setting> {
+        let chain = self.merged(main_path, main).chain();
+        let entries = self.entries(main_path, main);
+
+        let mut keys: Vec<&str> = Vec::new();
+        for (key, _) in &entries {
+            if !keys.contains(&key.as_str()) {
+                keys.push(key);
+            }
+        }
+
+        let mut out = Vec::new();
+        for key in keys {
+            let top_level = chain == Network::Main || !options::is_network_only(key);
+            // Values from the chain's section take precedence over the top level.
+            let mut applicable: Vec<&Entry> = entries
+                .iter()
+                .filter(|(k, e)| k == key && e.section.as_deref() == Some(chain.name()))
+                .map(|(_, e)| e)
+                .collect();
+            applicable.extend(
+                entries
+                    .iter()
+                    .filter(|(k, e)| k == key && e.section.is_none() && top_level)
+                    .map(|(_, e)| e),
+            );
+
+            if options::is_multi(key) {
+                out.extend(applicable.into_iter().map(|entry| Setting {
+                    key: key.to_string(),
+                    entry: entry.clone(),
+                    ignored: Vec::new(),
+                }));
+            } else if let Some((winner, ignored)) = applicable.split_first() {
+                out.push(Setting {
+                    key: key.to_string(),
+                    entry: (*winner).clone(),
+                    ignored: ignored.iter().map(|e| (*e).clone()).collect(),
+                });
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("pdm_include_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn file_name(path: &Path) -> String {
+        path.file_name()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn load(dir: &Path) -> (PathBuf, BitcoinConf, Includes) {
+        let path = dir.join("bitcoin.conf");
+        let conf = BitcoinConf::load(&path).unwrap();
+        let includes = resolve(&path, &conf);
+        (path, conf, includes)
+    }
+
+    #[test]
+    fn nested_includes_are_ignored() {
+        let dir = scratch("nested");
+        fs::write(
+            dir.join("bitcoin.conf"),
+            "includeconf=rpc.conf\nincludeconf=zmq.conf\n",
+        )
+        .unwrap();
+        fs::write(dir.join("rpc.conf"), "includeconf=auth.conf\nserver=1\n").unwrap();
+        fs::write(dir.join("auth.conf"), "rpcauth=u:s$h\n").unwrap();
+        fs::write(
+            dir.join("zmq.conf"),
+            "zmqpubhashblock=tcp://127.0.0.1:28332\n",
+        )
+        .unwrap();
+
+        let (path, conf, includes) = load(&dir);
+        let names: Vec<String> = includes.files.iter().map(|f| file_name(&f.path)).collect();
+        assert_eq!(names, vec!["rpc.conf", "zmq.conf"]);
+        assert_eq!(includes.problems.len(), 1);
+        assert!(
+            includes.problems[0]
+                .to_string()
+                .starts_with("rpc.conf:1: includeconf=auth.conf is ignored")
+        );
+
+        let merged = includes.merged(&path, &conf);
+        assert_eq!(merged.effective("server"), Some("1"));
+        assert_eq!(merged.effective("rpcauth"), None);
+    }
+
+    #[test]
+    fn reports_repeated_and_missing_files() {
+        let dir = scratch("missing");
+        fs::write(
+            dir.join("bitcoin.conf"),
+            "includeconf=a.conf\nincludeconf=missing.conf\nincludeconf=a.conf\n",
+        )
+        .unwrap();
+        fs::write(dir.join("a.conf"), "").unwrap();
+
+        let (_, _, includes) = load(&dir);
+        assert_eq!(includes.files.len(), 1);
+        assert_eq!(includes.problems.len(), 2);
+        assert!(
+            includes.problems[0]
+                .to_string()
+                .starts_with("bitcoin.conf:2: cannot read missing.conf")
+        );
+        assert_eq!(
+            includes.problems[1].to_string(),
+            "bitcoin.conf:3: a.conf is already included"
+        );
+    }
+
+    #[test]
+    fn relative_paths_follow_the_datadir() {
+        let dir = scratch("datadir");
+        let data = dir.join("data");
+        fs::create_dir_all(&data).unwrap();
+        fs::write(
+            dir.join("bitcoin.conf"),
+            format!("datadir={}\nincludeconf=extra.conf\n", data.display()),
+        )
+        .unwrap();
+        fs::write(dir.join("extra.conf"), "server=0\n").unwrap();
+        fs::write(data.join("extra.conf"), "server=1\n").unwrap();
+
+        let (_, _, includes) = load(&dir);
+        assert_eq!(includes.files.len(), 1);
+        assert_eq!(includes.files[0].path, data.join("extra.conf"));
+    }
+
+    #[test]
+    fn includes_for_other_networks_are_skipped() {
+        let dir = scratch("network");
+        fs::write(
+            dir.join("bitcoin.conf"),
+            "chain=signet\n[main]\nincludeconf=main.conf\n[signet]\nincludeconf=signet.conf\n",
+        )
+        .unwrap();
+        fs::write(dir.join("main.conf"), "").unwrap();
+        fs::write(dir.join("signet.conf"), "").unwrap();
+
+        let (_, _, includes) = load(&dir);
+        assert_eq!(includes.files.len(), 1);
+        assert_eq!(file_name(&includes.files[0].path), "signet.conf");
+    }
+
+    #[test]
+    fn settings_track_origins_and_overrides() {
+        let dir = scratch("settings");
+        fs::write(
+            dir.join("bitcoin.conf"),
+            "prune=550\naddnode=a\nincludeconf=extra.conf\n[main]\nrpcport=8332\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("extra.conf"),
+            "prune=10000\naddnode=b\nmain.rpcport=9332\n",
+        )
+        .unwrap();
+
+        let (path, conf, includes) = load(&dir);
+        let settings = includes.settings(&path, &conf);
+        let find = |key: &str| settings.iter().filter(|s| s.key == key).collect::<Vec<_>>();
+
+        // The main config is read first, so its value wins.
+        let prune = find("prune");
+        assert_eq!(prune.len(), 1);
+        assert_eq!(prune[0].entry.value, "550");
+        assert_eq!(prune[0].entry.origin.to_string(), "bitcoin.conf:1");
+        assert_eq!(prune[0].ignored[0].value, "10000");
+        assert_eq!(prune[0].ignored[0].origin.to_string(), "extra.conf:1");
+
+        // Repeatable options keep every value.
+        let addnode: Vec<&str> = find("addnode")
+            .iter()
+            .map(|s| s.entry.value.as_str())
+            .collect();
+        assert_eq!(addnode, vec!["a", "b"]);
+
+        let rpcport = find("rpcport");
+        assert_eq!(rpcport[0].entry.value, "8332");
+        assert_eq!(rpcport[0].entry.section.as_deref(), Some("main"));
+        assert_eq!(rpcport[0].ignored[0].value, "9332");
+    }
+}
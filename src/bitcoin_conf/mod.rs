@@ -0,0 +1,650 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! A lossless model of a `bitcoin.conf` file.
+//!
+//! The file is kept as a list of [`Line`]s. Every line remembers the exact
+//! text it was parsed from, so a config that is loaded and saved without
+//! modification is written back byte-for-byte. Lines touched through the
+//! mutation API are re-rendered in canonical `key=value` form.
+
+pub mod discover;
+pub mod include;
+pub mod options;
+pub mod readiness;
+pub mod rpcauth;
+pub mod validate;
+
+use crate::atomic_file;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The networks Bitcoin Core accepts as `[section]` names and `chain=` values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Network {
+    Main,
+    Test,
+    Testnet4,
+    Signet,
+    Regtest,
+}
+
+impl Network {
+    /// All networks, in the order Bitcoin Core lists them.
+    pub const ALL: [Network; 5] = [
+        Network::Main,
+        Network::Test,
+        Network::Testnet4,
+        Network::Signet,
+        Network::Regtest,
+    ];
+
+    /// Parses a section name or `chain=` value.
+    pub fn from_name(name: &str) -> Option<Network> {
+        match name {
+            "main" => Some(Network::Main),
+            "test" => Some(Network::Test),
+            "testnet4" => Some(Network::Testnet4),
+            "signet" => Some(Network::Signet),
+            "regtest" => Some(Network::Regtest),
+            _ => None,
+        }
+    }
+
+    /// The name used for `[section]` headers and `chain=` values.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Network::Main => "main",
+            Network::Test => "test",
+            Network::Testnet4 => "testnet4",
+            Network::Signet => "signet",
+            Network::Regtest => "regtest",
+        }
+    }
+
+    /// The JSON-RPC port bitcoind listens on by default.
+    pub fn default_rpc_port(&self) -> u16 {
+        match self {
+            Network::Main => 8332,
+            Network::Test => 18332,
+            Network::Testnet4 => 48332,
+            Network::Signet => 38332,
+            Network::Regtest => 18443,
+        }
+    }
+
+    /// The subdirectory of the datadir bitcoind keeps this network's data
+    /// (and RPC cookie) in. Mainnet uses the datadir itself.
+    pub fn data_subdir(&self) -> Option<&'static str> {
+        match self {
+            Network::Main => None,
+            Network::Test => Some("testnet3"),
+            Network::Testnet4 => Some("testnet4"),
+            Network::Signet => Some("signet"),
+            Network::Regtest => Some("regtest"),
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A `key=value` setting, optionally scoped with a `network.` prefix
+/// (e.g. `regtest.rpcport=18443`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfOption {
+    /// Network prefix written in front of the key, if any.
+    pub prefix: Option<String>,
+    pub key: String,
+    pub value: String,
+    /// Trailing `#` comment, without the `#`.
+    pub comment: Option<String>,
+}
+
+/// What a single line of the file contains.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineKind {
+    Blank,
+    /// A full-line comment, without the leading `#`.
+    Comment(String),
+    /// A `[name]` section header.
+    Section(String),
+    Option(ConfOption),
+    /// A line Bitcoin Core would refuse to parse. Kept verbatim.
+    Invalid(String),
+}
+
+/// One line of a `bitcoin.conf`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub kind: LineKind,
+    /// The original text of the line, cleared once the line is modified.
+    raw: Option<String>,
+    /// The line terminator (`"\n"`, `"\r\n"` or `""` for a final unterminated line).
+    eol: String,
+}
+
+impl Line {
+    fn new(kind: LineKind) -> Self {
+        Self {
+            kind,
+            raw: None,
+            eol: "\n".to_string(),
+        }
+    }
+
+    fn parse(text: &str) -> LineKind {
+        let trimmed = text.trim();
+        if trimmed.is_empty() {
+            return LineKind::Blank;
+        }
+        if let Some(comment) = trimmed.strip_prefix('#') {
+            return LineKind::Comment(comment.to_string());
+        }
+
+        let (body, comment) = match trimmed.split_once('#') {
+            Some((body, comment)) => (body.trim(), Some(comment.to_string())),
+            None => (trimmed, None),
+        };
+
+        if let Some(name) = body.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            return LineKind::Section(name.trim().to_string());
+        }
+
+        match body.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                let key = key.trim();
+                let (prefix, key) = match key.split_once('.') {
+                    Some((prefix, key)) => (Some(prefix.to_string()), key),
+                    None => (None, key),
+                };
+                LineKind::Option(ConfOption {
+                    prefix,
+                    key: key.to_string(),
+                    value: value.trim().to_string(),
+                    comment,
+                })
+            }
+            _ => LineKind::Invalid(trimmed.to_string()),
+        }
+    }
+
+    fn render(&self) -> String {
+        if let Some(raw) = &self.raw {
+            return raw.clone();
+        }
+        match &self.kind {
+            LineKind::Blank => String::new(),
+            LineKind::Comment(c) => format!("#{}", c),
+            LineKind::Section(name) => format!("[{}]", name),
+            LineKind::Option(opt) => {
+                let mut s = match &opt.prefix {
+                    Some(prefix) => format!("{}.{}={}", prefix, opt.key, opt.value),
+                    None => format!("{}={}", opt.key, opt.value),
+                };
+                if let Some(c) = &opt.comment {
+                    s.push_str(" #");
+                    s.push_str(c);
+                }
+                s
+            }
+            LineKind::Invalid(text) => text.clone(),
+        }
+    }
+}
+
+/// A reference to an option in the file, together with where it lives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OptionRef<'a> {
+    /// Zero-based line index in the file.
+    pub line: usize,
+    /// The section the option applies to: its `network.` prefix if present,
+    /// otherwise the enclosing `[section]`, or `None` for the top level.
+    pub section: Option<&'a str>,
+    pub option: &'a ConfOption,
+}
+
+/// A parsed `bitcoin.conf`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BitcoinConf {
+    pub lines: Vec<Line>,
+}
+
+impl BitcoinConf {
+    /// Parses the contents of a `bitcoin.conf`.
+    ///
+    /// Parsing never fails: lines Bitcoin Core would reject are kept as
+    /// [`LineKind::Invalid`] so they can be reported and round-tripped.
+    pub fn parse(text: &str) -> Self {
+        let lines = text
+            .split_inclusive('\n')
+            .map(|chunk| {
+                let (body, eol) = if let Some(body) = chunk.strip_suffix("\r\n") {
+                    (body, "\r\n")
+                } else if let Some(body) = chunk.strip_suffix('\n') {
+                    (body, "\n")
+                } else {
+                    (chunk, "")
+                };
+                Line {
+                    kind: Line::parse(body),
+                    raw: Some(body.to_string()),
+                    eol: eol.to_string(),
+                }
+            })
+            .collect();
+        Self { lines }
+    }
+
+    /// Reads and parses the file at `path`.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// Writes the config to `path`, atomically and keeping the file's
+    /// owner and permissions (see [`atomic_file::write`]).
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        atomic_file::write(path, self.to_string())
+    }
+
+    /// Iterates over every option together with the section it applies to.
+    pub fn options(&self) -> impl Iterator<Item = OptionRef<'_>> {
+        let mut section: Option<&str> = None;
+        self.lines
+            .iter()
+            .enumerate()
+            .filter_map(move |(line, l)| match &l.kind {
+                LineKind::Section(name) => {
+                    section = Some(name.as_str());
+                    None
+                }
+                LineKind::Option(option) => Some(OptionRef {
+                    line,
+                    section: option.prefix.as_deref().or(section),
+                    option,
+                }),
+                _ => None,
+            })
+    }
+
+    /// Returns the value set for `key` in `section` (`None` is the top level).
+    ///
+    /// Like Bitcoin Core reading a config file, the first occurrence of a
+    /// single-valued option wins and later ones are ignored.
+    pub fn get(&self, section: Option<&str>, key: &str) -> Option<&str> {
+        self.options()
+            .find(|o| o.section == section && o.option.key == key)
+            .map(|o| o.option.value.as_str())
+    }
+
+    /// Returns every value of a repeatable option such as `addnode`.
+    pub fn get_all(&self, section: Option<&str>, key: &str) -> Vec<&str> {
+        self.options()
+            .filter(|o| o.section == section && o.option.key == key)
+            .map(|o| o.option.value.as_str())
+            .collect()
+    }
+
+    /// The network selected by `chain=` or the legacy `testnet`/`signet`/`regtest`
+    /// flags at the top level, defaulting to mainnet.
+    ///
+    /// Unlike every other option, the chain selection is taken from the last
+    /// occurrence, as Bitcoin Core does.
+    pub fn chain(&self) -> Network {
+        let last = |key| self.get_all(None, key).last().copied();
+        if let Some(network) = last("chain").and_then(Network::from_name) {
+            return network;
+        }
+        for (flag, network) in [
+            ("regtest", Network::Regtest),
+            ("signet", Network::Signet),
+            ("testnet4", Network::Testnet4),
+            ("testnet", Network::Test),
+        ] {
+            if last(flag) == Some("1") {
+                return network;
+            }
+        }
+        Network::Main
+    }
+
+    /// bitcoind's datadir.
+    ///
+    /// `conf_path` is the file this was loaded from; without a `datadir=`
+    /// setting the datadir is taken to be the directory holding it.
+    pub fn datadir(&self, conf_path: &Path) -> PathBuf {
+        match self.effective("datadir") {
+            Some(dir) => PathBuf::from(dir),
+            None => conf_path.parent().unwrap_or(Path::new("")).to_path_buf(),
+        }
+    }
+
+    /// The directory bitcoind keeps the configured chain's data in, such
+    /// as the cookie file and debug.log.
+    pub fn net_datadir(&self, conf_path: &Path) -> PathBuf {
+        let datadir = self.datadir(conf_path);
+        match self.chain().data_subdir() {
+            Some(sub) => datadir.join(sub),
+            None => datadir,
+        }
+    }
+
+    /// The section in which `key` takes effect for the configured chain.
+    ///
+    /// A value already present in the chain's `[section]` overrides the top
+    /// level, and network-only options (see [`options::is_network_only`]) are
+    /// ignored at the top level on anything but mainnet.
+    pub fn section_for(&self, key: &str) -> Option<&'static str> {
+        let chain = self.chain();
+        let in_section = self
+            .options()
+            .any(|o| o.section == Some(chain.name()) && o.option.key == key);
+        if in_section || (chain != Network::Main && options::is_network_only(key)) {
+            Some(chain.name())
+        } else {
+            None
+        }
+    }
+
+    /// The value of `key` that applies to the configured chain.
+    pub fn effective(&self, key: &str) -> Option<&str> {
+        self.effective_all(key).first().copied()
+    }
+
+    /// Every value of `key` that applies to the configured chain, in order of
+    /// precedence: the chain's section first, then the top level, each in
+    /// file order.
+    pub fn effective_all(&self, key: &str) -> Vec<&str> {
+        let chain = self.chain();
+        let mut values = self.get_all(Some(chain.name()), key);
+        if chain == Network::Main || !options::is_network_only(key) {
+            values.extend(self.get_all(None, key));
+        }
+        values
+    }
+
+    /// Sets `key` to `value` in `section`.
+    ///
+    /// The first existing occurrence, the one bitcoind reads, is updated in
+    /// place and later duplicates are removed. If the key is not present,
+    /// it is appended to the section, creating the section if needed.
+    pub fn set(&mut self, section: Option<&str>, key: &str, value: &str) {
+        let existing: Vec<usize> = self
+            .options()
+            .filter(|o| o.section == section && o.option.key == key)
+            .map(|o| o.line)
+            .collect();
+        let Some((&first, duplicates)) = existing.split_first() else {
+            self.add(section, key, value);
+            return;
+        };
+        for index in duplicates.iter().rev() {
+            self.lines.remove(*index);
+        }
+        let line = &mut self.lines[first];
+        if let LineKind::Option(opt) = &mut line.kind {
+            opt.value = value.to_string();
+        }
+        line.raw = None;
+    }
+
+    /// Appends a new `key=value` line to `section`, even if the key already exists.
+    pub fn add(&mut self, section: Option<&str>, key: &str, value: &str) {
+        let line = Line::new(LineKind::Option(ConfOption {
+            prefix: None,
+            key: key.to_string(),
+            value: value.to_string(),
+            comment: None,
+        }));
+        let index = self.section_end(section);
+        self.insert_line(index, line);
+    }
+
+    /// Removes every occurrence of `key` in `section`. Returns how many lines were removed.
+    pub fn remove(&mut self, section: Option<&str>, key: &str) -> usize {
+        let doomed: Vec<usize> = self
+            .options()
+            .filter(|o| o.section == section && o.option.key == key)
+            .map(|o| o.line)
+            .collect();
+        for index in doomed.iter().rev() {
+            self.lines.remove(*index);
+        }
+        doomed.len()
+    }
+
+    /// Index just past the last non-blank line of `section`, appending a new
+    /// `[section]` header at the end of the file if it does not exist yet.
+    fn section_end(&mut self, section: Option<&str>) -> usize {
+        let mut current: Option<&str> = None;
+        let mut found = section.is_none();
+        let mut end = 0;
+        for (i, line) in self.lines.iter().enumerate() {
+            match &line.kind {
+                LineKind::Section(name) => current = Some(name.as_str()),
+                LineKind::Blank => continue,
+                _ => {}
+            }
+            if current == section {
+                found = true;
+                end = i + 1;
+            }
+        }
+
+        if found {
+            return end;
+        }
+
+        let name = section.unwrap_or_default().to_string();
+        if self.lines.last().is_some_and(|l| l.kind != LineKind::Blank) {
+            let len = self.lines.len();
+            self.insert_line(len, Line::new(LineKind::Blank));
+        }
+        let len = self.lines.len();
+        self.insert_line(len, Line::new(LineKind::Section(name)));
+        self.lines.len()
+    }
+
+    /// Inserts `line` at `index`, making sure the preceding line is terminated.
+    fn insert_line(&mut self, index: usize, mut line: Line) {
+        if index > 0
+            && let Some(prev) = self.lines.get_mut(index - 1)
+            && prev.eol.is_empty()
+        {
+            prev.eol = "\n".to_string();
+            // A new last line inherits the missing terminator.
+            if index == self.lines.len() {
+                line.eol = String::new();
+            }
+        }
+        self.lines.insert(index, line);
+    }
+}
+
+impl fmt::Display for BitcoinConf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            f.write_str(&line.render())?;
+            f.write_str(&line.eol)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+# Bitcoin Core config
+server=1
+rpcuser=alice   # legacy auth
+addnode=10.0.0.1
+addnode=10.0.0.2
+
+[test]
+rpcport=18332
+regtest.rpcport=18443
+not a setting
+";
+
+    #[test]
+    fn unmodified_config_round_trips_byte_for_byte() {
+        for text in [
+            SAMPLE,
+            "",
+            "server=1",
+            "server = 1 \r\n\r\n  # indented\r\n[main]\r\n",
+            "\n\n\t\n",
+        ] {
+            assert_eq!(BitcoinConf::parse(text).to_string(), text);
+        }
+    }
+
+    #[test]
+    fn parses_line_kinds() {
+        let conf = BitcoinConf::parse(SAMPLE);
+        assert_eq!(
+            conf.lines[0].kind,
+            LineKind::Comment(" Bitcoin Core config".into())
+        );
+        assert_eq!(conf.lines[5].kind, LineKind::Blank);
+        assert_eq!(conf.lines[6].kind, LineKind::Section("test".into()));
+        assert_eq!(
+            conf.lines[9].kind,
+            LineKind::Invalid("not a setting".into())
+        );
+        assert_eq!(
+            conf.lines[2].kind,
+            LineKind::Option(ConfOption {
+                prefix: None,
+                key: "rpcuser".into(),
+                value: "alice".into(),
+                comment: Some(" legacy auth".into()),
+            })
+        );
+    }
+
+    #[test]
+    fn options_are_scoped_to_sections_and_prefixes() {
+        let conf = BitcoinConf::parse(SAMPLE);
+        assert_eq!(conf.get(None, "server"), Some("1"));
+        assert_eq!(conf.get(Some("test"), "rpcport"), Some("18332"));
+        assert_eq!(conf.get(Some("regtest"), "rpcport"), Some("18443"));
+        assert_eq!(conf.get(None, "rpcport"), None);
+        assert_eq!(conf.get_all(None, "addnode"), vec!["10.0.0.1", "10.0.0.2"]);
+    }
+
+    #[test]
+    fn chain_detection() {
+        assert_eq!(BitcoinConf::parse("").chain(), Network::Main);
+        assert_eq!(BitcoinConf::parse("chain=signet").chain(), Network::Signet);
+        assert_eq!(BitcoinConf::parse("testnet=1").chain(), Network::Test);
+        assert_eq!(BitcoinConf::parse("regtest=1").chain(), Network::Regtest);
+        // A network flag inside a section does not select the chain.
+        assert_eq!(
+            BitcoinConf::parse("[test]\nregtest=1").chain(),
+            Network::Main
+        );
+    }
+
+    #[test]
+    fn effective_values_follow_network_rules() {
+        let conf = BitcoinConf::parse(
+            "chain=signet\nrpcport=1000\nserver=1\ntxindex=0\n[signet]\ntxindex=1\n",
+        );
+        // Network-only options are ignored at the top level off mainnet.
+        assert_eq!(conf.effective("rpcport"), None);
+        assert_eq!(conf.effective("server"), Some("1"));
+        // The chain's section wins over the top level.
+        assert_eq!(conf.effective("txindex"), Some("1"));
+
+        assert_eq!(conf.section_for("rpcport"), Some("signet"));
+        assert_eq!(conf.section_for("txindex"), Some("signet"));
+        assert_eq!(conf.section_for("server"), None);
+
+        let main = BitcoinConf::parse("rpcport=8332\n");
+        assert_eq!(main.effective("rpcport"), Some("8332"));
+        assert_eq!(main.section_for("rpcport"), None);
+    }
+
+    #[test]
+    fn set_updates_in_place_and_preserves_other_lines() {
+        let mut conf = BitcoinConf::parse(SAMPLE);
+        conf.set(None, "rpcuser", "bob");
+        let out = conf.to_string();
+        assert!(out.contains("rpcuser=bob # legacy auth\n"));
+        assert!(out.starts_with("# Bitcoin Core config\nserver=1\n"));
+        assert!(out.ends_with("regtest.rpcport=18443\nnot a setting\n"));
+    }
+
+    #[test]
+    fn first_occurrence_wins() {
+        let mut conf =
+            BitcoinConf::parse("rpcport=8332\nserver=1\nrpcport=9000\ntestnet=1\ntestnet=0\n");
+        assert_eq!(conf.get(None, "rpcport"), Some("8332"));
+        assert_eq!(conf.effective("rpcport"), Some("8332"));
+        // Except for the chain selection
+        assert_eq!(conf.chain(), Network::Main);
+
+        conf.set(None, "rpcport", "8400");
+        assert_eq!(
+            conf.to_string(),
+            "rpcport=8400\nserver=1\ntestnet=1\ntestnet=0\n"
+        );
+    }
+
+    #[test]
+    fn set_appends_to_existing_section() {
+        let mut conf = BitcoinConf::parse(SAMPLE);
+        conf.set(None, "txindex", "1");
+        conf.set(Some("test"), "rpcbind", "127.0.0.1");
+        let out = conf.to_string();
+        assert!(out.contains("addnode=10.0.0.2\ntxindex=1\n\n[test]"));
+        assert!(out.ends_with("not a setting\nrpcbind=127.0.0.1\n"));
+    }
+
+    #[test]
+    fn set_creates_missing_section() {
+        let mut conf = BitcoinConf::parse("server=1");
+        conf.set(Some("signet"), "rpcport", "38332");
+        assert_eq!(conf.to_string(), "server=1\n\n[signet]\nrpcport=38332");
+        assert_eq!(conf.get(Some("signet"), "rpcport"), Some("38332"));
+    }
+
+    #[test]
+    fn top_level_insert_goes_before_first_section() {
+        let mut conf = BitcoinConf::parse("[main]\nrpcport=8332\n");
+        conf.set(None, "server", "1");
+        assert_eq!(conf.to_string(), "server=1\n[main]\nrpcport=8332\n");
+    }
+
+    #[test]
+    fn add_and_remove_repeated_keys() {
+        let mut conf = BitcoinConf::parse(SAMPLE);
+        conf.add(None, "addnode", "10.0.0.3");
+        assert_eq!(conf.get_all(None, "addnode").len(), 3);
+        assert_eq!(conf.remove(None, "addnode"), 3);
+        assert!(conf.get_all(None, "addnode").is_empty());
+        assert_eq!(conf.remove(None, "addnode"), 0);
+    }
+
+    #[test]
+    fn load_and_save_round_trip() {
+        let path =
+            std::env::temp_dir().join(format!("pdm_bitcoin_conf_test_{}.conf", std::process::id()));
+        fs::write(&path, SAMPLE).unwrap();
+
+        let conf = BitcoinConf::load(&path).unwrap();
+        conf.save(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), SAMPLE);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
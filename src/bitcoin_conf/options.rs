@@ -0,0 +1,554 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Catalogue of the Bitcoin Core options pdm knows how to edit.
+
+use std::fmt;
+
+/// Groups used to organise options on the Bitcoin Config screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Network,
+    Rpc,
+    Pruning,
+    Indexes,
+    Zmq,
+    Wallet,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Category::Network => "Network",
+            Category::Rpc => "RPC",
+            Category::Pruning => "Pruning",
+            Category::Indexes => "Indexes",
+            Category::Zmq => "ZMQ",
+            Category::Wallet => "Wallet",
+        })
+    }
+}
+
+/// The type of value an option takes, which decides how it is edited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    /// `0` or `1`.
+    Bool,
+    /// A whole number in `min..=max`.
+    Integer { min: i64, max: i64 },
+    /// A filesystem path.
+    Path,
+    /// One of a fixed set of values.
+    Enum(&'static [&'static str]),
+    /// Free-form text such as an address or endpoint.
+    Text,
+}
+
+/// Description of a single Bitcoin Core option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OptionSpec {
+    pub key: &'static str,
+    pub category: Category,
+    pub kind: ValueKind,
+    /// Whether the option may appear more than once (e.g. `addnode`).
+    pub multi: bool,
+    pub description: &'static str,
+}
+
+const fn spec(
+    key: &'static str,
+    category: Category,
+    kind: ValueKind,
+    description: &'static str,
+) -> OptionSpec {
+    OptionSpec {
+        key,
+        category,
+        kind,
+        multi: false,
+        description,
+    }
+}
+
+const fn multi(
+    key: &'static str,
+    category: Category,
+    kind: ValueKind,
+    description: &'static str,
+) -> OptionSpec {
+    OptionSpec {
+        key,
+        category,
+        kind,
+        multi: true,
+        description,
+    }
+}
+
+const PORT: ValueKind = ValueKind::Integer { min: 1, max: 65535 };
+const COUNT: ValueKind = ValueKind::Integer {
+    min: 0,
+    max: i64::MAX,
+};
+
+/// Editable options, ordered by category.
+pub const OPTIONS: &[OptionSpec] = &[
+    // Network
+    spec(
+        "chain",
+        Category::Network,
+        ValueKind::Enum(&["main", "test", "testnet4", "signet", "regtest"]),
+        "Which chain to use",
+    ),
+    spec(
+        "listen",
+        Category::Network,
+        ValueKind::Bool,
+        "Accept incoming connections from peers",
+    ),
+    spec(
+        "port",
+        Category::Network,
+        PORT,
+        "Listen for peer connections on this port",
+    ),
+    multi(
+        "bind",
+        Category::Network,
+        ValueKind::Text,
+        "Bind to the given address and listen on it",
+    ),
+    multi(
+        "externalip",
+        Category::Network,
+        ValueKind::Text,
+        "Public address to advertise to peers",
+    ),
+    spec(
+        "maxconnections",
+        Category::Network,
+        COUNT,
+        "Maintain at most this many peer connections",
+    ),
+    multi(
+        "onlynet",
+        Category::Network,
+        ValueKind::Enum(&["ipv4", "ipv6", "onion", "i2p", "cjdns"]),
+        "Only connect to peers on this network",
+    ),
+    spec(
+        "proxy",
+        Category::Network,
+        ValueKind::Text,
+        "Connect through a SOCKS5 proxy (ip:port)",
+    ),
+    multi(
+        "addnode",
+        Category::Network,
+        ValueKind::Text,
+        "Add a node to connect to and keep open",
+    ),
+    multi(
+        "connect",
+        Category::Network,
+        ValueKind::Text,
+        "Connect only to the specified nodes",
+    ),
+    spec(
+        "blocksonly",
+        Category::Network,
+        ValueKind::Bool,
+        "Reject transactions from peers (no mempool relay)",
+    ),
+    // RPC
+    spec(
+        "server",
+        Category::Rpc,
+        ValueKind::Bool,
+        "Accept JSON-RPC commands",
+    ),
+    multi(
+        "rpcbind",
+        Category::Rpc,
+        ValueKind::Text,
+        "Bind the RPC server to this address",
+    ),
+    spec(
+        "rpcport",
+        Category::Rpc,
+        PORT,
+        "Listen for JSON-RPC connections on this port",
+    ),
+    multi(
+        "rpcallowip",
+        Category::Rpc,
+        ValueKind::Text,
+        "Allow JSON-RPC connections from this source",
+    ),
+    spec(
+        "rpcuser",
+        Category::Rpc,
+        ValueKind::Text,
+        "Username for JSON-RPC connections (legacy)",
+    ),
+    spec(
+        "rpcpassword",
+        Category::Rpc,
+        ValueKind::Text,
+        "Password for JSON-RPC connections (legacy)",
+    ),
+    multi(
+        "rpcauth",
+        Category::Rpc,
+        ValueKind::Text,
+        "Hashed JSON-RPC credentials (user:salt$hash)",
+    ),
+    spec(
+        "rpccookiefile",
+        Category::Rpc,
+        ValueKind::Path,
+        "Location of the auth cookie",
+    ),
+    spec(
+        "rpcthreads",
+        Category::Rpc,
+        ValueKind::Integer {
+            min: 1,
+            max: i64::MAX,
+        },
+        "Number of threads to service RPC calls",
+    ),
+    // Pruning
+    spec(
+        "prune",
+        Category::Pruning,
+        COUNT,
+        "Prune old blocks to this many MiB (0 = off, 1 = manual, >=550)",
+    ),
+    // Indexes
+    spec(
+        "txindex",
+        Category::Indexes,
+        ValueKind::Bool,
+        "Maintain a full transaction index",
+    ),
+    spec(
+        "blockfilterindex",
+        Category::Indexes,
+        ValueKind::Enum(&["0", "1", "basic"]),
+        "Maintain compact block filters",
+    ),
+    spec(
+        "coinstatsindex",
+        Category::Indexes,
+        ValueKind::Bool,
+        "Maintain the coinstats index used by gettxoutsetinfo",
+    ),
+    // ZMQ
+    spec(
+        "zmqpubhashblock",
+        Category::Zmq,
+        ValueKind::Text,
+        "Publish block hashes (e.g. tcp://127.0.0.1:28332)",
+    ),
+    spec(
+        "zmqpubrawblock",
+        Category::Zmq,
+        ValueKind::Text,
+        "Publish raw blocks",
+    ),
+    spec(
+        "zmqpubhashtx",
+        Category::Zmq,
+        ValueKind::Text,
+        "Publish transaction hashes",
+    ),
+    spec(
+        "zmqpubrawtx",
+        Category::Zmq,
+        ValueKind::Text,
+        "Publish raw transactions",
+    ),
+    spec(
+        "zmqpubsequence",
+        Category::Zmq,
+        ValueKind::Text,
+        "Publish block and mempool sequence events",
+    ),
+    // Wallet
+    spec(
+        "disablewallet",
+        Category::Wallet,
+        ValueKind::Bool,
+        "Do not load the wallet and disable wallet RPC calls",
+    ),
+    multi(
+        "wallet",
+        Category::Wallet,
+        ValueKind::Text,
+        "Wallet to load on startup",
+    ),
+    spec(
+        "walletdir",
+        Category::Wallet,
+        ValueKind::Path,
+        "Directory that holds wallets",
+    ),
+    spec(
+        "fallbackfee",
+        Category::Wallet,
+        ValueKind::Text,
+        "Fee rate (BTC/kvB) used when estimation has no data",
+    ),
+];
+
+/// Every option Bitcoin Core accepts in `bitcoin.conf`, including the ones
+/// pdm does not offer for editing. Used to flag unknown keys and typos.
+pub const KNOWN_KEYS: &[&str] = &[
+    // General
+    "alertnotify",
+    "assumevalid",
+    "blockfilterindex",
+    "blocknotify",
+    "blockreconstructionextratxn",
+    "blocksdir",
+    "blocksonly",
+    "blocksxor",
+    "coinstatsindex",
+    "conf",
+    "daemon",
+    "daemonwait",
+    "datadir",
+    "dbcache",
+    "debuglogfile",
+    "includeconf",
+    "loadblock",
+    "maxmempool",
+    "maxorphantx",
+    "mempoolexpiry",
+    "par",
+    "persistmempool",
+    "persistmempoolv1",
+    "pid",
+    "prune",
+    "reindex",
+    "reindex-chainstate",
+    "settings",
+    "shutdownnotify",
+    "startupnotify",
+    "sysperms",
+    "txindex",
+    "version",
+    // Connection
+    "addnode",
+    "asmap",
+    "bantime",
+    "bind",
+    "cjdnsreachable",
+    "connect",
+    "discover",
+    "dns",
+    "dnsseed",
+    "externalip",
+    "fixedseeds",
+    "forcednsseed",
+    "i2pacceptincoming",
+    "i2psam",
+    "listen",
+    "listenonion",
+    "maxconnections",
+    "maxreceivebuffer",
+    "maxsendbuffer",
+    "maxuploadtarget",
+    "natpmp",
+    "networkactive",
+    "onion",
+    "onlynet",
+    "peerblockfilters",
+    "peerbloomfilters",
+    "port",
+    "proxy",
+    "proxyrandomize",
+    "seednode",
+    "timeout",
+    "torcontrol",
+    "torpassword",
+    "upnp",
+    "v2transport",
+    "whitebind",
+    "whitelist",
+    // Wallet
+    "addresstype",
+    "avoidpartialspends",
+    "changetype",
+    "consolidatefeerate",
+    "disablewallet",
+    "discardfee",
+    "fallbackfee",
+    "keypool",
+    "maxapsfee",
+    "maxtxfee",
+    "mintxfee",
+    "paytxfee",
+    "signer",
+    "spendzeroconfchange",
+    "txconfirmtarget",
+    "wallet",
+    "walletbroadcast",
+    "walletdir",
+    "walletnotify",
+    "walletrbf",
+    // ZMQ
+    "zmqpubhashblock",
+    "zmqpubhashblockhwm",
+    "zmqpubhashtx",
+    "zmqpubhashtxhwm",
+    "zmqpubrawblock",
+    "zmqpubrawblockhwm",
+    "zmqpubrawtx",
+    "zmqpubrawtxhwm",
+    "zmqpubsequence",
+    "zmqpubsequencehwm",
+    // Debugging/Testing
+    "debug",
+    "debugexclude",
+    "help-debug",
+    "logips",
+    "logsourcelocations",
+    "logthreadnames",
+    "logtimestamps",
+    "maxtipage",
+    "printtoconsole",
+    "shrinkdebugfile",
+    "uacomment",
+    // Chain selection
+    "chain",
+    "signet",
+    "signetchallenge",
+    "signetseednode",
+    "testnet",
+    "testnet4",
+    "regtest",
+    "vbparams",
+    // Node relay
+    "bytespersigop",
+    "datacarrier",
+    "datacarriersize",
+    "mempoolfullrbf",
+    "minrelaytxfee",
+    "permitbaremultisig",
+    "whitelistforcerelay",
+    "whitelistrelay",
+    // Block creation
+    "blockmaxweight",
+    "blockmintxfee",
+    "blockversion",
+    // RPC server
+    "rest",
+    "rpcallowip",
+    "rpcauth",
+    "rpcbind",
+    "rpccookiefile",
+    "rpccookieperms",
+    "rpcpassword",
+    "rpcport",
+    "rpcserialversion",
+    "rpcservertimeout",
+    "rpcthreads",
+    "rpcuser",
+    "rpcwhitelist",
+    "rpcwhitelistdefault",
+    "rpcworkqueue",
+    "server",
+];
+
+/// Options that, when set at the top level, only apply to mainnet.
+///
+/// On other networks Bitcoin Core ignores them unless they appear in the
+/// network's `[section]`.
+pub const NETWORK_ONLY: &[&str] = &[
+    "addnode", "connect", "port", "bind", "rpcport", "rpcbind", "wallet",
+];
+
+/// Looks up the spec for `key`.
+pub fn lookup(key: &str) -> Option<&'static OptionSpec> {
+    OPTIONS.iter().find(|o| o.key == key)
+}
+
+/// Whether Bitcoin Core recognises `key`, including negated forms such as
+/// `nodebuglogfile`.
+pub fn is_known(key: &str) -> bool {
+    let known = |key: &str| KNOWN_KEYS.contains(&key) || lookup(key).is_some();
+    known(key) || key.strip_prefix("no").is_some_and(known)
+}
+
+/// Options Bitcoin Core reads as lists rather than single values, beyond
+/// the editable ones marked `multi` in [`OPTIONS`].
+const LIST_KEYS: &[&str] = &[
+    "debug",
+    "debugexclude",
+    "includeconf",
+    "loadblock",
+    "seednode",
+    "signetseednode",
+    "vbparams",
+    "whitebind",
+    "whitelist",
+    "rpcwhitelist",
+];
+
+/// Whether every occurrence of `key` applies, instead of the last one winning.
+pub fn is_multi(key: &str) -> bool {
+    LIST_KEYS.contains(&key) || lookup(key).is_some_and(|s| s.multi)
+}
+
+/// Whether `key` is ignored at the top level on networks other than mainnet.
+pub fn is_network_only(key: &str) -> bool {
+    NETWORK_ONLY.contains(&key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_are_grouped_by_category() {
+        let mut seen: Vec<Category> = Vec::new();
+        for option in OPTIONS {
+            if seen.last() != Some(&option.category) {
+                assert!(
+                    !seen.contains(&option.category),
+                    "{} is out of order",
+                    option.key
+                );
+                seen.push(option.category);
+            }
+        }
+        assert_eq!(seen.len(), 6);
+    }
+
+    #[test]
+    fn lookup_finds_known_keys() {
+        assert_eq!(lookup("prune").unwrap().category, Category::Pruning);
+        assert!(lookup("addnode").unwrap().multi);
+        assert!(is_multi("addnode") && is_multi("includeconf"));
+        assert!(!is_multi("prune"));
+        assert!(lookup("nosuchoption").is_none());
+        assert!(is_known("nodebuglogfile") && !is_known("nosuchoption"));
+    }
+
+    #[test]
+    fn editable_options_are_known() {
+        for option in OPTIONS {
+            assert!(KNOWN_KEYS.contains(&option.key), "{} missing", option.key);
+        }
+        assert!(!is_known("rpcpasword"));
+    }
+
+    #[test]
+    fn network_only_options() {
+        assert!(is_network_only("rpcport"));
+        assert!(!is_network_only("server"));
+    }
+}
@@ -0,0 +1,382 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Checks that a bitcoin.conf provides what a p2poolv2 node needs from bitcoind.
+
+use super::{BitcoinConf, Network};
+
+/// Default ZMQ endpoint suggested for `zmqpubhashblock`.
+pub const DEFAULT_ZMQ_HASHBLOCK: &str = "tcp://127.0.0.1:28332";
+/// Default ZMQ endpoint suggested for `zmqpubrawblock`.
+pub const DEFAULT_ZMQ_RAWBLOCK: &str = "tcp://127.0.0.1:28333";
+/// Smallest prune target, in MiB, that keeps enough recent blocks for
+/// p2poolv2 to follow reorgs and rebuild templates (a few weeks of mainnet
+/// blocks and undo data).
+pub const MIN_PRUNE_MIB: u64 = 10_000;
+/// Chain selection flags that predate `chain=`.
+const LEGACY_CHAIN_FLAGS: [&str; 4] = ["testnet", "testnet4", "signet", "regtest"];
+
+/// Outcome of a single readiness check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Missing,
+    Wrong,
+}
+
+/// A change to the config: set `key` to `value`, or remove it when `value`
+/// is `None`. Applied in the section where the key takes effect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub key: &'static str,
+    pub value: Option<String>,
+}
+
+/// What a [`Fix`] does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixAction {
+    /// Patch the config directly.
+    Edits(Vec<Edit>),
+    /// Ask for a user name and add generated `rpcauth=` credentials.
+    GenerateRpcAuth,
+}
+
+/// A one-keystroke fix for a failed check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    pub label: String,
+    pub action: FixAction,
+}
+
+impl Fix {
+    fn set(key: &'static str, value: impl Into<String>) -> Self {
+        let value = value.into();
+        Self {
+            label: format!("set {}={}", key, value),
+            action: FixAction::Edits(vec![Edit {
+                key,
+                value: Some(value),
+            }]),
+        }
+    }
+
+    /// Selects `network` with `chain=`, dropping the legacy `testnet=`,
+    /// `regtest=`, ... flags that bitcoind refuses to combine with it.
+    fn chain(network: Network) -> Self {
+        let mut edits: Vec<Edit> = LEGACY_CHAIN_FLAGS
+            .iter()
+            .map(|&key| Edit { key, value: None })
+            .collect();
+        edits.push(Edit {
+            key: "chain",
+            value: Some(network.name().to_string()),
+        });
+        Self {
+            label: format!("set chain={}", network.name()),
+            action: FixAction::Edits(edits),
+        }
+    }
+
+    fn remove(key: &'static str) -> Self {
+        Self {
+            label: format!("remove {}", key),
+            action: FixAction::Edits(vec![Edit { key, value: None }]),
+        }
+    }
+
+    fn generate_rpcauth() -> Self {
+        Self {
+            label: "generate rpcauth credentials".to_string(),
+            action: FixAction::GenerateRpcAuth,
+        }
+    }
+
+    /// Applies the edits to `conf`. Returns `false`, leaving `conf`
+    /// untouched, for fixes that need input from the user.
+    pub fn apply(&self, conf: &mut BitcoinConf) -> bool {
+        let FixAction::Edits(edits) = &self.action else {
+            return false;
+        };
+        for edit in edits {
+            let section = conf.section_for(edit.key);
+            match &edit.value {
+                Some(value) => conf.set(section, edit.key, value),
+                None => {
+                    conf.remove(section, edit.key);
+                }
+            }
+        }
+        true
+    }
+}
+
+/// The result of one readiness check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+    pub fix: Option<Fix>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: Status::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn failed(
+        name: &'static str,
+        status: Status,
+        detail: impl Into<String>,
+        fix: Option<Fix>,
+    ) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+            fix,
+        }
+    }
+}
+
+/// Runs every p2poolv2 readiness check against `conf`.
+///
+/// `expected` is the network p2poolv2 is configured for, when known.
+pub fn check(conf: &BitcoinConf, expected: Option<Network>) -> Vec<Check> {
+    vec![
+        check_network(conf, expected),
+        check_server(conf),
+        check_credentials(conf),
+        check_zmq(conf, "zmqpubhashblock", DEFAULT_ZMQ_HASHBLOCK),
+        check_zmq(conf, "zmqpubrawblock", DEFAULT_ZMQ_RAWBLOCK),
+        check_prune(conf),
+        check_mempool(conf),
+    ]
+}
+
+/// Whether every check passed.
+pub fn is_ready(checks: &[Check]) -> bool {
+    checks.iter().all(|c| c.status == Status::Ok)
+}
+
+fn check_network(conf: &BitcoinConf, expected: Option<Network>) -> Check {
+    let chain = conf.chain();
+    match expected {
+        Some(expected) if expected != chain => Check::failed(
+            "Network",
+            Status::Wrong,
+            format!(
+                "bitcoind runs on {} but p2poolv2 expects {}",
+                chain, expected
+            ),
+            Some(Fix::chain(expected)),
+        ),
+        _ => Check::ok("Network", format!("bitcoind runs on {}", chain)),
+    }
+}
+
+fn check_server(conf: &BitcoinConf) -> Check {
+    match conf.effective("server") {
+        Some("1") => Check::ok("RPC server", "server=1"),
+        Some(value) => Check::failed(
+            "RPC server",
+            Status::Wrong,
+            format!("server={} disables JSON-RPC", value),
+            Some(Fix::set("server", "1")),
+        ),
+        None => Check::failed(
+            "RPC server",
+            Status::Missing,
+            "server=1 is not set explicitly",
+            Some(Fix::set("server", "1")),
+        ),
+    }
+}
+
+fn check_credentials(conf: &BitcoinConf) -> Check {
+    let rpcauth = conf.effective_all("rpcauth");
+    let user = conf.effective("rpcuser");
+    let password = conf.effective("rpcpassword");
+    match (rpcauth.is_empty(), user, password) {
+        (false, _, _) => Check::ok(
+            "RPC credentials",
+            format!("{} rpcauth user(s)", rpcauth.len()),
+        ),
+        (true, Some(user), Some(_)) => Check::ok(
+            "RPC credentials",
+            format!("rpcuser={} (plain text password)", user),
+        ),
+        (true, Some(_), None) | (true, None, Some(_)) => Check::failed(
+            "RPC credentials",
+            Status::Wrong,
+            "rpcuser and rpcpassword must be set together",
+            Some(Fix::generate_rpcauth()),
+        ),
+        (true, None, None) => Check::failed(
+            "RPC credentials",
+            Status::Missing,
+            "no rpcauth or rpcuser/rpcpassword; p2poolv2 needs credentials",
+            Some(Fix::generate_rpcauth()),
+        ),
+    }
+}
+
+fn check_zmq(conf: &BitcoinConf, key: &'static str, default: &str) -> Check {
+    match conf.effective(key) {
+        Some(endpoint) if endpoint.starts_with("tcp://") || endpoint.starts_with("ipc://") => {
+            Check::ok(key, endpoint.to_string())
+        }
+        Some(endpoint) => Check::failed(
+            key,
+            Status::Wrong,
+            format!("'{}' is not a tcp:// or ipc:// endpoint", endpoint),
+            Some(Fix::set(key, default)),
+        ),
+        None => Check::failed(
+            key,
+            Status::Missing,
+            "p2poolv2 subscribes to this ZMQ topic",
+            Some(Fix::set(key, default)),
+        ),
+    }
+}
+
+fn check_prune(conf: &BitcoinConf) -> Check {
+    match conf.effective("prune").map(|v| v.parse::<u64>()) {
+        None | Some(Ok(0)) => Check::ok("Pruning", "full node, blocks are kept"),
+        Some(Ok(mib)) if mib >= MIN_PRUNE_MIB => {
+            Check::ok("Pruning", format!("prune={} keeps enough blocks", mib))
+        }
+        Some(_) => Check::failed(
+            "Pruning",
+            Status::Wrong,
+            format!(
+                "prune target below {} MiB keeps too few blocks",
+                MIN_PRUNE_MIB
+            ),
+            Some(Fix::set("prune", MIN_PRUNE_MIB.to_string())),
+        ),
+    }
+}
+
+fn check_mempool(conf: &BitcoinConf) -> Check {
+    match conf.effective("blocksonly") {
+        Some(value) if value != "0" => Check::failed(
+            "Mempool",
+            Status::Wrong,
+            "blocksonly=1 leaves block templates empty",
+            Some(Fix::remove("blocksonly")),
+        ),
+        _ => Check::ok("Mempool", "transactions are relayed"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const READY: &str = "\
+server=1
+rpcauth=p2pool:salt$hash
+zmqpubhashblock=tcp://127.0.0.1:28332
+zmqpubrawblock=tcp://127.0.0.1:28333
+";
+
+    fn find<'a>(checks: &'a [Check], name: &str) -> &'a Check {
+        checks.iter().find(|c| c.name == name).unwrap()
+    }
+
+    #[test]
+    fn complete_config_is_ready() {
+        let checks = check(&BitcoinConf::parse(READY), Some(Network::Main));
+        assert!(is_ready(&checks), "{:?}", checks);
+    }
+
+    #[test]
+    fn empty_config_lists_everything_missing() {
+        let checks = check(&BitcoinConf::default(), None);
+        let failed: Vec<&str> = checks
+            .iter()
+            .filter(|c| c.status != Status::Ok)
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(
+            failed,
+            vec![
+                "RPC server",
+                "RPC credentials",
+                "zmqpubhashblock",
+                "zmqpubrawblock"
+            ]
+        );
+    }
+
+    #[test]
+    fn fixes_make_the_config_ready() {
+        let mut conf = BitcoinConf::parse("rpcuser=u\nrpcpassword=p\nprune=550\nblocksonly=1\n");
+        for c in check(&conf, None) {
+            if let Some(fix) = c.fix {
+                fix.apply(&mut conf);
+            }
+        }
+        assert!(is_ready(&check(&conf, None)));
+        assert_eq!(conf.get(None, "prune"), Some("10000"));
+        assert_eq!(conf.get(None, "blocksonly"), None);
+    }
+
+    #[test]
+    fn network_mismatch_is_fixable() {
+        let mut conf = BitcoinConf::parse(READY);
+        let checks = check(&conf, Some(Network::Signet));
+        let network = find(&checks, "Network");
+        assert_eq!(network.status, Status::Wrong);
+
+        network.fix.as_ref().unwrap().apply(&mut conf);
+        assert_eq!(conf.chain(), Network::Signet);
+    }
+
+    #[test]
+    fn network_fix_drops_legacy_flags() {
+        let mut conf = BitcoinConf::parse("testnet=1\nserver=1\nregtest=0\n");
+        let checks = check(&conf, Some(Network::Signet));
+        let fix = find(&checks, "Network").fix.as_ref().unwrap();
+        assert_eq!(fix.label, "set chain=signet");
+
+        fix.apply(&mut conf);
+        assert_eq!(conf.to_string(), "server=1\nchain=signet\n");
+        assert_eq!(conf.chain(), Network::Signet);
+    }
+
+    #[test]
+    fn half_configured_credentials_are_wrong() {
+        let mut conf = BitcoinConf::parse("rpcuser=u\n");
+        let checks = check(&conf, None);
+        let credentials = find(&checks, "RPC credentials");
+        assert_eq!(credentials.status, Status::Wrong);
+
+        let fix = credentials.fix.as_ref().unwrap();
+        assert_eq!(fix.action, FixAction::GenerateRpcAuth);
+        assert!(!fix.apply(&mut conf));
+        assert_eq!(conf.to_string(), "rpcuser=u\n");
+    }
+
+    #[test]
+    fn bad_zmq_endpoint_is_wrong() {
+        let checks = check(
+            &BitcoinConf::parse("zmqpubhashblock=127.0.0.1:28332\n"),
+            None,
+        );
+        let zmq = find(&checks, "zmqpubhashblock");
+        assert_eq!(zmq.status, Status::Wrong);
+        assert_eq!(
+            zmq.fix.as_ref().unwrap().label,
+            "set zmqpubhashblock=tcp://127.0.0.1:28332"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! `rpcauth=` credentials, generated the same way as Bitcoin Core's
+//! `share/rpcauth/rpcauth.py`: a random 16 byte hex salt and the hex
+//! HMAC-SHA256 of the password keyed with that salt.
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::io;
+
+/// A freshly generated user with its plaintext password.
+///
+/// The password is only available here; bitcoind keeps just the HMAC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub user: String,
+    pub password: String,
+    pub salt: String,
+}
+
+impl Credentials {
+    /// The value for an `rpcauth=` line: `user:salt$hmac`.
+    pub fn rpcauth(&self) -> String {
+        format!(
+            "{}:{}${}",
+            self.user,
+            self.salt,
+            password_hmac(&self.salt, &self.password)
+        )
+    }
+}
+
+/// Generates a random password and salt for `user`.
+pub fn generate(user: &str) -> io::Result<Credentials> {
+    validate_user(user).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let mut salt = [0u8; 16];
+    let mut password = [0u8; 32];
+    getrandom::fill(&mut salt)?;
+    getrandom::fill(&mut password)?;
+
+    Ok(Credentials {
+        user: user.to_string(),
+        password: URL_SAFE_NO_PAD.encode(password),
+        salt: hex(&salt),
+    })
+}
+
+/// Checks that `user` can be used in an `rpcauth=` line.
+pub fn validate_user(user: &str) -> Result<(), String> {
+    if user.is_empty() {
+        Err("username must not be empty".to_string())
+    } else if user.contains(':') {
+        Err("username must not contain ':'".to_string())
+    } else if user.chars().any(|c| c.is_whitespace() || c == '#') {
+        Err("username must not contain spaces or '#'".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// Whether `user`/`password` match an `rpcauth=` value.
+pub fn verify(rpcauth: &str, user: &str, password: &str) -> bool {
+    let Some((auth_user, rest)) = rpcauth.split_once(':') else {
+        return false;
+    };
+    let Some((salt, hmac)) = rest.split_once('$') else {
+        return false;
+    };
+    auth_user == user && password_hmac(salt, password).eq_ignore_ascii_case(hmac)
+}
+
+/// The user name of an `rpcauth=` value.
+pub fn user_of(rpcauth: &str) -> Option<&str> {
+    rpcauth.split_once(':').map(|(user, _)| user)
+}
+
+fn password_hmac(salt: &str, password: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(salt.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(password.as_bytes());
+    hex(&mac.finalize().into_bytes())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_rpcauth_py() {
+        // Computed with Bitcoin Core's share/rpcauth/rpcauth.py algorithm.
+        let credentials = Credentials {
+            user: "alice".into(),
+            password: "ZYZmpjZ3zqjgZypQfXRw5-D_oYPEaTLdSrBSYZgLCls".into(),
+            salt: "cb77f0957de88ff388cf817ddbc72731".into(),
+        };
+        assert_eq!(
+            credentials.rpcauth(),
+            "alice:cb77f0957de88ff388cf817ddbc72731$623b6844d277d98fabab57901761db48eed4e5109e056b18c3cf93d1c590d9b0"
+        );
+    }
+
+    #[test]
+    fn generated_credentials_verify() {
+        let credentials = generate("p2pool").unwrap();
+        assert_eq!(credentials.salt.len(), 32);
+        assert_eq!(credentials.password.len(), 43);
+
+        let line = credentials.rpcauth();
+        assert!(verify(&line, "p2pool", &credentials.password));
+        assert!(!verify(&line, "p2pool", "wrong"));
+        assert!(!verify(&line, "other", &credentials.password));
+        assert_eq!(user_of(&line), Some("p2pool"));
+    }
+
+    #[test]
+    fn generated_credentials_are_random() {
+        let a = generate("u").unwrap();
+        let b = generate("u").unwrap();
+        assert_ne!(a.password, b.password);
+        assert_ne!(a.salt, b.salt);
+    }
+
+    #[test]
+    fn rejects_bad_usernames() {
+        assert!(generate("").is_err());
+        assert!(generate("a:b").is_err());
+        assert!(generate("a b").is_err());
+        assert!(!verify("garbage", "a", "b"));
+    }
+}
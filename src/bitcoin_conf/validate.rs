@@ -0,0 +1,395 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Semantic checks of a [`BitcoinConf`] against Bitcoin Core's option rules.
+
+use super::options::{self, KNOWN_KEYS, ValueKind};
+use super::{BitcoinConf, LineKind, Network};
+use std::collections::HashMap;
+use std::fmt;
+
+/// How serious a [`Diagnostic`] is. Errors block saving.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// A problem found on a specific line of the config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Zero-based line index.
+    pub line: usize,
+    pub severity: Severity,
+    /// The option the problem is about, if any.
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line + 1, self.message)
+    }
+}
+
+/// Number of hard errors among `diagnostics`.
+pub fn error_count(diagnostics: &[Diagnostic]) -> usize {
+    diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count()
+}
+
+/// Checks `conf` and returns every problem found, ordered by line.
+pub fn validate(conf: &BitcoinConf) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+    let chain = conf.chain();
+
+    let diag = |line: usize, severity: Severity, key: Option<&str>, message: String| Diagnostic {
+        line,
+        severity,
+        key: key.map(str::to_string),
+        message,
+    };
+
+    for (line, l) in conf.lines.iter().enumerate() {
+        match &l.kind {
+            LineKind::Invalid(text) => out.push(diag(
+                line,
+                Severity::Error,
+                None,
+                format!("cannot parse '{}', expected key=value", text),
+            )),
+            LineKind::Section(name) if Network::from_name(name).is_none() => out.push(diag(
+                line,
+                Severity::Warning,
+                None,
+                format!(
+                    "section [{}] is not a known network and will be ignored",
+                    name
+                ),
+            )),
+            _ => {}
+        }
+    }
+
+    // First line each (section, key) was set on, to report ignored duplicates.
+    let mut seen: HashMap<(Option<&str>, &str), usize> = HashMap::new();
+
+    for o in conf.options() {
+        let key = o.option.key.as_str();
+        let value = o.option.value.as_str();
+        let line = o.line;
+
+        if let Some(prefix) = &o.option.prefix
+            && Network::from_name(prefix).is_none()
+        {
+            out.push(diag(
+                line,
+                Severity::Warning,
+                Some(key),
+                format!("'{}.' is not a known network prefix", prefix),
+            ));
+        }
+
+        if !options::is_known(key) {
+            let message = match suggest(key) {
+                Some(s) => format!("unknown option '{}', did you mean '{}'?", key, s),
+                None => format!("unknown option '{}' will be ignored", key),
+            };
+            out.push(diag(line, Severity::Warning, Some(key), message));
+            continue;
+        }
+
+        if key == "rpcpassword" && o.option.comment.is_some() {
+            out.push(diag(
+                line,
+                Severity::Error,
+                Some(key),
+                "'#' in rpcpassword is treated as a comment; Bitcoin Core refuses this".into(),
+            ));
+        }
+
+        if let Some(message) = check_value(key, value) {
+            out.push(diag(line, Severity::Error, Some(key), message));
+        }
+        if let Some(message) = check_bool(key, value) {
+            out.push(diag(line, Severity::Warning, Some(key), message));
+        }
+
+        if o.section.is_none() && chain != Network::Main && options::is_network_only(key) {
+            out.push(diag(
+                line,
+                Severity::Warning,
+                Some(key),
+                format!(
+                    "{} at the top level only applies to mainnet; move it to [{}]",
+                    key, chain
+                ),
+            ));
+        }
+
+        if o.section.is_some()
+            && matches!(key, "chain" | "testnet" | "testnet4" | "signet" | "regtest")
+        {
+            out.push(diag(
+                line,
+                Severity::Warning,
+                Some(key),
+                format!("{} has no effect inside a network section", key),
+            ));
+        }
+
+        let multi = options::is_multi(key);
+        let first = *seen.entry((o.section, key)).or_insert(line);
+        if first != line && !multi {
+            out.push(diag(
+                line,
+                Severity::Warning,
+                Some(key),
+                format!(
+                    "{} is already set on line {}; this line is ignored",
+                    key,
+                    first + 1
+                ),
+            ));
+        }
+    }
+
+    check_conflicts(conf, &mut out);
+    out.sort_by_key(|d| d.line);
+    out
+}
+
+/// Checks a single value against its spec and option specific ranges.
+fn check_value(key: &str, value: &str) -> Option<String> {
+    if key == "prune" {
+        return match value.parse::<u64>() {
+            Ok(0 | 1) => None,
+            Ok(n) if n >= 550 => None,
+            _ => Some("prune must be 0 (off), 1 (manual) or at least 550 (MiB)".into()),
+        };
+    }
+
+    let spec = options::lookup(key)?;
+    match spec.kind {
+        ValueKind::Integer { min, max } => match value.parse::<i64>() {
+            Ok(n) if (min..=max).contains(&n) => None,
+            Ok(_) if max == i64::MAX => Some(format!("{} must be at least {}", key, min)),
+            Ok(_) => Some(format!("{} must be between {} and {}", key, min, max)),
+            Err(_) => Some(format!("{} must be a number, got '{}'", key, value)),
+        },
+        ValueKind::Enum(choices) if !choices.contains(&value) => Some(format!(
+            "{} must be one of {}, got '{}'",
+            key,
+            choices.join(", "),
+            value
+        )),
+        _ => None,
+    }
+}
+
+/// Boolean values other than `0` and `1`. Bitcoin Core accepts them, so
+/// this only explains how they are read.
+fn check_bool(key: &str, value: &str) -> Option<String> {
+    if !matches!(options::lookup(key)?.kind, ValueKind::Bool) || matches!(value, "0" | "1") {
+        return None;
+    }
+    Some(match value.parse::<i64>() {
+        _ if value.is_empty() => format!("{}= with no value is read as {}=1", key, key),
+        Ok(n) => format!("{}={} is read as {}={}", key, value, key, (n != 0) as u8),
+        Err(_) => format!(
+            "{} should be 0 or 1; '{}' is not a number and is read as 0",
+            key, value
+        ),
+    })
+}
+
+/// Combinations that Bitcoin Core rejects or that break p2poolv2.
+fn check_conflicts(conf: &BitcoinConf, out: &mut Vec<Diagnostic>) {
+    let line_of = |key: &str| {
+        let section = conf.section_for(key);
+        conf.options()
+            .find(|o| o.section == section && o.option.key == key)
+            .map(|o| o.line)
+    };
+    let enabled = |key: &str| conf.effective(key).is_some_and(|v| v != "0");
+
+    let pruned = conf
+        .effective("prune")
+        .and_then(|v| v.parse::<u64>().ok())
+        .is_some_and(|n| n > 0);
+    if pruned && enabled("txindex") {
+        out.push(Diagnostic {
+            line: line_of("txindex").unwrap_or_default(),
+            severity: Severity::Error,
+            key: Some("txindex".into()),
+            message: "txindex=1 is incompatible with prune".into(),
+        });
+    }
+
+    if enabled("blocksonly") {
+        let line = line_of("blocksonly").unwrap_or_default();
+        out.push(Diagnostic {
+            line,
+            severity: Severity::Warning,
+            key: Some("blocksonly".into()),
+            message: "blocksonly keeps the mempool empty; p2poolv2 block templates will have no transactions".into(),
+        });
+        for key in ["zmqpubhashtx", "zmqpubrawtx"] {
+            if let Some(line) = line_of(key) {
+                out.push(Diagnostic {
+                    line,
+                    severity: Severity::Warning,
+                    key: Some(key.into()),
+                    message: format!("{} will never fire with blocksonly=1", key),
+                });
+            }
+        }
+    }
+}
+
+/// Suggests the known option closest to `key`, if it is a likely typo.
+pub fn suggest(key: &str) -> Option<&'static str> {
+    KNOWN_KEYS
+        .iter()
+        .map(|k| (edit_distance(key, k), *k))
+        .filter(|(d, _)| *d <= 2.max(key.len() / 4))
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, k)| k)
+}
+
+/// Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(text: &str) -> Vec<(usize, Severity, String)> {
+        validate(&BitcoinConf::parse(text))
+            .into_iter()
+            .map(|d| (d.line, d.severity, d.message))
+            .collect()
+    }
+
+    #[test]
+    fn clean_config_has_no_diagnostics() {
+        let text = "server=1\nrpcauth=u:s$h\nrpcauth=v:s$h\nprune=550\n[main]\nrpcport=8332\n";
+        assert!(messages(text).is_empty());
+    }
+
+    #[test]
+    fn reports_invalid_lines_and_unknown_sections() {
+        let found = messages("garbage\n[mainnet]\n");
+        assert_eq!(found[0].0, 0);
+        assert_eq!(found[0].1, Severity::Error);
+        assert_eq!(found[1].1, Severity::Warning);
+        assert!(found[1].2.contains("[mainnet]"));
+    }
+
+    #[test]
+    fn unknown_keys_get_suggestions() {
+        let found = messages("rpcpasword=x\nzzzzzzzz=1\n");
+        assert!(found[0].2.contains("did you mean 'rpcpassword'"));
+        assert!(found[1].2.contains("unknown option 'zzzzzzzz'"));
+        assert_eq!(suggest("txindx"), Some("txindex"));
+        assert_eq!(suggest("zzzzzzzz"), None);
+    }
+
+    #[test]
+    fn values_are_range_checked() {
+        let found = messages("rpcport=70000\nprune=100\nchain=mainnet\n");
+        assert_eq!(found.len(), 3);
+        assert!(found.iter().all(|d| d.1 == Severity::Error));
+        assert!(found[0].2.contains("between 1 and 65535"));
+        assert!(found[1].2.contains("550"));
+        assert!(found[2].2.contains("one of"));
+    }
+
+    #[test]
+    fn odd_booleans_are_warnings() {
+        let found = messages("server=yes\nlisten=\ntxindex=2\nblocksonly=1\n");
+        assert_eq!(found.len(), 4);
+        assert_eq!(found[0].1, Severity::Warning);
+        assert!(found[0].2.contains("read as 0"));
+        assert_eq!(found[1].2, "listen= with no value is read as listen=1");
+        assert_eq!(found[2].2, "txindex=2 is read as txindex=1");
+        assert!(found[3].2.contains("block templates"));
+        assert_eq!(
+            error_count(&validate(&BitcoinConf::parse("server=yes\n"))),
+            0
+        );
+    }
+
+    #[test]
+    fn prune_conflicts_with_txindex() {
+        let conf = BitcoinConf::parse("prune=1000\ntxindex=1\n");
+        let found = validate(&conf);
+        assert_eq!(error_count(&found), 1);
+        assert_eq!(found[0].line, 1);
+        assert_eq!(found[0].key.as_deref(), Some("txindex"));
+    }
+
+    #[test]
+    fn blocksonly_warns_about_mempool_users() {
+        let found = messages("blocksonly=1\nzmqpubrawtx=tcp://127.0.0.1:28333\n");
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|d| d.1 == Severity::Warning));
+        assert!(found[0].2.contains("block templates"));
+    }
+
+    #[test]
+    fn network_only_options_in_wrong_section() {
+        let found = messages("chain=signet\nrpcport=38332\n[signet]\nchain=signet\n");
+        assert_eq!(found.len(), 2);
+        assert!(found[0].2.contains("move it to [signet]"));
+        assert!(found[1].2.contains("no effect inside a network section"));
+        // Fine on mainnet.
+        assert!(messages("rpcport=8332\n").is_empty());
+    }
+
+    #[test]
+    fn duplicates_and_hash_in_password() {
+        let found = messages("rpcuser=a\nrpcuser=b\nrpcpassword=se#cret\nrpcuser=c\n");
+        assert_eq!(found[0].0, 1);
+        assert!(found[0].2.contains("this line is ignored"));
+        assert_eq!(found[1].1, Severity::Error);
+        assert!(found[1].2.contains("'#'"));
+        assert!(found[2].2.contains("already set on line 1"));
+    }
+
+    #[test]
+    fn edit_distance_basics() {
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+}
@@ -0,0 +1,102 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_conf::discover::Candidate;
+use ratatui::widgets::ListState;
+use std::path::PathBuf;
+
+/// What the user picked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Choice {
+    Config(PathBuf),
+    /// Look for the file with the [`FileExplorer`](super::file_explorer::FileExplorer).
+    Browse,
+}
+
+/// `ConfPicker` lists discovered bitcoin.conf files, followed by an entry
+/// to browse for one instead.
+#[derive(Debug, Default, Clone)]
+pub struct ConfPicker {
+    pub candidates: Vec<Candidate>,
+    /// Index of the selected row; `candidates.len()` is "browse".
+    pub selected: usize,
+    /// Scroll state of the rendered list.
+    pub list_state: ListState,
+}
+
+impl ConfPicker {
+    pub fn new(candidates: Vec<Candidate>) -> Self {
+        Self {
+            candidates,
+            ..Self::default()
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.candidates.len() + 1
+    }
+
+    /// Moves the selection to the next row, wrapping around.
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % self.len();
+    }
+
+    /// Moves the selection to the previous row, wrapping around.
+    pub fn previous(&mut self) {
+        self.selected = self.selected.checked_sub(1).unwrap_or(self.len() - 1);
+    }
+
+    pub fn choice(&self) -> Choice {
+        match self.candidates.get(self.selected) {
+            Some(candidate) => Choice::Config(candidate.path.clone()),
+            None => Choice::Browse,
+        }
+    }
+
+    /// A good directory to start browsing in: next to the selected or
+    /// first candidate.
+    pub fn browse_dir(&self) -> Option<PathBuf> {
+        self.candidates
+            .get(self.selected)
+            .or(self.candidates.first())
+            .and_then(|c| c.path.parent())
+            .map(PathBuf::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn picker() -> ConfPicker {
+        ConfPicker::new(vec![Candidate {
+            path: PathBuf::from("/data/bitcoin.conf"),
+            source: "$BITCOIN_DATADIR".into(),
+        }])
+    }
+
+    #[test]
+    fn last_row_browses() {
+        let mut picker = picker();
+        assert_eq!(
+            picker.choice(),
+            Choice::Config(PathBuf::from("/data/bitcoin.conf"))
+        );
+        picker.next();
+        assert_eq!(picker.choice(), Choice::Browse);
+        assert_eq!(picker.browse_dir(), Some(PathBuf::from("/data")));
+        picker.next();
+        assert_eq!(picker.selected, 0);
+        picker.previous();
+        assert_eq!(picker.selected, 1);
+    }
+
+    #[test]
+    fn empty_picker_only_browses() {
+        let mut picker = ConfPicker::new(Vec::new());
+        picker.next();
+        assert_eq!(picker.choice(), Choice::Browse);
+        assert_eq!(picker.browse_dir(), None);
+    }
+}
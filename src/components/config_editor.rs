@@ -0,0 +1,419 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_conf::options::{self, OPTIONS, OptionSpec, ValueKind};
+use crate::bitcoin_conf::{BitcoinConf, Network};
+use ratatui::widgets::ListState;
+
+/// `ConfigEditor` holds the UI state of the bitcoin.conf editor: which
+/// option is selected, the text being typed for it and whether the loaded
+/// config has unsaved changes.
+///
+/// Values are written into the [`BitcoinConf`] as soon as an edit is
+/// committed, in the section where they take effect for the configured chain.
+#[derive(Debug, Default, Clone)]
+pub struct ConfigEditor {
+    /// Index into [`OPTIONS`] of the selected option.
+    pub selected: usize,
+    /// Scroll state of the rendered list.
+    pub list_state: ListState,
+    /// Text being typed for the selected option, while editing.
+    pub input: Option<String>,
+    /// Whether the config was changed since it was loaded or saved.
+    pub modified: bool,
+    /// Feedback for the last action (validation errors, save results).
+    pub message: Option<String>,
+    /// First line shown in the Problems pane.
+    pub problem_scroll: usize,
+}
+
+impl ConfigEditor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The spec of the selected option.
+    pub fn selected_spec(&self) -> &'static OptionSpec {
+        &OPTIONS[self.selected]
+    }
+
+    /// Whether a text value is being typed.
+    pub fn is_editing(&self) -> bool {
+        self.input.is_some()
+    }
+
+    /// Moves the selection to the next option, wrapping around.
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % OPTIONS.len();
+    }
+
+    /// Moves the selection to the previous option, wrapping around.
+    pub fn previous(&mut self) {
+        self.selected = self.selected.checked_sub(1).unwrap_or(OPTIONS.len() - 1);
+    }
+
+    /// The value shown for `spec`, or `None` if it is not set.
+    ///
+    /// Repeated options are joined with `", "`, which is also the format
+    /// accepted when editing them.
+    pub fn display_value(conf: &BitcoinConf, spec: &OptionSpec) -> Option<String> {
+        let values = conf.effective_all(spec.key);
+        if values.is_empty() {
+            None
+        } else if spec.multi {
+            Some(values.join(", "))
+        } else {
+            values.first().map(|v| v.to_string())
+        }
+    }
+
+    /// Activates the selected option: toggles booleans, cycles enums and
+    /// starts typing for everything else, including repeatable enums such
+    /// as `onlynet`.
+    pub fn activate(&mut self, conf: &mut BitcoinConf) {
+        let spec = self.selected_spec();
+        match spec.kind {
+            ValueKind::Bool => {
+                let value = match conf.effective(spec.key) {
+                    Some("1") => "0",
+                    _ => "1",
+                };
+                self.apply(conf, Some(value.to_string()));
+            }
+            ValueKind::Enum(_) if !spec.multi => self.cycle(conf, true),
+            _ => self.input = Some(Self::display_value(conf, spec).unwrap_or_default()),
+        }
+    }
+
+    /// Steps an enum option through its values, with "unset" between the
+    /// last and first value. Repeatable enums are typed instead, since
+    /// cycling would replace every value with one.
+    pub fn cycle(&mut self, conf: &mut BitcoinConf, forward: bool) {
+        let spec = self.selected_spec();
+        let ValueKind::Enum(choices) = spec.kind else {
+            return;
+        };
+        if spec.multi {
+            return;
+        }
+        let current = conf
+            .effective(spec.key)
+            .and_then(|v| choices.iter().position(|c| *c == v));
+        // Position `choices.len()` stands for "unset".
+        let slots = choices.len() + 1;
+        let position = current.unwrap_or(choices.len());
+        let next = if forward {
+            (position + 1) % slots
+        } else {
+            (position + slots - 1) % slots
+        };
+        self.apply(conf, choices.get(next).map(|c| c.to_string()));
+    }
+
+    /// Scrolls the Problems pane down by `lines`; the renderer stops it at
+    /// the last page.
+    pub fn scroll_problems_down(&mut self, lines: usize) {
+        self.problem_scroll += lines;
+    }
+
+    /// Scrolls the Problems pane up by `lines`.
+    pub fn scroll_problems_up(&mut self, lines: usize) {
+        self.problem_scroll = self.problem_scroll.saturating_sub(lines);
+    }
+
+    /// Appends `c` to the input, ignoring characters an integer cannot contain.
+    pub fn insert_char(&mut self, c: char) {
+        let min = match self.selected_spec().kind {
+            ValueKind::Integer { min, .. } => Some(min),
+            _ => None,
+        };
+        // Integers take digits, and a leading sign where negatives are valid
+        if let Some(input) = &mut self.input
+            && min.is_none_or(|min| c.is_ascii_digit() || (c == '-' && min < 0 && input.is_empty()))
+        {
+            input.push(c);
+        }
+    }
+
+    /// Removes the last character of the input.
+    pub fn backspace(&mut self) {
+        if let Some(input) = &mut self.input {
+            input.pop();
+        }
+    }
+
+    /// Abandons the current input.
+    pub fn cancel(&mut self) {
+        self.input = None;
+        self.message = None;
+    }
+
+    /// Validates the input and writes it to `conf`. An empty input unsets
+    /// the option. Invalid input is kept so it can be corrected.
+    pub fn commit(&mut self, conf: &mut BitcoinConf) {
+        let Some(input) = self.input.clone() else {
+            return;
+        };
+        let spec = self.selected_spec();
+        let value = input.trim();
+        if value.is_empty() {
+            self.input = None;
+            self.apply(conf, None);
+            return;
+        }
+        if let ValueKind::Integer { min, max } = spec.kind {
+            match value.parse::<i64>() {
+                Ok(n) if (min..=max).contains(&n) => {}
+                _ => {
+                    self.message = Some(if max == i64::MAX {
+                        format!("{} must be a number >= {}", spec.key, min)
+                    } else {
+                        format!("{} must be a number between {} and {}", spec.key, min, max)
+                    });
+                    return;
+                }
+            }
+        }
+        if let ValueKind::Enum(choices) = spec.kind
+            && let Some(bad) = value
+                .split(',')
+                .map(str::trim)
+                .find(|v| !v.is_empty() && !choices.contains(v))
+        {
+            self.message = Some(format!(
+                "{} must be one of {}, got '{}'",
+                spec.key,
+                choices.join(", "),
+                bad
+            ));
+            return;
+        }
+        self.input = None;
+        self.apply(conf, Some(value.to_string()));
+    }
+
+    /// Removes the selected option from `conf`.
+    pub fn unset(&mut self, conf: &mut BitcoinConf) {
+        self.apply(conf, None);
+    }
+
+    fn apply(&mut self, conf: &mut BitcoinConf, value: Option<String>) {
+        let spec = self.selected_spec();
+        let section = conf.section_for(spec.key);
+        match value {
+            Some(value) if spec.multi => {
+                clear(conf, spec.key);
+                for v in value.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+                    conf.add(section, spec.key, v);
+                }
+            }
+            Some(value) => conf.set(section, spec.key, &value),
+            None => clear(conf, spec.key),
+        }
+        self.modified = true;
+        self.message = None;
+    }
+}
+
+/// Removes `key` from every section [`BitcoinConf::effective_all`] reads it
+/// from, so that what is shown is exactly what gets replaced.
+fn clear(conf: &mut BitcoinConf, key: &str) {
+    let chain = conf.chain();
+    conf.remove(Some(chain.name()), key);
+    if chain == Network::Main || !options::is_network_only(key) {
+        conf.remove(None, key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn select(editor: &mut ConfigEditor, key: &str) {
+        editor.selected = OPTIONS.iter().position(|o| o.key == key).unwrap();
+    }
+
+    #[test]
+    fn navigation_wraps() {
+        let mut editor = ConfigEditor::new();
+        editor.previous();
+        assert_eq!(editor.selected, OPTIONS.len() - 1);
+        editor.next();
+        assert_eq!(editor.selected, 0);
+    }
+
+    #[test]
+    fn activating_bool_toggles() {
+        let mut conf = BitcoinConf::parse("server=1\n");
+        let mut editor = ConfigEditor::new();
+        select(&mut editor, "server");
+
+        editor.activate(&mut conf);
+        assert_eq!(conf.get(None, "server"), Some("0"));
+        editor.activate(&mut conf);
+        assert_eq!(conf.get(None, "server"), Some("1"));
+        assert!(editor.modified);
+        assert!(!editor.is_editing());
+    }
+
+    #[test]
+    fn cycling_enum_passes_through_unset() {
+        let mut conf = BitcoinConf::parse("chain=regtest\n");
+        let mut editor = ConfigEditor::new();
+        select(&mut editor, "chain");
+
+        editor.cycle(&mut conf, true);
+        assert_eq!(conf.get(None, "chain"), None);
+        editor.cycle(&mut conf, true);
+        assert_eq!(conf.get(None, "chain"), Some("main"));
+        editor.cycle(&mut conf, false);
+        editor.cycle(&mut conf, false);
+        assert_eq!(conf.get(None, "chain"), Some("regtest"));
+    }
+
+    #[test]
+    fn integer_input_is_validated() {
+        let mut conf = BitcoinConf::default();
+        let mut editor = ConfigEditor::new();
+        select(&mut editor, "rpcport");
+
+        editor.activate(&mut conf);
+        assert_eq!(editor.input.as_deref(), Some(""));
+        for c in "7x0000".chars() {
+            editor.insert_char(c);
+        }
+        assert_eq!(editor.input.as_deref(), Some("70000"));
+
+        editor.commit(&mut conf);
+        assert!(editor.is_editing());
+        assert!(
+            editor
+                .message
+                .as_deref()
+                .unwrap()
+                .contains("between 1 and 65535")
+        );
+
+        editor.backspace();
+        editor.backspace();
+        editor.commit(&mut conf);
+        assert!(!editor.is_editing());
+        assert_eq!(conf.get(None, "rpcport"), Some("700"));
+    }
+
+    #[test]
+    fn empty_input_unsets() {
+        let mut conf = BitcoinConf::parse("rpcuser=alice\n");
+        let mut editor = ConfigEditor::new();
+        select(&mut editor, "rpcuser");
+
+        editor.activate(&mut conf);
+        editor.input = Some(String::new());
+        editor.commit(&mut conf);
+        assert_eq!(conf.get(None, "rpcuser"), None);
+    }
+
+    #[test]
+    fn integers_take_digits_only() {
+        let mut conf = BitcoinConf::parse("prune=550\n");
+        let mut editor = ConfigEditor::new();
+        select(&mut editor, "prune");
+
+        editor.activate(&mut conf);
+        editor.input = Some(String::new());
+        for c in "-1x0".chars() {
+            editor.insert_char(c);
+        }
+        assert_eq!(editor.input.as_deref(), Some("10"));
+    }
+
+    #[test]
+    fn cancel_discards_input() {
+        let mut conf = BitcoinConf::parse("rpcuser=alice\n");
+        let mut editor = ConfigEditor::new();
+        select(&mut editor, "rpcuser");
+
+        editor.activate(&mut conf);
+        editor.insert_char('x');
+        editor.cancel();
+        assert_eq!(conf.get(None, "rpcuser"), Some("alice"));
+        assert!(!editor.modified);
+    }
+
+    #[test]
+    fn multi_values_are_comma_separated() {
+        let mut conf = BitcoinConf::parse("addnode=a\naddnode=b\n");
+        let mut editor = ConfigEditor::new();
+        select(&mut editor, "addnode");
+
+        editor.activate(&mut conf);
+        assert_eq!(editor.input.as_deref(), Some("a, b"));
+        editor.input = Some("b, c,".to_string());
+        editor.commit(&mut conf);
+        assert_eq!(conf.get_all(None, "addnode"), vec!["b", "c"]);
+    }
+
+    #[test]
+    fn multi_values_are_replaced_across_sections() {
+        let mut conf = BitcoinConf::parse("chain=signet\nrpcallowip=a\n[signet]\nrpcallowip=b\n");
+        let mut editor = ConfigEditor::new();
+        select(&mut editor, "rpcallowip");
+
+        editor.activate(&mut conf);
+        assert_eq!(editor.input.as_deref(), Some("b, a"));
+        editor.input = Some("b, a, c".to_string());
+        editor.commit(&mut conf);
+        assert_eq!(conf.effective_all("rpcallowip"), vec!["b", "a", "c"]);
+        assert_eq!(conf.get_all(None, "rpcallowip"), Vec::<&str>::new());
+        assert_eq!(
+            ConfigEditor::display_value(&conf, editor.selected_spec()).as_deref(),
+            Some("b, a, c")
+        );
+    }
+
+    #[test]
+    fn multi_enums_are_typed() {
+        let mut conf = BitcoinConf::parse("onlynet=ipv4\nonlynet=onion\n");
+        let mut editor = ConfigEditor::new();
+        select(&mut editor, "onlynet");
+
+        editor.cycle(&mut conf, true);
+        assert_eq!(conf.get_all(None, "onlynet"), vec!["ipv4", "onion"]);
+
+        editor.activate(&mut conf);
+        assert_eq!(editor.input.as_deref(), Some("ipv4, onion"));
+        editor.input = Some("ipv4, tor".to_string());
+        editor.commit(&mut conf);
+        assert!(editor.message.as_deref().unwrap().contains("got 'tor'"));
+        editor.input = Some("ipv4, i2p".to_string());
+        editor.commit(&mut conf);
+        assert_eq!(conf.get_all(None, "onlynet"), vec!["ipv4", "i2p"]);
+    }
+
+    #[test]
+    fn network_only_options_go_to_chain_section() {
+        let mut conf = BitcoinConf::parse("chain=regtest\n");
+        let mut editor = ConfigEditor::new();
+        select(&mut editor, "rpcport");
+
+        editor.input = Some("18443".to_string());
+        editor.commit(&mut conf);
+        assert_eq!(conf.get(Some("regtest"), "rpcport"), Some("18443"));
+        assert_eq!(
+            conf.to_string(),
+            "chain=regtest\n\n[regtest]\nrpcport=18443\n"
+        );
+    }
+
+    #[test]
+    fn unset_removes_option() {
+        let mut conf = BitcoinConf::parse("txindex=1\n");
+        let mut editor = ConfigEditor::new();
+        select(&mut editor, "txindex");
+
+        editor.unset(&mut conf);
+        assert_eq!(conf.get(None, "txindex"), None);
+        assert!(editor.modified);
+    }
+}
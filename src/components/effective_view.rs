@@ -0,0 +1,37 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use ratatui::widgets::ListState;
+
+/// `EffectiveView` holds the selection in the effective configuration view.
+///
+/// The settings are recomputed from the loaded files on every frame, so
+/// only the selected row is kept here.
+#[derive(Debug, Default, Clone)]
+pub struct EffectiveView {
+    /// Index of the selected setting.
+    pub selected: usize,
+    /// Scroll state of the rendered list.
+    pub list_state: ListState,
+}
+
+impl EffectiveView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the selection to the next of `len` settings, wrapping around.
+    pub fn next(&mut self, len: usize) {
+        if len > 0 {
+            self.selected = (self.selected + 1) % len;
+        }
+    }
+
+    /// Moves the selection to the previous of `len` settings, wrapping around.
+    pub fn previous(&mut self, len: usize) {
+        if len > 0 {
+            self.selected = self.selected.checked_sub(1).unwrap_or(len - 1);
+        }
+    }
+}
@@ -0,0 +1,22 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::fmt::Display;
+
+/// `ErrorDialog` reports a failed operation, such as a config write, over
+/// the current screen until it is dismissed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorDialog {
+    pub title: String,
+    pub message: String,
+}
+
+impl ErrorDialog {
+    pub fn new(title: impl Into<String>, message: impl Display) -> Self {
+        Self {
+            title: title.into(),
+            message: message.to_string(),
+        }
+    }
+}
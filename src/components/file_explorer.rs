@@ -37,6 +37,12 @@ impl FileExplorer {
         explorer
     }
 
+    /// Moves to `dir` and lists its contents.
+    pub fn open_dir(&mut self, dir: PathBuf) {
+        self.current_dir = dir;
+        self.load_directory();
+    }
+
     /// Loads the contents of `current_dir` into `files`.
     ///
     /// Directories are listed first, followed by files. If the directory
@@ -120,8 +126,7 @@ impl FileExplorer {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::env::temp_dir;
-    use std::fs::{File, create_dir};
+    use std::fs::File;
 
     fn setup_temp_fs() -> PathBuf {
         use std::time::{SystemTime, UNIX_EPOCH};
@@ -0,0 +1,113 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::backup::{self, Backup};
+use ratatui::widgets::ListState;
+use std::fs;
+use std::path::Path;
+
+/// `HistoryView` lists the backups of a config file, newest first, and
+/// compares them with the file as it is on disk.
+///
+/// Unsaved edits are left out of the comparison: they are not what a
+/// restore replaces.
+#[derive(Debug, Default, Clone)]
+pub struct HistoryView {
+    pub backups: Vec<Backup>,
+    /// Index of the selected backup.
+    pub selected: usize,
+    /// Scroll state of the rendered list.
+    pub list_state: ListState,
+    /// Outcome of the last action, or why the backups could not be listed.
+    pub message: Option<String>,
+    /// The config file on disk when the backups were listed, or why it
+    /// could not be read.
+    pub current: Option<Result<String, String>>,
+    /// First line of the diff shown.
+    pub scroll: usize,
+}
+
+impl HistoryView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lists the backups of `path` and reads the file itself, keeping the
+    /// selection in range.
+    pub fn load(&mut self, path: &Path) {
+        match backup::list(path) {
+            Ok(backups) => self.backups = backups,
+            Err(err) => {
+                self.backups.clear();
+                self.message = Some(format!("Could not list backups: {}", err));
+            }
+        }
+        self.current = Some(fs::read_to_string(path).map_err(|err| err.to_string()));
+        self.selected = self.selected.min(self.backups.len().saturating_sub(1));
+        self.scroll = 0;
+    }
+
+    pub fn selected_backup(&self) -> Option<&Backup> {
+        self.backups.get(self.selected)
+    }
+
+    /// Moves the selection to the next backup, wrapping around.
+    pub fn next(&mut self) {
+        if !self.backups.is_empty() {
+            self.selected = (self.selected + 1) % self.backups.len();
+            self.scroll = 0;
+        }
+    }
+
+    /// Moves the selection to the previous backup, wrapping around.
+    pub fn previous(&mut self) {
+        if !self.backups.is_empty() {
+            self.selected = self
+                .selected
+                .checked_sub(1)
+                .unwrap_or(self.backups.len() - 1);
+            self.scroll = 0;
+        }
+    }
+
+    /// Scrolls the diff down by `lines`; the renderer stops it at the end.
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll += lines;
+    }
+
+    /// Scrolls the diff up by `lines`.
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn lists_backups_of_a_file() {
+        let dir = std::env::temp_dir().join(format!("pdm_history_view_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("bitcoin.conf");
+        fs::write(&path, "server=1\n").unwrap();
+        backup::create(&path, 5).unwrap();
+        backup::create(&path, 5).unwrap();
+
+        let mut view = HistoryView::new();
+        view.load(&path);
+        assert_eq!(view.backups.len(), 2);
+        view.previous();
+        assert_eq!(view.selected, 1);
+        view.next();
+        assert_eq!(view.selected_backup(), view.backups.first());
+        assert_eq!(view.current, Some(Ok("server=1\n".to_string())));
+
+        view.scroll_down(3);
+        view.next();
+        assert_eq!(view.scroll, 0);
+    }
+}
@@ -0,0 +1,588 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_conf::BitcoinConf;
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// How much of an existing debug.log is shown when the viewer opens.
+pub const INITIAL_TAIL: u64 = 256 * 1024;
+
+/// Lines kept in memory; the oldest are dropped beyond this.
+pub const MAX_LINES: usize = 10_000;
+
+/// Most bytes read per poll, so a burst of logging cannot stall the UI.
+const MAX_READ: u64 = 4 * 1024 * 1024;
+
+/// Rows moved by PageUp and PageDown.
+pub const PAGE: usize = 10;
+
+/// Categories bitcoind tags lines with, as enabled by `debug=<category>`.
+const CATEGORIES: &[&str] = &[
+    "addrman",
+    "bench",
+    "blockstorage",
+    "cmpctblock",
+    "coindb",
+    "estimatefee",
+    "http",
+    "i2p",
+    "ipc",
+    "leveldb",
+    "libevent",
+    "lock",
+    "mempool",
+    "mempoolrej",
+    "net",
+    "proxy",
+    "prune",
+    "qt",
+    "rand",
+    "reindex",
+    "rpc",
+    "scan",
+    "selectcoins",
+    "tor",
+    "txpackages",
+    "txreconciliation",
+    "validation",
+    "walletdb",
+    "zmq",
+];
+
+/// Where bitcoind writes its log for `conf`, or `None` if logging to a
+/// file is turned off with `debuglogfile=0` or `nodebuglogfile=1`.
+pub fn debug_log_path(conf: &BitcoinConf, conf_path: &Path) -> Option<PathBuf> {
+    if conf.effective("nodebuglogfile").is_some_and(|v| v != "0") {
+        return None;
+    }
+    let net_dir = conf.net_datadir(conf_path);
+    match conf.effective("debuglogfile") {
+        Some("0") => None,
+        // An absolute path replaces the datadir
+        Some(file) => Some(net_dir.join(file)),
+        None => Some(net_dir.join("debug.log")),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+/// One line of debug.log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogLine {
+    pub text: String,
+    /// e.g. `net` for lines logged under `debug=net`.
+    pub category: Option<String>,
+    pub level: Level,
+    /// Added by pdm rather than read from the file.
+    pub notice: bool,
+}
+
+impl LogLine {
+    /// Reads the category and severity from a line such as
+    /// `2024-05-01T12:00:00Z [msghand] [net:warning] ...` or, for
+    /// uncategorised messages, `2024-05-01T12:00:00Z [warning] ...`.
+    pub fn parse(text: String) -> LogLine {
+        let mut category = None;
+        let mut level = None;
+        // After the timestamp, bracketed thread names and categories
+        for token in text.split_whitespace().skip(1) {
+            let Some(inner) = token.strip_prefix('[').and_then(|t| t.strip_suffix(']')) else {
+                break;
+            };
+            let (name, tag) = match inner.split_once(':') {
+                Some((name, tag)) => (name, Some(tag)),
+                None => (inner, None),
+            };
+            if tag.is_none() && matches!(name, "warning" | "error") {
+                level = Some(if name == "warning" {
+                    Level::Warning
+                } else {
+                    Level::Error
+                });
+            } else if CATEGORIES.contains(&name) {
+                category = Some(name.to_string());
+                level = match tag {
+                    Some("warning") => Some(Level::Warning),
+                    Some("error") => Some(Level::Error),
+                    _ => None,
+                };
+            }
+        }
+        let level = level.unwrap_or_else(|| {
+            let lower = text.to_ascii_lowercase();
+            if lower.contains("error:") || lower.contains("] error") {
+                Level::Error
+            } else if lower.contains("warning:") || lower.contains("] warning") {
+                Level::Warning
+            } else {
+                Level::Info
+            }
+        });
+        LogLine {
+            text,
+            category,
+            level,
+            notice: false,
+        }
+    }
+
+    fn notice(text: &str) -> LogLine {
+        LogLine {
+            text: text.to_string(),
+            category: None,
+            level: Level::Info,
+            notice: true,
+        }
+    }
+}
+
+/// What [`LogTail::poll`] found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Tailed {
+    Line(String),
+    /// The file was rotated or truncated; later lines come from the start
+    /// of the new file.
+    Rotated,
+}
+
+/// Follows a file the way `tail -F` does: lines appended to it are read
+/// as they arrive, and when it is replaced (as logrotate does) or
+/// truncated, reading carries on from the start of the new content.
+#[derive(Debug)]
+pub struct LogTail {
+    path: PathBuf,
+    file: File,
+    /// Offset of the next byte to read.
+    pos: u64,
+    id: Option<(u64, u64)>,
+    /// The last line, until its newline arrives.
+    partial: Vec<u8>,
+}
+
+#[cfg(unix)]
+fn file_id(meta: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+// Without inode numbers, only truncation is noticed
+#[cfg(not(unix))]
+fn file_id(_meta: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+impl LogTail {
+    /// Opens `path` and returns the complete lines in its last `tail`
+    /// bytes.
+    pub fn open(path: &Path, tail: u64) -> io::Result<(LogTail, Vec<String>)> {
+        let file = File::open(path)?;
+        let meta = file.metadata()?;
+        let start = meta.len().saturating_sub(tail);
+        let mut log = LogTail {
+            path: path.to_path_buf(),
+            file,
+            pos: start,
+            id: file_id(&meta),
+            partial: Vec::new(),
+        };
+        let mut lines = log.read_new()?;
+        if start > 0 && !lines.is_empty() {
+            // Most likely starts mid-line
+            lines.remove(0);
+        }
+        Ok((log, lines))
+    }
+
+    /// Reads what has been written since the last call.
+    pub fn poll(&mut self) -> io::Result<Vec<Tailed>> {
+        // Whatever was appended to the old file before a rotation comes first
+        let mut found: Vec<Tailed> = self.read_new()?.into_iter().map(Tailed::Line).collect();
+
+        let meta = match fs::metadata(&self.path) {
+            Ok(meta) => meta,
+            // Moved away, and the new file is not there yet
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(found),
+            Err(err) => return Err(err),
+        };
+        let replaced = file_id(&meta) != self.id;
+        if replaced || meta.len() < self.pos {
+            if replaced {
+                self.file = File::open(&self.path)?;
+                self.id = file_id(&self.file.metadata()?);
+            }
+            if !self.partial.is_empty() {
+                let line = String::from_utf8_lossy(&self.partial).into_owned();
+                found.push(Tailed::Line(line));
+                self.partial.clear();
+            }
+            self.pos = 0;
+            found.push(Tailed::Rotated);
+            found.extend(self.read_new()?.into_iter().map(Tailed::Line));
+        }
+        Ok(found)
+    }
+
+    fn read_new(&mut self) -> io::Result<Vec<String>> {
+        self.file.seek(SeekFrom::Start(self.pos))?;
+        let mut buf = Vec::new();
+        let read = (&mut self.file).take(MAX_READ).read_to_end(&mut buf)?;
+        self.pos += read as u64;
+
+        self.partial.extend_from_slice(&buf);
+        let Some(end) = self.partial.iter().rposition(|b| *b == b'\n') else {
+            return Ok(Vec::new());
+        };
+        let rest = self.partial.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.partial, rest);
+        Ok(complete[..end]
+            .split(|b| *b == b'\n')
+            .map(|line| {
+                let line = line.strip_suffix(b"\r").unwrap_or(line);
+                String::from_utf8_lossy(line).into_owned()
+            })
+            .collect())
+    }
+}
+
+/// `LogView` tails bitcoind's debug.log, with filters by category and
+/// regular expression.
+#[derive(Debug, Default)]
+pub struct LogView {
+    pub path: Option<PathBuf>,
+    tail: Option<LogTail>,
+    /// Oldest first.
+    pub lines: Vec<LogLine>,
+    /// Every category seen so far.
+    pub categories: BTreeSet<String>,
+    /// Only lines in this category are shown.
+    pub category: Option<String>,
+    /// Only lines matching this are shown, with the matches highlighted.
+    pub search: Option<Regex>,
+    /// Pattern being typed.
+    pub search_input: Option<String>,
+    /// Whether the newest lines are kept in view.
+    pub follow: bool,
+    /// Index into [`LogView::visible`] of the top row when not following.
+    pub top: usize,
+    /// Rows the log had on screen when last drawn.
+    pub height: usize,
+    /// Why the file cannot be read.
+    pub error: Option<String>,
+    pub message: Option<String>,
+}
+
+impl LogView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts tailing `path`, following new lines.
+    pub fn open(&mut self, path: PathBuf) {
+        *self = LogView {
+            path: Some(path),
+            follow: true,
+            ..LogView::new()
+        };
+        self.reopen();
+    }
+
+    fn reopen(&mut self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        match LogTail::open(path, INITIAL_TAIL) {
+            Ok((tail, lines)) => {
+                self.tail = Some(tail);
+                self.error = None;
+                for line in lines {
+                    self.push(LogLine::parse(line));
+                }
+            }
+            Err(err) => self.error = Some(format!("Cannot read {}: {}", path.display(), err)),
+        }
+    }
+
+    /// Picks up new lines. A file that could not be opened, for example
+    /// because bitcoind has not created it yet, is tried again.
+    pub fn poll(&mut self) {
+        let Some(tail) = &mut self.tail else {
+            self.reopen();
+            return;
+        };
+        match tail.poll() {
+            Ok(found) => {
+                for tailed in found {
+                    self.push(match tailed {
+                        Tailed::Line(line) => LogLine::parse(line),
+                        Tailed::Rotated => LogLine::notice("--- log rotated ---"),
+                    });
+                }
+            }
+            Err(err) => {
+                self.error = Some(format!("Lost {}: {}", tail.path.display(), err));
+                self.tail = None;
+            }
+        }
+    }
+
+    pub fn push(&mut self, line: LogLine) {
+        if let Some(category) = &line.category
+            && !self.categories.contains(category)
+        {
+            self.categories.insert(category.clone());
+        }
+        self.lines.push(line);
+        if self.lines.len() > MAX_LINES {
+            let excess = self.lines.len() - MAX_LINES;
+            // `top` counts visible lines only
+            let dropped = self.lines[..excess]
+                .iter()
+                .filter(|line| self.shows(line))
+                .count();
+            self.lines.drain(..excess);
+            self.top = self.top.saturating_sub(dropped);
+        }
+    }
+
+    /// The lines that pass the filters, oldest first.
+    pub fn visible(&self) -> Vec<&LogLine> {
+        self.lines.iter().filter(|line| self.shows(line)).collect()
+    }
+
+    fn shows(&self, line: &LogLine) -> bool {
+        (self.category.is_none() || line.notice || line.category == self.category)
+            && self
+                .search
+                .as_ref()
+                .is_none_or(|re| re.is_match(&line.text))
+    }
+
+    /// Index into `visible` of the first row to draw.
+    pub fn first_row(&self, visible: usize) -> usize {
+        let last_page = visible.saturating_sub(self.height);
+        if self.follow {
+            last_page
+        } else {
+            self.top.min(last_page)
+        }
+    }
+
+    /// Shows only the next category seen, or every line after the last.
+    pub fn cycle_category(&mut self) {
+        self.category = match &self.category {
+            None => self.categories.first().cloned(),
+            Some(current) => self.categories.iter().find(|c| *c > current).cloned(),
+        };
+    }
+
+    /// Applies the typed pattern; an empty one clears the search.
+    pub fn submit_search(&mut self) {
+        let Some(input) = self.search_input.take() else {
+            return;
+        };
+        if input.is_empty() {
+            self.search = None;
+            return;
+        }
+        match Regex::new(&input) {
+            Ok(re) => {
+                self.search = Some(re);
+                self.message = None;
+            }
+            Err(err) => self.message = Some(format!("Bad pattern: {}", err)),
+        }
+    }
+
+    pub fn toggle_follow(&mut self) {
+        if self.follow {
+            self.top = self.first_row(self.visible().len());
+        }
+        self.follow = !self.follow;
+    }
+
+    /// Scrolls up by `rows`, which stops following.
+    pub fn up(&mut self, rows: usize) {
+        let first = self.first_row(self.visible().len());
+        self.follow = false;
+        self.top = first.saturating_sub(rows);
+    }
+
+    /// Scrolls down by `rows`; reaching the end follows again.
+    pub fn down(&mut self, rows: usize) {
+        let last_page = self.visible().len().saturating_sub(self.height);
+        let first = self.first_row(self.visible().len()).saturating_add(rows);
+        if first >= last_page {
+            self.follow = true;
+        } else {
+            self.top = first;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env::temp_dir;
+    use std::io::Write;
+
+    #[test]
+    fn reads_categories_and_levels() {
+        let line = LogLine::parse(
+            "2024-05-01T12:00:00Z [msghand] [net:warning] peer=3 sent bad data".into(),
+        );
+        assert_eq!(line.category.as_deref(), Some("net"));
+        assert_eq!(line.level, Level::Warning);
+
+        let line = LogLine::parse("2024-05-01T12:00:00Z [mempool] AcceptToMemoryPool".into());
+        assert_eq!(line.category.as_deref(), Some("mempool"));
+        assert_eq!(line.level, Level::Info);
+
+        let line = LogLine::parse("2024-05-01T12:00:00Z Error: Disk space is too low!".into());
+        assert_eq!(line.category, None);
+        assert_eq!(line.level, Level::Error);
+
+        let line = LogLine::parse(
+            "2025-04-01T08:00:00Z [warning] Flushing large (2 GiB) UTXO set to disk".into(),
+        );
+        assert_eq!(line.category, None);
+        assert_eq!(line.level, Level::Warning);
+
+        let line = LogLine::parse(
+            "2025-04-01T08:00:00Z [init] [error] Failed to listen on any port".into(),
+        );
+        assert_eq!(line.level, Level::Error);
+    }
+
+    #[test]
+    fn debug_log_follows_datadir_and_chain() {
+        let conf = BitcoinConf::parse("datadir=/srv/bitcoin\nsignet=1\n");
+        assert_eq!(
+            debug_log_path(&conf, Path::new("/etc/bitcoin.conf")),
+            Some(PathBuf::from("/srv/bitcoin/signet/debug.log"))
+        );
+        let conf = BitcoinConf::parse("debuglogfile=/var/log/bitcoind.log\n");
+        assert_eq!(
+            debug_log_path(&conf, Path::new("/home/pool/.bitcoin/bitcoin.conf")),
+            Some(PathBuf::from("/var/log/bitcoind.log"))
+        );
+        let conf = BitcoinConf::parse("debuglogfile=0\n");
+        assert_eq!(debug_log_path(&conf, Path::new("/bitcoin.conf")), None);
+        let conf = BitcoinConf::parse("nodebuglogfile=1\n");
+        assert_eq!(debug_log_path(&conf, Path::new("/bitcoin.conf")), None);
+    }
+
+    #[test]
+    fn tail_follows_appends_truncation_and_rotation() {
+        let dir = temp_dir().join("pdm_log_tail_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("debug.log");
+        fs::write(&path, "first\nsecond\nthi").unwrap();
+
+        let (mut tail, lines) = LogTail::open(&path, 11).unwrap();
+        // The cut-off "st" of "first" is dropped, "thi" awaits its newline
+        assert_eq!(lines, ["second"]);
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"rd\n").unwrap();
+        assert_eq!(tail.poll().unwrap(), [Tailed::Line("third".into())]);
+        assert_eq!(tail.poll().unwrap(), []);
+
+        file.set_len(0).unwrap();
+        writeln!(file, "after truncate").unwrap();
+        assert_eq!(
+            tail.poll().unwrap(),
+            [Tailed::Rotated, Tailed::Line("after truncate".into())]
+        );
+
+        writeln!(file, "last in old file").unwrap();
+        fs::rename(&path, dir.join("debug.log.1")).unwrap();
+        fs::write(&path, "new file\n").unwrap();
+        let found = tail.poll().unwrap();
+        if cfg!(unix) {
+            assert_eq!(
+                found,
+                [
+                    Tailed::Line("last in old file".into()),
+                    Tailed::Rotated,
+                    Tailed::Line("new file".into()),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn filters_and_scrolls() {
+        let mut view = LogView::new();
+        view.follow = true;
+        view.height = 2;
+        for i in 0..6 {
+            let category = if i % 2 == 0 { "net" } else { "mempool" };
+            view.push(LogLine::parse(format!(
+                "2024-05-01T12:00:0{}Z [{}] line {}",
+                i, category, i
+            )));
+        }
+        assert_eq!(view.first_row(view.visible().len()), 4);
+
+        view.cycle_category();
+        assert_eq!(view.category.as_deref(), Some("mempool"));
+        assert_eq!(view.visible().len(), 3);
+        view.cycle_category();
+        assert_eq!(view.category.as_deref(), Some("net"));
+        view.cycle_category();
+        assert_eq!(view.category, None);
+
+        view.search_input = Some("line [45]".into());
+        view.submit_search();
+        assert_eq!(view.visible().len(), 2);
+        view.search_input = Some("(".into());
+        view.submit_search();
+        assert!(view.message.as_deref().unwrap().starts_with("Bad pattern"));
+        view.search_input = Some(String::new());
+        view.submit_search();
+        assert_eq!(view.search.as_ref().map(Regex::as_str), None);
+
+        view.up(3);
+        assert!(!view.follow);
+        assert_eq!(view.first_row(view.visible().len()), 1);
+        view.down(PAGE);
+        assert!(view.follow);
+    }
+
+    #[test]
+    fn paused_filtered_view_stays_put_as_old_lines_go() {
+        let mut view = LogView::new();
+        view.height = 2;
+        let line = |i: usize| {
+            let category = ["net", "mempool"][i % 2];
+            LogLine::parse(format!("2024-05-01T12:00:00Z [{}] line {}", category, i))
+        };
+        for i in 0..MAX_LINES {
+            view.push(line(i));
+        }
+        view.cycle_category();
+        assert_eq!(view.category.as_deref(), Some("mempool"));
+        view.follow = false;
+        view.top = 100;
+        let shown = |view: &LogView| view.visible()[view.top].text.clone();
+        let before = shown(&view);
+
+        for i in MAX_LINES..MAX_LINES + 10 {
+            view.push(line(i));
+        }
+        // Ten lines dropped, five of them mempool lines
+        assert_eq!(view.top, 95);
+        assert_eq!(shown(&view), before);
+    }
+}
@@ -0,0 +1,266 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::rpc::types::{MempoolEntry, MempoolInfo};
+use ratatui::widgets::ListState;
+use std::time::{Duration, Instant};
+
+/// How often the mempool is re-read while it is shown. `getrawmempool true`
+/// is expensive on a full mempool, so this is slower than other screens.
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Lower bounds of the fee-rate buckets, in sat/vB.
+pub const FEE_BUCKETS: [f64; 16] = [
+    0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 8.0, 10.0, 15.0, 20.0, 30.0, 50.0, 100.0, 200.0, 500.0,
+];
+
+/// Transactions paying between `min_rate` and the next bucket's bound.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeBucket {
+    /// sat/vB.
+    pub min_rate: f64,
+    /// sat/vB; `None` for the last bucket.
+    pub max_rate: Option<f64>,
+    pub count: u64,
+    pub vsize: u64,
+}
+
+impl FeeBucket {
+    /// e.g. `10-15` or `500+`.
+    pub fn label(&self) -> String {
+        match self.max_rate {
+            Some(max) => format!("{}-{}", self.min_rate, max),
+            None => format!("{}+", self.min_rate),
+        }
+    }
+}
+
+/// Sorts `entries` into [`FEE_BUCKETS`] by their own fee rate.
+pub fn histogram<'a>(entries: impl IntoIterator<Item = &'a MempoolEntry>) -> Vec<FeeBucket> {
+    let mut buckets: Vec<FeeBucket> = FEE_BUCKETS
+        .iter()
+        .enumerate()
+        .map(|(i, min)| FeeBucket {
+            min_rate: *min,
+            max_rate: FEE_BUCKETS.get(i + 1).copied(),
+            count: 0,
+            vsize: 0,
+        })
+        .collect();
+    for entry in entries {
+        let rate = entry.fee_rate();
+        let index = FEE_BUCKETS
+            .iter()
+            .rposition(|min| rate >= *min)
+            .unwrap_or(0);
+        buckets[index].count += 1;
+        buckets[index].vsize += entry.vsize;
+    }
+    buckets
+}
+
+/// A mempool transaction and its unconfirmed relatives.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TxDetail {
+    pub txid: String,
+    pub entry: MempoolEntry,
+    pub ancestors: Vec<String>,
+    pub descendants: Vec<String>,
+}
+
+impl TxDetail {
+    /// Ancestors, then descendants, as shown in the related list.
+    pub fn related(&self) -> impl Iterator<Item = &String> {
+        self.ancestors.iter().chain(&self.descendants)
+    }
+}
+
+/// Whether `text` looks like a txid.
+pub fn is_txid(text: &str) -> bool {
+    text.len() == 64 && text.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// `MempoolView` shows mempool statistics and a fee-rate histogram, and
+/// lets the user drill into single transactions.
+#[derive(Debug, Default, Clone)]
+pub struct MempoolView {
+    pub info: Option<MempoolInfo>,
+    pub histogram: Vec<FeeBucket>,
+    /// Why the last refresh failed.
+    pub error: Option<String>,
+    /// When the last refresh was requested.
+    pub last_fetch: Option<Instant>,
+    /// Txid being typed.
+    pub txid_input: Option<String>,
+    /// The transaction drilled into.
+    pub detail: Option<TxDetail>,
+    /// Transactions drilled into before `detail`, for going back.
+    pub trail: Vec<String>,
+    /// Index into `detail`'s related transactions.
+    pub selected: usize,
+    /// Scroll state of the related list.
+    pub list_state: ListState,
+    /// Outcome of the last lookup.
+    pub message: Option<String>,
+}
+
+impl MempoolView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the statistics are due for a refresh.
+    pub fn is_stale(&self, now: Instant) -> bool {
+        self.last_fetch
+            .is_none_or(|last| now.duration_since(last) >= REFRESH_INTERVAL)
+    }
+
+    pub fn set_stats(&mut self, info: MempoolInfo, histogram: Vec<FeeBucket>) {
+        self.info = Some(info);
+        self.histogram = histogram;
+        self.error = None;
+    }
+
+    /// Shows `detail`, remembering the transaction it replaces.
+    pub fn show_detail(&mut self, detail: TxDetail) {
+        if let Some(previous) = self.detail.take()
+            && previous.txid != detail.txid
+        {
+            self.trail.push(previous.txid);
+        }
+        self.detail = Some(detail);
+        self.selected = 0;
+        self.message = None;
+    }
+
+    /// Leaves the current transaction. Returns the one to go back to, if
+    /// any; otherwise the histogram is shown again.
+    pub fn back(&mut self) -> Option<String> {
+        self.detail = None;
+        self.selected = 0;
+        self.trail.pop()
+    }
+
+    /// The txid typed so far, if it is complete and valid.
+    pub fn submit_txid(&mut self) -> Option<String> {
+        let input = self.txid_input.take()?;
+        let txid = input.trim().to_lowercase();
+        if is_txid(&txid) {
+            self.trail.clear();
+            Some(txid)
+        } else {
+            self.message = Some(format!("Not a txid: {}", input.trim()));
+            None
+        }
+    }
+
+    pub fn selected_related(&self) -> Option<&String> {
+        self.detail.as_ref()?.related().nth(self.selected)
+    }
+
+    /// Moves the selection to the next related transaction, wrapping around.
+    pub fn next(&mut self) {
+        let len = self.detail.as_ref().map_or(0, |d| d.related().count());
+        if len > 0 {
+            self.selected = (self.selected + 1) % len;
+        }
+    }
+
+    /// Moves the selection to the previous related transaction, wrapping around.
+    pub fn previous(&mut self) {
+        let len = self.detail.as_ref().map_or(0, |d| d.related().count());
+        if len > 0 {
+            self.selected = self.selected.checked_sub(1).unwrap_or(len - 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::types::MempoolFees;
+
+    fn entry(fee_sats: u64, vsize: u64) -> MempoolEntry {
+        let btc = fee_sats as f64 / 100_000_000.0;
+        MempoolEntry {
+            vsize,
+            weight: vsize * 4,
+            time: 0,
+            ancestorcount: 1,
+            ancestorsize: vsize,
+            descendantcount: 1,
+            descendantsize: vsize,
+            fees: MempoolFees {
+                base: btc,
+                modified: btc,
+                ancestor: btc,
+                descendant: btc,
+            },
+            depends: Vec::new(),
+            spentby: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn buckets_by_fee_rate() {
+        let entries = [
+            entry(141, 141),
+            entry(1410, 141),
+            entry(1000, 100),
+            entry(100_000, 100),
+        ];
+        let buckets = histogram(&entries);
+        let one = &buckets[1];
+        assert_eq!(
+            (one.label().as_str(), one.count, one.vsize),
+            ("1-2", 1, 141)
+        );
+        let ten = &buckets[8];
+        assert_eq!(
+            (ten.label().as_str(), ten.count, ten.vsize),
+            ("10-15", 2, 241)
+        );
+        let top = buckets.last().unwrap();
+        assert_eq!((top.label().as_str(), top.count), ("500+", 1));
+    }
+
+    #[test]
+    fn sub_satoshi_rates_land_in_first_bucket() {
+        let buckets = histogram(&[entry(50, 200)]);
+        assert_eq!(buckets[0].label(), "0-1");
+        assert_eq!(buckets[0].count, 1);
+    }
+
+    #[test]
+    fn validates_typed_txids() {
+        let mut view = MempoolView::new();
+        view.txid_input = Some("xyz".into());
+        assert_eq!(view.submit_txid(), None);
+        assert_eq!(view.message.as_deref(), Some("Not a txid: xyz"));
+
+        let txid = "AB".repeat(32);
+        view.txid_input = Some(format!(" {} ", txid));
+        assert_eq!(view.submit_txid(), Some(txid.to_lowercase()));
+    }
+
+    #[test]
+    fn drilling_down_keeps_a_trail() {
+        let detail = |txid: &str| TxDetail {
+            txid: txid.into(),
+            entry: entry(1000, 100),
+            ancestors: vec!["a".into()],
+            descendants: vec!["d1".into(), "d2".into()],
+        };
+        let mut view = MempoolView::new();
+        view.show_detail(detail("t1"));
+        view.previous();
+        assert_eq!(view.selected_related().map(String::as_str), Some("d2"));
+
+        view.show_detail(detail("d2"));
+        assert_eq!(view.selected, 0);
+        assert_eq!(view.back().as_deref(), Some("t1"));
+        assert_eq!(view.detail, None);
+        assert_eq!(view.back(), None);
+    }
+}
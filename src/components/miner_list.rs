@@ -0,0 +1,629 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Per-worker statistics gathered from p2poolv2's log.
+//!
+//! p2poolv2 logs stratum activity through `tracing`, whose default format
+//! appends the fields of an event as `key=value` after the message:
+//!
+//! ```text
+//! 2025-05-01T10:00:00Z  INFO p2poolv2::stratum: Share accepted worker="bc1q….rig1" ip=10.0.0.5:51234 difficulty=1024
+//! ```
+//!
+//! Lines with a worker field are classified by their message, so the
+//! table fills in as the p2poolv2 started from pdm writes its output. That
+//! output reaches here with colour codes removed, and the worker pdm's own
+//! stratum monitor logs in as is left out.
+
+use crate::components::peer_list;
+use crate::stratum;
+use ratatui::widgets::TableState;
+use std::cmp::{Ordering, Reverse};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Accepted shares within this long are counted towards the hashrate.
+pub const HASHRATE_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// Rows moved by Page Up and Page Down.
+pub const PAGE: usize = 10;
+
+/// Hashes needed on average to find a share of difficulty 1.
+const HASHES_PER_DIFFICULTY: f64 = 4_294_967_296.0;
+
+/// Field names p2poolv2 and its dependencies use for each value.
+const WORKER_FIELDS: [&str; 4] = ["worker", "worker_name", "username", "user"];
+const IP_FIELDS: [&str; 4] = ["ip", "addr", "peer", "remote_addr"];
+const DIFFICULTY_FIELDS: [&str; 2] = ["difficulty", "diff"];
+
+/// How a share submission was judged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareOutcome {
+    Accepted,
+    Rejected,
+    /// For a job that was already replaced.
+    Stale,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    Connected,
+    Disconnected,
+    Share(ShareOutcome),
+    /// Anything else about the worker, such as a difficulty change.
+    Other,
+}
+
+/// A log line about a worker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkerLine {
+    pub worker: String,
+    pub ip: Option<String>,
+    pub difficulty: Option<f64>,
+    pub activity: Activity,
+}
+
+impl WorkerLine {
+    /// `None` for lines that name no worker or name pdm's stratum
+    /// monitor.
+    pub fn parse(line: &str) -> Option<WorkerLine> {
+        let (message, fields) = split_fields(line);
+        let field = |names: &[&str]| {
+            fields
+                .iter()
+                .find(|(key, _)| names.contains(key))
+                .map(|(_, value)| value.clone())
+        };
+        let worker = field(&WORKER_FIELDS).filter(|w| !w.is_empty() && !is_monitor(w))?;
+        let ip = field(&IP_FIELDS).map(|addr| peer_list::ban_subnet(&addr).unwrap_or(addr));
+        let difficulty = field(&DIFFICULTY_FIELDS).and_then(|d| d.parse().ok());
+
+        let message = message.to_ascii_lowercase();
+        let share = message.contains("share");
+        let activity = if share && message.contains("stale") {
+            Activity::Share(ShareOutcome::Stale)
+        } else if share && (message.contains("reject") || message.contains("invalid")) {
+            Activity::Share(ShareOutcome::Rejected)
+        } else if share && message.contains("accept") {
+            Activity::Share(ShareOutcome::Accepted)
+        } else if message.contains("disconnect") {
+            Activity::Disconnected
+        } else if message.contains("connect") || message.contains("authorize") {
+            Activity::Connected
+        } else {
+            Activity::Other
+        };
+        Some(WorkerLine {
+            worker,
+            ip,
+            difficulty,
+            activity,
+        })
+    }
+}
+
+/// Whether `worker` is the login of pdm's stratum monitor, with or
+/// without a payout address in front.
+fn is_monitor(worker: &str) -> bool {
+    worker.rsplit('.').next() == Some(stratum::WORKER)
+}
+
+/// Splits a line into the text outside `key=value` fields and the fields,
+/// with quotes removed from values.
+fn split_fields(line: &str) -> (String, Vec<(&str, String)>) {
+    let mut message = String::new();
+    let mut fields = Vec::new();
+    let mut rest = line;
+    while !rest.is_empty() {
+        // Tokens end at whitespace or span punctuation outside quotes
+        let mut quoted = false;
+        let end = rest
+            .char_indices()
+            .find(|&(_, c)| {
+                if c == '"' {
+                    quoted = !quoted;
+                }
+                !quoted && (c.is_whitespace() || matches!(c, '{' | '}' | ','))
+            })
+            .map_or(rest.len(), |(i, _)| i);
+        let token = &rest[..end];
+        match token.split_once('=') {
+            Some((key, value))
+                if !key.is_empty()
+                    && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+            {
+                fields.push((key, value.trim_matches('"').to_string()));
+            }
+            _ => message.push_str(token),
+        }
+        let separator = rest[end..].chars().next().map_or(0, char::len_utf8);
+        message.push(' ');
+        rest = &rest[end + separator..];
+    }
+    (message, fields)
+}
+
+/// What has been seen of one worker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Miner {
+    /// The stratum username, often `address.rig`.
+    pub worker: String,
+    pub ip: Option<String>,
+    pub difficulty: Option<f64>,
+    pub accepted: u64,
+    pub rejected: u64,
+    pub stale: u64,
+    pub last_share: Option<Instant>,
+    pub connected: bool,
+    first_seen: Instant,
+    /// Arrival and difficulty of accepted shares within
+    /// [`HASHRATE_WINDOW`], oldest first.
+    shares: VecDeque<(Instant, f64)>,
+}
+
+impl Miner {
+    fn new(worker: String, now: Instant) -> Self {
+        Self {
+            worker,
+            ip: None,
+            difficulty: None,
+            accepted: 0,
+            rejected: 0,
+            stale: 0,
+            last_share: None,
+            connected: true,
+            first_seen: now,
+            shares: VecDeque::new(),
+        }
+    }
+
+    /// The name after the payout address, or the whole username.
+    pub fn short_name(&self) -> &str {
+        self.worker
+            .split_once('.')
+            .map_or(self.worker.as_str(), |(_, rig)| rig)
+    }
+
+    /// Hashes per second implied by the accepted shares within
+    /// [`HASHRATE_WINDOW`], or `None` without any.
+    pub fn hashrate(&self, now: Instant) -> Option<f64> {
+        let recent = self
+            .shares
+            .iter()
+            .filter(|(at, _)| now.duration_since(*at) < HASHRATE_WINDOW);
+        let work: f64 = recent.map(|(_, difficulty)| difficulty).sum();
+        if work == 0.0 {
+            return None;
+        }
+        // A worker seen only briefly would otherwise look absurdly fast
+        let span = now
+            .duration_since(self.first_seen)
+            .clamp(Duration::from_secs(60), HASHRATE_WINDOW);
+        Some(work * HASHES_PER_DIFFICULTY / span.as_secs_f64())
+    }
+
+    fn record(&mut self, line: WorkerLine, now: Instant) {
+        if line.ip.is_some() {
+            self.ip = line.ip;
+        }
+        let difficulty = line.difficulty.or(self.difficulty);
+        match line.activity {
+            Activity::Connected => self.connected = true,
+            Activity::Disconnected => self.connected = false,
+            Activity::Share(outcome) => {
+                self.connected = true;
+                self.last_share = Some(now);
+                match outcome {
+                    ShareOutcome::Accepted => {
+                        self.accepted += 1;
+                        self.shares.push_back((now, difficulty.unwrap_or(1.0)));
+                    }
+                    ShareOutcome::Rejected => self.rejected += 1,
+                    ShareOutcome::Stale => self.stale += 1,
+                }
+                // Share lines carry the share's difficulty, which is not
+                // necessarily the one assigned now
+                if self.difficulty.is_none() {
+                    self.difficulty = line.difficulty;
+                }
+                return;
+            }
+            Activity::Other => {}
+        }
+        if line.difficulty.is_some() {
+            self.difficulty = line.difficulty;
+        }
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while self
+            .shares
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) >= HASHRATE_WINDOW)
+        {
+            self.shares.pop_front();
+        }
+    }
+}
+
+/// Column the miner table is sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MinerSort {
+    Worker,
+    Difficulty,
+    Accepted,
+    Rejected,
+    Stale,
+    Hashrate,
+    LastShare,
+}
+
+impl MinerSort {
+    /// In table column order.
+    pub const ALL: [MinerSort; 7] = [
+        MinerSort::Worker,
+        MinerSort::Difficulty,
+        MinerSort::Accepted,
+        MinerSort::Rejected,
+        MinerSort::Stale,
+        MinerSort::Hashrate,
+        MinerSort::LastShare,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MinerSort::Worker => "Worker",
+            MinerSort::Difficulty => "Diff",
+            MinerSort::Accepted => "Acc",
+            MinerSort::Rejected => "Rej",
+            MinerSort::Stale => "Stale",
+            MinerSort::Hashrate => "Hashrate",
+            MinerSort::LastShare => "Last",
+        }
+    }
+
+    fn compare(&self, a: &Miner, b: &Miner, now: Instant) -> Ordering {
+        // Unknown values go last
+        fn known<T: PartialOrd>(a: Option<T>, b: Option<T>) -> Ordering {
+            match (a, b) {
+                (Some(x), Some(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+        }
+        match self {
+            MinerSort::Worker => a.short_name().cmp(b.short_name()),
+            MinerSort::Difficulty => known(a.difficulty, b.difficulty),
+            MinerSort::Accepted => a.accepted.cmp(&b.accepted),
+            MinerSort::Rejected => a.rejected.cmp(&b.rejected),
+            MinerSort::Stale => a.stale.cmp(&b.stale),
+            MinerSort::Hashrate => known(a.hashrate(now), b.hashrate(now)),
+            // Most recent first
+            MinerSort::LastShare => known(a.last_share.map(Reverse), b.last_share.map(Reverse)),
+        }
+    }
+}
+
+/// `MinerList` is the table of workers seen in p2poolv2's log.
+#[derive(Debug, Clone)]
+pub struct MinerList {
+    /// In order of first appearance.
+    pub miners: Vec<Miner>,
+    pub sort: MinerSort,
+    pub descending: bool,
+    /// Only workers whose name or IP contains this, ignoring case, are
+    /// shown.
+    pub filter: Option<String>,
+    /// Filter being typed.
+    pub filter_input: Option<String>,
+    /// Index into [`MinerList::visible`] of the selected worker.
+    pub selected: usize,
+    /// Scroll state of the rendered table.
+    pub table_state: TableState,
+}
+
+impl Default for MinerList {
+    fn default() -> Self {
+        Self {
+            miners: Vec::new(),
+            sort: MinerSort::Worker,
+            descending: false,
+            filter: None,
+            filter_input: None,
+            selected: 0,
+            table_state: TableState::default(),
+        }
+    }
+}
+
+impl MinerList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes in a line p2poolv2 wrote, if it is about a worker.
+    pub fn observe(&mut self, line: &str, now: Instant) {
+        let Some(line) = WorkerLine::parse(line) else {
+            return;
+        };
+        let index = match self.miners.iter().position(|m| m.worker == line.worker) {
+            Some(index) => index,
+            None => {
+                self.miners.push(Miner::new(line.worker.clone(), now));
+                self.miners.len() - 1
+            }
+        };
+        let miner = &mut self.miners[index];
+        miner.record(line, now);
+        miner.prune(now);
+    }
+
+    /// Marks every worker disconnected, as when p2poolv2 exits.
+    pub fn disconnect_all(&mut self) {
+        for miner in &mut self.miners {
+            miner.connected = false;
+        }
+    }
+
+    /// The workers that pass the filter, in display order.
+    pub fn visible(&self, now: Instant) -> Vec<&Miner> {
+        let filter = self.filter.as_ref().map(|f| f.to_lowercase());
+        let mut miners: Vec<&Miner> = self
+            .miners
+            .iter()
+            .filter(|m| {
+                filter.as_ref().is_none_or(|f| {
+                    m.worker.to_lowercase().contains(f)
+                        || m.ip.as_ref().is_some_and(|ip| ip.contains(f))
+                })
+            })
+            .collect();
+        let (sort, descending) = (self.sort, self.descending);
+        miners.sort_by(|a, b| {
+            let order = sort.compare(a, b, now).then(a.worker.cmp(&b.worker));
+            if descending { order.reverse() } else { order }
+        });
+        miners
+    }
+
+    pub fn selected_miner(&self, now: Instant) -> Option<&Miner> {
+        self.visible(now).get(self.selected).copied()
+    }
+
+    /// Sorts by the next column, keeping the selected worker selected.
+    pub fn cycle_sort(&mut self) {
+        let index = MinerSort::ALL
+            .iter()
+            .position(|s| *s == self.sort)
+            .unwrap_or(0);
+        self.keep_selection(|list| list.sort = MinerSort::ALL[(index + 1) % MinerSort::ALL.len()]);
+    }
+
+    pub fn reverse(&mut self) {
+        self.keep_selection(|list| list.descending = !list.descending);
+    }
+
+    /// Applies the typed filter; an empty one shows every worker.
+    pub fn submit_filter(&mut self) {
+        let Some(input) = self.filter_input.take() else {
+            return;
+        };
+        let input = input.trim().to_string();
+        self.keep_selection(|list| list.filter = (!input.is_empty()).then_some(input));
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.keep_selection(|list| list.filter = None);
+    }
+
+    /// Runs `change`, then selects the same worker if it is still shown.
+    fn keep_selection(&mut self, change: impl FnOnce(&mut Self)) {
+        let now = Instant::now();
+        let worker = self.selected_miner(now).map(|m| m.worker.clone());
+        change(self);
+        let visible = self.visible(now);
+        self.selected = worker
+            .and_then(|w| visible.iter().position(|m| m.worker == w))
+            .unwrap_or(0);
+    }
+
+    /// Moves the selection down by `rows`, stopping at the last worker.
+    pub fn down(&mut self, rows: usize) {
+        let count = self.visible(Instant::now()).len();
+        self.selected = self
+            .selected
+            .saturating_add(rows)
+            .min(count.saturating_sub(1));
+    }
+
+    /// Moves the selection up by `rows`, stopping at the first worker.
+    pub fn up(&mut self, rows: usize) {
+        self.selected = self.selected.saturating_sub(rows);
+    }
+}
+
+/// A hashrate with an SI prefix, such as `12.3 TH/s`.
+pub fn format_hashrate(hashes_per_second: f64) -> String {
+    const PREFIXES: [&str; 7] = ["", "k", "M", "G", "T", "P", "E"];
+    let mut value = hashes_per_second;
+    let mut prefix = 0;
+    while value >= 1000.0 && prefix + 1 < PREFIXES.len() {
+        value /= 1000.0;
+        prefix += 1;
+    }
+    format!("{:.1} {}H/s", value, PREFIXES[prefix])
+}
+
+/// A share difficulty, shortened with `K`/`M`/`G` when large.
+pub fn format_difficulty(difficulty: f64) -> String {
+    match difficulty {
+        d if d >= 1e9 => format!("{:.0}G", d / 1e9),
+        d if d >= 1e6 => format!("{:.0}M", d / 1e6),
+        d if d >= 1e4 => format!("{:.0}K", d / 1e3),
+        d if d.fract() == 0.0 => format!("{:.0}", d),
+        d => format!("{:.2}", d),
+    }
+}
+
+/// How long ago `then` was, in its largest unit: `45s`, `12m`, `3h`.
+pub fn format_age(then: Instant, now: Instant) -> String {
+    let secs = now.saturating_duration_since(then).as_secs();
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        _ => format!("{}h", secs / 3600),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn share(worker: &str, outcome: &str, difficulty: u64) -> String {
+        format!(
+            "2025-05-01T10:00:00.000000Z  INFO p2poolv2::stratum: Share {} worker=\"{}\" ip=10.0.0.5:51234 difficulty={}",
+            outcome, worker, difficulty
+        )
+    }
+
+    #[test]
+    fn parses_worker_lines() {
+        assert_eq!(
+            WorkerLine::parse(&share("bc1qpayout.rig1", "accepted", 1024)),
+            Some(WorkerLine {
+                worker: "bc1qpayout.rig1".into(),
+                ip: Some("10.0.0.5".into()),
+                difficulty: Some(1024.0),
+                activity: Activity::Share(ShareOutcome::Accepted),
+            })
+        );
+        let stale = WorkerLine::parse(
+            "WARN p2poolv2::stratum: Rejected stale share for job 4f worker=rig2 reason=\"job not found\"",
+        )
+        .unwrap();
+        assert_eq!(stale.activity, Activity::Share(ShareOutcome::Stale));
+        let span = WorkerLine::parse(
+            "INFO session{addr=[fd00::5]:4000 worker=rig3}: p2poolv2::stratum: Miner disconnected",
+        )
+        .unwrap();
+        assert_eq!(span.activity, Activity::Disconnected);
+        assert_eq!(span.ip.as_deref(), Some("fd00::5"));
+        // A worker called "accepted" is not a share
+        let named = WorkerLine::parse("INFO miner authorized worker=accepted").unwrap();
+        assert_eq!(named.activity, Activity::Connected);
+        assert_eq!(WorkerLine::parse("INFO Share accepted difficulty=1"), None);
+        let monitor = format!(
+            "INFO Miner authorized worker=bc1qpayout.{}",
+            stratum::WORKER
+        );
+        assert_eq!(WorkerLine::parse(&monitor), None);
+    }
+
+    #[test]
+    fn parses_coloured_tracing_output() {
+        // tracing-subscriber's default format as written to a terminal
+        let excerpt = [
+            "\x1b[2m2025-05-01T10:00:00.412903Z\x1b[0m \x1b[32m INFO\x1b[0m \x1b[2mp2poolv2_lib::stratum::server\x1b[0m\x1b[2m:\x1b[0m New connection \x1b[3maddr\x1b[0m\x1b[2m=\x1b[0m10.0.0.5:51234",
+            "\x1b[2m2025-05-01T10:00:01.001877Z\x1b[0m \x1b[32m INFO\x1b[0m \x1b[1msession\x1b[0m\x1b[1m{\x1b[0m\x1b[3maddr\x1b[0m\x1b[2m=\x1b[0m10.0.0.5:51234\x1b[1m}\x1b[0m\x1b[2m:\x1b[0m \x1b[2mp2poolv2_lib::stratum::session\x1b[0m\x1b[2m:\x1b[0m Miner authorized \x1b[3mworker\x1b[0m\x1b[2m=\x1b[0m\"bc1qpayout.rig1\"",
+            "\x1b[2m2025-05-01T10:00:09.530211Z\x1b[0m \x1b[32m INFO\x1b[0m \x1b[2mp2poolv2_lib::stratum::session\x1b[0m\x1b[2m:\x1b[0m Share accepted \x1b[3mworker\x1b[0m\x1b[2m=\x1b[0m\"bc1qpayout.rig1\" \x1b[3mdifficulty\x1b[0m\x1b[2m=\x1b[0m1024",
+        ];
+        let lines: Vec<WorkerLine> = excerpt
+            .iter()
+            .filter_map(|line| WorkerLine::parse(&crate::process::strip_escapes(line)))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                WorkerLine {
+                    worker: "bc1qpayout.rig1".into(),
+                    ip: Some("10.0.0.5".into()),
+                    difficulty: None,
+                    activity: Activity::Connected,
+                },
+                WorkerLine {
+                    worker: "bc1qpayout.rig1".into(),
+                    ip: None,
+                    difficulty: Some(1024.0),
+                    activity: Activity::Share(ShareOutcome::Accepted),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_shares_and_estimates_hashrate() {
+        let start = Instant::now();
+        let mut list = MinerList::new();
+        list.observe(
+            "INFO Miner authorized worker=bc1qpayout.rig1 ip=10.0.0.5 difficulty=600",
+            start,
+        );
+        for i in 1..=10 {
+            list.observe(
+                &share("bc1qpayout.rig1", "accepted", 600),
+                start + Duration::from_secs(i * 6),
+            );
+        }
+        list.observe(&share("bc1qpayout.rig1", "rejected", 600), start);
+        list.observe(&share("bc1qpayout.rig1", "stale", 600), start);
+
+        let miner = &list.miners[0];
+        assert_eq!(miner.short_name(), "rig1");
+        assert_eq!((miner.accepted, miner.rejected, miner.stale), (10, 1, 1));
+        assert_eq!(miner.difficulty, Some(600.0));
+        // 10 shares of difficulty 600 in 60 seconds
+        let rate = miner.hashrate(start + Duration::from_secs(60)).unwrap();
+        assert!(
+            (rate - 100.0 * HASHES_PER_DIFFICULTY).abs() < 1.0,
+            "{}",
+            rate
+        );
+        assert_eq!(miner.hashrate(start + HASHRATE_WINDOW * 2), None);
+        assert_eq!(format_hashrate(rate), "429.5 GH/s");
+    }
+
+    #[test]
+    fn sorts_and_filters_workers() {
+        let now = Instant::now();
+        let mut list = MinerList::new();
+        for (worker, shares) in [("rack1.a", 3), ("rack2.b", 1), ("rack1.c", 2)] {
+            for _ in 0..shares {
+                list.observe(&share(worker, "accepted", 1), now);
+            }
+        }
+        let names = |list: &MinerList| {
+            list.visible(now)
+                .iter()
+                .map(|m| m.short_name().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&list), ["a", "b", "c"]);
+
+        list.down(1);
+        list.cycle_sort();
+        list.cycle_sort();
+        assert_eq!(list.sort, MinerSort::Accepted);
+        assert_eq!(names(&list), ["b", "c", "a"]);
+        assert_eq!(list.selected_miner(now).unwrap().short_name(), "b");
+
+        list.reverse();
+        list.filter_input = Some("RACK1".into());
+        list.submit_filter();
+        assert_eq!(names(&list), ["a", "c"]);
+        assert_eq!(list.selected, 0);
+        list.filter_input = Some("10.0.0".into());
+        list.submit_filter();
+        assert_eq!(names(&list).len(), 3);
+    }
+
+    #[test]
+    fn formats_values() {
+        assert_eq!(format_difficulty(65536.0), "66K");
+        assert_eq!(format_difficulty(0.5), "0.50");
+        assert_eq!(format_hashrate(950.0), "950.0 H/s");
+        let now = Instant::now();
+        assert_eq!(format_age(now, now + Duration::from_secs(125)), "2m");
+    }
+}
@@ -2,4 +2,19 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+pub mod conf_picker;
+pub mod config_editor;
+pub mod effective_view;
+pub mod error_dialog;
 pub mod file_explorer;
+pub mod history_view;
+pub mod log_view;
+pub mod mempool_view;
+pub mod miner_list;
+pub mod p2pool_editor;
+pub mod peer_list;
+pub mod readiness_report;
+pub mod rpcauth_dialog;
+pub mod save_preview;
+pub mod setup_wizard;
+pub mod template_view;
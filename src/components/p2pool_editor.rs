@@ -0,0 +1,193 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_conf::options::ValueKind;
+use crate::p2pool_conf::P2poolConf;
+use crate::p2pool_conf::fields::{FIELDS, FieldSpec};
+use ratatui::widgets::ListState;
+
+/// `P2poolEditor` holds the UI state of the p2poolv2 config editor, the
+/// counterpart of [`ConfigEditor`](super::config_editor::ConfigEditor) for
+/// the settings in [`FIELDS`].
+#[derive(Debug, Default, Clone)]
+pub struct P2poolEditor {
+    /// Index into [`FIELDS`] of the selected setting.
+    pub selected: usize,
+    /// Scroll state of the rendered list.
+    pub list_state: ListState,
+    /// Text being typed for the selected setting, while editing.
+    pub input: Option<String>,
+    /// Whether the config was changed since it was loaded or saved.
+    pub modified: bool,
+    /// Feedback for the last action (validation errors, save results).
+    pub message: Option<String>,
+}
+
+impl P2poolEditor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn selected_spec(&self) -> &'static FieldSpec {
+        &FIELDS[self.selected]
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.input.is_some()
+    }
+
+    /// Moves the selection to the next setting, wrapping around.
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % FIELDS.len();
+    }
+
+    /// Moves the selection to the previous setting, wrapping around.
+    pub fn previous(&mut self) {
+        self.selected = self.selected.checked_sub(1).unwrap_or(FIELDS.len() - 1);
+    }
+
+    /// Cycles enum settings and starts typing for everything else.
+    pub fn activate(&mut self, conf: &mut P2poolConf) {
+        match self.selected_spec().kind {
+            ValueKind::Enum(_) => self.cycle(conf, true),
+            _ => self.input = Some(conf.get(self.selected_spec()).unwrap_or_default()),
+        }
+    }
+
+    /// Steps an enum setting through its values, with "unset" between the
+    /// last and first value.
+    pub fn cycle(&mut self, conf: &mut P2poolConf, forward: bool) {
+        let spec = self.selected_spec();
+        let ValueKind::Enum(choices) = spec.kind else {
+            return;
+        };
+        let current = conf
+            .get(spec)
+            .and_then(|v| choices.iter().position(|c| *c == v));
+        // Position `choices.len()` stands for "unset".
+        let slots = choices.len() + 1;
+        let position = current.unwrap_or(choices.len());
+        let next = if forward {
+            (position + 1) % slots
+        } else {
+            (position + slots - 1) % slots
+        };
+        match choices.get(next) {
+            Some(choice) => {
+                self.apply(conf, choice);
+            }
+            None => self.unset(conf),
+        }
+    }
+
+    /// Appends `c` to the input, ignoring characters an integer cannot contain.
+    pub fn insert_char(&mut self, c: char) {
+        let integer = matches!(self.selected_spec().kind, ValueKind::Integer { .. });
+        if let Some(input) = &mut self.input
+            && (!integer || c.is_ascii_digit() || (c == '-' && input.is_empty()))
+        {
+            input.push(c);
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if let Some(input) = &mut self.input {
+            input.pop();
+        }
+    }
+
+    /// Abandons the current input.
+    pub fn cancel(&mut self) {
+        self.input = None;
+        self.message = None;
+    }
+
+    /// Validates the input and writes it to `conf`. An empty input unsets
+    /// the setting. Invalid input is kept so it can be corrected.
+    pub fn commit(&mut self, conf: &mut P2poolConf) {
+        let Some(input) = self.input.clone() else {
+            return;
+        };
+        let value = input.trim();
+        if value.is_empty() {
+            self.input = None;
+            self.unset(conf);
+            return;
+        }
+        if self.apply(conf, value) {
+            self.input = None;
+        }
+    }
+
+    /// Removes the selected setting from `conf`.
+    pub fn unset(&mut self, conf: &mut P2poolConf) {
+        if conf.unset(self.selected_spec()) {
+            self.modified = true;
+        }
+        self.message = None;
+    }
+
+    fn apply(&mut self, conf: &mut P2poolConf, value: &str) -> bool {
+        match conf.set(self.selected_spec(), value) {
+            Ok(()) => {
+                self.modified = true;
+                self.message = None;
+                true
+            }
+            Err(err) => {
+                self.message = Some(err);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn select(editor: &mut P2poolEditor, table: &str, key: &str) {
+        editor.selected = FIELDS
+            .iter()
+            .position(|f| f.table == table && f.key == key)
+            .unwrap();
+    }
+
+    #[test]
+    fn cycling_network_passes_through_unset() {
+        let mut conf = P2poolConf::parse("[stratum]\nnetwork = \"regtest\"\n").unwrap();
+        let mut editor = P2poolEditor::new();
+        select(&mut editor, "stratum", "network");
+
+        editor.activate(&mut conf);
+        assert_eq!(conf.get(editor.selected_spec()), None);
+        editor.cycle(&mut conf, true);
+        assert_eq!(conf.get(editor.selected_spec()).as_deref(), Some("bitcoin"));
+        editor.cycle(&mut conf, false);
+        editor.cycle(&mut conf, false);
+        assert_eq!(conf.get(editor.selected_spec()).as_deref(), Some("regtest"));
+        assert!(editor.modified);
+    }
+
+    #[test]
+    fn invalid_input_is_kept_for_correction() {
+        let mut conf = P2poolConf::default();
+        let mut editor = P2poolEditor::new();
+        select(&mut editor, "stratum", "port");
+
+        editor.activate(&mut conf);
+        for c in "7x0000".chars() {
+            editor.insert_char(c);
+        }
+        editor.commit(&mut conf);
+        assert!(editor.is_editing());
+        assert!(editor.message.as_deref().unwrap().contains("stratum.port"));
+
+        editor.backspace();
+        editor.backspace();
+        editor.commit(&mut conf);
+        assert!(!editor.is_editing());
+        assert_eq!(conf.settings().stratum_port, Some(700));
+    }
+}
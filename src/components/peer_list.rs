@@ -0,0 +1,374 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::rpc::types::PeerInfo;
+use ratatui::widgets::TableState;
+use std::cmp::Ordering;
+use std::time::{Duration, Instant};
+
+/// How often the peer list is refreshed while it is shown.
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long `setban` bans a peer for.
+pub const BAN_SECONDS: u64 = 24 * 60 * 60;
+
+/// Column the peer table is sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerSort {
+    Address,
+    Type,
+    Ping,
+    Height,
+    Sent,
+    Received,
+    Version,
+}
+
+impl PeerSort {
+    /// In table column order.
+    pub const ALL: [PeerSort; 7] = [
+        PeerSort::Address,
+        PeerSort::Type,
+        PeerSort::Ping,
+        PeerSort::Height,
+        PeerSort::Sent,
+        PeerSort::Received,
+        PeerSort::Version,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PeerSort::Address => "Address",
+            PeerSort::Type => "Type",
+            PeerSort::Ping => "Ping",
+            PeerSort::Height => "Height",
+            PeerSort::Sent => "Sent",
+            PeerSort::Received => "Recv",
+            PeerSort::Version => "Version",
+        }
+    }
+
+    fn compare(&self, a: &PeerInfo, b: &PeerInfo) -> Ordering {
+        match self {
+            PeerSort::Address => a.addr.cmp(&b.addr),
+            PeerSort::Type => connection_label(a).cmp(connection_label(b)),
+            // Peers that have not answered a ping yet go last.
+            PeerSort::Ping => match (a.pingtime, b.pingtime) {
+                (Some(x), Some(y)) => x.total_cmp(&y),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+            PeerSort::Height => a.synced_blocks.cmp(&b.synced_blocks),
+            PeerSort::Sent => a.bytessent.cmp(&b.bytessent),
+            PeerSort::Received => a.bytesrecv.cmp(&b.bytesrecv),
+            PeerSort::Version => a.subver.cmp(&b.subver),
+        }
+    }
+}
+
+/// A change to the node's peers, waiting for confirmation or running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PeerAction {
+    Disconnect { id: u64, addr: String },
+    Ban { subnet: String },
+    Add { address: String },
+}
+
+impl PeerAction {
+    /// The question asked before running the action.
+    pub fn prompt(&self) -> String {
+        match self {
+            PeerAction::Disconnect { addr, .. } => format!("Disconnect {}? [y/n]", addr),
+            PeerAction::Ban { subnet } => format!("Ban {} for 24 hours? [y/n]", subnet),
+            PeerAction::Add { address } => format!("Add {} as a node? [y/n]", address),
+        }
+    }
+
+    /// What to report once the node accepted the action.
+    pub fn done(&self) -> String {
+        match self {
+            PeerAction::Disconnect { addr, .. } => format!("Disconnected {}", addr),
+            PeerAction::Ban { subnet } => format!("Banned {}", subnet),
+            PeerAction::Add { address } => format!("Added {}", address),
+        }
+    }
+}
+
+/// `PeerList` is the table of the node's peers from `getpeerinfo`.
+#[derive(Debug, Clone)]
+pub struct PeerList {
+    /// In display order.
+    pub peers: Vec<PeerInfo>,
+    pub sort: PeerSort,
+    pub descending: bool,
+    /// Index of the selected peer.
+    pub selected: usize,
+    /// Scroll state of the rendered table.
+    pub table_state: TableState,
+    /// Address being typed for `addnode`.
+    pub add_input: Option<String>,
+    /// Action waiting for confirmation.
+    pub pending: Option<PeerAction>,
+    /// Outcome of the last action.
+    pub message: Option<String>,
+    /// Why the last refresh failed.
+    pub error: Option<String>,
+    /// When the last refresh was requested.
+    pub last_fetch: Option<Instant>,
+}
+
+impl Default for PeerList {
+    fn default() -> Self {
+        Self {
+            peers: Vec::new(),
+            sort: PeerSort::Ping,
+            descending: false,
+            selected: 0,
+            table_state: TableState::default(),
+            add_input: None,
+            pending: None,
+            message: None,
+            error: None,
+            last_fetch: None,
+        }
+    }
+}
+
+impl PeerList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the list is due for a refresh.
+    pub fn is_stale(&self, now: Instant) -> bool {
+        self.last_fetch
+            .is_none_or(|last| now.duration_since(last) >= REFRESH_INTERVAL)
+    }
+
+    /// Replaces the peers, keeping the same peer selected if it is still
+    /// connected.
+    pub fn set_peers(&mut self, peers: Vec<PeerInfo>) {
+        let selected_id = self.selected_peer().map(|p| p.id);
+        self.peers = peers;
+        self.error = None;
+        self.sort_peers();
+        self.selected = selected_id
+            .and_then(|id| self.peers.iter().position(|p| p.id == id))
+            .unwrap_or(self.selected.min(self.peers.len().saturating_sub(1)));
+    }
+
+    /// Sorts by the next column.
+    pub fn cycle_sort(&mut self) {
+        let index = PeerSort::ALL
+            .iter()
+            .position(|s| *s == self.sort)
+            .unwrap_or(0);
+        self.sort = PeerSort::ALL[(index + 1) % PeerSort::ALL.len()];
+        self.resort();
+    }
+
+    pub fn reverse(&mut self) {
+        self.descending = !self.descending;
+        self.resort();
+    }
+
+    fn resort(&mut self) {
+        let selected_id = self.selected_peer().map(|p| p.id);
+        self.sort_peers();
+        if let Some(index) = selected_id.and_then(|id| self.peers.iter().position(|p| p.id == id)) {
+            self.selected = index;
+        }
+    }
+
+    fn sort_peers(&mut self) {
+        let (sort, descending) = (self.sort, self.descending);
+        self.peers.sort_by(|a, b| {
+            let order = sort.compare(a, b).then(a.id.cmp(&b.id));
+            if descending { order.reverse() } else { order }
+        });
+    }
+
+    pub fn selected_peer(&self) -> Option<&PeerInfo> {
+        self.peers.get(self.selected)
+    }
+
+    /// Moves the selection to the next peer, wrapping around.
+    pub fn next(&mut self) {
+        if !self.peers.is_empty() {
+            self.selected = (self.selected + 1) % self.peers.len();
+        }
+    }
+
+    /// Moves the selection to the previous peer, wrapping around.
+    pub fn previous(&mut self) {
+        if !self.peers.is_empty() {
+            self.selected = self.selected.checked_sub(1).unwrap_or(self.peers.len() - 1);
+        }
+    }
+
+    /// Asks to disconnect the selected peer.
+    pub fn request_disconnect(&mut self) {
+        if let Some(peer) = self.selected_peer() {
+            self.pending = Some(PeerAction::Disconnect {
+                id: peer.id,
+                addr: peer.addr.clone(),
+            });
+        }
+    }
+
+    /// Asks to ban the selected peer's address.
+    pub fn request_ban(&mut self) {
+        let Some(peer) = self.selected_peer() else {
+            return;
+        };
+        match ban_subnet(&peer.addr) {
+            Some(subnet) => self.pending = Some(PeerAction::Ban { subnet }),
+            None => {
+                self.message = Some(format!(
+                    "Cannot ban {}: setban only takes IP addresses",
+                    peer.addr
+                ))
+            }
+        }
+    }
+
+    /// Starts typing an address for `addnode`.
+    pub fn start_add(&mut self) {
+        self.add_input = Some(String::new());
+    }
+
+    /// Turns the typed address into a pending action.
+    pub fn submit_add(&mut self) {
+        if let Some(address) = self.add_input.take() {
+            let address = address.trim().to_string();
+            if !address.is_empty() {
+                self.pending = Some(PeerAction::Add { address });
+            }
+        }
+    }
+}
+
+/// The IP address `setban` needs for a `getpeerinfo` address, or `None`
+/// for Tor, I2P and CJDNS peers that cannot be banned by address.
+pub fn ban_subnet(addr: &str) -> Option<String> {
+    let host = match addr.strip_prefix('[') {
+        Some(rest) => rest.split_once(']').map_or(rest, |(host, _)| host),
+        None => addr.rsplit_once(':').map_or(addr, |(host, _)| host),
+    };
+    host.parse::<std::net::IpAddr>()
+        .ok()
+        .map(|ip| ip.to_string())
+}
+
+/// A short name for a peer's `connection_type`; every type but `in` is an
+/// outbound connection.
+pub fn connection_label(peer: &PeerInfo) -> &str {
+    match peer.connection_type.as_str() {
+        "outbound-full-relay" => "full",
+        "block-relay-only" => "block",
+        "addr-fetch" => "addr",
+        "inbound" => "in",
+        "" if peer.inbound => "in",
+        "" => "?",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(id: u64, addr: &str, ping: Option<f64>) -> PeerInfo {
+        PeerInfo {
+            id,
+            addr: addr.into(),
+            network: "ipv4".into(),
+            inbound: false,
+            connection_type: "outbound-full-relay".into(),
+            version: 70016,
+            subver: "/Satoshi:27.0.0/".into(),
+            pingtime: ping,
+            bytessent: id * 100,
+            bytesrecv: id * 1000,
+            conntime: 0,
+            synced_headers: 850_000,
+            synced_blocks: 850_000,
+        }
+    }
+
+    #[test]
+    fn sorts_by_ping_with_unknown_last() {
+        let mut list = PeerList::new();
+        list.set_peers(vec![
+            peer(1, "203.0.113.1:8333", None),
+            peer(2, "203.0.113.2:8333", Some(0.2)),
+            peer(3, "203.0.113.3:8333", Some(0.05)),
+        ]);
+        let ids: Vec<u64> = list.peers.iter().map(|p| p.id).collect();
+        assert_eq!(ids, [3, 2, 1]);
+
+        list.reverse();
+        let ids: Vec<u64> = list.peers.iter().map(|p| p.id).collect();
+        assert_eq!(ids, [1, 2, 3]);
+    }
+
+    #[test]
+    fn selection_follows_peer_across_updates() {
+        let mut list = PeerList::new();
+        list.set_peers(vec![
+            peer(1, "203.0.113.1:8333", Some(0.1)),
+            peer(2, "203.0.113.2:8333", Some(0.2)),
+        ]);
+        list.next();
+        assert_eq!(list.selected_peer().unwrap().id, 2);
+
+        list.set_peers(vec![
+            peer(2, "203.0.113.2:8333", Some(0.01)),
+            peer(1, "203.0.113.1:8333", Some(0.1)),
+        ]);
+        assert_eq!(list.selected, 0);
+        assert_eq!(list.selected_peer().unwrap().id, 2);
+
+        list.cycle_sort();
+        assert_eq!(list.sort, PeerSort::Height);
+        assert_eq!(list.selected_peer().unwrap().id, 2);
+    }
+
+    #[test]
+    fn ban_needs_an_ip_address() {
+        assert_eq!(
+            ban_subnet("203.0.113.5:8333").as_deref(),
+            Some("203.0.113.5")
+        );
+        assert_eq!(
+            ban_subnet("[2001:db8::1]:8333").as_deref(),
+            Some("2001:db8::1")
+        );
+        assert_eq!(ban_subnet("abcdefghij.onion:8333"), None);
+
+        let mut list = PeerList::new();
+        list.set_peers(vec![peer(4, "abcdefghij.onion:8333", None)]);
+        list.request_ban();
+        assert_eq!(list.pending, None);
+        assert!(list.message.as_deref().unwrap().starts_with("Cannot ban"));
+    }
+
+    #[test]
+    fn typed_address_becomes_pending_action() {
+        let mut list = PeerList::new();
+        list.start_add();
+        list.add_input
+            .as_mut()
+            .unwrap()
+            .push_str(" 198.51.100.7:8333 ");
+        list.submit_add();
+        assert_eq!(
+            list.pending,
+            Some(PeerAction::Add {
+                address: "198.51.100.7:8333".into()
+            })
+        );
+    }
+}
@@ -0,0 +1,53 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use ratatui::widgets::ListState;
+
+/// `ReadinessReport` holds the selection in the p2pool compatibility report.
+///
+/// The checks themselves are recomputed from the loaded config on every
+/// frame, so only the selected row is kept here.
+#[derive(Debug, Default, Clone)]
+pub struct ReadinessReport {
+    /// Index of the selected check.
+    pub selected: usize,
+    /// Scroll state of the rendered list.
+    pub list_state: ListState,
+}
+
+impl ReadinessReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the selection to the next of `len` checks, wrapping around.
+    pub fn next(&mut self, len: usize) {
+        if len > 0 {
+            self.selected = (self.selected + 1) % len;
+        }
+    }
+
+    /// Moves the selection to the previous of `len` checks, wrapping around.
+    pub fn previous(&mut self, len: usize) {
+        if len > 0 {
+            self.selected = self.selected.checked_sub(1).unwrap_or(len - 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_and_previous_wrap() {
+        let mut report = ReadinessReport::new();
+        report.previous(3);
+        assert_eq!(report.selected, 2);
+        report.next(3);
+        assert_eq!(report.selected, 0);
+        report.next(0);
+        assert_eq!(report.selected, 0);
+    }
+}
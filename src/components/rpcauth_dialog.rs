@@ -0,0 +1,114 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::bitcoin_conf::BitcoinConf;
+use crate::bitcoin_conf::rpcauth::{self, Credentials};
+
+/// User name suggested when the dialog opens.
+pub const DEFAULT_USER: &str = "p2pool";
+
+/// `RpcAuthDialog` asks for a user name, generates `rpcauth=` credentials
+/// for it and shows the plaintext password exactly once.
+///
+/// The dialog is dropped when closed, taking the password with it.
+#[derive(Debug, Clone)]
+pub struct RpcAuthDialog {
+    /// User name being typed.
+    pub user: String,
+    /// Credentials generated and added to the config, once confirmed.
+    pub generated: Option<Credentials>,
+    /// Why generation failed, if it did.
+    pub error: Option<String>,
+}
+
+impl Default for RpcAuthDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RpcAuthDialog {
+    pub fn new() -> Self {
+        Self {
+            user: DEFAULT_USER.to_string(),
+            generated: None,
+            error: None,
+        }
+    }
+
+    /// Appends `c` to the user name, until credentials are generated.
+    pub fn insert_char(&mut self, c: char) {
+        if self.generated.is_none() {
+            self.user.push(c);
+            self.error = None;
+        }
+    }
+
+    /// Removes the last character of the user name.
+    pub fn backspace(&mut self) {
+        if self.generated.is_none() {
+            self.user.pop();
+            self.error = None;
+        }
+    }
+
+    /// Generates credentials for the typed user name and appends the
+    /// `rpcauth=` line to `conf`. Returns whether the config was changed.
+    pub fn generate(&mut self, conf: &mut BitcoinConf) -> bool {
+        if self.generated.is_some() {
+            return false;
+        }
+        match rpcauth::generate(self.user.trim()) {
+            Ok(credentials) => {
+                let section = conf.section_for("rpcauth");
+                conf.add(section, "rpcauth", &credentials.rpcauth());
+                self.generated = Some(credentials);
+                self.error = None;
+                true
+            }
+            Err(err) => {
+                self.error = Some(err.to_string());
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_adds_rpcauth_line() {
+        let mut conf = BitcoinConf::parse("server=1\n");
+        let mut dialog = RpcAuthDialog::new();
+        dialog.backspace();
+        dialog.insert_char('L');
+
+        assert!(dialog.generate(&mut conf));
+        let credentials = dialog.generated.clone().unwrap();
+        assert_eq!(credentials.user, "p2pooL");
+
+        let line = conf.get(None, "rpcauth").unwrap();
+        assert!(rpcauth::verify(line, "p2pooL", &credentials.password));
+
+        // A second confirm does not add another user.
+        assert!(!dialog.generate(&mut conf));
+        assert_eq!(conf.get_all(None, "rpcauth").len(), 1);
+    }
+
+    #[test]
+    fn invalid_user_reports_error() {
+        let mut conf = BitcoinConf::default();
+        let mut dialog = RpcAuthDialog::new();
+        dialog.insert_char(':');
+
+        assert!(!dialog.generate(&mut conf));
+        assert!(dialog.error.as_deref().unwrap().contains("':'"));
+        assert!(conf.lines.is_empty());
+
+        dialog.backspace();
+        assert!(dialog.error.is_none());
+    }
+}
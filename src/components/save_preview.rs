@@ -0,0 +1,92 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::diff::{self, DiffLine};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// `SavePreview` shows the diff between a file on disk and the contents
+/// about to replace it, and waits for the user to confirm.
+#[derive(Debug, Clone)]
+pub struct SavePreview {
+    pub path: PathBuf,
+    pub diff: Vec<DiffLine>,
+    /// First diff line shown.
+    pub scroll: usize,
+}
+
+impl SavePreview {
+    /// Compares `path` with `contents`. Returns `None` if writing would not
+    /// change the file. A missing file is compared as empty.
+    pub fn new(path: &Path, contents: &str) -> io::Result<Option<Self>> {
+        let current = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        let name = path.display().to_string();
+        let diff = diff::unified(&current, contents, &name, &format!("{} (pending)", name));
+        Ok((!diff.is_empty()).then(|| Self {
+            path: path.to_path_buf(),
+            diff,
+            scroll: 0,
+        }))
+    }
+
+    /// Lines added and removed.
+    pub fn stats(&self) -> (usize, usize) {
+        diff::stats(&self.diff)
+    }
+
+    /// Scrolls down by `lines`, stopping at the last line.
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll = (self.scroll + lines).min(self.diff.len().saturating_sub(1));
+    }
+
+    /// Scrolls up by `lines`.
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("pdm_save_preview_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("bitcoin.conf")
+    }
+
+    #[test]
+    fn unchanged_file_has_no_preview() {
+        let path = scratch("unchanged");
+        fs::write(&path, "server=1\n").unwrap();
+        assert!(SavePreview::new(&path, "server=1\n").unwrap().is_none());
+    }
+
+    #[test]
+    fn new_file_is_all_additions() {
+        let path = scratch("new");
+        let preview = SavePreview::new(&path, "server=1\nprune=550\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(preview.stats(), (2, 0));
+    }
+
+    #[test]
+    fn scrolling_is_clamped() {
+        let path = scratch("scroll");
+        fs::write(&path, "a\n").unwrap();
+        let mut preview = SavePreview::new(&path, "b\n").unwrap().unwrap();
+        preview.scroll_down(100);
+        assert_eq!(preview.scroll, preview.diff.len() - 1);
+        preview.scroll_up(100);
+        assert_eq!(preview.scroll, 0);
+    }
+}
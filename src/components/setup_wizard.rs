@@ -0,0 +1,619 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::atomic_file;
+use crate::bitcoin_conf::readiness::MIN_PRUNE_MIB;
+use crate::bitcoin_conf::rpcauth::{self, Credentials};
+use crate::bitcoin_conf::{BitcoinConf, Network};
+use crate::p2pool_conf;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The pages of the wizard, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Network,
+    DataDir,
+    Pruning,
+    RpcAuth,
+    Zmq,
+    P2pool,
+    Review,
+}
+
+impl Step {
+    pub const ALL: [Step; 7] = [
+        Step::Network,
+        Step::DataDir,
+        Step::Pruning,
+        Step::RpcAuth,
+        Step::Zmq,
+        Step::P2pool,
+        Step::Review,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Step::Network => "Network",
+            Step::DataDir => "Data directory",
+            Step::Pruning => "Pruning",
+            Step::RpcAuth => "RPC authentication",
+            Step::Zmq => "ZMQ notifications",
+            Step::P2pool => "p2poolv2",
+            Step::Review => "Review",
+        }
+    }
+
+    /// One line explaining what the page is for.
+    pub fn help(&self) -> &'static str {
+        match self {
+            Step::Network => "Which Bitcoin network bitcoind and p2poolv2 will run on.",
+            Step::DataDir => "Where bitcoind keeps its blocks; bitcoin.conf is written here.",
+            Step::Pruning => "Pruned nodes save disk space but must keep recent blocks.",
+            Step::RpcAuth => "p2poolv2 logs into bitcoind's RPC server with this user.",
+            Step::Zmq => "bitcoind announces new blocks to p2poolv2 on these ports.",
+            Step::P2pool => "Where miners connect and where block rewards are paid.",
+            Step::Review => "Check the generated files before they are written.",
+        }
+    }
+
+    /// The inputs shown on this page.
+    pub fn fields(&self) -> &'static [Field] {
+        match self {
+            Step::Network => &[Field::Network],
+            Step::DataDir => &[Field::DataDir],
+            Step::Pruning => &[Field::Prune],
+            Step::RpcAuth => &[Field::RpcUser],
+            Step::Zmq => &[Field::ZmqHashBlockPort, Field::ZmqRawBlockPort],
+            Step::P2pool => &[
+                Field::StratumPort,
+                Field::MinerAddress,
+                Field::P2poolConfigPath,
+            ],
+            Step::Review => &[],
+        }
+    }
+}
+
+/// A single input of the wizard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Network,
+    DataDir,
+    Prune,
+    RpcUser,
+    ZmqHashBlockPort,
+    ZmqRawBlockPort,
+    StratumPort,
+    MinerAddress,
+    P2poolConfigPath,
+}
+
+impl Field {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Field::Network => "Network",
+            Field::DataDir => "bitcoind data directory",
+            Field::Prune => "Prune target in MiB (0 = keep all blocks)",
+            Field::RpcUser => "RPC user for p2poolv2",
+            Field::ZmqHashBlockPort => "zmqpubhashblock port",
+            Field::ZmqRawBlockPort => "zmqpubrawblock port",
+            Field::StratumPort => "Stratum port",
+            Field::MinerAddress => "Payout address",
+            Field::P2poolConfigPath => "p2poolv2 config file",
+        }
+    }
+
+    fn numeric(&self) -> bool {
+        matches!(
+            self,
+            Field::Prune | Field::ZmqHashBlockPort | Field::ZmqRawBlockPort | Field::StratumPort
+        )
+    }
+}
+
+/// `SetupWizard` walks a new operator through generating a bitcoin.conf and
+/// a p2poolv2 config from scratch. Nothing is written until [`write`] is
+/// called from the review page.
+///
+/// [`write`]: SetupWizard::write
+#[derive(Debug, Clone)]
+pub struct SetupWizard {
+    /// Index into [`Step::ALL`].
+    pub step: usize,
+    /// Index into the current step's fields.
+    pub field: usize,
+    pub network: Network,
+    pub datadir: String,
+    pub prune: String,
+    pub rpc_user: String,
+    pub zmq_hashblock_port: String,
+    pub zmq_rawblock_port: String,
+    pub stratum_port: String,
+    pub miner_address: String,
+    pub p2pool_config_path: String,
+    /// Credentials generated for `rpc_user` when leaving the RPC page.
+    pub credentials: Option<Credentials>,
+    /// Why the current page cannot be left, or why writing failed.
+    pub error: Option<String>,
+}
+
+impl SetupWizard {
+    /// Creates a wizard with defaults relative to the `home` directory.
+    pub fn new(home: &Path) -> Self {
+        Self {
+            step: 0,
+            field: 0,
+            network: Network::Main,
+            datadir: home.join(".bitcoin").display().to_string(),
+            prune: "0".to_string(),
+            rpc_user: "p2pool".to_string(),
+            zmq_hashblock_port: "28332".to_string(),
+            zmq_rawblock_port: "28333".to_string(),
+            stratum_port: "3333".to_string(),
+            miner_address: String::new(),
+            p2pool_config_path: home
+                .join(".p2poolv2")
+                .join("config.toml")
+                .display()
+                .to_string(),
+            credentials: None,
+            error: None,
+        }
+    }
+
+    pub fn current_step(&self) -> Step {
+        Step::ALL[self.step]
+    }
+
+    /// The selected input on the current page, if it has any.
+    pub fn current_field(&self) -> Option<Field> {
+        self.current_step().fields().get(self.field).copied()
+    }
+
+    /// The text shown for `field`.
+    pub fn value(&self, field: Field) -> String {
+        match field {
+            Field::Network => self.network.to_string(),
+            _ => self
+                .text(field)
+                .map(String::as_str)
+                .unwrap_or_default()
+                .to_string(),
+        }
+    }
+
+    fn text(&self, field: Field) -> Option<&String> {
+        match field {
+            Field::Network => None,
+            Field::DataDir => Some(&self.datadir),
+            Field::Prune => Some(&self.prune),
+            Field::RpcUser => Some(&self.rpc_user),
+            Field::ZmqHashBlockPort => Some(&self.zmq_hashblock_port),
+            Field::ZmqRawBlockPort => Some(&self.zmq_rawblock_port),
+            Field::StratumPort => Some(&self.stratum_port),
+            Field::MinerAddress => Some(&self.miner_address),
+            Field::P2poolConfigPath => Some(&self.p2pool_config_path),
+        }
+    }
+
+    fn text_mut(&mut self, field: Field) -> Option<&mut String> {
+        match field {
+            Field::Network => None,
+            Field::DataDir => Some(&mut self.datadir),
+            Field::Prune => Some(&mut self.prune),
+            Field::RpcUser => Some(&mut self.rpc_user),
+            Field::ZmqHashBlockPort => Some(&mut self.zmq_hashblock_port),
+            Field::ZmqRawBlockPort => Some(&mut self.zmq_rawblock_port),
+            Field::StratumPort => Some(&mut self.stratum_port),
+            Field::MinerAddress => Some(&mut self.miner_address),
+            Field::P2poolConfigPath => Some(&mut self.p2pool_config_path),
+        }
+    }
+
+    /// Types `c` into the selected input. Numeric inputs only take digits.
+    pub fn insert_char(&mut self, c: char) {
+        let Some(field) = self.current_field() else {
+            return;
+        };
+        if field.numeric() && !c.is_ascii_digit() {
+            return;
+        }
+        if let Some(text) = self.text_mut(field) {
+            text.push(c);
+            self.error = None;
+        }
+    }
+
+    /// Deletes the last character of the selected input.
+    pub fn backspace(&mut self) {
+        if let Some(text) = self.current_field().and_then(|f| self.text_mut(f)) {
+            text.pop();
+            self.error = None;
+        }
+    }
+
+    /// Selects the next input on the page, wrapping around.
+    pub fn next_field(&mut self) {
+        let len = self.current_step().fields().len();
+        if len > 0 {
+            self.field = (self.field + 1) % len;
+        }
+    }
+
+    /// Selects the previous input on the page, wrapping around.
+    pub fn previous_field(&mut self) {
+        let len = self.current_step().fields().len();
+        if len > 0 {
+            self.field = self.field.checked_sub(1).unwrap_or(len - 1);
+        }
+    }
+
+    /// Steps through the networks on the network page.
+    pub fn cycle_network(&mut self, forward: bool) {
+        if self.current_field() != Some(Field::Network) {
+            return;
+        }
+        let len = Network::ALL.len();
+        let index = Network::ALL
+            .iter()
+            .position(|n| *n == self.network)
+            .unwrap_or_default();
+        let next = if forward {
+            (index + 1) % len
+        } else {
+            (index + len - 1) % len
+        };
+        self.network = Network::ALL[next];
+    }
+
+    /// Validates the current page and moves to the next one.
+    pub fn next_step(&mut self) {
+        if let Err(err) = self.validate(self.current_step()) {
+            self.error = Some(err);
+            return;
+        }
+        if self.current_step() == Step::RpcAuth
+            && self.credentials.as_ref().map(|c| c.user.as_str()) != Some(self.rpc_user.trim())
+        {
+            match rpcauth::generate(self.rpc_user.trim()) {
+                Ok(credentials) => self.credentials = Some(credentials),
+                Err(err) => {
+                    self.error = Some(err.to_string());
+                    return;
+                }
+            }
+        }
+        if self.step + 1 < Step::ALL.len() {
+            self.step += 1;
+            self.field = 0;
+        }
+        self.error = None;
+    }
+
+    /// Moves to the previous page. Returns `false` on the first page, where
+    /// going back means leaving the wizard.
+    pub fn previous_step(&mut self) -> bool {
+        self.error = None;
+        if self.step == 0 {
+            return false;
+        }
+        self.step -= 1;
+        self.field = 0;
+        true
+    }
+
+    fn validate(&self, step: Step) -> Result<(), String> {
+        let port = |field: Field| -> Result<u16, String> {
+            match self.value(field).parse::<u16>() {
+                Ok(port) if port > 0 => Ok(port),
+                _ => Err(format!("{} must be between 1 and 65535", field.label())),
+            }
+        };
+        match step {
+            Step::DataDir if self.datadir.trim().is_empty() => {
+                Err("data directory must not be empty".to_string())
+            }
+            Step::Pruning => match self.prune.parse::<u64>() {
+                Ok(0) => Ok(()),
+                Ok(n) if n >= MIN_PRUNE_MIB => Ok(()),
+                _ => Err(format!("prune must be 0 or at least {}", MIN_PRUNE_MIB)),
+            },
+            Step::RpcAuth => rpcauth::validate_user(self.rpc_user.trim()),
+            Step::Zmq => {
+                if port(Field::ZmqHashBlockPort)? == port(Field::ZmqRawBlockPort)? {
+                    Err("ZMQ ports must differ".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+            Step::P2pool => {
+                port(Field::StratumPort)?;
+                if self.miner_address.trim().is_empty() {
+                    Err("payout address must not be empty".to_string())
+                } else if self.p2pool_config_path.trim().is_empty() {
+                    Err("p2poolv2 config file must not be empty".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Where the generated bitcoin.conf will be written.
+    pub fn bitcoin_conf_path(&self) -> PathBuf {
+        Path::new(self.datadir.trim()).join("bitcoin.conf")
+    }
+
+    /// Where the generated p2poolv2 config will be written.
+    pub fn p2pool_conf_path(&self) -> PathBuf {
+        PathBuf::from(self.p2pool_config_path.trim())
+    }
+
+    /// The bitcoin.conf described by the answers so far.
+    pub fn bitcoin_conf(&self) -> BitcoinConf {
+        let mut conf = BitcoinConf::parse("# Generated by pdm\n");
+        conf.set(None, "chain", self.network.name());
+        conf.set(None, "server", "1");
+        if let Ok(prune) = self.prune.parse::<u64>()
+            && prune != 0
+        {
+            conf.set(None, "prune", &prune.to_string());
+        }
+        if let Some(credentials) = &self.credentials {
+            conf.add(None, "rpcauth", &credentials.rpcauth());
+        }
+        conf.set(
+            None,
+            "zmqpubhashblock",
+            &format!("tcp://127.0.0.1:{}", self.zmq_hashblock_port),
+        );
+        conf.set(
+            None,
+            "zmqpubrawblock",
+            &format!("tcp://127.0.0.1:{}", self.zmq_rawblock_port),
+        );
+        conf
+    }
+
+    /// The p2poolv2 config described by the answers so far, as TOML.
+    pub fn p2pool_config(&self) -> String {
+        let store = self
+            .p2pool_conf_path()
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join("store.db");
+        let (user, password) = match &self.credentials {
+            Some(c) => (c.user.as_str(), c.password.as_str()),
+            None => (self.rpc_user.as_str(), ""),
+        };
+        format!(
+            r#"# Generated by pdm
+
+[network]
+listen_address = "/ip4/0.0.0.0/tcp/6884"
+dial_peers = []
+
+[store]
+path = {store}
+
+[stratum]
+hostname = "0.0.0.0"
+port = {stratum_port}
+start_difficulty = 10000
+minimum_difficulty = 100
+zmqpubhashblock = "tcp://127.0.0.1:{zmq_port}"
+network = "{network}"
+
+[bitcoinrpc]
+url = "http://127.0.0.1:{rpc_port}"
+username = {user}
+password = {password}
+
+[miner]
+address = {address}
+"#,
+            store = toml_string(&store.display().to_string()),
+            stratum_port = self.stratum_port,
+            zmq_port = self.zmq_hashblock_port,
+            network = p2pool_conf::network_name(self.network),
+            rpc_port = self.network.default_rpc_port(),
+            user = toml_string(user),
+            password = toml_string(password),
+            address = toml_string(self.miner_address.trim()),
+        )
+    }
+
+    /// Writes both files, refusing to overwrite existing ones. If the
+    /// second write fails the first file is removed again, so the wizard
+    /// can simply be retried.
+    ///
+    /// Both files are created readable by the owner only since they hold
+    /// credentials.
+    pub fn write(&self) -> io::Result<(PathBuf, PathBuf)> {
+        let bitcoin_path = self.bitcoin_conf_path();
+        let p2pool_path = self.p2pool_conf_path();
+        for path in [&bitcoin_path, &p2pool_path] {
+            if path.exists() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} already exists", path.display()),
+                ));
+            }
+        }
+        write_new(&bitcoin_path, &self.bitcoin_conf().to_string())?;
+        if let Err(err) = write_new(&p2pool_path, &self.p2pool_config()) {
+            let _ = fs::remove_file(&bitcoin_path);
+            return Err(err);
+        }
+        Ok((bitcoin_path, p2pool_path))
+    }
+}
+
+/// Quotes `s` as a TOML basic string.
+fn toml_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn write_new(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    atomic_file::write(path, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_conf::readiness;
+    use std::env::temp_dir;
+
+    fn filled(home: &Path) -> SetupWizard {
+        let mut wizard = SetupWizard::new(home);
+        wizard.cycle_network(true); // test
+        wizard.cycle_network(true); // testnet4
+        wizard.cycle_network(true); // signet
+        wizard.next_step();
+        wizard.next_step(); // datadir
+        wizard.next_step(); // pruning
+        wizard.next_step(); // rpc auth
+        wizard.next_step(); // zmq
+        wizard.field = 1;
+        for c in "tb1qexample".chars() {
+            wizard.insert_char(c);
+        }
+        wizard.next_step(); // p2pool
+        wizard
+    }
+
+    #[test]
+    fn walks_through_all_steps() {
+        let wizard = filled(Path::new("/home/pool"));
+        assert_eq!(wizard.current_step(), Step::Review);
+        assert_eq!(wizard.error, None);
+        assert_eq!(wizard.credentials.as_ref().unwrap().user, "p2pool");
+        assert_eq!(
+            wizard.bitcoin_conf_path(),
+            PathBuf::from("/home/pool/.bitcoin/bitcoin.conf")
+        );
+    }
+
+    #[test]
+    fn can_go_back_and_forth() {
+        let mut wizard = SetupWizard::new(Path::new("/home/pool"));
+        assert!(!wizard.previous_step());
+        wizard.next_step();
+        wizard.next_step();
+        assert_eq!(wizard.current_step(), Step::Pruning);
+        assert!(wizard.previous_step());
+        assert_eq!(wizard.current_step(), Step::DataDir);
+        assert_eq!(wizard.datadir, "/home/pool/.bitcoin");
+    }
+
+    #[test]
+    fn prune_is_written_as_a_number() {
+        let mut wizard = SetupWizard::new(Path::new("/home/pool"));
+        wizard.prune = "00".to_string();
+        assert_eq!(wizard.bitcoin_conf().get(None, "prune"), None);
+        wizard.prune = "010000".to_string();
+        assert_eq!(wizard.bitcoin_conf().get(None, "prune"), Some("10000"));
+    }
+
+    #[test]
+    fn invalid_input_blocks_the_step() {
+        let mut wizard = SetupWizard::new(Path::new("/home/pool"));
+        wizard.step = 2; // pruning
+        wizard.backspace();
+        for c in "1x00".chars() {
+            wizard.insert_char(c);
+        }
+        assert_eq!(wizard.prune, "100");
+        wizard.next_step();
+        assert_eq!(wizard.current_step(), Step::Pruning);
+        assert!(wizard.error.as_deref().unwrap().contains("10000"));
+
+        wizard.step = 4; // zmq
+        wizard.zmq_rawblock_port = wizard.zmq_hashblock_port.clone();
+        wizard.next_step();
+        assert_eq!(wizard.error.as_deref(), Some("ZMQ ports must differ"));
+
+        wizard.step = 5; // p2pool
+        wizard.next_step();
+        assert!(wizard.error.as_deref().unwrap().contains("payout address"));
+    }
+
+    #[test]
+    fn generated_bitcoin_conf_is_p2pool_ready() {
+        let wizard = filled(Path::new("/home/pool"));
+        let conf = wizard.bitcoin_conf();
+        assert_eq!(conf.chain(), Network::Signet);
+        assert!(readiness::is_ready(&readiness::check(
+            &conf,
+            Some(Network::Signet)
+        )));
+    }
+
+    #[test]
+    fn generated_p2pool_config_has_credentials() {
+        let wizard = filled(Path::new("/home/pool"));
+        let password = &wizard.credentials.as_ref().unwrap().password;
+        let toml = wizard.p2pool_config();
+        assert!(toml.contains("url = \"http://127.0.0.1:38332\""));
+        assert!(toml.contains(&format!("password = \"{}\"", password)));
+        assert!(toml.contains("network = \"signet\""));
+        assert!(toml.contains("address = \"tb1qexample\""));
+        assert!(toml.contains("path = \"/home/pool/.p2poolv2/store.db\""));
+    }
+
+    #[test]
+    fn write_creates_files_once() {
+        let home = temp_dir().join(format!("pdm_wizard_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        let wizard = filled(&home);
+
+        let (bitcoin, p2pool) = wizard.write().unwrap();
+        let conf = BitcoinConf::load(&bitcoin).unwrap();
+        assert_eq!(conf.to_string(), wizard.bitcoin_conf().to_string());
+        assert!(
+            fs::read_to_string(&p2pool)
+                .unwrap()
+                .contains("[bitcoinrpc]")
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&p2pool).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let err = wizard.write().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn failed_write_leaves_nothing_behind() {
+        let home = temp_dir().join(format!("pdm_wizard_rollback_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        fs::create_dir_all(&home).unwrap();
+        let mut wizard = filled(&home);
+        // A file where the p2pool config's directory should be
+        let blocker = home.join("blocker");
+        fs::write(&blocker, "").unwrap();
+        wizard.p2pool_config_path = blocker.join("p2pool.toml").display().to_string();
+
+        assert!(wizard.write().is_err());
+        assert!(!wizard.bitcoin_conf_path().exists());
+
+        wizard.p2pool_config_path = home.join("p2pool.toml").display().to_string();
+        assert!(wizard.write().is_ok());
+    }
+
+    #[test]
+    fn toml_strings_are_escaped() {
+        assert_eq!(toml_string(r#"a"b\c"#), r#""a\"b\\c""#);
+    }
+}
@@ -0,0 +1,193 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::rpc::types::{BlockTemplate, TemplateTransaction};
+use ratatui::widgets::TableState;
+use std::time::{Duration, Instant};
+
+/// How often the template is rebuilt while it is shown. A new block
+/// announced over ZMQ refreshes it sooner.
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Consensus limits, for templates from nodes that do not report them.
+pub const MAX_BLOCK_WEIGHT: u64 = 4_000_000;
+pub const MAX_BLOCK_SIGOPS: u64 = 80_000;
+
+/// Rows moved by PageUp and PageDown.
+pub const PAGE: usize = 10;
+
+/// Totals over a template's transactions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TemplateSummary {
+    /// Satoshis.
+    pub fees: u64,
+    /// Satoshis: the coinbase value less fees.
+    pub subsidy: u64,
+    pub weight: u64,
+    pub weight_limit: u64,
+    pub sigops: u64,
+    pub sigop_limit: u64,
+}
+
+impl TemplateSummary {
+    pub fn of(template: &BlockTemplate) -> Self {
+        let fees = template.transactions.iter().map(|tx| tx.fee).sum();
+        let or_default = |limit: u64, default| if limit == 0 { default } else { limit };
+        Self {
+            fees,
+            subsidy: template.coinbasevalue.saturating_sub(fees),
+            weight: template.transactions.iter().map(|tx| tx.weight).sum(),
+            weight_limit: or_default(template.weightlimit, MAX_BLOCK_WEIGHT),
+            sigops: template.transactions.iter().map(|tx| tx.sigops).sum(),
+            sigop_limit: or_default(template.sigoplimit, MAX_BLOCK_SIGOPS),
+        }
+    }
+}
+
+/// sat/vB of a template transaction.
+pub fn fee_rate(tx: &TemplateTransaction) -> f64 {
+    tx.fee as f64 * 4.0 / tx.weight.max(1) as f64
+}
+
+/// `TemplateView` shows the block `getblocktemplate` would have the pool
+/// mine, with its transactions by fee rate.
+#[derive(Debug, Default, Clone)]
+pub struct TemplateView {
+    pub template: Option<BlockTemplate>,
+    /// Indexes into the template's transactions, highest fee rate first.
+    pub order: Vec<usize>,
+    /// Why the last refresh failed.
+    pub error: Option<String>,
+    /// When the last refresh was requested.
+    pub last_fetch: Option<Instant>,
+    /// Index into `order`.
+    pub selected: usize,
+    /// Scroll state of the rendered table.
+    pub table_state: TableState,
+}
+
+impl TemplateView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the template is due for a refresh.
+    pub fn is_stale(&self, now: Instant) -> bool {
+        self.last_fetch
+            .is_none_or(|last| now.duration_since(last) >= REFRESH_INTERVAL)
+    }
+
+    pub fn set_template(&mut self, template: BlockTemplate) {
+        let txs = &template.transactions;
+        let mut order: Vec<usize> = (0..txs.len()).collect();
+        order.sort_by(|a, b| {
+            fee_rate(&txs[*b])
+                .total_cmp(&fee_rate(&txs[*a]))
+                .then(a.cmp(b))
+        });
+        // A new template rarely shares positions with the old one
+        if self.template.as_ref().map(|t| &t.previousblockhash) != Some(&template.previousblockhash)
+        {
+            self.selected = 0;
+        }
+        self.selected = self.selected.min(order.len().saturating_sub(1));
+        self.order = order;
+        self.template = Some(template);
+        self.error = None;
+    }
+
+    pub fn summary(&self) -> Option<TemplateSummary> {
+        self.template.as_ref().map(TemplateSummary::of)
+    }
+
+    /// The template's transactions in display order, with their 1-based
+    /// position in the template.
+    pub fn transactions(&self) -> impl Iterator<Item = (usize, &TemplateTransaction)> {
+        let txs = self.template.as_ref().map_or(&[][..], |t| &t.transactions);
+        self.order.iter().map(move |i| (i + 1, &txs[*i]))
+    }
+
+    pub fn selected_transaction(&self) -> Option<&TemplateTransaction> {
+        let index = *self.order.get(self.selected)?;
+        self.template.as_ref()?.transactions.get(index)
+    }
+
+    /// Moves the selection down by `rows`, stopping at the last row.
+    pub fn down(&mut self, rows: usize) {
+        self.selected = self
+            .selected
+            .saturating_add(rows)
+            .min(self.order.len().saturating_sub(1));
+    }
+
+    /// Moves the selection up by `rows`, stopping at the first row.
+    pub fn up(&mut self, rows: usize) {
+        self.selected = self.selected.saturating_sub(rows);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(txid: &str, fee: u64, weight: u64) -> TemplateTransaction {
+        TemplateTransaction {
+            txid: txid.into(),
+            fee,
+            sigops: 4,
+            weight,
+            depends: Vec::new(),
+        }
+    }
+
+    fn template(transactions: Vec<TemplateTransaction>) -> BlockTemplate {
+        BlockTemplate {
+            version: 0x2000_0000,
+            previousblockhash: "00".repeat(32),
+            height: 850_001,
+            coinbasevalue: 312_500_000 + transactions.iter().map(|t| t.fee).sum::<u64>(),
+            transactions,
+            sigoplimit: 0,
+            weightlimit: 0,
+            curtime: 0,
+            bits: "17034219".into(),
+            target: String::new(),
+        }
+    }
+
+    #[test]
+    fn sorts_by_fee_rate_and_sums_totals() {
+        let mut view = TemplateView::new();
+        view.set_template(template(vec![
+            tx("a", 1_000, 800),
+            tx("b", 10_000, 800),
+            tx("c", 5_000, 2_000),
+        ]));
+        let order: Vec<&str> = view.transactions().map(|(_, t)| t.txid.as_str()).collect();
+        assert_eq!(order, ["b", "c", "a"]);
+        assert_eq!(view.transactions().next().unwrap().0, 2);
+        assert_eq!(fee_rate(view.selected_transaction().unwrap()), 50.0);
+
+        let summary = view.summary().unwrap();
+        assert_eq!(summary.fees, 16_000);
+        assert_eq!(summary.subsidy, 312_500_000);
+        assert_eq!(summary.weight, 3_600);
+        assert_eq!(summary.weight_limit, MAX_BLOCK_WEIGHT);
+        assert_eq!(summary.sigops, 12);
+    }
+
+    #[test]
+    fn selection_stays_in_range() {
+        let mut view = TemplateView::new();
+        view.set_template(template(vec![tx("a", 1, 400), tx("b", 2, 400)]));
+        view.down(PAGE);
+        assert_eq!(view.selected, 1);
+        view.up(PAGE);
+        assert_eq!(view.selected, 0);
+
+        view.down(1);
+        view.set_template(template(vec![tx("a", 1, 400)]));
+        assert_eq!(view.selected, 0);
+    }
+}
@@ -0,0 +1,98 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Line based unified diffs of config files.
+
+use similar::{ChangeTag, TextDiff};
+
+/// Lines of context shown around each change.
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    /// `---`/`+++` file names.
+    Header,
+    /// `@@ -a,b +c,d @@` hunk ranges.
+    Hunk,
+    Context,
+    Added,
+    Removed,
+}
+
+/// One line of a unified diff, without its trailing newline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+/// A unified diff from `old` to `new`. Empty if they are identical.
+pub fn unified(old: &str, new: &str, old_name: &str, new_name: &str) -> Vec<DiffLine> {
+    let diff = TextDiff::from_lines(old, new);
+    let mut out = Vec::new();
+    let line = |kind, text: String| DiffLine { kind, text };
+
+    let mut unified = diff.unified_diff();
+    unified.context_radius(CONTEXT);
+    for (i, hunk) in unified.iter_hunks().enumerate() {
+        if i == 0 {
+            out.push(line(DiffKind::Header, format!("--- {}", old_name)));
+            out.push(line(DiffKind::Header, format!("+++ {}", new_name)));
+        }
+        out.push(line(DiffKind::Hunk, hunk.header().to_string()));
+        for change in hunk.iter_changes() {
+            let (kind, sign) = match change.tag() {
+                ChangeTag::Equal => (DiffKind::Context, ' '),
+                ChangeTag::Insert => (DiffKind::Added, '+'),
+                ChangeTag::Delete => (DiffKind::Removed, '-'),
+            };
+            let text = change.value().trim_end_matches(['\n', '\r']);
+            out.push(line(kind, format!("{}{}", sign, text)));
+        }
+    }
+    out
+}
+
+/// Number of added and removed lines in `diff`.
+pub fn stats(diff: &[DiffLine]) -> (usize, usize) {
+    let count = |kind| diff.iter().filter(|l| l.kind == kind).count();
+    (count(DiffKind::Added), count(DiffKind::Removed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_texts_have_no_diff() {
+        assert!(unified("a\nb\n", "a\nb\n", "old", "new").is_empty());
+    }
+
+    #[test]
+    fn changed_line_is_removed_and_added() {
+        let diff = unified("server=1\nprune=550\n", "server=1\nprune=10000\n", "a", "b");
+        let text: Vec<&str> = diff.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(
+            text,
+            vec![
+                "--- a",
+                "+++ b",
+                "@@ -1,2 +1,2 @@",
+                " server=1",
+                "-prune=550",
+                "+prune=10000"
+            ]
+        );
+        assert_eq!(stats(&diff), (1, 1));
+    }
+
+    #[test]
+    fn distant_changes_get_separate_hunks() {
+        let old: String = (0..20).map(|i| format!("l{}\n", i)).collect();
+        let new = old.replace("l1\n", "x\n").replace("l18\n", "y\n");
+        let diff = unified(&old, &new, "a", "b");
+        let hunks = diff.iter().filter(|l| l.kind == DiffKind::Hunk).count();
+        assert_eq!(hunks, 2);
+    }
+}
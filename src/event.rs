@@ -0,0 +1,156 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! The events `run_app` reacts to, merged onto one channel.
+//!
+//! Terminal input, a periodic tick, ZMQ and stratum notifications and
+//! results from background tasks all arrive through an [`EventChannel`],
+//! so the loop reacts to any of them instead of waiting for a keypress.
+//! The screen is redrawn on input and on every tick.
+
+use crate::node_status::NodeStatus;
+use crate::process::ProcessEvent;
+use crate::stratum::StratumEvent;
+use crate::tasks::RpcReply;
+use crate::zmq::ZmqEvent;
+use crossterm::event::{self, Event};
+use std::io;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// Redraws per second unless configured otherwise.
+pub const DEFAULT_FRAME_RATE: u32 = 4;
+
+#[derive(Debug)]
+pub enum AppEvent {
+    /// Key presses, resizes and other terminal events.
+    Input(Event),
+    /// Time to redraw.
+    Tick,
+    /// A result from the [`StatusPoller`](crate::node_status::StatusPoller)
+    /// with id `poller`.
+    NodeStatus {
+        poller: u64,
+        status: Result<NodeStatus, String>,
+    },
+    /// A task started through [`RpcTasks`](crate::tasks::RpcTasks) finished.
+    Rpc(RpcReply),
+    /// A notification from the [`ZmqSubscriber`](crate::zmq::ZmqSubscriber)
+    /// with id `subscriber`.
+    Zmq { subscriber: u64, event: ZmqEvent },
+    /// Output or the exit of the child [`Process`](crate::process::Process)
+    /// with id `process`.
+    Process { process: u64, event: ProcessEvent },
+    /// What the [`StratumMonitor`](crate::stratum::StratumMonitor) with id
+    /// `monitor` heard from the pool.
+    Stratum { monitor: u64, event: StratumEvent },
+}
+
+impl From<Event> for AppEvent {
+    fn from(event: Event) -> Self {
+        AppEvent::Input(event)
+    }
+}
+
+/// The interval between ticks for `frame_rate` redraws per second.
+pub fn tick_interval(frame_rate: u32) -> Duration {
+    Duration::from_secs(1) / frame_rate.max(1)
+}
+
+pub struct EventChannel {
+    sender: Sender<AppEvent>,
+    receiver: Receiver<AppEvent>,
+}
+
+impl EventChannel {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self { sender, receiver }
+    }
+
+    /// A handle background tasks send their results through.
+    pub fn sender(&self) -> Sender<AppEvent> {
+        self.sender.clone()
+    }
+
+    /// Forwards terminal events from a reader thread.
+    pub fn spawn_input(&self) {
+        let sender = self.sender();
+        thread::spawn(move || {
+            loop {
+                let event = match event::read() {
+                    Ok(event) => event,
+                    Err(_) => return,
+                };
+                if sender.send(AppEvent::Input(event)).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+
+    /// Sends a [`AppEvent::Tick`] `frame_rate` times per second.
+    pub fn spawn_ticks(&self, frame_rate: u32) {
+        let sender = self.sender();
+        let interval = tick_interval(frame_rate);
+        thread::spawn(move || {
+            loop {
+                thread::sleep(interval);
+                if sender.send(AppEvent::Tick).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+
+    /// Waits for the next event.
+    pub fn next(&self) -> io::Result<AppEvent> {
+        // Never disconnected: we hold a sender ourselves.
+        self.receiver
+            .recv()
+            .map_err(|err| io::Error::new(io::ErrorKind::BrokenPipe, err))
+    }
+}
+
+impl Default for EventChannel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn frame_rate_sets_tick_interval() {
+        assert_eq!(tick_interval(4), Duration::from_millis(250));
+        assert_eq!(tick_interval(0), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn merges_ticks_and_background_results() {
+        let events = EventChannel::new();
+        let sender = events.sender();
+        thread::spawn(move || {
+            sender
+                .send(AppEvent::NodeStatus {
+                    poller: 7,
+                    status: Err("down".into()),
+                })
+                .unwrap();
+        });
+        assert!(matches!(
+            events.next().unwrap(),
+            AppEvent::NodeStatus { poller: 7, .. }
+        ));
+
+        let start = Instant::now();
+        events.spawn_ticks(50);
+        assert!(matches!(events.next().unwrap(), AppEvent::Tick));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+}
@@ -3,5 +3,18 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 pub mod app;
+pub mod atomic_file;
+pub mod backup;
+pub mod bitcoin_conf;
 pub mod components;
+pub mod diff;
+pub mod event;
+pub mod node_status;
+pub mod p2pool_conf;
+pub mod process;
+pub mod rpc;
+pub mod state;
+pub mod stratum;
+pub mod tasks;
 pub mod ui;
+pub mod zmq;
@@ -2,19 +2,37 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
-use pdm::app::{App, CurrentScreen};
+use pdm::app::{
+    App, ConfKind, ConfigView, CurrentScreen, Discard, EXIT_GRACE, Focus, P2poolView, SIDEBAR_ITEMS,
+};
+use pdm::bitcoin_conf::discover::Discovery;
+use pdm::bitcoin_conf::readiness::{self, FixAction};
+use pdm::components::conf_picker::Choice;
+use pdm::components::log_view;
+use pdm::components::miner_list;
+use pdm::components::rpcauth_dialog::RpcAuthDialog;
+use pdm::components::setup_wizard::Step;
+use pdm::components::template_view;
+use pdm::event::{AppEvent, EventChannel};
+use pdm::process::{self, ProcessAction, Supervisor};
+use pdm::state::PdmState;
 use pdm::ui;
 
 use anyhow::Result;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{Event, KeyCode, KeyEventKind},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use ratatui::{Terminal, backend::Backend, backend::CrosstermBackend};
 use std::io;
+use std::time::{Duration, Instant};
 
 fn main() -> Result<()> {
+    //  Restore the previous session, or start the setup wizard
+    let mut app = App::from_state(PdmState::default_path());
+    app.discovery = Discovery::from_env();
+
     //  Setup Terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -22,9 +40,15 @@ fn main() -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
+    //  Merge input, ticks and background results into one stream
+    let events = EventChannel::new();
+    events.spawn_input();
+    events.spawn_ticks(app.frame_rate);
+    app.events = Some(events.sender());
+    app.watch_node();
+
     //  Run App
-    let mut app = App::new();
-    let res = run_app(&mut terminal, &mut app, |_app: &mut App| event::read());
+    let res = run_app(&mut terminal, &mut app, |_app: &mut App| events.next());
 
     //  Restore Terminal
     disable_raw_mode()?;
@@ -35,68 +59,843 @@ fn main() -> Result<()> {
         println!("{err:?}");
     }
 
+    //  Stop what was started from pdm
+    let stopping = app.stop_children();
+    if !stopping.is_empty() {
+        println!("Waiting for {} to stop...", stopping.join(" and "));
+        for note in wait_for_children(&mut app, || events.next(), EXIT_GRACE)? {
+            println!("{note}");
+        }
+    }
+
     Ok(())
 }
 
+/// Feeds process events to `app` until nothing it started is running, or
+/// until the first tick after `grace`. Then a p2poolv2 still running is
+/// killed, while bitcoind is left to finish flushing its databases, and
+/// what was done about each is returned.
+fn wait_for_children<F>(
+    app: &mut App,
+    mut next_event: F,
+    grace: Duration,
+) -> io::Result<Vec<String>>
+where
+    F: FnMut() -> io::Result<AppEvent>,
+{
+    let deadline = Instant::now() + grace;
+    while app.bitcoind.is_running() || app.p2pool.is_running() {
+        match next_event()? {
+            AppEvent::Process { process, event } => app.handle_process_event(process, event),
+            AppEvent::Tick if Instant::now() >= deadline => {
+                let mut notes = Vec::new();
+                if let Some(process) = &app.p2pool.process {
+                    notes.push(match process.kill() {
+                        Ok(()) => {
+                            format!("Killed p2poolv2 (pid {}), it ignored SIGTERM", process.pid)
+                        }
+                        Err(err) => {
+                            format!("Could not kill p2poolv2 (pid {}): {}", process.pid, err)
+                        }
+                    });
+                }
+                if let Some(process) = &app.bitcoind.process {
+                    notes.push(format!(
+                        "bitcoind (pid {}) is still shutting down",
+                        process.pid
+                    ));
+                }
+                return Ok(notes);
+            }
+            _ => {}
+        }
+    }
+    Ok(Vec::new())
+}
+
 // Accept any Backend and an Event Provider Closure
-fn run_app<B: Backend, F>(
+fn run_app<B: Backend, F, E>(
     terminal: &mut Terminal<B>,
     app: &mut App,
     mut event_provider: F,
 ) -> io::Result<()>
 where
-    F: FnMut(&mut App) -> io::Result<Event>,
+    F: FnMut(&mut App) -> io::Result<E>,
+    E: Into<AppEvent>,
 {
+    let mut redraw = true;
     loop {
-        terminal.draw(|f| ui::ui(f, app))?;
+        if redraw {
+            terminal.draw(|f| ui::ui(f, app))?;
+        }
 
-        // We check the event from our provider
-        if let Event::Key(key) = event_provider(app)?
-            && key.kind == KeyEventKind::Press
-        {
-            if key.code == KeyCode::Char('q') {
+        // We check the event from our provider. Background events only
+        // update state and show up on the next tick, so a burst of them
+        // does not cost a redraw each.
+        let event = event_provider(app)?.into();
+        redraw = matches!(event, AppEvent::Tick | AppEvent::Input(_));
+        let key = match event {
+            AppEvent::Input(Event::Key(key)) if key.kind == KeyEventKind::Press => key,
+            AppEvent::NodeStatus { poller, status } => {
+                app.handle_node_status(poller, status);
+                continue;
+            }
+            AppEvent::Rpc(reply) => {
+                app.handle_rpc_reply(reply);
+                continue;
+            }
+            AppEvent::Zmq { subscriber, event } => {
+                app.handle_zmq(subscriber, event);
+                continue;
+            }
+            AppEvent::Process { process, event } => {
+                app.handle_process_event(process, event);
+                continue;
+            }
+            AppEvent::Stratum { monitor, event } => {
+                app.handle_stratum(monitor, event);
+                continue;
+            }
+            AppEvent::Tick => {
+                app.tick();
+                continue;
+            }
+            // Resizes and key releases only need a redraw
+            AppEvent::Input(_) => continue,
+        };
+
+        // While typing a value, 'q' is just a character
+        if key.code == KeyCode::Char('q') && !app.is_typing() {
+            if app.unsaved(Discard::Quit).is_empty() {
                 return Ok(());
             }
-            match app.current_screen {
-                // File Explorer Modal
-                CurrentScreen::FileExplorer => match key.code {
-                    KeyCode::Up => app.explorer.previous(),
-                    KeyCode::Down => app.explorer.next(),
-                    KeyCode::Esc => app.toggle_menu(), // Cancel
-                    KeyCode::Enter => {
-                        if let Some(path) = app.explorer.select() {
-                            // File Selected!
-                            app.bitcoin_conf_path = Some(path);
-                            app.toggle_menu(); // Go back to main screen
-                        }
-                    }
-                    _ => {}
-                },
+            app.discard_prompt = Some(Discard::Quit);
+            continue;
+        }
+        // Dialogs take every key until they are closed
+        if app.error_dialog.is_some() {
+            if matches!(key.code, KeyCode::Enter | KeyCode::Esc) {
+                app.error_dialog = None;
+            }
+            continue;
+        }
+        if app.rpcauth_dialog.is_some() {
+            handle_rpcauth_dialog_key(app, key.code);
+            continue;
+        }
+        if app.save_preview.is_some() {
+            handle_save_preview_key(app, key.code);
+            continue;
+        }
+        if let Some(action) = app.discard_prompt.take() {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Enter if action == Discard::Quit => return Ok(()),
+                KeyCode::Char('y') | KeyCode::Enter => app.confirm_discard(action),
+                KeyCode::Char('n') | KeyCode::Esc => {}
+                _ => app.discard_prompt = Some(action),
+            }
+            continue;
+        }
+        match app.current_screen {
+            // First-run Setup
+            CurrentScreen::Wizard => handle_wizard_key(app, key.code),
 
-                // Standard Navigation
-                _ => match key.code {
-                    KeyCode::Up => {
-                        if app.sidebar_index > 0 {
-                            app.sidebar_index -= 1;
-                            app.toggle_menu();
+            // File Explorer Modal
+            CurrentScreen::FileExplorer => match key.code {
+                KeyCode::Up => app.explorer.previous(),
+                KeyCode::Down => app.explorer.next(),
+                KeyCode::Esc => app.toggle_menu(), // Cancel
+                KeyCode::Enter => {
+                    if let Some(path) = app.explorer.select() {
+                        // File Selected!
+                        match app.explorer_target {
+                            ConfKind::Bitcoin => app.load_bitcoin_conf(path),
+                            ConfKind::P2pool => app.load_p2pool_conf(path),
                         }
-                    }
-                    KeyCode::Down => {
-                        if app.sidebar_index < 1 {
-                            app.sidebar_index += 1;
-                            app.toggle_menu();
+                        if let Err(err) = app.persist_state() {
+                            app.show_error("Could not remember selection", err);
                         }
+                        app.toggle_menu(); // Go back to main screen
                     }
-                    KeyCode::Enter => {
-                        // If we are on "Bitcoin Config", open the explorer
-                        if app.current_screen == CurrentScreen::BitcoinConfig {
-                            app.current_screen = CurrentScreen::FileExplorer;
-                        }
+                }
+                _ => {}
+            },
+
+            // Discovered Configs
+            CurrentScreen::BitcoinConfig if app.conf_picker.is_some() => {
+                handle_conf_picker_key(app, key.code)
+            }
+
+            // p2pool Compatibility Report
+            CurrentScreen::BitcoinConfig
+                if app.focus == Focus::Content && app.config_view == ConfigView::Readiness =>
+            {
+                handle_readiness_key(app, key.code)
+            }
+
+            // Effective Configuration
+            CurrentScreen::BitcoinConfig
+                if app.focus == Focus::Content && app.config_view == ConfigView::Effective =>
+            {
+                handle_effective_view_key(app, key.code)
+            }
+
+            // Config Backups
+            CurrentScreen::BitcoinConfig
+                if app.focus == Focus::Content && app.config_view == ConfigView::History =>
+            {
+                handle_history_key(app, key.code, ConfKind::Bitcoin)
+            }
+            CurrentScreen::P2poolConfig
+                if app.focus == Focus::Content && app.p2pool_view == P2poolView::History =>
+            {
+                handle_history_key(app, key.code, ConfKind::P2pool)
+            }
+
+            // Peer List
+            CurrentScreen::Peers if app.focus == Focus::Content => handle_peers_key(app, key.code),
+
+            // Mempool Explorer
+            CurrentScreen::Mempool if app.focus == Focus::Content => {
+                handle_mempool_key(app, key.code)
+            }
+
+            // Block Template Inspector
+            CurrentScreen::Template if app.focus == Focus::Content => {
+                handle_template_key(app, key.code)
+            }
+
+            // Debug Log
+            CurrentScreen::Log if app.focus == Focus::Content => handle_log_key(app, key.code),
+
+            // bitcoind Process
+            CurrentScreen::Bitcoind if app.focus == Focus::Content => {
+                handle_bitcoind_key(app, key.code)
+            }
+
+            // p2poolv2 Process
+            CurrentScreen::P2pool if app.focus == Focus::Content => {
+                handle_p2pool_key(app, key.code)
+            }
+
+            // Stratum Monitor
+            CurrentScreen::Stratum if app.focus == Focus::Content => {
+                handle_stratum_key(app, key.code)
+            }
+
+            // Connected Miners
+            CurrentScreen::Miners if app.focus == Focus::Content => {
+                handle_miners_key(app, key.code)
+            }
+
+            // Bitcoin Config Editor
+            CurrentScreen::BitcoinConfig if app.focus == Focus::Content => {
+                handle_config_editor_key(app, key.code)
+            }
+
+            // bitcoin.conf Cross-check
+            CurrentScreen::P2poolConfig
+                if app.focus == Focus::Content && app.p2pool_view == P2poolView::CrossCheck =>
+            {
+                handle_crosscheck_key(app, key.code)
+            }
+
+            // p2pool Config Editor
+            CurrentScreen::P2poolConfig if app.focus == Focus::Content => {
+                handle_p2pool_editor_key(app, key.code)
+            }
+
+            // Standard Navigation
+            _ => match key.code {
+                KeyCode::Up if app.sidebar_index > 0 => {
+                    app.sidebar_index -= 1;
+                    app.toggle_menu();
+                }
+                KeyCode::Down if app.sidebar_index + 1 < SIDEBAR_ITEMS.len() => {
+                    app.sidebar_index += 1;
+                    app.toggle_menu();
+                }
+                // Screens without a file to pick: focus the screen's content
+                KeyCode::Enter
+                    if matches!(
+                        app.current_screen,
+                        CurrentScreen::Peers
+                            | CurrentScreen::Mempool
+                            | CurrentScreen::Template
+                            | CurrentScreen::Log
+                            | CurrentScreen::Bitcoind
+                            | CurrentScreen::P2pool
+                            | CurrentScreen::Stratum
+                            | CurrentScreen::Miners
+                    ) =>
+                {
+                    app.focus = Focus::Content;
+                }
+                // On "Bitcoin Config", edit the loaded file or open the explorer
+                KeyCode::Enter if app.current_screen == CurrentScreen::BitcoinConfig => {
+                    if app.bitcoin_conf.is_some() {
+                        app.focus = Focus::Content;
+                    } else {
+                        app.choose_bitcoin_conf();
                     }
-                    _ => {}
-                },
+                }
+                KeyCode::Enter if app.current_screen == CurrentScreen::P2poolConfig => {
+                    if app.p2pool_conf.is_some() {
+                        app.focus = Focus::Content;
+                    } else {
+                        app.choose_p2pool_conf();
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+}
+
+fn handle_peers_key(app: &mut App, code: KeyCode) {
+    let peers = &mut app.peer_list;
+    if let Some(input) = peers.add_input.as_mut() {
+        match code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => peers.submit_add(),
+            KeyCode::Esc => peers.add_input = None,
+            _ => {}
+        }
+        return;
+    }
+    if let Some(action) = peers.pending.take() {
+        match code {
+            KeyCode::Char('y') | KeyCode::Enter => app.run_peer_action(action),
+            _ => peers.message = Some("Cancelled".to_string()),
+        }
+        return;
+    }
+    match code {
+        KeyCode::Up => peers.previous(),
+        KeyCode::Down => peers.next(),
+        KeyCode::Char('s') => peers.cycle_sort(),
+        KeyCode::Char('S') => peers.reverse(),
+        KeyCode::Char('d') => peers.request_disconnect(),
+        KeyCode::Char('b') => peers.request_ban(),
+        KeyCode::Char('a') => peers.start_add(),
+        KeyCode::Char('r') => app.refresh_peers(),
+        KeyCode::Esc => app.focus = Focus::Sidebar,
+        _ => {}
+    }
+}
+
+fn handle_mempool_key(app: &mut App, code: KeyCode) {
+    let view = &mut app.mempool_view;
+    if let Some(input) = view.txid_input.as_mut() {
+        match code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                if let Some(txid) = view.submit_txid() {
+                    app.lookup_transaction(txid);
+                }
+            }
+            KeyCode::Esc => view.txid_input = None,
+            _ => {}
+        }
+        return;
+    }
+    match code {
+        KeyCode::Char('t') | KeyCode::Char('/') => view.txid_input = Some(String::new()),
+        KeyCode::Char('r') => app.refresh_mempool(),
+        KeyCode::Up => view.previous(),
+        KeyCode::Down => view.next(),
+        KeyCode::Enter => {
+            if let Some(txid) = view.selected_related().cloned() {
+                app.lookup_transaction(txid);
+            }
+        }
+        KeyCode::Esc | KeyCode::Backspace if view.detail.is_some() => {
+            if let Some(txid) = view.back() {
+                app.lookup_transaction(txid);
+            }
+        }
+        KeyCode::Esc => app.focus = Focus::Sidebar,
+        _ => {}
+    }
+}
+
+fn handle_template_key(app: &mut App, code: KeyCode) {
+    let view = &mut app.template_view;
+    match code {
+        KeyCode::Up => view.up(1),
+        KeyCode::Down => view.down(1),
+        KeyCode::PageUp => view.up(template_view::PAGE),
+        KeyCode::PageDown => view.down(template_view::PAGE),
+        KeyCode::Home => view.up(usize::MAX),
+        KeyCode::End => view.down(usize::MAX),
+        KeyCode::Char('r') => app.refresh_template(),
+        KeyCode::Esc => app.focus = Focus::Sidebar,
+        _ => {}
+    }
+}
+
+fn handle_log_key(app: &mut App, code: KeyCode) {
+    let view = &mut app.log_view;
+    if let Some(input) = view.search_input.as_mut() {
+        match code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => view.submit_search(),
+            KeyCode::Esc => view.search_input = None,
+            _ => {}
+        }
+        return;
+    }
+    match code {
+        KeyCode::Char('/') => {
+            let current = view.search.as_ref().map(|re| re.as_str().to_string());
+            view.search_input = Some(current.unwrap_or_default());
+        }
+        KeyCode::Char('c') => view.cycle_category(),
+        KeyCode::Char('f') => view.toggle_follow(),
+        KeyCode::Up => view.up(1),
+        KeyCode::Down => view.down(1),
+        KeyCode::PageUp => view.up(log_view::PAGE),
+        KeyCode::PageDown => view.down(log_view::PAGE),
+        KeyCode::Home => view.up(usize::MAX),
+        KeyCode::End => view.follow = true,
+        KeyCode::Esc if view.search.is_some() || view.category.is_some() => {
+            view.search = None;
+            view.category = None;
+        }
+        KeyCode::Esc => app.focus = Focus::Sidebar,
+        _ => {}
+    }
+}
+
+fn handle_bitcoind_key(app: &mut App, code: KeyCode) {
+    if let Some(action) = app.bitcoind.pending.take() {
+        match code {
+            KeyCode::Char('y') | KeyCode::Enter => match action {
+                ProcessAction::Stop => app.stop_bitcoind(),
+                ProcessAction::Restart => app.restart_bitcoind(),
+            },
+            _ => app.bitcoind.message = Some("Cancelled".to_string()),
+        }
+        return;
+    }
+    match code {
+        KeyCode::Char('s') => app.start_bitcoind(),
+        KeyCode::Char('x') => app.bitcoind.pending = Some(ProcessAction::Stop),
+        KeyCode::Char('R') => app.bitcoind.pending = Some(ProcessAction::Restart),
+        KeyCode::Esc => app.focus = Focus::Sidebar,
+        code => scroll_output(&mut app.bitcoind, code),
+    }
+}
+
+fn handle_p2pool_key(app: &mut App, code: KeyCode) {
+    if let Some(action) = app.p2pool.pending.take() {
+        match code {
+            KeyCode::Char('y') | KeyCode::Enter => match action {
+                ProcessAction::Stop => app.stop_p2pool(),
+                ProcessAction::Restart => app.restart_p2pool(),
+            },
+            _ => app.p2pool.message = Some("Cancelled".to_string()),
+        }
+        return;
+    }
+    match code {
+        KeyCode::Char('s') => app.start_p2pool(),
+        KeyCode::Char('x') => app.p2pool.pending = Some(ProcessAction::Stop),
+        KeyCode::Char('R') => app.p2pool.pending = Some(ProcessAction::Restart),
+        KeyCode::Char('a') => app.toggle_p2pool_auto_restart(),
+        KeyCode::Esc => app.focus = Focus::Sidebar,
+        code => scroll_output(&mut app.p2pool, code),
+    }
+}
+
+fn handle_stratum_key(app: &mut App, code: KeyCode) {
+    match code {
+        KeyCode::Char('r') => app.watch_stratum(),
+        KeyCode::Char('x') => app.stop_stratum(),
+        KeyCode::Esc => app.focus = Focus::Sidebar,
+        _ => {}
+    }
+}
+
+fn handle_miners_key(app: &mut App, code: KeyCode) {
+    let miners = &mut app.miner_list;
+    if let Some(input) = miners.filter_input.as_mut() {
+        match code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => miners.submit_filter(),
+            KeyCode::Esc => miners.filter_input = None,
+            _ => {}
+        }
+        return;
+    }
+    match code {
+        KeyCode::Up => miners.up(1),
+        KeyCode::Down => miners.down(1),
+        KeyCode::PageUp => miners.up(miner_list::PAGE),
+        KeyCode::PageDown => miners.down(miner_list::PAGE),
+        KeyCode::Home => miners.up(usize::MAX),
+        KeyCode::End => miners.down(usize::MAX),
+        KeyCode::Char('s') => miners.cycle_sort(),
+        KeyCode::Char('S') => miners.reverse(),
+        KeyCode::Char('/') => miners.filter_input = Some(miners.filter.clone().unwrap_or_default()),
+        KeyCode::Esc if miners.filter.is_some() => miners.clear_filter(),
+        KeyCode::Esc => app.focus = Focus::Sidebar,
+        _ => {}
+    }
+}
+
+/// Scrolls the output of a process screen.
+fn scroll_output(supervisor: &mut Supervisor, code: KeyCode) {
+    match code {
+        KeyCode::Up => supervisor.scroll_up(1),
+        KeyCode::Down => supervisor.scroll_down(1),
+        KeyCode::PageUp => supervisor.scroll_up(process::PAGE),
+        KeyCode::PageDown => supervisor.scroll_down(process::PAGE),
+        KeyCode::Home => supervisor.scroll_up(usize::MAX),
+        KeyCode::End => supervisor.scroll = 0,
+        _ => {}
+    }
+}
+
+fn handle_conf_picker_key(app: &mut App, code: KeyCode) {
+    let Some(picker) = app.conf_picker.as_mut() else {
+        return;
+    };
+
+    match code {
+        KeyCode::Up => picker.previous(),
+        KeyCode::Down => picker.next(),
+        KeyCode::Enter => match picker.choice() {
+            Choice::Config(path) => {
+                app.load_bitcoin_conf(path);
+                if let Err(err) = app.persist_state() {
+                    app.show_error("Could not remember selection", err);
+                }
+            }
+            Choice::Browse => {
+                if let Some(dir) = picker.browse_dir() {
+                    app.explorer.open_dir(dir);
+                }
+                app.conf_picker = None;
+                app.focus = Focus::Sidebar;
+                app.current_screen = CurrentScreen::FileExplorer;
+            }
+        },
+        KeyCode::Esc => {
+            app.conf_picker = None;
+            app.focus = Focus::Sidebar;
+        }
+        _ => {}
+    }
+}
+
+fn handle_wizard_key(app: &mut App, code: KeyCode) {
+    let Some(wizard) = app.wizard.as_mut() else {
+        app.cancel_wizard();
+        return;
+    };
+
+    match code {
+        KeyCode::Enter if wizard.current_step() == Step::Review => {
+            if let Err(err) = app.finish_wizard() {
+                app.show_error("Could not write configs", err);
+            }
+        }
+        KeyCode::Enter => wizard.next_step(),
+        KeyCode::Esc => {
+            // Going back from the first page leaves the wizard
+            let went_back = wizard.previous_step();
+            if !went_back {
+                app.cancel_wizard();
+            }
+        }
+        KeyCode::Down | KeyCode::Tab => wizard.next_field(),
+        KeyCode::Up | KeyCode::BackTab => wizard.previous_field(),
+        KeyCode::Left => wizard.cycle_network(false),
+        KeyCode::Right => wizard.cycle_network(true),
+        KeyCode::Backspace => wizard.backspace(),
+        KeyCode::Char(c) => wizard.insert_char(c),
+        _ => {}
+    }
+}
+
+fn handle_config_editor_key(app: &mut App, code: KeyCode) {
+    let Some(conf) = app.bitcoin_conf.as_mut() else {
+        app.focus = Focus::Sidebar;
+        return;
+    };
+    let editor = &mut app.config_editor;
+
+    if editor.is_editing() {
+        match code {
+            KeyCode::Enter => editor.commit(conf),
+            KeyCode::Esc => editor.cancel(),
+            KeyCode::Backspace => editor.backspace(),
+            KeyCode::Char(c) => editor.insert_char(c),
+            _ => {}
+        }
+        return;
+    }
+
+    match code {
+        KeyCode::Up => editor.previous(),
+        KeyCode::Down => editor.next(),
+        KeyCode::Enter | KeyCode::Char(' ') => editor.activate(conf),
+        KeyCode::Left => editor.cycle(conf, false),
+        KeyCode::Right => editor.cycle(conf, true),
+        KeyCode::Delete => editor.unset(conf),
+        KeyCode::PageDown => editor.scroll_problems_down(1),
+        KeyCode::PageUp => editor.scroll_problems_up(1),
+        KeyCode::Char('o') => app.discard(Discard::Open(ConfKind::Bitcoin)),
+        KeyCode::Char('s') => match app.preview_bitcoin_conf_save() {
+            Ok(true) => {}
+            Ok(false) => app.config_editor.message = Some("No changes to save".to_string()),
+            // Validation errors are listed in the editor already
+            Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+                app.config_editor.message = Some(format!("Save failed: {}", err))
+            }
+            Err(err) => app.show_error("Save failed", err),
+        },
+        KeyCode::Char('p') => app.config_view = ConfigView::Readiness,
+        KeyCode::Char('e') => app.show_effective_config(),
+        KeyCode::Char('h') => app.show_history(ConfKind::Bitcoin),
+        KeyCode::Char('a') => app.rpcauth_dialog = Some(RpcAuthDialog::new()),
+        KeyCode::Esc => app.focus = Focus::Sidebar,
+        _ => {}
+    }
+}
+
+fn handle_p2pool_editor_key(app: &mut App, code: KeyCode) {
+    let Some(conf) = app.p2pool_conf.as_mut() else {
+        app.focus = Focus::Sidebar;
+        return;
+    };
+    let editor = &mut app.p2pool_editor;
+
+    if editor.is_editing() {
+        match code {
+            KeyCode::Enter => editor.commit(conf),
+            KeyCode::Esc => editor.cancel(),
+            KeyCode::Backspace => editor.backspace(),
+            KeyCode::Char(c) => editor.insert_char(c),
+            _ => {}
+        }
+        return;
+    }
+
+    match code {
+        KeyCode::Up => editor.previous(),
+        KeyCode::Down => editor.next(),
+        KeyCode::Enter | KeyCode::Char(' ') => editor.activate(conf),
+        KeyCode::Left => editor.cycle(conf, false),
+        KeyCode::Right => editor.cycle(conf, true),
+        KeyCode::Delete => editor.unset(conf),
+        KeyCode::Char('o') => app.discard(Discard::Open(ConfKind::P2pool)),
+        KeyCode::Char('h') => app.show_history(ConfKind::P2pool),
+        KeyCode::Char('c') if app.bitcoin_conf.is_some() => {
+            app.p2pool_view = P2poolView::CrossCheck;
+        }
+        KeyCode::Char('c') => {
+            editor.message = Some("Load a bitcoin.conf first to cross-check".to_string());
+        }
+        KeyCode::Char('s') => match app.preview_p2pool_conf_save() {
+            Ok(true) => {}
+            Ok(false) => app.p2pool_editor.message = Some("No changes to save".to_string()),
+            Err(err) => app.show_error("Save failed", err),
+        },
+        KeyCode::Esc => app.focus = Focus::Sidebar,
+        _ => {}
+    }
+}
+
+fn handle_crosscheck_key(app: &mut App, code: KeyCode) {
+    let Some(checks) = app.p2pool_crosscheck() else {
+        app.p2pool_view = P2poolView::Settings;
+        return;
+    };
+    let report = &mut app.crosscheck_report;
+
+    match code {
+        KeyCode::Up => report.previous(checks.len()),
+        KeyCode::Down => report.next(checks.len()),
+        KeyCode::Enter => {
+            let (Some(fix), Some(conf)) = (
+                checks.get(report.selected).and_then(|c| c.fix.as_ref()),
+                app.p2pool_conf.as_mut(),
+            ) else {
+                return;
+            };
+            let editor = &mut app.p2pool_editor;
+            match fix.apply(conf) {
+                Ok(()) => {
+                    editor.modified = true;
+                    editor.message = Some(format!("Applied: {}", fix.label));
+                }
+                Err(err) => editor.message = Some(err),
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('c') => app.p2pool_view = P2poolView::Settings,
+        _ => {}
+    }
+}
+
+fn handle_readiness_key(app: &mut App, code: KeyCode) {
+    let expected = app.p2pool_network();
+    let Some(conf) = app.bitcoin_conf.as_mut() else {
+        app.focus = Focus::Sidebar;
+        return;
+    };
+    let checks = readiness::check(conf, expected);
+    let report = &mut app.readiness_report;
+
+    match code {
+        KeyCode::Up => report.previous(checks.len()),
+        KeyCode::Down => report.next(checks.len()),
+        KeyCode::Enter => {
+            let Some(fix) = checks.get(report.selected).and_then(|c| c.fix.as_ref()) else {
+                return;
+            };
+            match fix.action {
+                FixAction::Edits(_) => {
+                    fix.apply(conf);
+                    app.config_editor.modified = true;
+                    app.config_editor.message = Some(format!("Applied: {}", fix.label));
+                }
+                FixAction::GenerateRpcAuth => app.rpcauth_dialog = Some(RpcAuthDialog::new()),
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('p') => app.config_view = ConfigView::Options,
+        _ => {}
+    }
+}
+
+fn handle_effective_view_key(app: &mut App, code: KeyCode) {
+    let (Some(path), Some(conf)) = (&app.bitcoin_conf_path, &app.bitcoin_conf) else {
+        app.focus = Focus::Sidebar;
+        return;
+    };
+    let len = app.bitcoin_includes.settings(path, conf).len();
+    let view = &mut app.effective_view;
+
+    match code {
+        KeyCode::Up => view.previous(len),
+        KeyCode::Down => view.next(len),
+        KeyCode::Esc | KeyCode::Char('e') => app.config_view = ConfigView::Options,
+        _ => {}
+    }
+}
+
+fn handle_history_key(app: &mut App, code: KeyCode, kind: ConfKind) {
+    let view = &mut app.history_view;
+
+    match code {
+        KeyCode::Up => view.previous(),
+        KeyCode::Down => view.next(),
+        KeyCode::PageDown => view.scroll_down(10),
+        KeyCode::PageUp => view.scroll_up(10),
+        KeyCode::Char('r') if view.selected_backup().is_some() => {
+            app.discard(Discard::Restore(kind))
+        }
+        KeyCode::Char('+') | KeyCode::Char('-') => {
+            app.backup_history = if code == KeyCode::Char('+') {
+                app.backup_history + 1
+            } else {
+                app.backup_history.saturating_sub(1)
+            };
+            match app.persist_state() {
+                Ok(()) => {
+                    app.history_view.message =
+                        Some(format!("Keeping {} backup(s) per file", app.backup_history))
+                }
+                Err(err) => app.show_error("Could not save setting", err),
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('h') => match kind {
+            ConfKind::Bitcoin => app.config_view = ConfigView::Options,
+            ConfKind::P2pool => app.p2pool_view = P2poolView::Settings,
+        },
+        _ => {}
+    }
+}
+
+fn handle_save_preview_key(app: &mut App, code: KeyCode) {
+    let Some(preview) = app.save_preview.as_mut() else {
+        return;
+    };
+
+    match code {
+        // The preview was opened from the p2pool screen or the bitcoin.conf one
+        KeyCode::Enter | KeyCode::Char('y')
+            if app.current_screen == CurrentScreen::P2poolConfig =>
+        {
+            app.save_preview = None;
+            match app.save_p2pool_conf() {
+                Ok(()) => app.p2pool_editor.message = Some("Saved".to_string()),
+                Err(err) => app.show_error("Save failed", err),
+            }
+        }
+        KeyCode::Enter | KeyCode::Char('y') => {
+            app.save_preview = None;
+            match app.save_bitcoin_conf() {
+                Ok(()) => app.config_editor.message = Some("Saved".to_string()),
+                Err(err) => app.show_error("Save failed", err),
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('n') => {
+            app.save_preview = None;
+            let message = Some("Save cancelled".to_string());
+            if app.current_screen == CurrentScreen::P2poolConfig {
+                app.p2pool_editor.message = message;
+            } else {
+                app.config_editor.message = message;
+            }
+        }
+        KeyCode::Up => preview.scroll_up(1),
+        KeyCode::Down => preview.scroll_down(1),
+        KeyCode::PageUp => preview.scroll_up(10),
+        KeyCode::PageDown => preview.scroll_down(10),
+        _ => {}
+    }
+}
+
+fn handle_rpcauth_dialog_key(app: &mut App, code: KeyCode) {
+    let Some(dialog) = app.rpcauth_dialog.as_mut() else {
+        return;
+    };
+
+    if dialog.generated.is_some() {
+        // The password has been shown; closing forgets it
+        if matches!(code, KeyCode::Enter | KeyCode::Esc) {
+            app.rpcauth_dialog = None;
+        }
+        return;
+    }
+
+    match code {
+        KeyCode::Enter => {
+            if let Some(conf) = app.bitcoin_conf.as_mut()
+                && dialog.generate(conf)
+            {
+                app.config_editor.modified = true;
+                app.config_editor.message = Some(format!("Added rpcauth for {}", dialog.user));
             }
         }
+        KeyCode::Esc => app.rpcauth_dialog = None,
+        KeyCode::Backspace => dialog.backspace(),
+        KeyCode::Char(c) => dialog.insert_char(c),
+        _ => {}
     }
 }
 
@@ -245,7 +1044,7 @@ mod tests {
 
         let mut step = 0;
 
-        let event_provider = |app: &mut App| {
+        let event_provider = |_app: &mut App| {
             step += 1;
             match step {
                 1 => Ok(Event::Key(KeyEvent::new(
@@ -276,5 +1075,1759 @@ mod tests {
         assert!(res.is_ok());
 
         assert_eq!(app.bitcoin_conf_path, Some(file_path));
+        assert!(app.bitcoin_conf.is_some());
+    }
+
+    #[test]
+    fn test_config_editor_edit_and_save() {
+        use std::env::temp_dir;
+        use std::fs;
+
+        let base = temp_dir().join("pdm_config_editor_test");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base).unwrap();
+        let file_path = base.join("bitcoin.conf");
+        fs::write(&file_path, "# node\nserver=0\n").unwrap();
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::new();
+        app.sidebar_index = 1;
+        app.toggle_menu();
+        app.load_bitcoin_conf(file_path.clone());
+
+        let selected = pdm::bitcoin_conf::options::OPTIONS
+            .iter()
+            .position(|o| o.key == "rpcuser")
+            .unwrap();
+        let mut keys = vec![KeyCode::Enter]; // focus the editor
+        keys.extend(std::iter::repeat_n(KeyCode::Down, selected));
+        keys.push(KeyCode::Enter); // start typing
+        keys.extend("pq".chars().map(KeyCode::Char)); // 'q' must not quit
+        keys.push(KeyCode::Enter); // apply
+        keys.push(KeyCode::Char('s')); // preview
+        keys.push(KeyCode::Enter); // confirm
+        keys.push(KeyCode::Esc); // back to sidebar
+        keys.push(KeyCode::Char('q'));
+        let mut keys = keys.into_iter();
+
+        let event_provider = |_app: &mut App| {
+            let code = keys.next().expect("should have exited");
+            Ok(Event::Key(KeyEvent::new(code, KeyModifiers::empty())))
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+
+        assert_eq!(app.focus, Focus::Sidebar);
+        assert!(!app.config_editor.modified);
+        assert_eq!(
+            fs::read_to_string(&file_path).unwrap(),
+            "# node\nserver=0\nrpcuser=pq\n"
+        );
+    }
+
+    #[test]
+    fn test_config_editor_refuses_to_save_errors() {
+        use std::env::temp_dir;
+        use std::fs;
+
+        let base = temp_dir().join("pdm_config_editor_errors_test");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base).unwrap();
+        let file_path = base.join("bitcoin.conf");
+        fs::write(&file_path, "prune=1000\n").unwrap();
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::new();
+        app.sidebar_index = 1;
+        app.toggle_menu();
+        app.load_bitcoin_conf(file_path.clone());
+
+        let selected = pdm::bitcoin_conf::options::OPTIONS
+            .iter()
+            .position(|o| o.key == "txindex")
+            .unwrap();
+        let mut keys = vec![KeyCode::Enter];
+        keys.extend(std::iter::repeat_n(KeyCode::Down, selected));
+        keys.push(KeyCode::Char(' ')); // txindex=1 conflicts with prune
+        keys.push(KeyCode::Char('s'));
+        // Quitting with unsaved edits asks first
+        keys.extend([KeyCode::Char('q'), KeyCode::Char('n')]);
+        keys.extend([KeyCode::Char('q'), KeyCode::Char('y')]);
+        let mut keys = keys.into_iter();
+
+        let event_provider = |_app: &mut App| {
+            let code = keys.next().expect("should have exited");
+            Ok(Event::Key(KeyEvent::new(code, KeyModifiers::empty())))
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+
+        assert!(app.config_editor.modified);
+        assert!(
+            app.config_editor
+                .message
+                .as_deref()
+                .unwrap()
+                .contains("1 error(s)")
+        );
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "prune=1000\n");
+    }
+
+    #[test]
+    fn test_unsaved_edits_need_confirmation() {
+        use std::env::temp_dir;
+        use std::fs;
+
+        let base = temp_dir().join("pdm_unsaved_edits_test");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base).unwrap();
+        let file_path = base.join("bitcoin.conf");
+        fs::write(&file_path, "server=1\n").unwrap();
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::new();
+        app.sidebar_index = 1;
+        app.toggle_menu();
+        app.load_bitcoin_conf(file_path.clone());
+
+        let selected = pdm::bitcoin_conf::options::OPTIONS
+            .iter()
+            .position(|o| o.key == "server")
+            .unwrap();
+        let mut keys = vec![KeyCode::Enter];
+        keys.extend(std::iter::repeat_n(KeyCode::Down, selected));
+        keys.push(KeyCode::Char(' ')); // server=0, unsaved
+        let mut keys = keys.into_iter();
+        let mut step = 0;
+
+        let event_provider = |app: &mut App| {
+            let code = match keys.next() {
+                Some(code) => code,
+                None => {
+                    step += 1;
+                    match step {
+                        1 => KeyCode::Char('o'),
+                        2 => {
+                            assert_eq!(app.discard_prompt, Some(Discard::Open(ConfKind::Bitcoin)));
+                            KeyCode::Esc
+                        }
+                        3 => {
+                            assert_eq!(app.discard_prompt, None);
+                            assert_eq!(app.unsaved(Discard::Quit), vec!["bitcoin.conf"]);
+                            KeyCode::Char('q')
+                        }
+                        4 => {
+                            assert_eq!(app.discard_prompt, Some(Discard::Quit));
+                            KeyCode::Char('x') // keeps asking
+                        }
+                        5 => {
+                            assert_eq!(app.discard_prompt, Some(Discard::Quit));
+                            KeyCode::Esc
+                        }
+                        6 => KeyCode::Char('o'),
+                        7 => KeyCode::Char('y'),
+                        8 => {
+                            // The picker or explorer is open; nothing was reloaded yet
+                            assert_eq!(app.discard_prompt, None);
+                            assert!(app.config_editor.modified);
+                            KeyCode::Esc
+                        }
+                        9 => KeyCode::Char('q'),
+                        10 => KeyCode::Char('y'),
+                        _ => panic!("should have exited"),
+                    }
+                }
+            };
+            Ok(Event::Key(KeyEvent::new(code, KeyModifiers::empty())))
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+        assert_eq!(step, 10);
+    }
+
+    #[test]
+    fn test_readiness_fix_patches_config() {
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::new();
+        app.sidebar_index = 1;
+        app.toggle_menu();
+        app.bitcoin_conf = Some(pdm::bitcoin_conf::BitcoinConf::default());
+
+        // Focus, open the report, select "RPC server" and fix it.
+        let mut keys = vec![
+            KeyCode::Enter,
+            KeyCode::Char('p'),
+            KeyCode::Down,
+            KeyCode::Enter,
+            KeyCode::Esc,
+            KeyCode::Char('q'),
+            KeyCode::Char('y'),
+        ]
+        .into_iter();
+
+        let event_provider = |_app: &mut App| {
+            let code = keys.next().expect("should have exited");
+            Ok(Event::Key(KeyEvent::new(code, KeyModifiers::empty())))
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+
+        assert_eq!(app.config_view, ConfigView::Options);
+        assert!(app.config_editor.modified);
+        let conf = app.bitcoin_conf.as_ref().unwrap();
+        assert_eq!(conf.get(None, "server"), Some("1"));
+    }
+
+    #[test]
+    fn test_rpcauth_dialog_adds_credentials() {
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::new();
+        app.sidebar_index = 1;
+        app.toggle_menu();
+        app.bitcoin_conf = Some(pdm::bitcoin_conf::BitcoinConf::default());
+
+        let mut step = 0;
+        let event_provider = |app: &mut App| {
+            step += 1;
+            let code = match step {
+                1 => KeyCode::Enter,     // focus the editor
+                2 => KeyCode::Char('a'), // open the dialog
+                3 => KeyCode::Char('q'), // typed into the user name
+                4 => KeyCode::Enter,     // generate
+                5 => {
+                    let dialog = app.rpcauth_dialog.as_ref().unwrap();
+                    let credentials = dialog.generated.as_ref().unwrap();
+                    assert_eq!(credentials.user, "p2poolq");
+                    let conf = app.bitcoin_conf.as_ref().unwrap();
+                    assert!(pdm::bitcoin_conf::rpcauth::verify(
+                        conf.get(None, "rpcauth").unwrap(),
+                        "p2poolq",
+                        &credentials.password
+                    ));
+                    KeyCode::Enter // close
+                }
+                6 => {
+                    assert!(app.rpcauth_dialog.is_none());
+                    KeyCode::Char('q')
+                }
+                7 => KeyCode::Char('y'),
+                _ => panic!("should have exited"),
+            };
+            Ok(Event::Key(KeyEvent::new(code, KeyModifiers::empty())))
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+        assert!(app.config_editor.modified);
+    }
+
+    #[test]
+    fn test_setup_wizard_writes_configs() {
+        use std::env::temp_dir;
+        use std::fs;
+
+        let base = temp_dir().join("pdm_setup_wizard_test");
+        let _ = fs::remove_dir_all(&base);
+        let state_path = base.join("state.conf");
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::from_state(Some(state_path.clone()));
+        assert_eq!(app.current_screen, CurrentScreen::Wizard);
+        let wizard = app.wizard.as_mut().unwrap();
+        wizard.datadir = base.join("bitcoin").display().to_string();
+        wizard.p2pool_config_path = base.join("p2pool.toml").display().to_string();
+
+        let mut keys = vec![
+            KeyCode::Right, // testnet3
+            KeyCode::Enter, // network
+            KeyCode::Enter, // data dir
+            KeyCode::Esc,   // back to data dir
+            KeyCode::Enter,
+            KeyCode::Enter, // pruning
+            KeyCode::Enter, // rpc auth
+            KeyCode::Enter, // zmq
+            KeyCode::Tab,   // payout address
+        ];
+        keys.extend("tb1qq".chars().map(KeyCode::Char)); // 'q' must not quit
+        keys.push(KeyCode::Enter); // to review
+        keys.push(KeyCode::Enter); // write
+        keys.push(KeyCode::Char('q'));
+        let mut keys = keys.into_iter();
+
+        let event_provider = |_app: &mut App| {
+            let code = keys.next().expect("should have exited");
+            Ok(Event::Key(KeyEvent::new(code, KeyModifiers::empty())))
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+
+        assert!(app.wizard.is_none());
+        assert_eq!(app.current_screen, CurrentScreen::BitcoinConfig);
+        let conf = app.bitcoin_conf.as_ref().unwrap();
+        assert_eq!(conf.chain(), pdm::bitcoin_conf::Network::Test);
+        assert!(
+            fs::read_to_string(base.join("p2pool.toml"))
+                .unwrap()
+                .contains("address = \"tb1qq\"")
+        );
+
+        // The next start goes straight to the generated config.
+        let app = App::from_state(Some(state_path));
+        assert_eq!(app.current_screen, CurrentScreen::Home);
+        assert_eq!(
+            app.bitcoin_conf_path,
+            Some(base.join("bitcoin").join("bitcoin.conf"))
+        );
+        assert_eq!(app.p2pool_conf_path, Some(base.join("p2pool.toml")));
+    }
+
+    #[test]
+    fn test_state_without_bitcoin_conf_skips_wizard() {
+        use std::env::temp_dir;
+        use std::fs;
+
+        let base = temp_dir().join("pdm_state_restore_test");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base).unwrap();
+
+        let state_path = base.join("state.conf");
+        fs::write(&state_path, "frame_rate=10\n").unwrap();
+        let app = App::from_state(Some(state_path));
+        assert!(app.wizard.is_none());
+        assert_eq!(app.current_screen, CurrentScreen::Home);
+        assert_eq!(app.frame_rate, 10);
+
+        // An unreadable state is shown, not fatal
+        let app = App::from_state(Some(base.clone()));
+        assert!(app.wizard.is_none());
+        assert_eq!(app.error_dialog.unwrap().title, "Could not read pdm state");
+        assert!(app.state_path.is_none());
+    }
+
+    #[test]
+    fn test_conf_picker_offers_discovered_configs() {
+        use std::env::temp_dir;
+        use std::fs;
+
+        let base = temp_dir().join("pdm_conf_picker_test");
+        let _ = fs::remove_dir_all(&base);
+        let conf_path = base.join("home/.bitcoin/bitcoin.conf");
+        fs::create_dir_all(conf_path.parent().unwrap()).unwrap();
+        fs::write(&conf_path, "server=1\n").unwrap();
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::new();
+        app.discovery = Discovery {
+            root: base.clone(),
+            home: Some(base.join("home")),
+            datadir: None,
+        };
+
+        let mut step = 0;
+        let event_provider = |app: &mut App| {
+            step += 1;
+            let code = match step {
+                1 => KeyCode::Down,  // Bitcoin Config
+                2 => KeyCode::Enter, // look for configs
+                3 => {
+                    let picker = app.conf_picker.as_ref().unwrap();
+                    assert_eq!(picker.candidates.len(), 1);
+                    KeyCode::Up // wrap to "browse"
+                }
+                4 => KeyCode::Enter,
+                5 => {
+                    // The explorer opens next to the discovered config.
+                    assert_eq!(app.current_screen, CurrentScreen::FileExplorer);
+                    assert_eq!(app.explorer.current_dir, base.join("home/.bitcoin"));
+                    KeyCode::Esc
+                }
+                6 => KeyCode::Enter, // look again
+                7 => KeyCode::Enter, // pick the discovered config
+                8 => KeyCode::Char('q'),
+                _ => panic!("should have exited"),
+            };
+            Ok(Event::Key(KeyEvent::new(code, KeyModifiers::empty())))
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+
+        assert!(app.conf_picker.is_none());
+        assert_eq!(app.bitcoin_conf_path, Some(conf_path));
+        assert_eq!(app.focus, Focus::Content);
+        assert!(app.bitcoin_conf.is_some());
+    }
+
+    #[test]
+    fn test_history_restores_backup() {
+        use std::env::temp_dir;
+        use std::fs;
+
+        let base = temp_dir().join("pdm_history_test");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base).unwrap();
+        let file_path = base.join("bitcoin.conf");
+        fs::write(&file_path, "server=0\n").unwrap();
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::new();
+        app.sidebar_index = 1;
+        app.toggle_menu();
+        app.load_bitcoin_conf(file_path.clone());
+
+        let mut step = 0;
+        let event_provider = |app: &mut App| {
+            step += 1;
+            let code = match step {
+                1 => KeyCode::Enter,     // focus the editor
+                2 => KeyCode::Char(' '), // toggle server=1
+                3 => KeyCode::Char('s'), // preview
+                4 => KeyCode::Enter,     // save, backing up server=0
+                5 => KeyCode::Char('h'), // history
+                6 => {
+                    assert_eq!(app.config_view, ConfigView::History);
+                    assert_eq!(app.history_view.backups.len(), 1);
+                    KeyCode::Char('r')
+                }
+                7 => {
+                    let message = app.history_view.message.as_deref().unwrap();
+                    assert!(message.starts_with("Restored"), "{}", message);
+                    // The overwritten version was backed up as well.
+                    assert_eq!(app.history_view.backups.len(), 2);
+                    KeyCode::Esc
+                }
+                8 => KeyCode::Char('q'),
+                _ => panic!("should have exited"),
+            };
+            Ok(Event::Key(KeyEvent::new(code, KeyModifiers::empty())))
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "server=0\n");
+        assert_eq!(
+            app.bitcoin_conf.as_ref().unwrap().get(None, "server"),
+            Some("0")
+        );
+    }
+
+    #[test]
+    fn test_save_preview_can_be_cancelled() {
+        use std::env::temp_dir;
+        use std::fs;
+
+        let base = temp_dir().join("pdm_save_preview_test");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base).unwrap();
+        let file_path = base.join("bitcoin.conf");
+        fs::write(&file_path, "server=0\n").unwrap();
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::new();
+        app.sidebar_index = 1;
+        app.toggle_menu();
+        app.load_bitcoin_conf(file_path.clone());
+
+        let mut step = 0;
+        let event_provider = |app: &mut App| {
+            step += 1;
+            let code = match step {
+                1 => KeyCode::Enter,     // focus the editor
+                2 => KeyCode::Char(' '), // change the first option
+                3 => KeyCode::Char('s'), // preview
+                4 => {
+                    let preview = app.save_preview.as_ref().unwrap();
+                    assert_eq!(preview.stats().0, 1);
+                    KeyCode::Char('q') // does not quit over the preview
+                }
+                5 => KeyCode::Esc, // cancel
+                6 => {
+                    assert!(app.save_preview.is_none());
+                    KeyCode::Char('q')
+                }
+                7 => KeyCode::Char('y'),
+                _ => panic!("should have exited"),
+            };
+            Ok(Event::Key(KeyEvent::new(code, KeyModifiers::empty())))
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+
+        assert!(app.config_editor.modified);
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "server=0\n");
+    }
+
+    #[test]
+    fn test_write_errors_open_a_dialog() {
+        use std::env::temp_dir;
+        use std::fs;
+
+        let base = temp_dir().join("pdm_write_error_test");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base).unwrap();
+        let file_path = base.join("bitcoin.conf");
+        fs::write(&file_path, "server=0\n").unwrap();
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::new();
+        app.sidebar_index = 1;
+        app.toggle_menu();
+        app.load_bitcoin_conf(file_path.clone());
+        app.backup_history = 0;
+
+        let mut step = 0;
+        let event_provider = |app: &mut App| {
+            step += 1;
+            let code = match step {
+                1 => KeyCode::Enter,     // focus the editor
+                2 => KeyCode::Char(' '), // change the first option
+                3 => KeyCode::Char('s'), // preview
+                4 => {
+                    // The directory disappears before the save is confirmed.
+                    fs::remove_dir_all(&base).unwrap();
+                    KeyCode::Enter
+                }
+                5 => {
+                    let dialog = app.error_dialog.as_ref().unwrap();
+                    assert_eq!(dialog.title, "Save failed");
+                    KeyCode::Char('s') // swallowed by the dialog
+                }
+                6 => KeyCode::Esc, // dismiss
+                7 => {
+                    assert!(app.error_dialog.is_none());
+                    assert!(app.save_preview.is_none());
+                    KeyCode::Char('q')
+                }
+                8 => KeyCode::Char('y'),
+                _ => panic!("should have exited"),
+            };
+            Ok(Event::Key(KeyEvent::new(code, KeyModifiers::empty())))
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+        assert!(app.config_editor.modified);
+    }
+
+    #[test]
+    fn test_background_results_update_status() {
+        use pdm::node_status::NodeStatus;
+        use std::env::temp_dir;
+        use std::fs;
+        use std::sync::mpsc;
+
+        let dir = temp_dir().join("pdm_background_results_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("bitcoin.conf");
+        // Nothing listens there, so the real poller only reports errors.
+        fs::write(&file_path, "rpcconnect=127.0.0.1:1\n").unwrap();
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::new();
+        let (sender, _receiver) = mpsc::channel();
+        app.events = Some(sender);
+        app.load_bitcoin_conf(file_path);
+        let poller = app.status_poller.as_ref().unwrap().id;
+
+        let status = NodeStatus {
+            chain: "regtest".into(),
+            blocks: 101,
+            headers: 101,
+            verification_progress: 1.0,
+            initial_block_download: false,
+            connections: 0,
+            connections_in: 0,
+            connections_out: 0,
+            mempool_txs: 0,
+            mempool_bytes: 0,
+            uptime: 60,
+            subversion: "/Satoshi:27.0.0/".into(),
+        };
+        let mut step = 0;
+        let event_provider = |app: &mut App| {
+            step += 1;
+            Ok(match step {
+                1 => AppEvent::NodeStatus {
+                    poller,
+                    status: Ok(status.clone()),
+                },
+                2 => AppEvent::Tick,
+                3 => {
+                    assert_eq!(app.node_status.as_ref().unwrap().blocks, 101);
+                    // A result from a poller that has been replaced
+                    AppEvent::NodeStatus {
+                        poller: poller + 1000,
+                        status: Err("stale".into()),
+                    }
+                }
+                4 => {
+                    assert_eq!(app.node_error, None);
+                    Event::Key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::empty())).into()
+                }
+                _ => panic!("should have exited"),
+            })
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn test_peer_actions_need_confirmation() {
+        use pdm::components::peer_list::PeerAction;
+        use pdm::rpc::types::PeerInfo;
+        use pdm::tasks::RpcReply;
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::new();
+        app.peer_list.set_peers(vec![PeerInfo {
+            id: 7,
+            addr: "203.0.113.5:8333".into(),
+            network: "ipv4".into(),
+            inbound: true,
+            connection_type: "inbound".into(),
+            version: 70016,
+            subver: "/Satoshi:26.0.0/".into(),
+            pingtime: Some(0.05),
+            bytessent: 1000,
+            bytesrecv: 2000,
+            conntime: 0,
+            synced_headers: 100,
+            synced_blocks: 100,
+        }]);
+
+        let key = |code| AppEvent::from(Event::Key(KeyEvent::new(code, KeyModifiers::empty())));
+        let mut step = 0;
+        let event_provider = |app: &mut App| {
+            step += 1;
+            Ok(match step {
+                1 | 2 => key(KeyCode::Down), // Peers
+                3 => {
+                    assert_eq!(app.current_screen, CurrentScreen::Peers);
+                    key(KeyCode::Enter)
+                }
+                4 => key(KeyCode::Char('b')),
+                5 => {
+                    assert_eq!(
+                        app.peer_list.pending,
+                        Some(PeerAction::Ban {
+                            subnet: "203.0.113.5".into()
+                        })
+                    );
+                    key(KeyCode::Char('q')) // declines, does not quit
+                }
+                6 => {
+                    assert_eq!(app.peer_list.pending, None);
+                    assert_eq!(app.peer_list.message.as_deref(), Some("Cancelled"));
+                    key(KeyCode::Char('d'))
+                }
+                7 => key(KeyCode::Char('y')),
+                8 => {
+                    // No node in tests: the action is refused up front
+                    assert!(
+                        app.peer_list
+                            .message
+                            .as_deref()
+                            .unwrap()
+                            .starts_with("No node")
+                    );
+                    AppEvent::Rpc(RpcReply::PeerAction(Ok(
+                        "Disconnected 203.0.113.5:8333".into()
+                    )))
+                }
+                9 => {
+                    assert_eq!(
+                        app.peer_list.message.as_deref(),
+                        Some("Disconnected 203.0.113.5:8333")
+                    );
+                    key(KeyCode::Esc)
+                }
+                10 => {
+                    assert_eq!(app.focus, Focus::Sidebar);
+                    key(KeyCode::Char('q'))
+                }
+                _ => panic!("should have exited"),
+            })
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn test_mempool_drill_down() {
+        use pdm::components::mempool_view::TxDetail;
+        use pdm::rpc::types::{MempoolEntry, MempoolFees};
+        use pdm::tasks::RpcReply;
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::new();
+
+        let txid = "ab".repeat(32);
+        let parent = "cd".repeat(32);
+        let detail = TxDetail {
+            txid: txid.clone(),
+            entry: MempoolEntry {
+                vsize: 141,
+                weight: 561,
+                time: 0,
+                ancestorcount: 2,
+                ancestorsize: 250,
+                descendantcount: 1,
+                descendantsize: 141,
+                fees: MempoolFees {
+                    base: 0.0000141,
+                    modified: 0.0000141,
+                    ancestor: 0.000025,
+                    descendant: 0.0000141,
+                },
+                depends: vec![parent.clone()],
+                spentby: Vec::new(),
+            },
+            ancestors: vec![parent.clone()],
+            descendants: Vec::new(),
+        };
+
+        let key = |code| AppEvent::from(Event::Key(KeyEvent::new(code, KeyModifiers::empty())));
+        let mut typed = txid.chars().collect::<Vec<_>>().into_iter();
+        let mut step = 0;
+        let event_provider = |app: &mut App| {
+            step += 1;
+            Ok(match step {
+                1..=3 => key(KeyCode::Down),
+                4 => {
+                    assert_eq!(app.current_screen, CurrentScreen::Mempool);
+                    key(KeyCode::Enter)
+                }
+                5 => key(KeyCode::Char('t')),
+                6 => key(KeyCode::Char('q')), // typed, not quit
+                7 => key(KeyCode::Enter),
+                8 => {
+                    assert_eq!(app.mempool_view.message.as_deref(), Some("Not a txid: q"));
+                    key(KeyCode::Char('/'))
+                }
+                9..=72 => key(KeyCode::Char(typed.next().unwrap())),
+                73 => key(KeyCode::Enter),
+                74 => {
+                    // No node in tests: the lookup is refused up front
+                    assert!(
+                        app.mempool_view
+                            .message
+                            .as_deref()
+                            .unwrap()
+                            .starts_with("No node")
+                    );
+                    AppEvent::Rpc(RpcReply::Transaction(Ok(detail.clone())))
+                }
+                75 => {
+                    assert_eq!(app.mempool_view.selected_related(), Some(&parent));
+                    key(KeyCode::Esc)
+                }
+                76 => {
+                    assert_eq!(app.mempool_view.detail, None);
+                    key(KeyCode::Esc)
+                }
+                77 => {
+                    assert_eq!(app.focus, Focus::Sidebar);
+                    key(KeyCode::Char('q'))
+                }
+                _ => panic!("should have exited"),
+            })
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn test_zmq_blocks_update_home() {
+        use pdm::zmq::{LinkState, ZmqEvent};
+        use std::env::temp_dir;
+        use std::fs;
+        use std::sync::mpsc;
+
+        let dir = temp_dir().join("pdm_zmq_events_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("bitcoin.conf");
+        // Nothing listens on either port.
+        fs::write(
+            &file_path,
+            "rpcconnect=127.0.0.1:1\nzmqpubhashblock=tcp://0.0.0.0:9\n",
+        )
+        .unwrap();
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::new();
+        let (sender, _receiver) = mpsc::channel();
+        app.events = Some(sender);
+        app.load_bitcoin_conf(file_path);
+        let subscriber = app.zmq_subscriber.as_ref().unwrap().id;
+        assert_eq!(
+            app.zmq.links.get("127.0.0.1:9"),
+            Some(&LinkState::Connecting)
+        );
+
+        // Due, but a restart without a p2poolv2 config would clear it
+        app.p2pool.restart_at = Some(std::time::Instant::now());
+
+        let hash = "00".repeat(32);
+        let mut step = 0;
+        let event_provider = |app: &mut App| {
+            step += 1;
+            Ok(match step {
+                1 => AppEvent::Zmq {
+                    subscriber,
+                    event: ZmqEvent::Connected {
+                        address: "127.0.0.1:9".into(),
+                    },
+                },
+                2 => AppEvent::Zmq {
+                    subscriber,
+                    event: ZmqEvent::Block {
+                        hash: hash.clone(),
+                        sequence: 0,
+                    },
+                },
+                3 => {
+                    assert_eq!(app.zmq.links["127.0.0.1:9"], LinkState::Connected);
+                    assert_eq!(app.zmq.last_block.as_ref(), Some(&hash));
+                    let now = std::time::Instant::now();
+                    assert_eq!(app.zmq.recent_block(now), Some(hash.as_str()));
+                    // Only a tick moves the supervisors' timers on
+                    assert!(app.p2pool.restart_at.is_some());
+                    // From a subscriber that has been replaced
+                    AppEvent::Zmq {
+                        subscriber: subscriber + 1000,
+                        event: ZmqEvent::Block {
+                            hash: "11".repeat(32),
+                            sequence: 1,
+                        },
+                    }
+                }
+                4 => {
+                    assert_eq!(app.zmq.blocks, 1);
+                    Event::Key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::empty())).into()
+                }
+                _ => panic!("should have exited"),
+            })
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn test_template_scrolls_by_fee_rate() {
+        use pdm::rpc::types::{BlockTemplate, TemplateTransaction};
+        use pdm::tasks::RpcReply;
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::new();
+
+        let transactions: Vec<TemplateTransaction> = (1..=25u64)
+            .map(|n| TemplateTransaction {
+                txid: format!("{:064x}", n),
+                fee: n * 1_000,
+                sigops: 1,
+                weight: 400,
+                depends: Vec::new(),
+            })
+            .collect();
+        let template = BlockTemplate {
+            version: 0x2000_0000,
+            previousblockhash: "00".repeat(32),
+            height: 101,
+            coinbasevalue: 5_000_000_000,
+            transactions,
+            sigoplimit: 80_000,
+            weightlimit: 4_000_000,
+            curtime: 0,
+            bits: "207fffff".into(),
+            target: String::new(),
+        };
+
+        let key = |code| AppEvent::from(Event::Key(KeyEvent::new(code, KeyModifiers::empty())));
+        let mut step = 0;
+        let event_provider = |app: &mut App| {
+            step += 1;
+            Ok(match step {
+                1..=4 => key(KeyCode::Down),
+                5 => {
+                    assert_eq!(app.current_screen, CurrentScreen::Template);
+                    // No node in tests
+                    assert!(app.template_view.error.is_some());
+                    key(KeyCode::Enter)
+                }
+                6 => AppEvent::Rpc(RpcReply::Template(Ok(template.clone()))),
+                7 => {
+                    assert_eq!(app.template_view.error, None);
+                    // Highest fee rate first
+                    let top = app.template_view.selected_transaction().unwrap();
+                    assert_eq!(top.fee, 25_000);
+                    key(KeyCode::PageDown)
+                }
+                8 => {
+                    assert_eq!(app.template_view.selected, 10);
+                    key(KeyCode::End)
+                }
+                9 => {
+                    let last = app.template_view.selected_transaction().unwrap();
+                    assert_eq!(last.fee, 1_000);
+                    key(KeyCode::Esc)
+                }
+                10 => {
+                    assert_eq!(app.focus, Focus::Sidebar);
+                    key(KeyCode::Char('q'))
+                }
+                _ => panic!("should have exited"),
+            })
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn test_debug_log_follows_and_filters() {
+        use std::env::temp_dir;
+        use std::fs;
+        use std::io::Write;
+
+        let dir = temp_dir().join("pdm_debug_log_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("regtest")).unwrap();
+        let file_path = dir.join("bitcoin.conf");
+        fs::write(&file_path, "regtest=1\n").unwrap();
+        let log_path = dir.join("regtest").join("debug.log");
+        fs::write(
+            &log_path,
+            "2024-05-01T12:00:00Z [net] Added connection peer=1\n\
+             2024-05-01T12:00:01Z UpdateTip: new best=00ab height=1\n",
+        )
+        .unwrap();
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::new();
+        app.load_bitcoin_conf(file_path);
+
+        let key = |code| AppEvent::from(Event::Key(KeyEvent::new(code, KeyModifiers::empty())));
+        let mut step = 0;
+        let event_provider = |app: &mut App| {
+            step += 1;
+            Ok(match step {
+                1..=5 => key(KeyCode::Down),
+                6 => {
+                    assert_eq!(app.current_screen, CurrentScreen::Log);
+                    assert_eq!(app.log_view.path.as_ref(), Some(&log_path));
+                    assert_eq!(app.log_view.lines.len(), 2);
+                    key(KeyCode::Enter)
+                }
+                7 => key(KeyCode::Char('c')),
+                8 => {
+                    assert_eq!(app.log_view.category.as_deref(), Some("net"));
+                    assert_eq!(app.log_view.visible().len(), 1);
+                    key(KeyCode::Esc)
+                }
+                9 => key(KeyCode::Char('/')),
+                10 => key(KeyCode::Char('q')), // typed, not quit
+                11 => key(KeyCode::Enter),
+                12 => {
+                    assert_eq!(app.log_view.visible().len(), 0);
+                    let mut log = fs::OpenOptions::new().append(true).open(&log_path).unwrap();
+                    writeln!(log, "2024-05-01T12:00:02Z quit requested").unwrap();
+                    AppEvent::Tick
+                }
+                13 => {
+                    assert_eq!(app.log_view.visible().len(), 1);
+                    key(KeyCode::Esc)
+                }
+                14 => {
+                    assert!(app.log_view.search.is_none());
+                    key(KeyCode::Esc)
+                }
+                15 => {
+                    assert_eq!(app.focus, Focus::Sidebar);
+                    key(KeyCode::Char('q'))
+                }
+                _ => panic!("should have exited"),
+            })
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bitcoind_stops_and_reports_crashes() {
+        use pdm::process::RunState;
+        use std::env::temp_dir;
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let dir = temp_dir().join("pdm_bitcoind_process_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("bitcoin.conf");
+        // Nothing listens on port 1, so the stop RPC fails
+        fs::write(&file_path, "regtest=1\n[regtest]\nrpcport=1\n").unwrap();
+        let crash = dir.join("crash");
+        let bitcoind = dir.join("bitcoind");
+        fs::write(
+            &bitcoind,
+            format!(
+                "#!/bin/sh\ntrap 'exit 0' TERM\necho \"args: $*\"\n\
+                 if [ -e {} ]; then echo 'Error: Cannot obtain a lock'; exit 1; fi\n\
+                 while true; do sleep 0.05; done\n",
+                crash.display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&bitcoind, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let (sender, receiver) = mpsc::channel();
+        let mut app = App::new();
+        app.events = Some(sender);
+        app.bitcoind_path = bitcoind;
+        app.load_bitcoin_conf(file_path.clone());
+
+        let key = |code| AppEvent::from(Event::Key(KeyEvent::new(code, KeyModifiers::empty())));
+        let next = || receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        let mut step = 0;
+        let event_provider = |app: &mut App| {
+            step += 1;
+            Ok(match step {
+                1..=6 => key(KeyCode::Down),
+                7 => {
+                    assert_eq!(app.current_screen, CurrentScreen::Bitcoind);
+                    key(KeyCode::Enter)
+                }
+                8 => key(KeyCode::Char('s')),
+                9 if app.bitcoind.output.is_empty() => {
+                    step -= 1;
+                    next()
+                }
+                9 => {
+                    assert_eq!(app.bitcoind.state, RunState::Running);
+                    assert_eq!(
+                        app.bitcoind.output[0],
+                        format!("args: -conf={} -daemon=0", file_path.display())
+                    );
+                    key(KeyCode::Char('x'))
+                }
+                10 => {
+                    assert!(app.bitcoind.pending.is_some());
+                    key(KeyCode::Char('y'))
+                }
+                11 if app.bitcoind.is_running() => {
+                    step -= 1;
+                    next()
+                }
+                11 => {
+                    // The failed stop RPC fell back to SIGTERM
+                    assert!(matches!(app.bitcoind.state, RunState::Exited(r) if r.success()));
+                    fs::write(&crash, "").unwrap();
+                    key(KeyCode::Char('s'))
+                }
+                12 if app.bitcoind.is_running() => {
+                    step -= 1;
+                    next()
+                }
+                12 => {
+                    assert!(matches!(app.bitcoind.state, RunState::Crashed(_)));
+                    assert_eq!(
+                        app.bitcoind.message.as_deref(),
+                        Some("bitcoind crashed: exited with code 1")
+                    );
+                    key(KeyCode::Esc)
+                }
+                13 => {
+                    assert_eq!(app.focus, Focus::Sidebar);
+                    key(KeyCode::Char('q'))
+                }
+                _ => panic!("should have exited"),
+            })
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_p2pool_supervision_restarts_after_crashes() {
+        use pdm::process::RunState;
+        use std::env::temp_dir;
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+        use std::sync::mpsc;
+        use std::time::{Duration, Instant};
+
+        let dir = temp_dir().join("pdm_p2pool_process_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let conf_path = dir.join("p2pool.toml");
+        fs::write(&conf_path, "[stratum]\nnetwork = \"signet\"\n").unwrap();
+        let crash = dir.join("crash");
+        let p2pool = dir.join("p2poolv2");
+        fs::write(
+            &p2pool,
+            "#!/bin/sh\nif [ -e crash ]; then echo 'store is locked' >&2; exit 2; fi\n\
+             trap 'exit 0' TERM\necho \"args: $*\"\n\
+             while true; do sleep 0.05; done\n",
+        )
+        .unwrap();
+        fs::set_permissions(&p2pool, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let (sender, receiver) = mpsc::channel();
+        let mut app = App::new();
+        app.events = Some(sender);
+        app.p2pool_path = p2pool;
+        app.load_p2pool_conf(conf_path.clone());
+
+        let key = |code| AppEvent::from(Event::Key(KeyEvent::new(code, KeyModifiers::empty())));
+        let next = || receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        let mut step = 0;
+        let event_provider = |app: &mut App| {
+            step += 1;
+            Ok(match step {
+                1..=8 => key(KeyCode::Down),
+                9 => {
+                    assert_eq!(app.current_screen, CurrentScreen::P2pool);
+                    key(KeyCode::Enter)
+                }
+                10 => key(KeyCode::Char('s')),
+                11 if app.p2pool.output.is_empty() => {
+                    step -= 1;
+                    next()
+                }
+                11 => {
+                    assert_eq!(app.p2pool.state, RunState::Running);
+                    assert_eq!(
+                        app.p2pool.output[0],
+                        format!("args: --config {}", conf_path.display())
+                    );
+                    key(KeyCode::Char('a'))
+                }
+                12 => {
+                    assert!(app.p2pool.auto_restart);
+                    fs::write(&crash, "").unwrap();
+                    key(KeyCode::Char('R'))
+                }
+                13 => key(KeyCode::Char('y')),
+                // Stops, is restarted and crashes on the new start
+                14 if app.p2pool.crash_streak == 0 => {
+                    step -= 1;
+                    next()
+                }
+                14 => {
+                    assert!(matches!(app.p2pool.state, RunState::Crashed(_)));
+                    assert_eq!(app.p2pool.output.back().unwrap(), "store is locked");
+                    assert_eq!(
+                        app.p2pool.message.as_deref(),
+                        Some("p2poolv2 crashed: exited with code 2, restarting in 1s")
+                    );
+                    // Skip the backoff
+                    app.p2pool.restart_at = Some(Instant::now());
+                    AppEvent::Tick
+                }
+                15 if app.p2pool.crash_streak == 1 => {
+                    step -= 1;
+                    next()
+                }
+                15 => {
+                    assert_eq!(app.p2pool.crash_streak, 2);
+                    assert!(app.p2pool.restart_at.is_some());
+                    key(KeyCode::Char('x'))
+                }
+                16 => key(KeyCode::Char('y')),
+                17 => {
+                    assert_eq!(app.p2pool.restart_at, None);
+                    assert_eq!(
+                        app.p2pool.message.as_deref(),
+                        Some("p2poolv2 is not running")
+                    );
+                    key(KeyCode::Esc)
+                }
+                18 => key(KeyCode::Char('q')),
+                _ => panic!("should have exited"),
+            })
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn test_stratum_monitor_reports_pool_work() {
+        use pdm::zmq::LinkState;
+        use std::env::temp_dir;
+        use std::fs;
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+        use std::sync::mpsc;
+        use std::thread;
+        use std::time::Duration;
+
+        // A stub pool: answers subscribe and authorize, then sends work
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let pool = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            let mut requests = Vec::new();
+            for reply in [
+                r#"{"id":1,"result":[[],"2a000001",4],"error":null}"#,
+                r#"{"id":2,"result":true,"error":null}"#,
+            ] {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                requests.push(line);
+                writeln!(writer, "{}", reply).unwrap();
+            }
+            writeln!(
+                writer,
+                r#"{{"id":null,"method":"mining.set_difficulty","params":[32]}}"#
+            )
+            .unwrap();
+            writeln!(
+                writer,
+                r#"{{"id":null,"method":"mining.notify","params":["b7","00","","",[],"20000000","1d00ffff","00000000",true]}}"#
+            )
+            .unwrap();
+            // Until pdm disconnects
+            let mut rest = String::new();
+            while reader.read_line(&mut rest).unwrap_or(0) > 0 {}
+            requests
+        });
+
+        let dir = temp_dir().join("pdm_stratum_monitor_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let conf_path = dir.join("p2pool.toml");
+        fs::write(
+            &conf_path,
+            format!("[stratum]\nhostname = \"0.0.0.0\"\nport = {}\n", port),
+        )
+        .unwrap();
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let (sender, receiver) = mpsc::channel();
+        let mut app = App::new();
+        app.events = Some(sender);
+        app.load_p2pool_conf(conf_path);
+
+        let key = |code| AppEvent::from(Event::Key(KeyEvent::new(code, KeyModifiers::empty())));
+        let next = || receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        let mut step = 0;
+        let event_provider = |app: &mut App| {
+            step += 1;
+            Ok(match step {
+                1..=9 => key(KeyCode::Down),
+                10 => {
+                    assert_eq!(app.current_screen, CurrentScreen::Stratum);
+                    assert!(app.stratum_monitor.is_some());
+                    key(KeyCode::Enter)
+                }
+                11 if app.stratum.as_ref().unwrap().jobs == 0 => {
+                    step -= 1;
+                    next()
+                }
+                11 => {
+                    let status = app.stratum.as_ref().unwrap();
+                    assert_eq!(status.target.address, format!("127.0.0.1:{}", port));
+                    assert_eq!(status.link, LinkState::Connected);
+                    assert_eq!(status.extranonce1.as_deref(), Some("2a000001"));
+                    assert_eq!(status.authorized, Some(true));
+                    assert_eq!(status.difficulty, Some(32.0));
+                    assert_eq!(status.last_job.as_ref().unwrap().0, "b7");
+                    key(KeyCode::Char('x'))
+                }
+                12 => {
+                    assert!(app.stratum_monitor.is_none());
+                    assert_eq!(
+                        app.stratum.as_ref().unwrap().link,
+                        LinkState::Down("disconnected by pdm".into())
+                    );
+                    key(KeyCode::Esc)
+                }
+                13 => key(KeyCode::Char('q')),
+                _ => panic!("should have exited"),
+            })
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+        let requests = pool.join().unwrap();
+        assert!(requests[0].contains("mining.subscribe"));
+        assert!(requests[1].contains(r#"["pdm-monitor","x"]"#));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_miners_from_p2pool_output() {
+        use std::env::temp_dir;
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let dir = temp_dir().join("pdm_miners_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let conf_path = dir.join("p2pool.toml");
+        fs::write(&conf_path, "[stratum]\nnetwork = \"signet\"\n").unwrap();
+        let p2pool = dir.join("p2poolv2");
+        fs::write(
+            &p2pool,
+            "#!/bin/sh\ntrap 'exit 0' TERM\n\
+             echo 'INFO Miner authorized worker=tb1qpayout.rack1-a ip=10.0.1.1:4001 difficulty=64'\n\
+             echo 'INFO Miner authorized worker=tb1qpayout.rack2-b ip=10.0.2.1:4001 difficulty=32'\n\
+             echo 'INFO Share accepted worker=tb1qpayout.rack1-a difficulty=64'\n\
+             echo 'INFO Share accepted worker=tb1qpayout.rack1-a difficulty=64'\n\
+             echo 'WARN Share rejected worker=tb1qpayout.rack2-b reason=\"low difficulty\"'\n\
+             echo 'INFO Share accepted worker=tb1qpayout.rack2-b difficulty=32'\n\
+             echo 'INFO Miner authorized worker=tb1qpayout.rack1-c ip=10.0.1.2:4001'\n\
+             echo 'INFO Miner authorized worker=tb1qpayout.pdm-monitor ip=127.0.0.1:4002'\n\
+             echo \"colours: ${NO_COLOR:-on}\"\n\
+             printf '\\033[32m INFO\\033[0m Share accepted \\033[3mworker\\033[0m\\033[2m=\\033[0mtb1qpayout.rack1-c\\n'\n\
+             echo done\n\
+             while true; do sleep 0.05; done\n",
+        )
+        .unwrap();
+        fs::set_permissions(&p2pool, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let (sender, receiver) = mpsc::channel();
+        let mut app = App::new();
+        app.events = Some(sender);
+        app.p2pool_path = p2pool;
+        app.load_p2pool_conf(conf_path);
+        app.start_p2pool();
+
+        let key = |code| AppEvent::from(Event::Key(KeyEvent::new(code, KeyModifiers::empty())));
+        let next = || receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        let names = |app: &App| {
+            app.miner_list
+                .visible(std::time::Instant::now())
+                .iter()
+                .map(|m| m.short_name().to_string())
+                .collect::<Vec<_>>()
+        };
+        let mut step = 0;
+        let event_provider = |app: &mut App| {
+            step += 1;
+            Ok(match step {
+                1 if app.p2pool.output.back().map(String::as_str) != Some("done") => {
+                    step -= 1;
+                    next()
+                }
+                1 => {
+                    assert!(app.p2pool.output.contains(&"colours: 1".to_string()));
+                    assert_eq!(names(app), ["rack1-a", "rack1-c", "rack2-b"]);
+                    assert_eq!(app.miner_list.miners[2].accepted, 1);
+                    let b = &app.miner_list.miners[1];
+                    assert_eq!((b.accepted, b.rejected), (1, 1));
+                    assert_eq!(b.ip.as_deref(), Some("10.0.2.1"));
+                    key(KeyCode::Down)
+                }
+                2..=10 => key(KeyCode::Down),
+                11 => {
+                    assert_eq!(app.current_screen, CurrentScreen::Miners);
+                    key(KeyCode::Enter)
+                }
+                // Most accepted shares first
+                12 | 13 => key(KeyCode::Char('s')),
+                14 => key(KeyCode::Char('S')),
+                15 => {
+                    assert_eq!(names(app), ["rack1-a", "rack2-b", "rack1-c"]);
+                    key(KeyCode::Char('/'))
+                }
+                16 => {
+                    assert!(app.is_typing());
+                    key(KeyCode::Char('q'))
+                }
+                17 => key(KeyCode::Backspace),
+                18 => key(KeyCode::Char('1')),
+                19 => key(KeyCode::Char('0')),
+                20 => key(KeyCode::Char('.')),
+                21 => key(KeyCode::Char('0')),
+                22 => key(KeyCode::Char('.')),
+                23 => key(KeyCode::Char('1')),
+                24 => key(KeyCode::Enter),
+                25 => {
+                    // By IP: both rack 1 machines
+                    assert_eq!(app.miner_list.filter.as_deref(), Some("10.0.1"));
+                    assert_eq!(names(app), ["rack1-a", "rack1-c"]);
+                    key(KeyCode::Down)
+                }
+                26 => {
+                    let now = std::time::Instant::now();
+                    let selected = app.miner_list.selected_miner(now).unwrap();
+                    assert_eq!(selected.short_name(), "rack1-c");
+                    key(KeyCode::Esc)
+                }
+                27 => {
+                    assert_eq!(app.miner_list.filter, None);
+                    assert_eq!(app.focus, Focus::Content);
+                    key(KeyCode::Esc)
+                }
+                28 => key(KeyCode::Char('q')),
+                _ => panic!("should have exited"),
+            })
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+        app.stop_p2pool();
+    }
+
+    #[test]
+    fn test_children_are_stopped_on_exit() {
+        use std::env::temp_dir;
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let dir = temp_dir().join("pdm_stop_on_exit_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let conf_path = dir.join("p2pool.toml");
+        fs::write(&conf_path, "[stratum]\nnetwork = \"signet\"\n").unwrap();
+        let p2pool = dir.join("p2poolv2");
+        fs::write(
+            &p2pool,
+            "#!/bin/sh\ntrap 'echo shutting down; exit 0' TERM\n\
+             echo ready\n\
+             while true; do sleep 0.05; done\n",
+        )
+        .unwrap();
+        fs::set_permissions(&p2pool, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        let mut app = App::new();
+        app.events = Some(sender);
+        app.p2pool_path = p2pool;
+        app.load_p2pool_conf(conf_path);
+        app.p2pool.set_auto_restart(true);
+        app.start_p2pool();
+        let next = || {
+            receiver
+                .recv_timeout(Duration::from_secs(10))
+                .map_err(io::Error::other)
+        };
+        // Signals sent before the trap is set would kill the shell outright
+        while app.p2pool.output.is_empty() {
+            if let AppEvent::Process { process, event } = next().unwrap() {
+                app.handle_process_event(process, event);
+            }
+        }
+
+        assert_eq!(app.stop_children(), ["p2poolv2"]);
+        let notes = wait_for_children(&mut app, next, Duration::from_secs(10)).unwrap();
+        assert!(notes.is_empty());
+        assert!(!app.p2pool.is_running());
+        assert_eq!(app.p2pool.restart_at, None);
+        assert_eq!(
+            app.p2pool.output.back().map(String::as_str),
+            Some("shutting down")
+        );
+        assert!(app.stop_children().is_empty());
+
+        // One that ignores SIGTERM is killed once the grace period is over
+        fs::write(
+            &app.p2pool_path,
+            "#!/bin/sh\ntrap '' TERM\necho ready\nwhile true; do sleep 0.05; done\n",
+        )
+        .unwrap();
+        app.start_p2pool();
+        let pid = app.p2pool.process.as_ref().unwrap().pid;
+        while app.p2pool.output.back().map(String::as_str) != Some("ready") {
+            if let AppEvent::Process { process, event } = next().unwrap() {
+                app.handle_process_event(process, event);
+            }
+        }
+        assert_eq!(app.stop_children(), ["p2poolv2"]);
+        let ticks = || Ok(receiver.try_recv().unwrap_or(AppEvent::Tick));
+        let notes = wait_for_children(&mut app, ticks, Duration::ZERO).unwrap();
+        assert_eq!(
+            notes,
+            [format!("Killed p2poolv2 (pid {}), it ignored SIGTERM", pid)]
+        );
+        wait_for_children(&mut app, next, Duration::from_secs(10)).unwrap();
+        assert!(matches!(
+            app.p2pool.state,
+            process::RunState::Exited(report) if report.signal == Some(9)
+        ));
+    }
+
+    #[test]
+    fn test_p2pool_config_edit_and_save() {
+        use pdm::backup;
+        use std::env::temp_dir;
+        use std::fs;
+
+        let dir = temp_dir().join("pdm_p2pool_config_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("p2pool.toml");
+        fs::write(
+            &file_path,
+            "# my pool\n[stratum]\nport = 3333 # firewall\nnetwork = \"signet\"\n",
+        )
+        .unwrap();
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::new();
+        app.explorer.open_dir(dir.clone());
+
+        let key = |code| AppEvent::from(Event::Key(KeyEvent::new(code, KeyModifiers::empty())));
+        let mut step = 0;
+        let event_provider = |app: &mut App| {
+            step += 1;
+            Ok(match step {
+                1..=7 => key(KeyCode::Down),
+                8 => {
+                    assert_eq!(app.current_screen, CurrentScreen::P2poolConfig);
+                    key(KeyCode::Enter)
+                }
+                9 => {
+                    assert_eq!(app.current_screen, CurrentScreen::FileExplorer);
+                    assert_eq!(app.explorer_target, ConfKind::P2pool);
+                    key(KeyCode::Down) // past ".."
+                }
+                10 => key(KeyCode::Enter),
+                11 => {
+                    assert_eq!(app.current_screen, CurrentScreen::P2poolConfig);
+                    assert_eq!(app.p2pool_conf_path.as_ref(), Some(&file_path));
+                    assert!(app.bitcoin_conf_path.is_none());
+                    key(KeyCode::Enter)
+                }
+                12 => {
+                    assert_eq!(app.focus, Focus::Content);
+                    key(KeyCode::Right) // network: signet -> regtest
+                }
+                13 | 14 => key(KeyCode::Down),
+                15 => key(KeyCode::Enter), // edit stratum.port
+                16..=19 => key(KeyCode::Backspace),
+                20 => key(KeyCode::Char('q')), // not a digit, not quit
+                21..=24 => key(KeyCode::Char('4')),
+                25 => key(KeyCode::Enter),
+                26 => {
+                    let settings = app.p2pool_conf.as_ref().unwrap().settings();
+                    assert_eq!(settings.stratum_port, Some(4444));
+                    assert!(app.p2pool_editor.modified);
+                    key(KeyCode::Char('s'))
+                }
+                27 => {
+                    assert!(app.save_preview.is_some());
+                    key(KeyCode::Char('y'))
+                }
+                28 => {
+                    assert_eq!(app.p2pool_editor.message.as_deref(), Some("Saved"));
+                    assert!(!app.p2pool_editor.modified);
+                    key(KeyCode::Esc)
+                }
+                29 => key(KeyCode::Char('q')),
+                _ => panic!("should have exited"),
+            })
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+        assert_eq!(
+            fs::read_to_string(&file_path).unwrap(),
+            "# my pool\n[stratum]\nport = 4444 # firewall\nnetwork = \"regtest\"\n"
+        );
+        assert_eq!(backup::list(&file_path).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_p2pool_history_restore_asks_about_edits() {
+        use pdm::backup;
+        use std::env::temp_dir;
+        use std::fs;
+
+        let dir = temp_dir().join("pdm_p2pool_history_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("p2pool.toml");
+        fs::write(&file_path, "[stratum]\nnetwork = \"signet\"\n").unwrap();
+        backup::create(&file_path, 5).unwrap();
+        fs::write(&file_path, "[stratum]\nnetwork = \"main\"\n").unwrap();
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::new();
+        app.load_p2pool_conf(file_path.clone());
+
+        let key = |code| AppEvent::from(Event::Key(KeyEvent::new(code, KeyModifiers::empty())));
+        let mut step = 0;
+        let event_provider = |app: &mut App| {
+            step += 1;
+            Ok(match step {
+                1..=7 => key(KeyCode::Down),
+                8 => key(KeyCode::Enter),
+                9 => key(KeyCode::Right), // an unsaved edit
+                10 => key(KeyCode::Char('h')),
+                11 => {
+                    assert_eq!(app.p2pool_view, P2poolView::History);
+                    assert_eq!(app.history_view.backups.len(), 1);
+                    key(KeyCode::Char('r'))
+                }
+                12 => {
+                    assert_eq!(app.discard_prompt, Some(Discard::Restore(ConfKind::P2pool)));
+                    key(KeyCode::Char('n'))
+                }
+                13 => {
+                    assert!(app.p2pool_editor.modified);
+                    key(KeyCode::Char('r'))
+                }
+                14 => key(KeyCode::Char('y')),
+                15 => {
+                    let message = app.history_view.message.as_deref().unwrap();
+                    assert!(message.starts_with("Restored"), "{}", message);
+                    assert!(!app.p2pool_editor.modified);
+                    key(KeyCode::Char('h'))
+                }
+                16 => {
+                    assert_eq!(app.p2pool_view, P2poolView::Settings);
+                    key(KeyCode::Char('q'))
+                }
+                _ => panic!("should have exited"),
+            })
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+        assert_eq!(
+            fs::read_to_string(&file_path).unwrap(),
+            "[stratum]\nnetwork = \"signet\"\n"
+        );
+    }
+
+    #[test]
+    fn test_p2pool_crosscheck_applies_fixes() {
+        use std::env::temp_dir;
+        use std::fs;
+
+        let dir = temp_dir().join("pdm_p2pool_crosscheck_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let bitcoin_path = dir.join("bitcoin.conf");
+        fs::write(
+            &bitcoin_path,
+            "chain=signet\nrpcuser=pool\nrpcpassword=secret\nzmqpubhashblock=tcp://0.0.0.0:28332\n",
+        )
+        .unwrap();
+        let p2pool_path = dir.join("p2pool.toml");
+        fs::write(
+            &p2pool_path,
+            "[stratum]\nnetwork = \"signet\"\n\n[bitcoinrpc]\nurl = \"http://127.0.0.1:8332\"\n",
+        )
+        .unwrap();
+
+        let backend = TestBackend::new(80, 25);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::new();
+        app.load_p2pool_conf(p2pool_path);
+
+        let key = |code| AppEvent::from(Event::Key(KeyEvent::new(code, KeyModifiers::empty())));
+        let issues = |app: &App| {
+            app.p2pool_crosscheck()
+                .unwrap()
+                .iter()
+                .filter(|c| c.status != readiness::Status::Ok)
+                .map(|c| c.name)
+                .collect::<Vec<_>>()
+        };
+        let mut step = 0;
+        let event_provider = |app: &mut App| {
+            step += 1;
+            Ok(match step {
+                1..=7 => key(KeyCode::Down),
+                8 => key(KeyCode::Enter),
+                9 => key(KeyCode::Char('c')),
+                10 => {
+                    // Without a bitcoin.conf there is nothing to compare with
+                    assert_eq!(app.p2pool_view, P2poolView::Settings);
+                    assert!(app.p2pool_editor.message.is_some());
+                    app.load_bitcoin_conf(bitcoin_path.clone());
+                    key(KeyCode::Char('c'))
+                }
+                11 => {
+                    assert_eq!(app.p2pool_view, P2poolView::CrossCheck);
+                    assert_eq!(
+                        issues(app),
+                        vec!["RPC port", "RPC credentials", "ZMQ blocks", "Payout"]
+                    );
+                    key(KeyCode::Down)
+                }
+                12 => key(KeyCode::Enter), // RPC port
+                13 => key(KeyCode::Down),
+                14 => key(KeyCode::Enter), // RPC credentials
+                15 => key(KeyCode::Down),
+                16 => key(KeyCode::Enter), // ZMQ blocks
+                17 => {
+                    assert_eq!(issues(app), vec!["Payout"]);
+                    assert!(app.p2pool_editor.modified);
+                    assert_eq!(
+                        app.p2pool_editor.message.as_deref(),
+                        Some("Applied: set stratum.zmqpubhashblock = tcp://127.0.0.1:28332")
+                    );
+                    key(KeyCode::Esc)
+                }
+                18 => {
+                    assert_eq!(app.p2pool_view, P2poolView::Settings);
+                    assert_eq!(app.focus, Focus::Content);
+                    key(KeyCode::Esc)
+                }
+                19 => key(KeyCode::Char('q')),
+                20 => key(KeyCode::Char('y')),
+                _ => panic!("should have exited"),
+            })
+        };
+
+        let res = run_app(&mut terminal, &mut app, event_provider);
+        assert!(res.is_ok());
+        let settings = app.p2pool_conf.unwrap().settings();
+        assert_eq!(settings.rpc_url.as_deref(), Some("http://127.0.0.1:38332"));
+        assert_eq!(settings.rpc_username.as_deref(), Some("pool"));
     }
 }
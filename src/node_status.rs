@@ -0,0 +1,269 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Periodic snapshots of bitcoind's state for the Home dashboard.
+//!
+//! A [`StatusPoller`] runs on the [`RpcTasks`] runtime and hands finished
+//! snapshots to the event loop, so the UI never waits on the node.
+
+use crate::event::AppEvent;
+use crate::rpc::types::{BlockchainInfo, MempoolInfo, NetworkInfo};
+use crate::rpc::{self, Client, Request};
+use crate::tasks::RpcTasks;
+use serde_json::json;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+/// How often the dashboard is refreshed.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// What the dashboard shows about the node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeStatus {
+    pub chain: String,
+    pub blocks: u64,
+    pub headers: u64,
+    /// 0.0 to 1.0.
+    pub verification_progress: f64,
+    pub initial_block_download: bool,
+    pub connections: u32,
+    pub connections_in: u32,
+    pub connections_out: u32,
+    pub mempool_txs: u64,
+    /// Sum of virtual sizes, in bytes.
+    pub mempool_bytes: u64,
+    /// Seconds.
+    pub uptime: u64,
+    /// e.g. `/Satoshi:27.0.0/`
+    pub subversion: String,
+}
+
+impl NodeStatus {
+    /// Fetches everything in one batched round trip.
+    pub async fn fetch(client: &Client) -> Result<NodeStatus, rpc::Error> {
+        let mut results = client
+            .batch(&[
+                Request::new("getblockchaininfo", json!([])),
+                Request::new("getnetworkinfo", json!([])),
+                Request::new("getmempoolinfo", json!([])),
+                Request::new("uptime", json!([])),
+            ])
+            .await?
+            .into_iter();
+        let mut next = || results.next().expect("one result per request");
+        let chain: BlockchainInfo = rpc::parse(next()?)?;
+        let network: NetworkInfo = rpc::parse(next()?)?;
+        let mempool: MempoolInfo = rpc::parse(next()?)?;
+        let uptime: u64 = rpc::parse(next()?)?;
+
+        Ok(NodeStatus {
+            chain: chain.chain,
+            blocks: chain.blocks,
+            headers: chain.headers,
+            verification_progress: chain.verificationprogress,
+            initial_block_download: chain.initialblockdownload,
+            connections: network.connections,
+            connections_in: network.connections_in,
+            connections_out: network.connections_out,
+            mempool_txs: mempool.size,
+            mempool_bytes: mempool.bytes,
+            uptime,
+            subversion: network.subversion,
+        })
+    }
+}
+
+/// Fetches a [`NodeStatus`] every `interval` in the background and sends
+/// it to the event loop as [`AppEvent::NodeStatus`].
+///
+/// Polling stops once the poller is dropped. Results carry the poller's
+/// `id` so that one still queued from a replaced poller can be ignored.
+pub struct StatusPoller {
+    pub id: u64,
+    /// Where the node is, for display.
+    pub address: String,
+    wake: Arc<Notify>,
+    task: JoinHandle<()>,
+}
+
+static NEXT_POLLER_ID: AtomicU64 = AtomicU64::new(1);
+
+impl StatusPoller {
+    pub fn start(tasks: &RpcTasks, interval: Duration) -> StatusPoller {
+        let id = NEXT_POLLER_ID.fetch_add(1, Ordering::Relaxed);
+        let client = tasks.client();
+        let config = client.config();
+        let address = format!("{}:{}", config.host, config.port);
+        let events = tasks.events();
+        let wake = Arc::new(Notify::new());
+        let woken = Arc::clone(&wake);
+        let task = tasks.handle().spawn(async move {
+            loop {
+                let status = NodeStatus::fetch(&client)
+                    .await
+                    .map_err(|err| err.to_string());
+                if events
+                    .send(AppEvent::NodeStatus { poller: id, status })
+                    .is_err()
+                {
+                    return;
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {}
+                    _ = woken.notified() => {}
+                }
+            }
+        });
+        StatusPoller {
+            id,
+            address,
+            wake,
+            task,
+        }
+    }
+
+    /// Fetches a new snapshot now instead of at the next interval.
+    pub fn poll_now(&self) {
+        self.wake.notify_one();
+    }
+}
+
+impl Drop for StatusPoller {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Formats seconds as e.g. `3d 4h 12m`.
+pub fn format_uptime(secs: u64) -> String {
+    let (days, hours, minutes) = (secs / 86_400, secs % 86_400 / 3600, secs % 3600 / 60);
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m {}s", minutes, secs % 60)
+    }
+}
+
+/// Formats a byte count with a decimal unit, e.g. `5.2 MB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["kB", "MB", "GB", "TB"];
+    if bytes < 1000 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::{Auth, RpcConfig, mock};
+    use serde_json::Value;
+    use std::collections::HashMap;
+    use std::sync::mpsc;
+
+    fn node_results() -> HashMap<&'static str, Value> {
+        HashMap::from([
+            (
+                "getblockchaininfo",
+                json!({
+                    "chain": "signet", "blocks": 200000, "headers": 200010,
+                    "bestblockhash": "00ab", "difficulty": 0.003,
+                    "verificationprogress": 0.9995, "initialblockdownload": true,
+                }),
+            ),
+            (
+                "getnetworkinfo",
+                json!({
+                    "version": 270000, "subversion": "/Satoshi:27.0.0/",
+                    "protocolversion": 70016, "connections": 10,
+                    "connections_in": 2, "connections_out": 8,
+                    "relayfee": 0.00001, "warnings": "",
+                }),
+            ),
+            (
+                "getmempoolinfo",
+                json!({
+                    "loaded": true, "size": 1234, "bytes": 5_200_000, "usage": 9_000_000,
+                    "maxmempool": 300_000_000, "mempoolminfee": 0.00001,
+                    "minrelaytxfee": 0.00001,
+                }),
+            ),
+            ("uptime", json!(273_600)),
+        ])
+    }
+
+    fn config(port: u16) -> RpcConfig {
+        RpcConfig {
+            host: "127.0.0.1".into(),
+            port,
+            auth: Auth::UserPass {
+                user: "pool".into(),
+                password: "secret".into(),
+            },
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_combines_calls() {
+        let port = mock::node(node_results()).await;
+        let status = NodeStatus::fetch(&Client::new(config(port))).await.unwrap();
+        assert_eq!(status.chain, "signet");
+        assert_eq!((status.blocks, status.headers), (200000, 200010));
+        assert!(status.initial_block_download);
+        assert_eq!(status.connections_out, 8);
+        assert_eq!(status.mempool_txs, 1234);
+        assert_eq!(status.uptime, 273_600);
+        assert_eq!(status.subversion, "/Satoshi:27.0.0/");
+    }
+
+    #[tokio::test]
+    async fn fetch_fails_if_any_call_fails() {
+        let mut results = node_results();
+        results.remove("uptime");
+        let port = mock::node(results).await;
+        let err = NodeStatus::fetch(&Client::new(config(port)))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Method not found (code -32601)");
+    }
+
+    #[test]
+    fn poller_delivers_results() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let port = runtime.block_on(mock::node(node_results()));
+        let (sender, receiver) = mpsc::channel();
+        let tasks = RpcTasks::start(config(port), sender).unwrap();
+        let poller = StatusPoller::start(&tasks, Duration::from_secs(60));
+
+        match receiver.recv_timeout(Duration::from_secs(5)).unwrap() {
+            AppEvent::NodeStatus { poller: id, status } => {
+                assert_eq!(id, poller.id);
+                assert_eq!(status.unwrap().blocks, 200000);
+            }
+            event => panic!("unexpected {:?}", event),
+        }
+    }
+
+    #[test]
+    fn formats_durations_and_sizes() {
+        assert_eq!(format_uptime(273_600 + 720), "3d 4h 12m");
+        assert_eq!(format_uptime(3_700), "1h 1m");
+        assert_eq!(format_uptime(59), "0m 59s");
+        assert_eq!(format_bytes(999), "999 B");
+        assert_eq!(format_bytes(5_200_000), "5.2 MB");
+        assert_eq!(format_bytes(1_500_000_000_000), "1.5 TB");
+    }
+}
@@ -0,0 +1,243 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Bitcoin address validation for payout settings.
+//!
+//! Segwit addresses are checked as bech32 (version 0) or bech32m (BIP 350),
+//! legacy ones as base58check. Only the encoding and the network prefix
+//! are checked; whether anyone holds the key is not knowable.
+
+use crate::bitcoin_conf::Network;
+use sha2::{Digest, Sha256};
+
+const MAIN: &[Network] = &[Network::Main];
+/// Legacy test prefixes are shared by every test network, regtest included.
+const TEST: &[Network] = &[
+    Network::Test,
+    Network::Testnet4,
+    Network::Signet,
+    Network::Regtest,
+];
+const TEST_SEGWIT: &[Network] = &[Network::Test, Network::Testnet4, Network::Signet];
+const REGTEST: &[Network] = &[Network::Regtest];
+
+const BECH32_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32_CONST: u32 = 1;
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Checks that `address` can be paid to on `network`.
+pub fn validate(address: &str, network: Network) -> Result<(), String> {
+    let networks = networks(address)?;
+    if networks.contains(&network) {
+        Ok(())
+    } else {
+        let names: Vec<&str> = networks.iter().map(Network::name).collect();
+        Err(format!("{} address, not {}", names.join("/"), network))
+    }
+}
+
+/// The networks `address` is valid on.
+pub fn networks(address: &str) -> Result<&'static [Network], String> {
+    let lower = address.to_ascii_lowercase();
+    for (hrp, networks) in [("bcrt1", REGTEST), ("bc1", MAIN), ("tb1", TEST_SEGWIT)] {
+        if lower.starts_with(hrp) {
+            return check_segwit(address).map(|()| networks);
+        }
+    }
+    let bytes = base58check(address).ok_or_else(|| "not a bitcoin address".to_string())?;
+    match (bytes.len(), bytes.first()) {
+        (21, Some(0x00 | 0x05)) => Ok(MAIN),
+        (21, Some(0x6f | 0xc4)) => Ok(TEST),
+        _ => Err("not a bitcoin address".to_string()),
+    }
+}
+
+/// Checks the bech32/bech32m encoding of a segwit address.
+fn check_segwit(address: &str) -> Result<(), String> {
+    let invalid = |why: &str| Err(format!("invalid segwit address: {}", why));
+    if address.chars().any(|c| c.is_ascii_lowercase())
+        && address.chars().any(|c| c.is_ascii_uppercase())
+    {
+        return invalid("mixed case");
+    }
+    let lower = address.to_ascii_lowercase();
+    let Some((hrp, data)) = lower.rsplit_once('1') else {
+        return invalid("no separator");
+    };
+    let Some(values) = data
+        .bytes()
+        .map(|c| BECH32_CHARSET.iter().position(|&b| b == c).map(|v| v as u8))
+        .collect::<Option<Vec<u8>>>()
+    else {
+        return invalid("invalid character");
+    };
+    if values.len() < 7 {
+        return invalid("too short");
+    }
+
+    let version = values[0];
+    let expected = if version == 0 {
+        BECH32_CONST
+    } else {
+        BECH32M_CONST
+    };
+    let mut checked = hrp_expand(hrp);
+    checked.extend(&values);
+    if polymod(&checked) != expected {
+        return invalid("bad checksum");
+    }
+
+    let Some(program) = from_base32(&values[1..values.len() - 6]) else {
+        return invalid("bad padding");
+    };
+    match (version, program.len()) {
+        (0, 20 | 32) => Ok(()),
+        (0, _) => invalid("version 0 programs are 20 or 32 bytes"),
+        (1..=16, 2..=40) => Ok(()),
+        _ => invalid("bad witness program"),
+    }
+}
+
+fn hrp_expand(hrp: &str) -> Vec<u8> {
+    let mut out: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+    out.push(0);
+    out.extend(hrp.bytes().map(|b| b & 31));
+    out
+}
+
+fn polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    let mut chk: u32 = 1;
+    for &value in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x01ff_ffff) << 5) ^ u32::from(value);
+        for (i, g) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+/// Regroups 5-bit values into bytes, rejecting non-zero padding.
+fn from_base32(values: &[u8]) -> Option<Vec<u8>> {
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    let mut out = Vec::new();
+    for &value in values {
+        acc = (acc << 5) | u32::from(value);
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    (bits < 5 && acc == 0).then_some(out)
+}
+
+/// Decodes base58check, returning the payload without its checksum.
+fn base58check(s: &str) -> Option<Vec<u8>> {
+    // Big-endian digits of the decoded number
+    let mut number: Vec<u8> = Vec::new();
+    for c in s.bytes() {
+        let mut carry = BASE58_ALPHABET.iter().position(|&b| b == c)? as u32;
+        for byte in number.iter_mut().rev() {
+            carry += u32::from(*byte) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            number.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+    let zeros = s.bytes().take_while(|&c| c == b'1').count();
+    let mut bytes = vec![0; zeros];
+    bytes.extend(number);
+
+    if bytes.len() < 4 {
+        return None;
+    }
+    let (payload, checksum) = bytes.split_at(bytes.len() - 4);
+    let hash = Sha256::digest(Sha256::digest(payload));
+    (hash[..4] == *checksum).then(|| payload.to_vec())
+}
+
+/// Whether `pubkey` is a hex-encoded compressed or uncompressed public key.
+pub fn is_pubkey(pubkey: &str) -> bool {
+    let hex = pubkey.bytes().all(|b| b.is_ascii_hexdigit());
+    match pubkey.len() {
+        66 => hex && (pubkey.starts_with("02") || pubkey.starts_with("03")),
+        130 => hex && pubkey.starts_with("04"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_valid_addresses_for_their_network() {
+        for (address, network) in [
+            ("BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4", Network::Main),
+            (
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+                Network::Main,
+            ),
+            (
+                "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+                Network::Signet,
+            ),
+            ("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2", Network::Main),
+            ("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy", Network::Main),
+            ("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn", Network::Regtest),
+        ] {
+            assert_eq!(validate(address, network), Ok(()), "{}", address);
+        }
+    }
+
+    #[test]
+    fn rejects_addresses_for_other_networks() {
+        let err = validate("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2", Network::Signet).unwrap_err();
+        assert_eq!(err, "main address, not signet");
+        let err = validate(
+            "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+            Network::Regtest,
+        )
+        .unwrap_err();
+        assert!(err.contains("test/testnet4/signet"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_corrupted_addresses() {
+        for address in [
+            // Last character changed
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5",
+            // Version 1 with a bech32 rather than bech32m checksum
+            "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7k7grplx",
+            "Bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN3",
+            "not an address",
+        ] {
+            assert!(networks(address).is_err(), "{}", address);
+        }
+    }
+
+    #[test]
+    fn recognises_public_keys() {
+        assert!(is_pubkey(&format!("02{}", "ab".repeat(32))));
+        assert!(!is_pubkey(&format!("05{}", "ab".repeat(32))));
+        assert!(!is_pubkey("02abcd"));
+    }
+}
@@ -0,0 +1,491 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Checks that a p2poolv2 config agrees with the bitcoin.conf of the node
+//! it talks to: the same network, an RPC address and credentials bitcoind
+//! accepts, a ZMQ endpoint bitcoind publishes on, and a payout address for
+//! the right network.
+//!
+//! The counterpart of [`readiness`](crate::bitcoin_conf::readiness), which
+//! only looks at bitcoin.conf. Fixes here edit the p2poolv2 config.
+
+use super::fields::{self, FieldSpec};
+use super::{P2poolConf, P2poolSettings, address, network_name};
+use crate::bitcoin_conf::readiness::Status;
+use crate::bitcoin_conf::{BitcoinConf, rpcauth};
+use crate::rpc::split_host_port;
+use crate::zmq::connect_address;
+
+/// The user name bitcoind writes to its cookie file.
+const COOKIE_USER: &str = "__cookie__";
+
+/// A one-keystroke fix: set each field to its value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    pub label: String,
+    pub edits: Vec<(&'static FieldSpec, String)>,
+}
+
+impl Fix {
+    fn set(table: &str, key: &str, value: impl Into<String>) -> Self {
+        let spec = field(table, key);
+        let value = value.into();
+        Self {
+            label: format!("set {} = {}", spec.path(), value),
+            edits: vec![(spec, value)],
+        }
+    }
+
+    fn credentials(user: &str, password: &str) -> Self {
+        Self {
+            label: format!("use rpcuser {} and its rpcpassword", user),
+            edits: vec![
+                (field("bitcoinrpc", "username"), user.to_string()),
+                (field("bitcoinrpc", "password"), password.to_string()),
+            ],
+        }
+    }
+
+    /// Applies the edits to `conf`, stopping at the first one rejected.
+    pub fn apply(&self, conf: &mut P2poolConf) -> Result<(), String> {
+        for (spec, value) in &self.edits {
+            conf.set(spec, value)?;
+        }
+        Ok(())
+    }
+}
+
+fn field(table: &str, key: &str) -> &'static FieldSpec {
+    fields::find(table, key).expect("known p2poolv2 field")
+}
+
+/// The result of one cross-check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+    pub fix: Option<Fix>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: Status::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn failed(
+        name: &'static str,
+        status: Status,
+        detail: impl Into<String>,
+        fix: Option<Fix>,
+    ) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+            fix,
+        }
+    }
+}
+
+/// Runs every cross-check of `p2pool` against `bitcoin`.
+///
+/// `bitcoin` should have its includes merged. `cookie` is the password in
+/// bitcoind's cookie file, when it could be read.
+pub fn check(bitcoin: &BitcoinConf, p2pool: &P2poolConf, cookie: Option<&str>) -> Vec<Check> {
+    let settings = p2pool.settings();
+    vec![
+        check_network(bitcoin, &settings),
+        check_rpc_port(bitcoin, &settings),
+        check_credentials(bitcoin, &settings, cookie),
+        check_zmq(bitcoin, &settings),
+        check_payout(bitcoin, &settings),
+    ]
+}
+
+fn check_network(bitcoin: &BitcoinConf, settings: &P2poolSettings) -> Check {
+    let chain = bitcoin.chain();
+    let fix = Some(Fix::set("stratum", "network", network_name(chain)));
+    match settings.network {
+        Some(network) if network == chain => Check::ok("Network", format!("both run on {}", chain)),
+        Some(network) => Check::failed(
+            "Network",
+            Status::Wrong,
+            format!("p2pool {}, bitcoind {}", network, chain),
+            fix,
+        ),
+        None => Check::failed(
+            "Network",
+            Status::Missing,
+            format!("not set; bitcoind runs {}", chain),
+            fix,
+        ),
+    }
+}
+
+/// The host and port of an `http://host:port/` URL. A URL without a port
+/// uses the scheme's default.
+fn url_host_port(url: &str) -> (String, u16) {
+    let (rest, default) = match url.split_once("://") {
+        Some(("https", rest)) => (rest, 443),
+        Some((_, rest)) => (rest, 80),
+        None => (url, 80),
+    };
+    let authority = rest.split('/').next().unwrap_or_default();
+    let (host, port) = split_host_port(authority);
+    (host, port.unwrap_or(default))
+}
+
+fn check_rpc_port(bitcoin: &BitcoinConf, settings: &P2poolSettings) -> Check {
+    let rpcport = bitcoin
+        .effective("rpcport")
+        .and_then(|p| p.parse::<u16>().ok())
+        .unwrap_or(bitcoin.chain().default_rpc_port());
+    // An rpcbind=host:port listens on its own port, one without a port on
+    // rpcport
+    let mut ports: Vec<u16> = bitcoin
+        .effective_all("rpcbind")
+        .iter()
+        .map(|bind| split_host_port(bind).1.unwrap_or(rpcport))
+        .collect();
+    if ports.is_empty() {
+        ports.push(rpcport);
+    }
+    let port = ports[0];
+    let Some(url) = &settings.rpc_url else {
+        return Check::failed(
+            "RPC port",
+            Status::Missing,
+            "bitcoinrpc.url is not set",
+            Some(Fix::set(
+                "bitcoinrpc",
+                "url",
+                format!("http://127.0.0.1:{}", port),
+            )),
+        );
+    };
+    let (host, url_port) = url_host_port(url);
+    if ports.contains(&url_port) {
+        return Check::ok("RPC port", format!("bitcoind listens on {}", url_port));
+    }
+    let host = match host.as_str() {
+        "" => "127.0.0.1".to_string(),
+        h if h.contains(':') => format!("[{}]", h),
+        h => h.to_string(),
+    };
+    Check::failed(
+        "RPC port",
+        Status::Wrong,
+        format!("port {}, but bitcoind listens on {}", url_port, port),
+        Some(Fix::set(
+            "bitcoinrpc",
+            "url",
+            format!("http://{}:{}", host, port),
+        )),
+    )
+}
+
+fn check_credentials(
+    bitcoin: &BitcoinConf,
+    settings: &P2poolSettings,
+    cookie: Option<&str>,
+) -> Check {
+    const NAME: &str = "RPC credentials";
+    let rpcuser = bitcoin.effective("rpcuser");
+    let rpcpassword = bitcoin.effective("rpcpassword");
+    let plain = rpcuser.zip(rpcpassword);
+    let plain_fix = plain.map(|(user, password)| Fix::credentials(user, password));
+
+    let (Some(user), Some(password)) = (&settings.rpc_username, &settings.rpc_password) else {
+        return Check::failed(
+            NAME,
+            Status::Missing,
+            "bitcoinrpc.username and bitcoinrpc.password must both be set",
+            plain_fix,
+        );
+    };
+
+    if user == COOKIE_USER {
+        return match cookie {
+            Some(cookie) if cookie == password => {
+                Check::ok(NAME, "cookie file; it changes every time bitcoind starts")
+            }
+            Some(cookie) => Check::failed(
+                NAME,
+                Status::Wrong,
+                "password differs from bitcoind's current cookie",
+                Some(Fix::set("bitcoinrpc", "password", cookie)),
+            ),
+            None => Check::failed(
+                NAME,
+                Status::Wrong,
+                "cookie file cannot be read; is bitcoind running?",
+                plain_fix,
+            ),
+        };
+    }
+
+    let rpcauth = bitcoin.effective_all("rpcauth");
+    if rpcauth.iter().any(|a| rpcauth::verify(a, user, password)) {
+        return Check::ok(NAME, format!("matches rpcauth user {}", user));
+    }
+    if plain == Some((user.as_str(), password.as_str())) {
+        return Check::ok(NAME, format!("matches rpcuser {}", user));
+    }
+    let detail = if rpcauth.iter().any(|a| rpcauth::user_of(a) == Some(user)) {
+        format!("wrong password for rpcauth {}", user)
+    } else if rpcuser == Some(user.as_str()) {
+        format!("wrong password for rpcuser {}", user)
+    } else {
+        format!("bitcoind has no user {}", user)
+    };
+    Check::failed(NAME, Status::Wrong, detail, plain_fix)
+}
+
+fn check_zmq(bitcoin: &BitcoinConf, settings: &P2poolSettings) -> Check {
+    const NAME: &str = "ZMQ blocks";
+    let published: Vec<String> = bitcoin
+        .effective_all("zmqpubhashblock")
+        .into_iter()
+        .filter_map(connect_address)
+        .collect();
+    let fix = published
+        .first()
+        .map(|address| Fix::set("stratum", "zmqpubhashblock", format!("tcp://{}", address)));
+    match &settings.zmqpubhashblock {
+        _ if published.is_empty() => Check::failed(
+            NAME,
+            Status::Missing,
+            "bitcoind publishes no tcp:// zmqpubhashblock; fix it under Bitcoin Config [p]",
+            None,
+        ),
+        Some(endpoint) if connect_address(endpoint).is_some_and(|a| published.contains(&a)) => {
+            Check::ok(NAME, endpoint.clone())
+        }
+        Some(endpoint) => Check::failed(
+            NAME,
+            Status::Wrong,
+            format!(
+                "{} is not among bitcoind's zmqpubhashblock endpoints",
+                endpoint
+            ),
+            fix,
+        ),
+        None => Check::failed(
+            NAME,
+            Status::Missing,
+            "stratum.zmqpubhashblock is not set",
+            fix,
+        ),
+    }
+}
+
+fn check_payout(bitcoin: &BitcoinConf, settings: &P2poolSettings) -> Check {
+    const NAME: &str = "Payout";
+    let network = settings.network.unwrap_or(bitcoin.chain());
+    if let Some(address) = &settings.miner_address {
+        return match address::validate(address, network) {
+            Ok(()) => Check::ok(NAME, format!("{} address", network)),
+            Err(err) => Check::failed(NAME, Status::Wrong, err, None),
+        };
+    }
+    match &settings.miner_pubkey {
+        Some(pubkey) if address::is_pubkey(pubkey) => Check::ok(NAME, "miner.pubkey"),
+        Some(_) => Check::failed(
+            NAME,
+            Status::Wrong,
+            "miner.pubkey is not a hex encoded public key",
+            None,
+        ),
+        None => Check::failed(
+            NAME,
+            Status::Missing,
+            "set miner.address to be paid block rewards",
+            None,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_conf::Network;
+    use crate::bitcoin_conf::rpcauth::Credentials;
+
+    const BITCOIN: &str = "\
+chain=signet
+rpcuser=pool
+rpcpassword=secret
+zmqpubhashblock=tcp://0.0.0.0:28332
+";
+
+    const P2POOL: &str = r#"[stratum]
+network = "signet"
+zmqpubhashblock = "tcp://127.0.0.1:28332"
+
+[bitcoinrpc]
+url = "http://127.0.0.1:38332"
+username = "pool"
+password = "secret"
+
+[miner]
+address = "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7"
+"#;
+
+    fn find<'a>(checks: &'a [Check], name: &str) -> &'a Check {
+        checks.iter().find(|c| c.name == name).unwrap()
+    }
+
+    fn failed(checks: &[Check]) -> Vec<&str> {
+        checks
+            .iter()
+            .filter(|c| c.status != Status::Ok)
+            .map(|c| c.name)
+            .collect()
+    }
+
+    #[test]
+    fn matching_configs_pass() {
+        let checks = check(
+            &BitcoinConf::parse(BITCOIN),
+            &P2poolConf::parse(P2POOL).unwrap(),
+            None,
+        );
+        assert_eq!(failed(&checks), Vec::<&str>::new(), "{:?}", checks);
+    }
+
+    #[test]
+    fn empty_p2pool_config_is_fixable() {
+        let bitcoin = BitcoinConf::parse(BITCOIN);
+        let mut p2pool = P2poolConf::default();
+        let checks = check(&bitcoin, &p2pool, None);
+        assert_eq!(
+            failed(&checks),
+            vec![
+                "Network",
+                "RPC port",
+                "RPC credentials",
+                "ZMQ blocks",
+                "Payout"
+            ]
+        );
+
+        for c in &checks {
+            if let Some(fix) = &c.fix {
+                fix.apply(&mut p2pool).unwrap();
+            }
+        }
+        let settings = p2pool.settings();
+        assert_eq!(settings.network, Some(Network::Signet));
+        assert_eq!(settings.rpc_url.as_deref(), Some("http://127.0.0.1:38332"));
+        assert_eq!(
+            settings.zmqpubhashblock.as_deref(),
+            Some("tcp://127.0.0.1:28332")
+        );
+        // Only the payout address needs the user.
+        assert_eq!(failed(&check(&bitcoin, &p2pool, None)), vec!["Payout"]);
+    }
+
+    #[test]
+    fn wrong_port_keeps_the_host() {
+        let mut p2pool = P2poolConf::parse(P2POOL).unwrap();
+        p2pool
+            .set(field("bitcoinrpc", "url"), "http://10.0.0.2:8332/")
+            .unwrap();
+        let checks = check(&BitcoinConf::parse(BITCOIN), &p2pool, None);
+        let port = find(&checks, "RPC port");
+        assert_eq!(port.status, Status::Wrong);
+        assert_eq!(
+            port.fix.as_ref().unwrap().label,
+            "set bitcoinrpc.url = http://10.0.0.2:38332"
+        );
+    }
+
+    #[test]
+    fn rpcbind_ports_take_precedence() {
+        let p2pool = P2poolConf::parse(P2POOL).unwrap();
+        let bitcoin =
+            BitcoinConf::parse(&format!("{}[signet]\nrpcbind=127.0.0.1:18000\n", BITCOIN));
+        let port = find(&check(&bitcoin, &p2pool, None), "RPC port").clone();
+        assert_eq!(port.status, Status::Wrong);
+        assert_eq!(port.detail, "port 38332, but bitcoind listens on 18000");
+        assert_eq!(
+            port.fix.unwrap().label,
+            "set bitcoinrpc.url = http://127.0.0.1:18000"
+        );
+
+        // Binds without a port use rpcport, or the network's default
+        let bitcoin = BitcoinConf::parse(&format!(
+            "{}[signet]\nrpcbind=127.0.0.1:18000\nrpcbind=[::1]\n",
+            BITCOIN
+        ));
+        let checks = check(&bitcoin, &p2pool, None);
+        assert_eq!(find(&checks, "RPC port").status, Status::Ok);
+    }
+
+    #[test]
+    fn credentials_are_checked_against_rpcauth_and_cookie() {
+        let credentials = Credentials {
+            user: "pool".into(),
+            password: "secret".into(),
+            salt: "00112233445566778899aabbccddeeff".into(),
+        };
+        let bitcoin = BitcoinConf::parse(&format!(
+            "chain=signet\nrpcauth={}\nzmqpubhashblock=tcp://127.0.0.1:28332\n",
+            credentials.rpcauth()
+        ));
+        let mut p2pool = P2poolConf::parse(P2POOL).unwrap();
+        let checks = check(&bitcoin, &p2pool, None);
+        assert_eq!(find(&checks, "RPC credentials").status, Status::Ok);
+
+        let password = field("bitcoinrpc", "password");
+        p2pool.set(password, "guess").unwrap();
+        let checks = check(&bitcoin, &p2pool, None);
+        let credentials = find(&checks, "RPC credentials");
+        assert_eq!(credentials.status, Status::Wrong);
+        assert_eq!(credentials.detail, "wrong password for rpcauth pool");
+        assert_eq!(credentials.fix, None);
+
+        p2pool
+            .set(field("bitcoinrpc", "username"), COOKIE_USER)
+            .unwrap();
+        let checks = check(&bitcoin, &p2pool, Some("abc123"));
+        let fix = find(&checks, "RPC credentials").fix.clone().unwrap();
+        fix.apply(&mut p2pool).unwrap();
+        assert_eq!(p2pool.get(password).as_deref(), Some("abc123"));
+        let checks = check(&bitcoin, &p2pool, Some("abc123"));
+        assert_eq!(find(&checks, "RPC credentials").status, Status::Ok);
+    }
+
+    #[test]
+    fn zmq_and_payout_mismatches_are_reported() {
+        let mut p2pool = P2poolConf::parse(P2POOL).unwrap();
+        p2pool
+            .set(field("stratum", "zmqpubhashblock"), "tcp://127.0.0.1:28333")
+            .unwrap();
+        p2pool
+            .set(
+                field("miner", "address"),
+                "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2",
+            )
+            .unwrap();
+        let checks = check(&BitcoinConf::parse(BITCOIN), &p2pool, None);
+        assert_eq!(failed(&checks), vec!["ZMQ blocks", "Payout"]);
+        assert_eq!(
+            find(&checks, "ZMQ blocks").fix.as_ref().unwrap().label,
+            "set stratum.zmqpubhashblock = tcp://127.0.0.1:28332"
+        );
+        assert!(find(&checks, "Payout").detail.contains("not signet"));
+
+        let checks = check(&BitcoinConf::parse("chain=signet\n"), &p2pool, None);
+        let zmq = find(&checks, "ZMQ blocks");
+        assert_eq!(zmq.status, Status::Missing);
+        assert_eq!(zmq.fix, None);
+    }
+}
@@ -0,0 +1,140 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Catalogue of the p2poolv2 settings pdm knows how to edit.
+
+use crate::bitcoin_conf::options::ValueKind;
+
+/// Values of `stratum.network`, named as p2poolv2 expects them.
+pub const NETWORKS: [&str; 5] = ["bitcoin", "testnet", "testnet4", "signet", "regtest"];
+
+/// Description of a single p2poolv2 setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldSpec {
+    /// The `[table]` the key lives in.
+    pub table: &'static str,
+    pub key: &'static str,
+    pub kind: ValueKind,
+    pub description: &'static str,
+}
+
+impl FieldSpec {
+    /// `table.key`, as the setting is referred to in messages.
+    pub fn path(&self) -> String {
+        format!("{}.{}", self.table, self.key)
+    }
+}
+
+const fn field(
+    table: &'static str,
+    key: &'static str,
+    kind: ValueKind,
+    description: &'static str,
+) -> FieldSpec {
+    FieldSpec {
+        table,
+        key,
+        kind,
+        description,
+    }
+}
+
+const PORT: ValueKind = ValueKind::Integer { min: 1, max: 65535 };
+const DIFFICULTY: ValueKind = ValueKind::Integer {
+    min: 1,
+    max: i64::MAX,
+};
+
+/// Every setting shown on the p2pool Config screen, in display order.
+pub const FIELDS: &[FieldSpec] = &[
+    field(
+        "stratum",
+        "network",
+        ValueKind::Enum(&NETWORKS),
+        "Bitcoin network the pool mines on; must match bitcoind",
+    ),
+    field(
+        "stratum",
+        "hostname",
+        ValueKind::Text,
+        "Address the stratum server listens on for miners",
+    ),
+    field(
+        "stratum",
+        "port",
+        PORT,
+        "Port the stratum server listens on",
+    ),
+    field(
+        "stratum",
+        "start_difficulty",
+        DIFFICULTY,
+        "Share difficulty a new miner starts at",
+    ),
+    field(
+        "stratum",
+        "minimum_difficulty",
+        DIFFICULTY,
+        "Lowest share difficulty vardiff may assign",
+    ),
+    field(
+        "stratum",
+        "maximum_difficulty",
+        DIFFICULTY,
+        "Highest share difficulty vardiff may assign",
+    ),
+    field(
+        "stratum",
+        "zmqpubhashblock",
+        ValueKind::Text,
+        "bitcoind ZMQ endpoint announcing new blocks",
+    ),
+    field(
+        "bitcoinrpc",
+        "url",
+        ValueKind::Text,
+        "bitcoind JSON-RPC address, e.g. http://127.0.0.1:8332",
+    ),
+    field(
+        "bitcoinrpc",
+        "username",
+        ValueKind::Text,
+        "RPC user, matching an rpcauth= or rpcuser= line",
+    ),
+    field(
+        "bitcoinrpc",
+        "password",
+        ValueKind::Text,
+        "RPC password for that user",
+    ),
+    field(
+        "store",
+        "path",
+        ValueKind::Path,
+        "Database holding the share chain",
+    ),
+    field(
+        "miner",
+        "pubkey",
+        ValueKind::Text,
+        "Public key block rewards are paid to",
+    ),
+    field(
+        "miner",
+        "address",
+        ValueKind::Text,
+        "Bitcoin address block rewards are paid to",
+    ),
+    field(
+        "network",
+        "listen_address",
+        ValueKind::Text,
+        "Multiaddr the pool listens on for other p2pool nodes",
+    ),
+];
+
+/// The spec of `table.key`, if pdm knows it.
+pub fn find(table: &str, key: &str) -> Option<&'static FieldSpec> {
+    FIELDS.iter().find(|f| f.table == table && f.key == key)
+}
@@ -0,0 +1,285 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! The p2poolv2 config, a TOML file.
+//!
+//! [`P2poolConf`] keeps the parsed [`toml_edit`] document, so comments,
+//! ordering and formatting survive editing: a config that is loaded and
+//! saved without modification is written back byte-for-byte, and only the
+//! values changed through [`P2poolConf::set`] are re-rendered.
+//! [`P2poolConf::settings`] reads the values pdm works with into a typed
+//! [`P2poolSettings`].
+
+pub mod address;
+pub mod crosscheck;
+pub mod fields;
+
+use crate::atomic_file;
+use crate::bitcoin_conf::Network;
+use crate::bitcoin_conf::options::ValueKind;
+use fields::FieldSpec;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, Value};
+
+/// The name p2poolv2 uses for `network`.
+pub fn network_name(network: Network) -> &'static str {
+    match network {
+        Network::Main => "bitcoin",
+        Network::Test => "testnet",
+        other => other.name(),
+    }
+}
+
+/// Parses a p2poolv2 network name.
+pub fn parse_network(name: &str) -> Option<Network> {
+    match name {
+        "bitcoin" => Some(Network::Main),
+        "testnet" => Some(Network::Test),
+        "testnet4" | "signet" | "regtest" => Network::from_name(name),
+        _ => None,
+    }
+}
+
+/// The settings of a p2poolv2 config that pdm works with. Missing or
+/// mistyped values are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct P2poolSettings {
+    pub network: Option<Network>,
+    pub stratum_hostname: Option<String>,
+    pub stratum_port: Option<u16>,
+    pub start_difficulty: Option<u64>,
+    pub minimum_difficulty: Option<u64>,
+    pub maximum_difficulty: Option<u64>,
+    /// bitcoind's ZMQ block announcements, e.g. `tcp://127.0.0.1:28332`.
+    pub zmqpubhashblock: Option<String>,
+    pub rpc_url: Option<String>,
+    pub rpc_username: Option<String>,
+    pub rpc_password: Option<String>,
+    pub store_path: Option<PathBuf>,
+    pub miner_pubkey: Option<String>,
+    pub miner_address: Option<String>,
+    /// libp2p multiaddr of the share chain listener.
+    pub listen_address: Option<String>,
+}
+
+/// A p2poolv2 config file.
+#[derive(Debug, Clone, Default)]
+pub struct P2poolConf {
+    doc: DocumentMut,
+}
+
+impl P2poolConf {
+    pub fn parse(text: &str) -> Result<Self, toml_edit::TomlError> {
+        Ok(Self { doc: text.parse()? })
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string().trim()))
+    }
+
+    /// Writes the config to `path`, atomically and keeping the file's
+    /// owner and permissions (see [`atomic_file::write`]).
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        atomic_file::write(path, self.to_string())
+    }
+
+    fn value(&self, table: &str, key: &str) -> Option<&Value> {
+        self.doc.get(table)?.get(key)?.as_value()
+    }
+
+    fn string(&self, table: &str, key: &str) -> Option<String> {
+        self.value(table, key)?.as_str().map(str::to_string)
+    }
+
+    fn integer<T: TryFrom<i64>>(&self, table: &str, key: &str) -> Option<T> {
+        T::try_from(self.value(table, key)?.as_integer()?).ok()
+    }
+
+    /// The value of `spec` as the editor shows it, or `None` if it is not
+    /// set. Strings are shown without quotes.
+    pub fn get(&self, spec: &FieldSpec) -> Option<String> {
+        let value = self.value(spec.table, spec.key)?;
+        Some(match value.as_str() {
+            Some(s) => s.to_string(),
+            None => value.clone().decorated("", "").to_string(),
+        })
+    }
+
+    /// Sets `spec` to `value`, typed as the setting expects. A comment
+    /// after the old value is kept, and a missing table is added at the
+    /// end of the file.
+    pub fn set(&mut self, spec: &FieldSpec, value: &str) -> Result<(), String> {
+        let new = match spec.kind {
+            ValueKind::Integer { min, max } => match value.parse::<i64>() {
+                Ok(n) if (min..=max).contains(&n) => Value::from(n),
+                _ if max == i64::MAX => {
+                    return Err(format!("{} must be a number >= {}", spec.path(), min));
+                }
+                _ => {
+                    return Err(format!(
+                        "{} must be a number between {} and {}",
+                        spec.path(),
+                        min,
+                        max
+                    ));
+                }
+            },
+            ValueKind::Bool => match value {
+                "true" | "1" => Value::from(true),
+                "false" | "0" => Value::from(false),
+                _ => return Err(format!("{} must be true or false", spec.path())),
+            },
+            ValueKind::Enum(choices) if !choices.contains(&value) => {
+                return Err(format!(
+                    "{} must be one of {}",
+                    spec.path(),
+                    choices.join(", ")
+                ));
+            }
+            _ => Value::from(value),
+        };
+        let table = self
+            .doc
+            .entry(spec.table)
+            .or_insert_with(toml_edit::table)
+            .as_table_like_mut()
+            .ok_or_else(|| format!("{} is not a table", spec.table))?;
+        match table.get_mut(spec.key).and_then(Item::as_value_mut) {
+            Some(old) => {
+                let decor = old.decor().clone();
+                *old = new;
+                *old.decor_mut() = decor;
+            }
+            None => {
+                table.insert(spec.key, Item::Value(new));
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes `spec` from the config. Returns whether it was set.
+    pub fn unset(&mut self, spec: &FieldSpec) -> bool {
+        self.doc
+            .get_mut(spec.table)
+            .and_then(Item::as_table_like_mut)
+            .and_then(|table| table.remove(spec.key))
+            .is_some()
+    }
+
+    pub fn settings(&self) -> P2poolSettings {
+        P2poolSettings {
+            network: self
+                .string("stratum", "network")
+                .and_then(|n| parse_network(&n)),
+            stratum_hostname: self.string("stratum", "hostname"),
+            stratum_port: self.integer("stratum", "port"),
+            start_difficulty: self.integer("stratum", "start_difficulty"),
+            minimum_difficulty: self.integer("stratum", "minimum_difficulty"),
+            maximum_difficulty: self.integer("stratum", "maximum_difficulty"),
+            zmqpubhashblock: self.string("stratum", "zmqpubhashblock"),
+            rpc_url: self.string("bitcoinrpc", "url"),
+            rpc_username: self.string("bitcoinrpc", "username"),
+            rpc_password: self.string("bitcoinrpc", "password"),
+            store_path: self.string("store", "path").map(PathBuf::from),
+            miner_pubkey: self.string("miner", "pubkey"),
+            miner_address: self.string("miner", "address"),
+            listen_address: self.string("network", "listen_address"),
+        }
+    }
+}
+
+impl fmt::Display for P2poolConf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.doc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"# p2poolv2 on signet
+
+[store]
+path = "/home/pool/.p2poolv2/store.db"
+
+[stratum]
+hostname = "0.0.0.0"
+port = 3333 # open in the firewall
+start_difficulty = 10000
+network = "signet"
+
+[bitcoinrpc]
+url = "http://127.0.0.1:38332"
+username = "p2pool"
+password = "secret"
+"#;
+
+    fn spec(table: &str, key: &str) -> &'static FieldSpec {
+        fields::find(table, key).unwrap()
+    }
+
+    #[test]
+    fn round_trips_unchanged() {
+        let conf = P2poolConf::parse(SAMPLE).unwrap();
+        assert_eq!(conf.to_string(), SAMPLE);
+    }
+
+    #[test]
+    fn reads_typed_settings() {
+        let settings = P2poolConf::parse(SAMPLE).unwrap().settings();
+        assert_eq!(settings.network, Some(Network::Signet));
+        assert_eq!(settings.stratum_port, Some(3333));
+        assert_eq!(settings.start_difficulty, Some(10000));
+        assert_eq!(settings.minimum_difficulty, None);
+        assert_eq!(settings.rpc_username.as_deref(), Some("p2pool"));
+        assert_eq!(
+            settings.store_path,
+            Some(PathBuf::from("/home/pool/.p2poolv2/store.db"))
+        );
+    }
+
+    #[test]
+    fn set_keeps_comments_and_types_values() {
+        let mut conf = P2poolConf::parse(SAMPLE).unwrap();
+        conf.set(spec("stratum", "port"), "3334").unwrap();
+        conf.set(spec("stratum", "network"), "bitcoin").unwrap();
+        conf.set(spec("miner", "address"), "bc1qexample").unwrap();
+
+        let text = conf.to_string();
+        assert!(text.starts_with("# p2poolv2 on signet\n"));
+        assert!(text.contains("port = 3334 # open in the firewall\n"));
+        assert!(text.contains("network = \"bitcoin\"\n"));
+        assert!(text.ends_with("[miner]\naddress = \"bc1qexample\"\n"));
+        assert_eq!(conf.settings().network, Some(Network::Main));
+    }
+
+    #[test]
+    fn set_rejects_invalid_values() {
+        let mut conf = P2poolConf::parse(SAMPLE).unwrap();
+        let err = conf.set(spec("stratum", "port"), "70000").unwrap_err();
+        assert!(err.contains("between 1 and 65535"));
+        let err = conf.set(spec("stratum", "network"), "main").unwrap_err();
+        assert!(err.contains("bitcoin, testnet"));
+        assert_eq!(conf.to_string(), SAMPLE);
+    }
+
+    #[test]
+    fn unset_removes_the_key() {
+        let mut conf = P2poolConf::parse(SAMPLE).unwrap();
+        assert!(conf.unset(spec("bitcoinrpc", "password")));
+        assert!(!conf.unset(spec("miner", "pubkey")));
+        assert_eq!(conf.get(spec("bitcoinrpc", "password")), None);
+        assert_eq!(conf.get(spec("stratum", "port")).as_deref(), Some("3333"));
+    }
+
+    #[test]
+    fn invalid_toml_is_an_error() {
+        assert!(P2poolConf::parse("[stratum\nport = 1\n").is_err());
+    }
+}
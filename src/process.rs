@@ -0,0 +1,661 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Child processes pdm starts and watches: bitcoind and p2poolv2.
+//!
+//! A [`Process`] hands every line its child writes, and finally how the
+//! child ended, to the event loop as [`AppEvent::Process`]. A
+//! [`Supervisor`] keeps one such process per program together with what
+//! its screen shows: whether it runs, its recent output and how the last
+//! run ended.
+//!
+//! With `auto_restart` set, a supervisor schedules a restart after each
+//! crash, waiting twice as long after every further crash in a row, and
+//! gives up once [`CRASH_LOOP_LIMIT`] crashes in a row show the program
+//! cannot stay up.
+//!
+//! Children are sent SIGTERM when pdm exits. A p2poolv2 still running
+//! after a grace period is killed; a bitcoind is left to finish flushing.
+
+use crate::event::AppEvent;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Output lines a [`Supervisor`] keeps.
+pub const OUTPUT_LINES: usize = 1000;
+/// Rows moved by PageUp and PageDown.
+pub const PAGE: usize = 10;
+
+/// Crashes in a row after which a [`Supervisor`] stops restarting on its
+/// own.
+pub const CRASH_LOOP_LIMIT: u32 = 5;
+/// A run lasting this long before crashing starts a new streak.
+pub const STABLE_RUN: Duration = Duration::from_secs(60);
+/// Wait before the first automatic restart, doubled for each further
+/// crash in a row.
+pub const BACKOFF_INITIAL: Duration = Duration::from_secs(1);
+pub const BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProcessEvent {
+    /// A line the child wrote to stdout or stderr.
+    Output(String),
+    Exited(ExitReport),
+}
+
+/// How a child ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExitReport {
+    pub code: Option<i32>,
+    /// The signal that killed it, on Unix.
+    pub signal: Option<i32>,
+}
+
+impl ExitReport {
+    pub fn from_status(status: ExitStatus) -> Self {
+        #[cfg(unix)]
+        let signal = std::os::unix::process::ExitStatusExt::signal(&status);
+        #[cfg(not(unix))]
+        let signal = None;
+        Self {
+            code: status.code(),
+            signal,
+        }
+    }
+
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+impl fmt::Display for ExitReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.code, self.signal) {
+            (Some(code), _) => write!(f, "exited with code {}", code),
+            (None, Some(signal)) => write!(f, "killed by signal {}", signal),
+            (None, None) => write!(f, "exited"),
+        }
+    }
+}
+
+/// A running child. Its output and exit arrive as events tagged with `id`.
+#[derive(Debug)]
+pub struct Process {
+    pub id: u64,
+    pub pid: u32,
+}
+
+static NEXT_PROCESS_ID: AtomicU64 = AtomicU64::new(1);
+
+impl Process {
+    /// Starts `command` with its stdout and stderr captured.
+    pub fn spawn(mut command: Command, events: Sender<AppEvent>) -> io::Result<Process> {
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let id = NEXT_PROCESS_ID.fetch_add(1, Ordering::Relaxed);
+        let pid = child.id();
+
+        let readers: Vec<_> = [
+            child
+                .stdout
+                .take()
+                .map(|s| forward_lines(s, id, events.clone())),
+            child
+                .stderr
+                .take()
+                .map(|s| forward_lines(s, id, events.clone())),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        thread::spawn(move || {
+            let report = match child.wait() {
+                Ok(status) => ExitReport::from_status(status),
+                Err(_) => ExitReport {
+                    code: None,
+                    signal: None,
+                },
+            };
+            // The last lines often say why the child exited, so they go
+            // out before the exit
+            for reader in readers {
+                let _ = reader.join();
+            }
+            let _ = events.send(AppEvent::Process {
+                process: id,
+                event: ProcessEvent::Exited(report),
+            });
+        });
+        Ok(Process { id, pid })
+    }
+
+    /// Sends SIGTERM, asking the child to shut down.
+    #[cfg(unix)]
+    pub fn terminate(&self) -> io::Result<()> {
+        self.signal(libc::SIGTERM)
+    }
+
+    /// Sends SIGKILL, for a child that ignored SIGTERM.
+    #[cfg(unix)]
+    pub fn kill(&self) -> io::Result<()> {
+        self.signal(libc::SIGKILL)
+    }
+
+    #[cfg(unix)]
+    fn signal(&self, signal: libc::c_int) -> io::Result<()> {
+        let pid = libc::pid_t::try_from(self.pid).map_err(io::Error::other)?;
+        // SAFETY: kill() takes no pointers.
+        if unsafe { libc::kill(pid, signal) } == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn terminate(&self) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "signals are only supported on Unix",
+        ))
+    }
+
+    #[cfg(not(unix))]
+    pub fn kill(&self) -> io::Result<()> {
+        self.terminate()
+    }
+}
+
+/// `command` as it would be typed, for display.
+pub fn command_line(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| arg.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Removes terminal escape sequences, such as the colours `tracing` adds
+/// when it believes it writes to a terminal.
+pub fn strip_escapes(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            plain.push(c);
+            continue;
+        }
+        // A control sequence runs up to a letter; other escapes are one
+        // character long
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if ('\x40'..='\x7e').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    plain
+}
+
+fn forward_lines(
+    stream: impl Read + Send + 'static,
+    id: u64,
+    events: Sender<AppEvent>,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) | Err(_) => return,
+                Ok(_) => {}
+            }
+            let text = strip_escapes(String::from_utf8_lossy(&line).trim_end());
+            let event = ProcessEvent::Output(text);
+            if events
+                .send(AppEvent::Process { process: id, event })
+                .is_err()
+            {
+                return;
+            }
+        }
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunState {
+    /// Not started from pdm.
+    Idle,
+    Running,
+    /// Asked to shut down.
+    Stopping,
+    /// Ended after being asked to, or successfully.
+    Exited(ExitReport),
+    /// Ended on its own with a failure.
+    Crashed(ExitReport),
+}
+
+/// A supervisor action that waits for confirmation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessAction {
+    Stop,
+    Restart,
+}
+
+impl ProcessAction {
+    pub fn prompt(&self, name: &str) -> String {
+        match self {
+            ProcessAction::Stop => format!("Stop {}? [y/n]", name),
+            ProcessAction::Restart => format!("Restart {}? [y/n]", name),
+        }
+    }
+}
+
+/// Starts, stops and watches one program.
+#[derive(Debug)]
+pub struct Supervisor {
+    /// e.g. `bitcoind`, for messages.
+    pub name: &'static str,
+    pub process: Option<Process>,
+    /// Id of the latest process, whose late output is still wanted.
+    last_id: Option<u64>,
+    pub state: RunState,
+    /// The latest command, for display.
+    pub command_line: Option<String>,
+    /// Latest output, oldest first.
+    pub output: VecDeque<String>,
+    /// When to send SIGTERM if the process has not exited by then.
+    stop_deadline: Option<Instant>,
+    /// Start again once the process has exited.
+    pub restart_pending: bool,
+    /// Action waiting for confirmation.
+    pub pending: Option<ProcessAction>,
+    /// Outcome of the last action.
+    pub message: Option<String>,
+    /// Start again, after a backoff, when the process crashes.
+    pub auto_restart: bool,
+    /// When the automatic restart is due.
+    pub restart_at: Option<Instant>,
+    /// Crashes in a row, each within [`STABLE_RUN`] of starting.
+    pub crash_streak: u32,
+    started_at: Option<Instant>,
+    /// How many lines the output view is scrolled back from the newest.
+    pub scroll: usize,
+}
+
+impl Supervisor {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            process: None,
+            last_id: None,
+            state: RunState::Idle,
+            command_line: None,
+            output: VecDeque::new(),
+            stop_deadline: None,
+            restart_pending: false,
+            pending: None,
+            message: None,
+            auto_restart: false,
+            restart_at: None,
+            crash_streak: 0,
+            started_at: None,
+            scroll: 0,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.process.is_some()
+    }
+
+    pub fn start(&mut self, command: Command, events: Sender<AppEvent>) -> io::Result<()> {
+        if self.is_running() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} is already running", self.name),
+            ));
+        }
+        let command_line = command_line(&command);
+        let process = Process::spawn(command, events)?;
+        self.message = Some(format!("Started {} (pid {})", self.name, process.pid));
+        self.last_id = Some(process.id);
+        self.process = Some(process);
+        self.command_line = Some(command_line);
+        self.state = RunState::Running;
+        self.stop_deadline = None;
+        self.restart_at = None;
+        self.started_at = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Whether the process keeps crashing soon after starting.
+    pub fn is_crash_looping(&self) -> bool {
+        self.crash_streak >= CRASH_LOOP_LIMIT
+    }
+
+    /// Turns automatic restarts on or off, starting a fresh crash streak.
+    pub fn set_auto_restart(&mut self, on: bool) {
+        self.auto_restart = on;
+        self.crash_streak = 0;
+        self.restart_at = None;
+        self.message = Some(format!(
+            "Auto-restart {}",
+            if on { "enabled" } else { "disabled" }
+        ));
+    }
+
+    /// How long to wait before restarting after `crashes` crashes in a row.
+    pub fn backoff(crashes: u32) -> Duration {
+        let doublings = crashes.saturating_sub(1).min(16);
+        (BACKOFF_INITIAL * 2u32.pow(doublings)).min(BACKOFF_MAX)
+    }
+
+    /// Marks the process as shutting down after the caller asked it to in
+    /// its own way; it gets SIGTERM if it is still running after `grace`.
+    pub fn begin_stop(&mut self, grace: Duration) {
+        if self.is_running() {
+            self.state = RunState::Stopping;
+            self.stop_deadline = Some(Instant::now() + grace);
+        }
+    }
+
+    /// Sends SIGTERM now. Without a process, cancels a pending automatic
+    /// restart instead.
+    pub fn terminate(&mut self) -> io::Result<()> {
+        let Some(process) = &self.process else {
+            self.restart_at = None;
+            return Ok(());
+        };
+        self.stop_deadline = None;
+        self.state = RunState::Stopping;
+        process.terminate()
+    }
+
+    /// Sends SIGTERM once a graceful stop has taken too long. Returns true
+    /// when an automatic restart is due.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        if self.stop_deadline.is_some_and(|deadline| now >= deadline) {
+            self.message = Some(match self.terminate() {
+                Ok(()) => format!("{} did not stop in time, sent SIGTERM", self.name),
+                Err(err) => format!("Could not signal {}: {}", self.name, err),
+            });
+        }
+        if self.restart_at.is_some_and(|at| now >= at) {
+            self.restart_at = None;
+            return true;
+        }
+        false
+    }
+
+    /// Scrolls the output view `lines` further back, up to the oldest line.
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.scroll = self
+            .scroll
+            .saturating_add(lines)
+            .min(self.output.len().saturating_sub(1));
+    }
+
+    /// Scrolls the output view `lines` towards the newest line.
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+
+    /// Whether `process` is one of ours.
+    pub fn owns(&self, process: u64) -> bool {
+        self.last_id == Some(process)
+    }
+
+    /// Takes in an event from one of our processes. Returns true when the
+    /// process exited and a restart is due.
+    pub fn handle(&mut self, event: ProcessEvent) -> bool {
+        match event {
+            ProcessEvent::Output(line) => {
+                self.output.push_back(line);
+                if self.output.len() > OUTPUT_LINES {
+                    self.output.pop_front();
+                }
+                // Keep a scrolled back view on the same lines
+                if self.scroll > 0 {
+                    self.scroll_up(1);
+                }
+                false
+            }
+            ProcessEvent::Exited(report) => {
+                let asked = self.state == RunState::Stopping;
+                self.process = None;
+                self.stop_deadline = None;
+                self.state = if asked || report.success() {
+                    RunState::Exited(report)
+                } else {
+                    RunState::Crashed(report)
+                };
+                self.message = Some(match self.state {
+                    RunState::Crashed(_) => format!("{} crashed: {}", self.name, report),
+                    _ => format!("{} {}", self.name, report),
+                });
+                let restart = std::mem::take(&mut self.restart_pending);
+                if matches!(self.state, RunState::Crashed(_)) {
+                    self.crashed(restart);
+                } else {
+                    self.crash_streak = 0;
+                }
+                restart
+            }
+        }
+    }
+
+    /// Counts a crash and schedules the automatic restart, unless one is
+    /// already `restarting` or the process is crash looping.
+    fn crashed(&mut self, restarting: bool) {
+        let stable = self
+            .started_at
+            .is_some_and(|started| started.elapsed() >= STABLE_RUN);
+        self.crash_streak = if stable { 1 } else { self.crash_streak + 1 };
+        if self.is_crash_looping() {
+            self.message = Some(format!(
+                "{} is crash looping: {} crashes in a row{}",
+                self.name,
+                self.crash_streak,
+                if self.auto_restart {
+                    ", auto-restart paused"
+                } else {
+                    ""
+                }
+            ));
+        } else if self.auto_restart && !restarting {
+            let delay = Self::backoff(self.crash_streak);
+            self.restart_at = Some(Instant::now() + delay);
+            if let Some(message) = &mut self.message {
+                message.push_str(&format!(", restarting in {}s", delay.as_secs()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod fake {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// Writes an executable shell script named `name` into `dir`.
+    pub fn script(dir: &Path, name: &str, body: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, format!("#!/bin/sh\n{}", body)).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        path
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::env::temp_dir;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::mpsc::{self, Receiver};
+
+    fn next(receiver: &Receiver<AppEvent>) -> (u64, ProcessEvent) {
+        match receiver.recv_timeout(Duration::from_secs(10)).unwrap() {
+            AppEvent::Process { process, event } => (process, event),
+            event => panic!("unexpected {:?}", event),
+        }
+    }
+
+    /// Feeds events to `supervisor` until its process has exited.
+    fn run_to_exit(supervisor: &mut Supervisor, receiver: &Receiver<AppEvent>) -> bool {
+        loop {
+            let (id, event) = next(receiver);
+            assert!(supervisor.owns(id));
+            let exited = matches!(event, ProcessEvent::Exited(_));
+            let restart = supervisor.handle(event);
+            if exited {
+                return restart;
+            }
+        }
+    }
+
+    fn dir(name: &str) -> PathBuf {
+        let dir = temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn reports_output_and_crashes() {
+        let dir = dir("pdm_process_crash_test");
+        let bin = fake::script(
+            &dir,
+            "bitcoind",
+            "echo \"args: $*\"\necho oops >&2\nexit 3\n",
+        );
+        let (sender, receiver) = mpsc::channel();
+        let mut supervisor = Supervisor::new("bitcoind");
+        let mut command = Command::new(&bin);
+        command.arg("-conf=/tmp/bitcoin.conf");
+        supervisor.start(command, sender).unwrap();
+        assert_eq!(supervisor.state, RunState::Running);
+
+        assert!(!run_to_exit(&mut supervisor, &receiver));
+        let report = ExitReport {
+            code: Some(3),
+            signal: None,
+        };
+        assert_eq!(supervisor.state, RunState::Crashed(report));
+        assert_eq!(
+            supervisor.message.as_deref(),
+            Some("bitcoind crashed: exited with code 3")
+        );
+        // Both streams are captured, in whatever order they arrived
+        let mut output: Vec<&str> = supervisor.output.iter().map(String::as_str).collect();
+        output.sort();
+        assert_eq!(output, ["args: -conf=/tmp/bitcoin.conf", "oops"]);
+    }
+
+    #[test]
+    fn strips_colours_from_output() {
+        assert_eq!(
+            strip_escapes("\x1b[2m10:00:00\x1b[0m \x1b[32m INFO\x1b[0m ready \x1b="),
+            "10:00:00  INFO ready "
+        );
+        assert_eq!(strip_escapes("plain\x1b"), "plain");
+    }
+
+    #[test]
+    fn terminates_after_grace_period() {
+        let dir = dir("pdm_process_stop_test");
+        let bin = fake::script(
+            &dir,
+            "bitcoind",
+            "trap 'exit 0' TERM\necho ready\nwhile true; do sleep 0.05; done\n",
+        );
+        let (sender, receiver) = mpsc::channel();
+        let mut supervisor = Supervisor::new("bitcoind");
+        supervisor.start(Command::new(&bin), sender).unwrap();
+        // Signals sent before the trap is set would kill the shell outright
+        assert_eq!(next(&receiver).1, ProcessEvent::Output("ready".into()));
+
+        supervisor.begin_stop(Duration::ZERO);
+        supervisor.restart_pending = true;
+        supervisor.tick(Instant::now());
+        assert!(run_to_exit(&mut supervisor, &receiver));
+        assert!(matches!(supervisor.state, RunState::Exited(r) if r.success()));
+        assert!(!supervisor.restart_pending);
+    }
+
+    #[test]
+    fn auto_restart_backs_off_until_a_crash_loop() {
+        let dir = dir("pdm_process_crash_loop_test");
+        let bin = fake::script(&dir, "p2poolv2", "echo starting\nexit 1\n");
+        let (sender, receiver) = mpsc::channel();
+        let mut supervisor = Supervisor::new("p2poolv2");
+        supervisor.set_auto_restart(true);
+
+        let mut delays = Vec::new();
+        for crash in 1..=CRASH_LOOP_LIMIT {
+            supervisor
+                .start(Command::new(&bin), sender.clone())
+                .unwrap();
+            assert!(!run_to_exit(&mut supervisor, &receiver));
+            assert_eq!(supervisor.crash_streak, crash);
+            let Some(at) = supervisor.restart_at else {
+                break;
+            };
+            delays.push(Supervisor::backoff(crash));
+            assert!(!supervisor.tick(Instant::now()));
+            assert!(supervisor.tick(at));
+            assert_eq!(supervisor.restart_at, None);
+        }
+
+        let secs: Vec<u64> = delays.iter().map(Duration::as_secs).collect();
+        assert_eq!(secs, [1, 2, 4, 8]);
+        assert!(supervisor.is_crash_looping());
+        assert_eq!(
+            supervisor.message.as_deref(),
+            Some("p2poolv2 is crash looping: 5 crashes in a row, auto-restart paused")
+        );
+        assert_eq!(Supervisor::backoff(30), BACKOFF_MAX);
+    }
+
+    #[test]
+    fn stopping_cancels_a_scheduled_restart() {
+        let mut supervisor = Supervisor::new("p2poolv2");
+        supervisor.set_auto_restart(true);
+        supervisor.restart_at = Some(Instant::now());
+        supervisor.terminate().unwrap();
+        assert!(!supervisor.tick(Instant::now()));
+    }
+
+    #[test]
+    fn scrolled_back_output_stays_in_place() {
+        let mut supervisor = Supervisor::new("p2poolv2");
+        for i in 0..5 {
+            supervisor.handle(ProcessEvent::Output(format!("line {}", i)));
+        }
+        supervisor.scroll_up(2);
+        supervisor.handle(ProcessEvent::Output("line 5".into()));
+        assert_eq!(supervisor.scroll, 3);
+        supervisor.scroll_up(usize::MAX);
+        assert_eq!(supervisor.scroll, 5);
+        supervisor.scroll_down(10);
+        assert_eq!(supervisor.scroll, 0);
+        supervisor.handle(ProcessEvent::Output("line 6".into()));
+        assert_eq!(supervisor.scroll, 0);
+    }
+}
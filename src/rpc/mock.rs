@@ -0,0 +1,86 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! A stand-in bitcoind for tests.
+
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// Serves one connection: returns the raw request it received and answers
+/// with `status` and `body`.
+pub async fn serve_once(status: &'static str, body: String) -> (u16, JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let handle = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let request = read_request(&mut socket).await;
+        reply(&mut socket, status, &body).await;
+        request
+    });
+    (port, handle)
+}
+
+/// Serves connections until the runtime shuts down, answering each call,
+/// batched or not, with the result registered for its method.
+pub async fn node(results: HashMap<&'static str, Value>) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let request = read_request(&mut socket).await;
+            let body = request.split_once("\r\n\r\n").unwrap().1;
+            let answer = |call: &Value| {
+                let method = call["method"].as_str().unwrap_or_default();
+                match results.get(method) {
+                    Some(result) => json!({"result": result, "error": null, "id": call["id"]}),
+                    None => json!({
+                        "result": null,
+                        "error": {"code": -32601, "message": "Method not found"},
+                        "id": call["id"],
+                    }),
+                }
+            };
+            let reply_body = match serde_json::from_str(body).unwrap() {
+                Value::Array(calls) => Value::Array(calls.iter().map(answer).collect()),
+                call => answer(&call),
+            };
+            reply(&mut socket, "200 OK", &reply_body.to_string()).await;
+        }
+    });
+    port
+}
+
+async fn reply(socket: &mut TcpStream, status: &str, body: &str) {
+    let reply = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    socket.write_all(reply.as_bytes()).await.unwrap();
+}
+
+async fn read_request(socket: &mut TcpStream) -> String {
+    let mut raw = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = socket.read(&mut buf).await.unwrap();
+        raw.extend_from_slice(&buf[..n]);
+        let text = String::from_utf8_lossy(&raw).to_string();
+        if let Some((head, body)) = text.split_once("\r\n\r\n") {
+            let length: usize = head
+                .lines()
+                .find_map(|l| l.strip_prefix("Content-Length: "))
+                .unwrap()
+                .parse()
+                .unwrap();
+            if body.len() >= length {
+                return text;
+            }
+        }
+    }
+}
@@ -0,0 +1,678 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! An async JSON-RPC client for bitcoind.
+//!
+//! [`RpcConfig::from_conf`] finds the node the way `bitcoin-cli` would from
+//! the same bitcoin.conf: `rpcconnect`/`rpcport` for the address and
+//! `rpcuser`/`rpcpassword` or the cookie file in the datadir for
+//! credentials. Requests are plain HTTP/1.1 with one connection per call,
+//! which is all bitcoind's RPC server needs.
+
+#[cfg(test)]
+pub(crate) mod mock;
+pub mod types;
+
+use crate::bitcoin_conf::BitcoinConf;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use types::{BlockTemplate, BlockchainInfo, MempoolEntry, MempoolInfo, NetworkInfo, PeerInfo};
+
+/// How long a call may take, connection included, unless configured otherwise.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Responses larger than this are refused rather than buffered. A full
+/// `getrawmempool true` can exceed 100 MB.
+const MAX_RESPONSE: usize = 256 * 1024 * 1024;
+
+/// How to authenticate to bitcoind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Auth {
+    UserPass {
+        user: String,
+        password: String,
+    },
+    /// Read on every call, since bitcoind writes a new cookie each start.
+    Cookie(PathBuf),
+}
+
+impl Auth {
+    pub(crate) fn credentials(&self) -> Result<(String, String), Error> {
+        match self {
+            Auth::UserPass { user, password } => Ok((user.clone(), password.clone())),
+            Auth::Cookie(path) => {
+                let text = fs::read_to_string(path).map_err(|err| Error::Cookie {
+                    path: path.clone(),
+                    err,
+                })?;
+                text.trim_end()
+                    .split_once(':')
+                    .map(|(user, password)| (user.to_string(), password.to_string()))
+                    .ok_or_else(|| Error::Cookie {
+                        path: path.clone(),
+                        err: io::Error::new(io::ErrorKind::InvalidData, "not user:password"),
+                    })
+            }
+        }
+    }
+}
+
+/// Where and how to reach bitcoind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcConfig {
+    pub host: String,
+    pub port: u16,
+    pub auth: Auth,
+    pub timeout: Duration,
+}
+
+impl RpcConfig {
+    /// Connection settings for the chain `conf` selects.
+    ///
+    /// `conf_path` is the file `conf` was loaded from; without a `datadir=`
+    /// setting the datadir is taken to be the directory holding it.
+    pub fn from_conf(conf: &BitcoinConf, conf_path: &Path) -> RpcConfig {
+        let chain = conf.chain();
+        let (host, connect_port) = split_host_port(conf.effective("rpcconnect").unwrap_or(""));
+        let port = conf
+            .effective("rpcport")
+            .and_then(|p| p.parse().ok())
+            .or(connect_port)
+            .unwrap_or(chain.default_rpc_port());
+
+        let auth = match (conf.effective("rpcuser"), conf.effective("rpcpassword")) {
+            (Some(user), Some(password)) => Auth::UserPass {
+                user: user.to_string(),
+                password: password.to_string(),
+            },
+            _ => {
+                let cookie = conf.effective("rpccookiefile").unwrap_or(".cookie");
+                Auth::Cookie(conf.net_datadir(conf_path).join(cookie))
+            }
+        };
+
+        RpcConfig {
+            host: if host.is_empty() {
+                "127.0.0.1".to_string()
+            } else {
+                host
+            },
+            port,
+            auth,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+/// Splits `host`, `host:port` or `[v6]:port`.
+pub(crate) fn split_host_port(value: &str) -> (String, Option<u16>) {
+    if let Some(rest) = value.strip_prefix('[')
+        && let Some((host, tail)) = rest.split_once(']')
+    {
+        return (
+            host.to_string(),
+            tail.strip_prefix(':').and_then(|p| p.parse().ok()),
+        );
+    }
+    match value.rsplit_once(':') {
+        // A bare IPv6 address has several colons and no port.
+        Some((host, port)) if !host.contains(':') => (host.to_string(), port.parse().ok()),
+        _ => (value.to_string(), None),
+    }
+}
+
+/// Why a call failed.
+#[derive(Debug)]
+pub enum Error {
+    /// Connecting, sending or receiving failed.
+    Io(io::Error),
+    Timeout(Duration),
+    Cookie {
+        path: PathBuf,
+        err: io::Error,
+    },
+    Unauthorized,
+    /// An HTTP error without a JSON-RPC error body.
+    Http(u16),
+    /// The reply was not the JSON we expected.
+    Parse(String),
+    /// bitcoind ran the call and reported an error.
+    Rpc {
+        code: i64,
+        message: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Timeout(after) => write!(f, "no reply after {}s", after.as_secs_f64()),
+            Error::Cookie { path, err } => {
+                write!(f, "cannot read cookie {}: {}", path.display(), err)
+            }
+            Error::Unauthorized => f.write_str("bitcoind rejected the RPC credentials"),
+            Error::Http(status) => write!(f, "HTTP status {}", status),
+            Error::Parse(msg) => write!(f, "unexpected reply: {}", msg),
+            Error::Rpc { code, message } => write!(f, "{} (code {})", message, code),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+/// One call of a batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub method: String,
+    pub params: Value,
+}
+
+impl Request {
+    pub fn new(method: &str, params: Value) -> Self {
+        Self {
+            method: method.to_string(),
+            params,
+        }
+    }
+}
+
+/// Converts a result from [`Client::batch`] to a typed response.
+pub fn parse<T: DeserializeOwned>(value: Value) -> Result<T, Error> {
+    serde_json::from_value(value).map_err(|err| Error::Parse(err.to_string()))
+}
+
+/// A JSON-RPC client for one bitcoind.
+#[derive(Debug)]
+pub struct Client {
+    config: RpcConfig,
+    next_id: AtomicU64,
+}
+
+impl Client {
+    pub fn new(config: RpcConfig) -> Self {
+        Self {
+            config,
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    pub fn config(&self) -> &RpcConfig {
+        &self.config
+    }
+
+    /// Calls `method` and decodes its result.
+    pub async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, Error> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let body = json!({"jsonrpc": "1.0", "id": id, "method": method, "params": params});
+        let reply = self.post(&body).await?;
+        parse(take_result(reply)?)
+    }
+
+    /// Sends `requests` in one round trip. The outer error is for the
+    /// exchange as a whole; each call's own result is returned in request
+    /// order.
+    pub async fn batch(&self, requests: &[Request]) -> Result<Vec<Result<Value, Error>>, Error> {
+        if requests.is_empty() {
+            return Ok(Vec::new());
+        }
+        let first = self
+            .next_id
+            .fetch_add(requests.len() as u64, Ordering::Relaxed);
+        let body: Vec<Value> = requests
+            .iter()
+            .zip(first..)
+            .map(|(r, id)| json!({"jsonrpc": "1.0", "id": id, "method": r.method, "params": r.params}))
+            .collect();
+
+        let reply = self.post(&Value::Array(body)).await?;
+        let Value::Array(replies) = reply else {
+            // A batch rejected as a whole gets a single error object back.
+            take_result(reply)?;
+            return Err(Error::Parse("batch reply is not a list".into()));
+        };
+
+        let mut results: Vec<Option<Result<Value, Error>>> =
+            requests.iter().map(|_| None).collect();
+        for reply in replies {
+            let slot = reply
+                .get("id")
+                .and_then(Value::as_u64)
+                .and_then(|id| id.checked_sub(first))
+                .and_then(|i| results.get_mut(i as usize));
+            if let Some(slot) = slot {
+                *slot = Some(take_result(reply));
+            }
+        }
+        Ok(results
+            .into_iter()
+            .map(|r| r.unwrap_or_else(|| Err(Error::Parse("no reply for call".into()))))
+            .collect())
+    }
+
+    pub async fn get_blockchain_info(&self) -> Result<BlockchainInfo, Error> {
+        self.call("getblockchaininfo", json!([])).await
+    }
+
+    pub async fn get_network_info(&self) -> Result<NetworkInfo, Error> {
+        self.call("getnetworkinfo", json!([])).await
+    }
+
+    pub async fn get_mempool_info(&self) -> Result<MempoolInfo, Error> {
+        self.call("getmempoolinfo", json!([])).await
+    }
+
+    pub async fn get_peer_info(&self) -> Result<Vec<PeerInfo>, Error> {
+        self.call("getpeerinfo", json!([])).await
+    }
+
+    /// Asks bitcoind to shut down.
+    pub async fn stop(&self) -> Result<String, Error> {
+        self.call("stop", json!([])).await
+    }
+
+    /// Seconds since bitcoind started.
+    pub async fn uptime(&self) -> Result<u64, Error> {
+        self.call("uptime", json!([])).await
+    }
+
+    /// Every mempool transaction, by txid.
+    pub async fn get_raw_mempool_verbose(&self) -> Result<HashMap<String, MempoolEntry>, Error> {
+        self.call("getrawmempool", json!([true])).await
+    }
+
+    pub async fn get_mempool_entry(&self, txid: &str) -> Result<MempoolEntry, Error> {
+        self.call("getmempoolentry", json!([txid])).await
+    }
+
+    /// Txids of the unconfirmed transactions `txid` spends from.
+    pub async fn get_mempool_ancestors(&self, txid: &str) -> Result<Vec<String>, Error> {
+        self.call("getmempoolancestors", json!([txid])).await
+    }
+
+    /// Txids of the unconfirmed transactions spending from `txid`.
+    pub async fn get_mempool_descendants(&self, txid: &str) -> Result<Vec<String>, Error> {
+        self.call("getmempooldescendants", json!([txid])).await
+    }
+
+    /// Drops the connection to the peer with id `node_id`.
+    pub async fn disconnect_node(&self, node_id: u64) -> Result<(), Error> {
+        self.call("disconnectnode", json!(["", node_id])).await
+    }
+
+    /// Bans an IP address or subnet for `seconds`.
+    pub async fn set_ban(&self, subnet: &str, seconds: u64) -> Result<(), Error> {
+        self.call("setban", json!([subnet, "add", seconds])).await
+    }
+
+    /// Adds `address` to the node's list of peers to keep connected to.
+    pub async fn add_node(&self, address: &str) -> Result<(), Error> {
+        self.call("addnode", json!([address, "add"])).await
+    }
+
+    pub async fn get_block_template(&self) -> Result<BlockTemplate, Error> {
+        self.call("getblocktemplate", json!([{"rules": ["segwit"]}]))
+            .await
+    }
+
+    /// POSTs `body` and returns the decoded JSON reply.
+    async fn post(&self, body: &Value) -> Result<Value, Error> {
+        let timeout = self.config.timeout;
+        tokio::time::timeout(timeout, self.exchange(body))
+            .await
+            .map_err(|_| Error::Timeout(timeout))?
+    }
+
+    async fn exchange(&self, body: &Value) -> Result<Value, Error> {
+        let (user, password) = self.config.auth.credentials()?;
+        let body = body.to_string();
+        let host = if self.config.host.contains(':') {
+            format!("[{}]", self.config.host)
+        } else {
+            self.config.host.clone()
+        };
+        let request = format!(
+            "POST / HTTP/1.1\r\n\
+             Host: {}:{}\r\n\
+             Authorization: Basic {}\r\n\
+             Content-Type: application/json\r\n\
+             Content-Length: {}\r\n\
+             Connection: close\r\n\
+             \r\n{}",
+            host,
+            self.config.port,
+            BASE64.encode(format!("{}:{}", user, password)),
+            body.len(),
+            body
+        );
+
+        let mut stream = TcpStream::connect((self.config.host.as_str(), self.config.port)).await?;
+        stream.write_all(request.as_bytes()).await?;
+        let mut raw = Vec::new();
+        (&mut stream)
+            .take(MAX_RESPONSE as u64 + 1)
+            .read_to_end(&mut raw)
+            .await?;
+        if raw.len() > MAX_RESPONSE {
+            return Err(Error::Parse("reply too large".into()));
+        }
+
+        let (status, body) = parse_http(&raw)?;
+        // bitcoind answers failed calls with 404/500 and a JSON-RPC body.
+        match serde_json::from_slice(&body) {
+            Ok(value) => Ok(value),
+            Err(_) if status == 401 => Err(Error::Unauthorized),
+            Err(_) if status != 200 => Err(Error::Http(status)),
+            Err(err) => Err(Error::Parse(err.to_string())),
+        }
+    }
+}
+
+/// Extracts `result` from a JSON-RPC reply object, or its `error`.
+fn take_result(mut reply: Value) -> Result<Value, Error> {
+    match reply.get("error") {
+        Some(Value::Null) | None => {}
+        Some(error) => {
+            return Err(Error::Rpc {
+                code: error.get("code").and_then(Value::as_i64).unwrap_or(0),
+                message: error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown error")
+                    .to_string(),
+            });
+        }
+    }
+    match reply.get_mut("result") {
+        Some(result) => Ok(result.take()),
+        None => Err(Error::Parse("reply has no result".into())),
+    }
+}
+
+/// Splits a complete HTTP/1.1 response into status and body.
+fn parse_http(raw: &[u8]) -> Result<(u16, Vec<u8>), Error> {
+    let split = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| Error::Parse("truncated HTTP response".into()))?;
+    let head = String::from_utf8_lossy(&raw[..split]);
+    let body = &raw[split + 4..];
+
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| Error::Parse("bad HTTP status line".into()))?;
+    let chunked = lines.any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("transfer-encoding")
+                && value.trim().eq_ignore_ascii_case("chunked")
+        })
+    });
+
+    let body = if chunked {
+        dechunk(body)?
+    } else {
+        body.to_vec()
+    };
+    Ok((status, body))
+}
+
+fn dechunk(mut raw: &[u8]) -> Result<Vec<u8>, Error> {
+    let bad = || Error::Parse("bad chunked encoding".into());
+    let mut body = Vec::new();
+    loop {
+        let end = raw.windows(2).position(|w| w == b"\r\n").ok_or_else(bad)?;
+        let size_field = String::from_utf8_lossy(&raw[..end]);
+        let size_hex = size_field.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size_hex, 16).map_err(|_| bad())?;
+        raw = &raw[end + 2..];
+        if size == 0 {
+            return Ok(body);
+        }
+        if raw.len() < size + 2 {
+            return Err(bad());
+        }
+        body.extend_from_slice(&raw[..size]);
+        raw = &raw[size + 2..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn client(port: u16) -> Client {
+        Client::new(RpcConfig {
+            host: "127.0.0.1".into(),
+            port,
+            auth: Auth::UserPass {
+                user: "pool".into(),
+                password: "secret".into(),
+            },
+            timeout: Duration::from_secs(5),
+        })
+    }
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pdm_rpc_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn config_uses_rpcuser_and_chain_port() {
+        let conf = BitcoinConf::parse(
+            "chain=signet\n[signet]\nrpcuser=pool\nrpcpassword=secret\nrpcconnect=10.0.0.2\n",
+        );
+        let config = RpcConfig::from_conf(&conf, Path::new("/data/bitcoin.conf"));
+        assert_eq!(config.host, "10.0.0.2");
+        assert_eq!(config.port, 38332);
+        assert_eq!(
+            config.auth,
+            Auth::UserPass {
+                user: "pool".into(),
+                password: "secret".into()
+            }
+        );
+    }
+
+    #[test]
+    fn config_falls_back_to_cookie_in_datadir() {
+        let conf = BitcoinConf::parse("testnet=1\nrpcauth=pool:abc$def\n");
+        let config = RpcConfig::from_conf(&conf, Path::new("/data/bitcoin.conf"));
+        assert_eq!(config.host, "127.0.0.1");
+        assert_eq!(config.port, 18332);
+        assert_eq!(
+            config.auth,
+            Auth::Cookie(PathBuf::from("/data/testnet3/.cookie"))
+        );
+
+        let conf = BitcoinConf::parse("datadir=/srv/btc\nrpccookiefile=/run/btc.cookie\n");
+        let config = RpcConfig::from_conf(&conf, Path::new("/etc/bitcoin.conf"));
+        assert_eq!(config.auth, Auth::Cookie(PathBuf::from("/run/btc.cookie")));
+    }
+
+    #[test]
+    fn splits_rpcconnect_ports() {
+        assert_eq!(split_host_port("node:8400"), ("node".into(), Some(8400)));
+        assert_eq!(split_host_port("[::1]:8400"), ("::1".into(), Some(8400)));
+        assert_eq!(split_host_port("::1"), ("::1".into(), None));
+        let conf = BitcoinConf::parse("rpcconnect=node:8400\n");
+        assert_eq!(RpcConfig::from_conf(&conf, Path::new("b.conf")).port, 8400);
+    }
+
+    #[tokio::test]
+    async fn call_sends_basic_auth_and_decodes_result() {
+        let body = json!({"result": {
+            "chain": "main", "blocks": 850000, "headers": 850001,
+            "bestblockhash": "00ab", "difficulty": 8.3e13,
+            "verificationprogress": 0.9999, "initialblockdownload": false,
+        }, "error": null, "id": 1});
+        let (port, server) = mock::serve_once("200 OK", body.to_string()).await;
+
+        let info = client(port).get_blockchain_info().await.unwrap();
+        assert_eq!(info.blocks, 850000);
+        assert_eq!(info.headers, 850001);
+        assert!(!info.initialblockdownload);
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST / HTTP/1.1\r\n"));
+        // base64("pool:secret")
+        assert!(request.contains("Authorization: Basic cG9vbDpzZWNyZXQ=\r\n"));
+        assert!(request.contains(r#""method":"getblockchaininfo""#));
+    }
+
+    #[tokio::test]
+    async fn cookie_is_read_per_call() {
+        let dir = scratch("cookie");
+        let cookie = dir.join(".cookie");
+        fs::write(&cookie, "__cookie__:abc123").unwrap();
+        let body = json!({"result": 42, "error": null, "id": 1});
+        let (port, server) = mock::serve_once("200 OK", body.to_string()).await;
+
+        let mut config = client(port).config().clone();
+        config.auth = Auth::Cookie(cookie);
+        let uptime: u64 = Client::new(config).call("uptime", json!([])).await.unwrap();
+        assert_eq!(uptime, 42);
+        // base64("__cookie__:abc123")
+        assert!(
+            server
+                .await
+                .unwrap()
+                .contains("Basic X19jb29raWVfXzphYmMxMjM=")
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_cookie_is_reported() {
+        let mut config = client(1).config().clone();
+        config.auth = Auth::Cookie(scratch("nocookie").join(".cookie"));
+        let err = Client::new(config).get_network_info().await.unwrap_err();
+        assert!(matches!(err, Error::Cookie { .. }));
+    }
+
+    #[tokio::test]
+    async fn rpc_errors_are_typed() {
+        let body = json!({"result": null, "error": {"code": -32601, "message": "Method not found"}, "id": 1});
+        let (port, _server) = mock::serve_once("404 Not Found", body.to_string()).await;
+        let err = client(port).get_mempool_info().await.unwrap_err();
+        assert!(matches!(err, Error::Rpc { code: -32601, .. }));
+        assert_eq!(err.to_string(), "Method not found (code -32601)");
+    }
+
+    #[tokio::test]
+    async fn bad_credentials_are_reported() {
+        let (port, _server) = mock::serve_once("401 Unauthorized", String::new()).await;
+        let err = client(port).get_peer_info().await.unwrap_err();
+        assert!(matches!(err, Error::Unauthorized));
+    }
+
+    #[tokio::test]
+    async fn batch_results_follow_request_order() {
+        // Replies out of order, one of them failed.
+        let body = json!([
+            {"result": null, "error": {"code": -1, "message": "boom"}, "id": 2},
+            {"result": 7, "error": null, "id": 1},
+        ]);
+        let (port, server) = mock::serve_once("200 OK", body.to_string()).await;
+
+        let results = client(port)
+            .batch(&[
+                Request::new("getconnectioncount", json!([])),
+                Request::new("getmempoolinfo", json!([])),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(results[0].as_ref().unwrap(), &json!(7));
+        assert!(matches!(results[1], Err(Error::Rpc { code: -1, .. })));
+
+        let request = server.await.unwrap();
+        let sent = request.split_once("\r\n\r\n").unwrap().1;
+        let sent: Value = serde_json::from_str(sent).unwrap();
+        assert_eq!(sent.as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn slow_nodes_time_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let _server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            drop(socket);
+        });
+
+        let mut config = client(port).config().clone();
+        config.timeout = Duration::from_millis(100);
+        let err = Client::new(config).get_blockchain_info().await.unwrap_err();
+        assert!(matches!(err, Error::Timeout(_)));
+    }
+
+    #[test]
+    fn decodes_chunked_bodies() {
+        let raw = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\n{\"a\"\r\n3\r\n:1}\r\n0\r\n\r\n";
+        let (status, body) = parse_http(raw).unwrap();
+        assert_eq!(status, 200);
+        assert_eq!(body, b"{\"a\":1}");
+    }
+
+    #[test]
+    fn parses_block_templates() {
+        let template: BlockTemplate = parse(json!({
+            "version": 536870912, "previousblockhash": "00ff", "height": 100,
+            "coinbasevalue": 312500000, "sigoplimit": 80000, "weightlimit": 4000000,
+            "curtime": 1700000000, "bits": "1703a30c", "target": "00",
+            "transactions": [
+                {"data": "02", "txid": "aa", "hash": "aa", "fee": 1200, "sigops": 4, "weight": 800, "depends": []}
+            ]
+        }))
+        .unwrap();
+        assert_eq!(template.transactions[0].fee, 1200);
+        assert_eq!(template.coinbasevalue, 312500000);
+    }
+
+    #[test]
+    fn parses_mempool_entries() {
+        let entries: HashMap<String, MempoolEntry> = parse(json!({
+            "aa": {
+                "vsize": 141, "weight": 561, "time": 1700000000, "height": 850000,
+                "descendantcount": 1, "descendantsize": 141,
+                "ancestorcount": 2, "ancestorsize": 250,
+                "wtxid": "bb", "fees": {
+                    "base": 0.00001410, "modified": 0.00001410,
+                    "ancestor": 0.00002500, "descendant": 0.00001410
+                },
+                "depends": ["cc"], "spentby": [], "bip125-replaceable": false,
+                "unbroadcast": false
+            }
+        }))
+        .unwrap();
+        let entry = &entries["aa"];
+        assert_eq!(entry.fee_sats(), 1410);
+        assert_eq!(entry.fee_rate(), 10.0);
+        assert_eq!(entry.ancestor_fee_rate(), 10.0);
+        assert_eq!(entry.depends, ["cc"]);
+    }
+}
@@ -0,0 +1,204 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Typed results of the RPC calls pdm makes.
+//!
+//! Only the fields pdm shows are modelled. Fields added in recent Bitcoin
+//! Core releases default when missing so older nodes still parse.
+
+use serde::Deserialize;
+use serde_json::Value;
+
+/// `getblockchaininfo`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct BlockchainInfo {
+    pub chain: String,
+    pub blocks: u64,
+    pub headers: u64,
+    pub bestblockhash: String,
+    pub difficulty: f64,
+    pub verificationprogress: f64,
+    pub initialblockdownload: bool,
+    #[serde(default)]
+    pub size_on_disk: u64,
+    #[serde(default)]
+    pub pruned: bool,
+}
+
+/// `getnetworkinfo`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct NetworkInfo {
+    pub version: u64,
+    pub subversion: String,
+    pub protocolversion: u64,
+    pub connections: u32,
+    #[serde(default)]
+    pub connections_in: u32,
+    #[serde(default)]
+    pub connections_out: u32,
+    #[serde(default = "default_true")]
+    pub networkactive: bool,
+    /// BTC/kvB.
+    pub relayfee: f64,
+    /// A string before Bitcoin Core 28, a list of strings since.
+    #[serde(default)]
+    pub warnings: Value,
+}
+
+impl NetworkInfo {
+    /// Non-empty warnings, whichever form the node reported them in.
+    pub fn warnings(&self) -> Vec<String> {
+        match &self.warnings {
+            Value::String(s) if !s.is_empty() => vec![s.clone()],
+            Value::Array(list) => list
+                .iter()
+                .filter_map(Value::as_str)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// `getmempoolinfo`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MempoolInfo {
+    #[serde(default = "default_true")]
+    pub loaded: bool,
+    /// Number of transactions.
+    pub size: u64,
+    /// Sum of virtual sizes.
+    pub bytes: u64,
+    /// Memory used, including overhead.
+    pub usage: u64,
+    /// BTC.
+    #[serde(default)]
+    pub total_fee: f64,
+    pub maxmempool: u64,
+    /// BTC/kvB.
+    pub mempoolminfee: f64,
+    /// BTC/kvB.
+    pub minrelaytxfee: f64,
+}
+
+/// One entry of `getpeerinfo`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PeerInfo {
+    pub id: u64,
+    pub addr: String,
+    #[serde(default)]
+    pub network: String,
+    pub inbound: bool,
+    /// `outbound-full-relay`, `block-relay-only`, `manual`, ...
+    #[serde(default)]
+    pub connection_type: String,
+    pub version: u32,
+    #[serde(default)]
+    pub subver: String,
+    /// Seconds; missing until the first pong.
+    #[serde(default)]
+    pub pingtime: Option<f64>,
+    pub bytessent: u64,
+    pub bytesrecv: u64,
+    #[serde(default)]
+    pub conntime: u64,
+    #[serde(default = "unknown_height")]
+    pub synced_headers: i64,
+    #[serde(default = "unknown_height")]
+    pub synced_blocks: i64,
+}
+
+fn unknown_height() -> i64 {
+    -1
+}
+
+/// `getblocktemplate`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct BlockTemplate {
+    pub version: u32,
+    pub previousblockhash: String,
+    pub height: u64,
+    /// Satoshis: subsidy plus fees.
+    pub coinbasevalue: u64,
+    pub transactions: Vec<TemplateTransaction>,
+    #[serde(default)]
+    pub sigoplimit: u64,
+    #[serde(default)]
+    pub weightlimit: u64,
+    pub curtime: u64,
+    pub bits: String,
+    #[serde(default)]
+    pub target: String,
+}
+
+/// A transaction selected into a [`BlockTemplate`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TemplateTransaction {
+    pub txid: String,
+    /// Satoshis.
+    pub fee: u64,
+    pub sigops: u64,
+    pub weight: u64,
+    /// 1-based indexes of earlier template transactions this one spends.
+    #[serde(default)]
+    pub depends: Vec<u32>,
+}
+
+/// One transaction of `getrawmempool true`, or `getmempoolentry`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MempoolEntry {
+    pub vsize: u64,
+    #[serde(default)]
+    pub weight: u64,
+    /// When it entered the mempool, in seconds since the epoch.
+    pub time: u64,
+    pub ancestorcount: u64,
+    pub ancestorsize: u64,
+    pub descendantcount: u64,
+    pub descendantsize: u64,
+    pub fees: MempoolFees,
+    /// Unconfirmed parents.
+    #[serde(default)]
+    pub depends: Vec<String>,
+    /// Unconfirmed children.
+    #[serde(default)]
+    pub spentby: Vec<String>,
+}
+
+impl MempoolEntry {
+    /// The fee paid by the transaction itself, in satoshis.
+    pub fn fee_sats(&self) -> u64 {
+        btc_to_sats(self.fees.base)
+    }
+
+    /// sat/vB, from the transaction's own fee.
+    pub fn fee_rate(&self) -> f64 {
+        self.fee_sats() as f64 / self.vsize.max(1) as f64
+    }
+
+    /// sat/vB of the transaction together with its unconfirmed ancestors,
+    /// which is what block templates select by.
+    pub fn ancestor_fee_rate(&self) -> f64 {
+        btc_to_sats(self.fees.ancestor) as f64 / self.ancestorsize.max(1) as f64
+    }
+}
+
+/// Fees of a [`MempoolEntry`], in BTC.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MempoolFees {
+    pub base: f64,
+    pub modified: f64,
+    pub ancestor: f64,
+    pub descendant: f64,
+}
+
+/// Converts an amount in BTC, as RPC reports it, to satoshis.
+pub fn btc_to_sats(btc: f64) -> u64 {
+    (btc * 100_000_000.0).round() as u64
+}
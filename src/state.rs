@@ -0,0 +1,147 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! pdm's own state, remembered between runs.
+//!
+//! Stored as `key=value` lines in `$XDG_CONFIG_HOME/pdm/state.conf`
+//! (falling back to `~/.config/pdm/state.conf`).
+
+use crate::atomic_file;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PdmState {
+    pub bitcoin_conf_path: Option<PathBuf>,
+    pub p2pool_conf_path: Option<PathBuf>,
+    /// How many backups to keep per config file.
+    pub backup_history: Option<usize>,
+    /// Redraws per second.
+    pub frame_rate: Option<u32>,
+    /// The bitcoind executable pdm starts, if not the one on `PATH`.
+    pub bitcoind_path: Option<PathBuf>,
+    /// The p2poolv2 executable pdm starts, if not the one on `PATH`.
+    pub p2pool_path: Option<PathBuf>,
+    /// Whether pdm restarts p2poolv2 when it crashes.
+    pub p2pool_auto_restart: Option<bool>,
+}
+
+impl PdmState {
+    /// Where the state file lives, if a config directory can be determined.
+    pub fn default_path() -> Option<PathBuf> {
+        let base = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        Some(base.join("pdm").join("state.conf"))
+    }
+
+    /// Reads the state at `path`. Returns `Ok(None)` if there is no state yet.
+    pub fn load(path: &Path) -> io::Result<Option<Self>> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+
+        let mut state = Self::default();
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "bitcoin_conf_path" => state.bitcoin_conf_path = Some(PathBuf::from(value)),
+                "p2pool_conf_path" => state.p2pool_conf_path = Some(PathBuf::from(value)),
+                "backup_history" => state.backup_history = value.parse().ok(),
+                "frame_rate" => state.frame_rate = value.parse().ok(),
+                "bitcoind_path" => state.bitcoind_path = Some(PathBuf::from(value)),
+                "p2pool_path" => state.p2pool_path = Some(PathBuf::from(value)),
+                "p2pool_auto_restart" => state.p2pool_auto_restart = value.parse().ok(),
+                _ => {}
+            }
+        }
+        Ok(Some(state))
+    }
+
+    /// Writes the state to `path`, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut text = String::new();
+        if let Some(p) = &self.bitcoin_conf_path {
+            text.push_str(&format!("bitcoin_conf_path={}\n", p.display()));
+        }
+        if let Some(p) = &self.p2pool_conf_path {
+            text.push_str(&format!("p2pool_conf_path={}\n", p.display()));
+        }
+        if let Some(n) = self.backup_history {
+            text.push_str(&format!("backup_history={}\n", n));
+        }
+        if let Some(n) = self.frame_rate {
+            text.push_str(&format!("frame_rate={}\n", n));
+        }
+        if let Some(p) = &self.bitcoind_path {
+            text.push_str(&format!("bitcoind_path={}\n", p.display()));
+        }
+        if let Some(p) = &self.p2pool_path {
+            text.push_str(&format!("p2pool_path={}\n", p.display()));
+        }
+        if let Some(on) = self.p2pool_auto_restart {
+            text.push_str(&format!("p2pool_auto_restart={}\n", on));
+        }
+        atomic_file::write(path, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_state_path(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("pdm_state_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("pdm").join("state.conf")
+    }
+
+    #[test]
+    fn missing_state_is_none() {
+        let path = temp_state_path("missing");
+        assert_eq!(PdmState::load(&path).unwrap(), None);
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = temp_state_path("round_trip");
+        let state = PdmState {
+            bitcoin_conf_path: Some(PathBuf::from("/home/bitcoin/.bitcoin/bitcoin.conf")),
+            p2pool_conf_path: None,
+            backup_history: Some(3),
+            frame_rate: Some(10),
+            bitcoind_path: Some(PathBuf::from("/usr/local/bin/bitcoind")),
+            p2pool_path: Some(PathBuf::from("/opt/p2poolv2/bin/p2poolv2")),
+            p2pool_auto_restart: Some(true),
+        };
+        state.save(&path).unwrap();
+        assert_eq!(PdmState::load(&path).unwrap(), Some(state));
+    }
+
+    #[test]
+    fn unknown_lines_are_ignored() {
+        let path = temp_state_path("unknown");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            &path,
+            "# comment\nfuture_key=1\np2pool_conf_path=/p2pool.toml\n",
+        )
+        .unwrap();
+
+        let state = PdmState::load(&path).unwrap().unwrap();
+        assert_eq!(state.bitcoin_conf_path, None);
+        assert_eq!(state.p2pool_conf_path, Some(PathBuf::from("/p2pool.toml")));
+    }
+}
@@ -0,0 +1,666 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! A Stratum v1 client that checks the local p2poolv2 serves work.
+//!
+//! [`StratumMonitor`] connects to the stratum port of the loaded p2poolv2
+//! config as a miner would: it subscribes, authorizes a test worker and
+//! then listens for difficulty changes and job notifications, reporting
+//! each to the event loop. It never submits shares.
+//!
+//! Stratum v1 is newline-delimited JSON-RPC over TCP. Requests carry an
+//! `id` the pool echoes in its reply; notifications carry a `method` and
+//! no `id`.
+
+use crate::event::AppEvent;
+use crate::p2pool_conf::P2poolSettings;
+use crate::zmq::LinkState;
+use serde_json::{Value, json};
+use std::collections::VecDeque;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::runtime::Runtime;
+
+/// How long to wait before reconnecting after the pool went away.
+pub const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long a connection attempt or a reply may take.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Lines longer than this are refused. `mining.notify` is the largest
+/// message and grows only with the merkle branch.
+const MAX_LINE: u64 = 64 * 1024;
+
+/// Errors kept for display.
+const MAX_ERRORS: usize = 5;
+
+/// The worker name pdm authorizes as, after the payout address if set.
+pub const WORKER: &str = "pdm-monitor";
+
+const SUBSCRIBE: u64 = 1;
+const AUTHORIZE: u64 = 2;
+
+/// Where to connect and whom to authorize as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    /// `host:port`.
+    pub address: String,
+    pub worker: String,
+}
+
+impl Target {
+    /// The stratum server configured by `settings`. Wildcard listen
+    /// addresses are reached over loopback.
+    pub fn from_settings(settings: &P2poolSettings) -> Result<Target, String> {
+        let port = settings
+            .stratum_port
+            .ok_or_else(|| "stratum.port is not set".to_string())?;
+        let host = match settings.stratum_hostname.as_deref().map(str::trim) {
+            None | Some("" | "0.0.0.0" | "*") => "127.0.0.1",
+            Some("::" | "[::]") => "[::1]",
+            Some(host) => host,
+        };
+        let address = if host.contains(':') && !host.starts_with('[') {
+            format!("[{}]:{}", host, port)
+        } else {
+            format!("{}:{}", host, port)
+        };
+        let worker = match &settings.miner_address {
+            Some(payout) => format!("{}.{}", payout, WORKER),
+            None => WORKER.to_string(),
+        };
+        Ok(Target { address, worker })
+    }
+}
+
+/// What a [`StratumMonitor`] reports.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StratumEvent {
+    Connected,
+    /// The pool answered `mining.subscribe`.
+    Subscribed {
+        latency: Duration,
+        extranonce1: String,
+        extranonce2_size: u64,
+    },
+    /// The pool answered `mining.authorize`.
+    Authorized {
+        latency: Duration,
+        accepted: bool,
+    },
+    /// `mining.set_difficulty`: the share difficulty for new jobs.
+    Difficulty(f64),
+    /// `mining.notify`: new work. `clean` jobs replace earlier ones, as
+    /// after a new block.
+    Job {
+        id: String,
+        clean: bool,
+    },
+    /// The pool answered a request with an error.
+    Error(String),
+    Disconnected {
+        error: String,
+    },
+}
+
+/// What has been heard from the pool, for display.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StratumStatus {
+    pub target: Target,
+    pub link: LinkState,
+    /// Round trip of `mining.subscribe` on the current connection.
+    pub subscribe_latency: Option<Duration>,
+    pub authorize_latency: Option<Duration>,
+    pub authorized: Option<bool>,
+    pub extranonce1: Option<String>,
+    pub difficulty: Option<f64>,
+    /// Id and arrival of the latest job.
+    pub last_job: Option<(String, Instant)>,
+    pub jobs: u64,
+    pub clean_jobs: u64,
+    /// Most recent last.
+    pub errors: VecDeque<String>,
+}
+
+impl StratumStatus {
+    pub fn new(target: Target) -> Self {
+        Self {
+            target,
+            link: LinkState::Connecting,
+            subscribe_latency: None,
+            authorize_latency: None,
+            authorized: None,
+            extranonce1: None,
+            difficulty: None,
+            last_job: None,
+            jobs: 0,
+            clean_jobs: 0,
+            errors: VecDeque::new(),
+        }
+    }
+
+    pub fn record(&mut self, event: StratumEvent) {
+        match event {
+            StratumEvent::Connected => {
+                // What the previous connection negotiated no longer holds
+                *self = Self {
+                    link: LinkState::Connected,
+                    last_job: self.last_job.take(),
+                    jobs: self.jobs,
+                    clean_jobs: self.clean_jobs,
+                    errors: std::mem::take(&mut self.errors),
+                    ..Self::new(self.target.clone())
+                };
+            }
+            StratumEvent::Subscribed {
+                latency,
+                extranonce1,
+                ..
+            } => {
+                self.subscribe_latency = Some(latency);
+                self.extranonce1 = Some(extranonce1);
+            }
+            StratumEvent::Authorized { latency, accepted } => {
+                self.authorize_latency = Some(latency);
+                self.authorized = Some(accepted);
+            }
+            StratumEvent::Difficulty(difficulty) => self.difficulty = Some(difficulty),
+            StratumEvent::Job { id, clean } => {
+                self.last_job = Some((id, Instant::now()));
+                self.jobs += 1;
+                if clean {
+                    self.clean_jobs += 1;
+                }
+            }
+            StratumEvent::Error(error) => self.push_error(error),
+            StratumEvent::Disconnected { error } => {
+                self.push_error(error.clone());
+                self.link = LinkState::Down(error);
+            }
+        }
+    }
+
+    fn push_error(&mut self, error: String) {
+        if self.errors.len() == MAX_ERRORS {
+            self.errors.pop_front();
+        }
+        self.errors.push_back(error);
+    }
+}
+
+/// Monitors a stratum server in the background and sends what happens
+/// to the event loop as [`AppEvent::Stratum`], reconnecting after `retry`
+/// whenever the connection ends.
+///
+/// p2poolv2 can be monitored without a bitcoin.conf loaded, so unlike
+/// [`ZmqSubscriber`](crate::zmq::ZmqSubscriber) the monitor has a runtime
+/// of its own rather than the [`RpcTasks`](crate::tasks::RpcTasks) one.
+/// Events carry the monitor's `id` and the work stops when it is dropped.
+pub struct StratumMonitor {
+    pub id: u64,
+    /// Always `Some` until dropped.
+    runtime: Option<Runtime>,
+}
+
+static NEXT_MONITOR_ID: AtomicU64 = AtomicU64::new(1);
+
+impl StratumMonitor {
+    pub fn start(
+        target: Target,
+        events: Sender<AppEvent>,
+        retry: Duration,
+    ) -> io::Result<StratumMonitor> {
+        let id = NEXT_MONITOR_ID.fetch_add(1, Ordering::Relaxed);
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("pdm-stratum")
+            .enable_all()
+            .build()?;
+        let send = move |event| {
+            events
+                .send(AppEvent::Stratum { monitor: id, event })
+                .is_ok()
+        };
+        runtime.spawn(async move {
+            loop {
+                let error = match monitor(&target, &send).await {
+                    Ok(()) => "closed by the pool".to_string(),
+                    Err(err) => err.to_string(),
+                };
+                if !send(StratumEvent::Disconnected { error }) {
+                    return;
+                }
+                tokio::time::sleep(retry).await;
+            }
+        });
+        Ok(StratumMonitor {
+            id,
+            runtime: Some(runtime),
+        })
+    }
+}
+
+impl Drop for StratumMonitor {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn timed_out(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::TimedOut, message.into())
+}
+
+/// Subscribes and authorizes at `target`, then forwards what the pool
+/// sends until the connection ends or `send` reports the event loop has
+/// gone.
+async fn monitor(target: &Target, send: &impl Fn(StratumEvent) -> bool) -> io::Result<()> {
+    let stream = tokio::time::timeout(TIMEOUT, TcpStream::connect(&target.address))
+        .await
+        .map_err(|_| timed_out("connection timed out"))??;
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    if !send(StratumEvent::Connected) {
+        return Ok(());
+    }
+
+    let subscribe = request(SUBSCRIBE, "mining.subscribe", json!(["pdm"]));
+    writer.write_all(subscribe.as_bytes()).await?;
+    // The request awaiting a reply and when it was sent
+    let mut pending = Some((SUBSCRIBE, Instant::now()));
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let mut limited = (&mut reader).take(MAX_LINE);
+        let read = limited.read_line(&mut line);
+        let read = match pending {
+            Some((id, sent)) => tokio::time::timeout(TIMEOUT.saturating_sub(sent.elapsed()), read)
+                .await
+                .map_err(|_| timed_out(format!("no reply to {}", method_name(id))))?,
+            None => read.await,
+        }?;
+        if read == 0 {
+            return Ok(());
+        }
+        if !line.ends_with('\n') && read as u64 == MAX_LINE {
+            return Err(invalid("line too long"));
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let events = match parse(&line).map_err(invalid)? {
+            Message::Reply { id, result, error } => {
+                let Some((expected, sent)) = pending.filter(|(expected, _)| *expected == id) else {
+                    continue;
+                };
+                let latency = sent.elapsed();
+                pending = None;
+                match expected {
+                    SUBSCRIBE => {
+                        if let Some(error) = error {
+                            return Err(invalid(format!("mining.subscribe refused: {}", error)));
+                        }
+                        let event = subscribed(&result, latency)
+                            .ok_or_else(|| invalid("malformed mining.subscribe reply"))?;
+                        let authorize =
+                            request(AUTHORIZE, "mining.authorize", json!([target.worker, "x"]));
+                        writer.write_all(authorize.as_bytes()).await?;
+                        pending = Some((AUTHORIZE, Instant::now()));
+                        vec![event]
+                    }
+                    _ => {
+                        let accepted = result == Value::Bool(true);
+                        let mut events = vec![StratumEvent::Authorized { latency, accepted }];
+                        if let Some(error) = error {
+                            events
+                                .push(StratumEvent::Error(format!("mining.authorize: {}", error)));
+                        }
+                        events
+                    }
+                }
+            }
+            Message::Notification { method, params } => {
+                notification(&method, &params).into_iter().collect()
+            }
+        };
+        for event in events {
+            if !send(event) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn method_name(id: u64) -> &'static str {
+    match id {
+        SUBSCRIBE => "mining.subscribe",
+        _ => "mining.authorize",
+    }
+}
+
+/// A request as one line.
+fn request(id: u64, method: &str, params: Value) -> String {
+    let mut line = json!({"id": id, "method": method, "params": params}).to_string();
+    line.push('\n');
+    line
+}
+
+#[derive(Debug, PartialEq)]
+enum Message {
+    Reply {
+        id: u64,
+        result: Value,
+        error: Option<String>,
+    },
+    Notification {
+        method: String,
+        params: Value,
+    },
+}
+
+fn parse(line: &str) -> Result<Message, String> {
+    let value: Value =
+        serde_json::from_str(line).map_err(|err| format!("invalid JSON from the pool: {}", err))?;
+    if let Some(method) = value.get("method").and_then(Value::as_str) {
+        return Ok(Message::Notification {
+            method: method.to_string(),
+            params: value.get("params").cloned().unwrap_or(Value::Null),
+        });
+    }
+    let id = value
+        .get("id")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| "message without method or id".to_string())?;
+    let error = match value.get("error") {
+        None | Some(Value::Null) => None,
+        Some(error) => Some(error_text(error)),
+    };
+    Ok(Message::Reply {
+        id,
+        result: value.get("result").cloned().unwrap_or(Value::Null),
+        error,
+    })
+}
+
+/// Stratum errors are `[code, message, traceback]`; some pools send
+/// JSON-RPC style `{code, message}` objects instead.
+fn error_text(error: &Value) -> String {
+    let (code, message) = match error {
+        Value::Array(parts) => (parts.first(), parts.get(1)),
+        Value::Object(fields) => (fields.get("code"), fields.get("message")),
+        other => return other.to_string(),
+    };
+    match (code, message.and_then(Value::as_str)) {
+        (Some(code), Some(message)) => format!("{} ({})", message, code),
+        (_, Some(message)) => message.to_string(),
+        _ => error.to_string(),
+    }
+}
+
+/// The event for a `mining.subscribe` result:
+/// `[subscriptions, extranonce1, extranonce2_size]`.
+fn subscribed(result: &Value, latency: Duration) -> Option<StratumEvent> {
+    Some(StratumEvent::Subscribed {
+        latency,
+        extranonce1: result.get(1)?.as_str()?.to_string(),
+        extranonce2_size: result.get(2)?.as_u64()?,
+    })
+}
+
+/// The event for a notification, `None` for methods pdm does not follow.
+fn notification(method: &str, params: &Value) -> Option<StratumEvent> {
+    match method {
+        "mining.set_difficulty" => Some(StratumEvent::Difficulty(params.get(0)?.as_f64()?)),
+        // job_id, prevhash, coinb1, coinb2, merkle_branch, version, nbits,
+        // ntime, clean_jobs
+        "mining.notify" => Some(StratumEvent::Job {
+            id: params.get(0)?.as_str()?.to_string(),
+            clean: params.get(8).and_then(Value::as_bool).unwrap_or(false),
+        }),
+        _ => None,
+    }
+}
+
+/// A stand-in for p2poolv2's stratum server.
+#[cfg(test)]
+pub(crate) mod mock {
+    use super::*;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use tokio::task::JoinHandle;
+
+    /// Accepts one miner, answers its subscribe and authorize requests
+    /// (accepting only `worker`) and then writes each line sent through
+    /// the returned channel. The connection closes with the channel, and
+    /// the task returns the requests received.
+    pub async fn pool(
+        worker: &str,
+    ) -> (u16, mpsc::UnboundedSender<String>, JoinHandle<Vec<Value>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let worker = worker.to_string();
+        let (sender, mut lines) = mpsc::unbounded_channel::<String>();
+        let task = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut reader = BufReader::new(reader);
+            let mut requests = Vec::new();
+            for _ in 0..2 {
+                let mut line = String::new();
+                reader.read_line(&mut line).await.unwrap();
+                let request: Value = serde_json::from_str(&line).unwrap();
+                let reply = match request["method"].as_str().unwrap() {
+                    "mining.subscribe" => json!({
+                        "id": request["id"],
+                        "result": [[["mining.notify", "ae6812eb4cd7735a"]], "08000002", 4],
+                        "error": null,
+                    }),
+                    _ if request["params"][0] == worker.as_str() => json!({
+                        "id": request["id"], "result": true, "error": null,
+                    }),
+                    _ => json!({
+                        "id": request["id"],
+                        "result": false,
+                        "error": [24, "Unauthorized worker", null],
+                    }),
+                };
+                requests.push(request);
+                writer
+                    .write_all(format!("{}\n", reply).as_bytes())
+                    .await
+                    .unwrap();
+            }
+            while let Some(line) = lines.recv().await {
+                writer.write_all(line.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+        (port, sender, task)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn target_comes_from_stratum_settings() {
+        let mut settings = P2poolSettings {
+            stratum_hostname: Some("0.0.0.0".into()),
+            stratum_port: Some(3333),
+            miner_address: Some("tb1qpayout".into()),
+            ..P2poolSettings::default()
+        };
+        assert_eq!(
+            Target::from_settings(&settings),
+            Ok(Target {
+                address: "127.0.0.1:3333".into(),
+                worker: "tb1qpayout.pdm-monitor".into(),
+            })
+        );
+        settings.stratum_hostname = Some("fd00::1".into());
+        settings.miner_address = None;
+        let target = Target::from_settings(&settings).unwrap();
+        assert_eq!(target.address, "[fd00::1]:3333");
+        assert_eq!(target.worker, "pdm-monitor");
+        settings.stratum_port = None;
+        assert!(Target::from_settings(&settings).is_err());
+    }
+
+    #[test]
+    fn parses_pool_messages() {
+        let notify = parse(
+            r#"{"id":null,"method":"mining.notify","params":["4f","00ab","01","02",[],"20000000","1d00ffff","504e86b9",true]}"#,
+        )
+        .unwrap();
+        let Message::Notification { method, params } = notify else {
+            panic!("not a notification");
+        };
+        assert_eq!(
+            notification(&method, &params),
+            Some(StratumEvent::Job {
+                id: "4f".into(),
+                clean: true
+            })
+        );
+        assert_eq!(
+            notification("mining.set_difficulty", &json!([512])),
+            Some(StratumEvent::Difficulty(512.0))
+        );
+        assert_eq!(notification("client.reconnect", &json!([])), None);
+
+        assert_eq!(
+            parse(r#"{"id":2,"result":null,"error":[24,"Unauthorized worker",null]}"#),
+            Ok(Message::Reply {
+                id: 2,
+                result: Value::Null,
+                error: Some("Unauthorized worker (24)".into()),
+            })
+        );
+        assert_eq!(
+            error_text(&json!({"code": -1, "message": "busy"})),
+            "busy (-1)"
+        );
+        assert!(parse("not json").is_err());
+    }
+
+    #[test]
+    fn status_keeps_recent_errors_and_resets_on_reconnect() {
+        let mut status = StratumStatus::new(Target {
+            address: "127.0.0.1:3333".into(),
+            worker: WORKER.into(),
+        });
+        status.record(StratumEvent::Connected);
+        status.record(StratumEvent::Difficulty(8.0));
+        status.record(StratumEvent::Job {
+            id: "1".into(),
+            clean: true,
+        });
+        for i in 0..MAX_ERRORS + 1 {
+            status.record(StratumEvent::Error(format!("error {}", i)));
+        }
+        assert_eq!(status.errors.len(), MAX_ERRORS);
+        assert_eq!(status.errors.front().unwrap(), "error 1");
+
+        status.record(StratumEvent::Connected);
+        assert_eq!(status.difficulty, None);
+        assert_eq!((status.jobs, status.clean_jobs), (1, 1));
+        assert_eq!(status.errors.len(), MAX_ERRORS);
+    }
+
+    #[test]
+    fn monitor_reports_work_from_pool() {
+        let server = Runtime::new().unwrap();
+        let (port, send_line, requests) = server.block_on(mock::pool("tb1qpayout.pdm-monitor"));
+        let (sender, receiver) = mpsc::channel();
+        let target = Target {
+            address: format!("127.0.0.1:{}", port),
+            worker: "tb1qpayout.pdm-monitor".into(),
+        };
+        let monitor = StratumMonitor::start(target, sender, Duration::from_secs(60)).unwrap();
+        let next = || match receiver.recv_timeout(Duration::from_secs(5)).unwrap() {
+            AppEvent::Stratum { monitor: id, event } => {
+                assert_eq!(id, monitor.id);
+                event
+            }
+            event => panic!("unexpected {:?}", event),
+        };
+
+        assert_eq!(next(), StratumEvent::Connected);
+        assert!(matches!(
+            next(),
+            StratumEvent::Subscribed { extranonce1, extranonce2_size: 4, .. }
+                if extranonce1 == "08000002"
+        ));
+        assert!(matches!(
+            next(),
+            StratumEvent::Authorized { accepted: true, .. }
+        ));
+        send_line
+            .send("{\"id\":null,\"method\":\"mining.set_difficulty\",\"params\":[16]}\n".into())
+            .unwrap();
+        assert_eq!(next(), StratumEvent::Difficulty(16.0));
+        send_line
+            .send("{\"id\":null,\"method\":\"mining.notify\",\"params\":[\"a1\",\"00\",\"\",\"\",[],\"20000000\",\"1d00ffff\",\"00000000\",false]}\n".into())
+            .unwrap();
+        assert_eq!(
+            next(),
+            StratumEvent::Job {
+                id: "a1".into(),
+                clean: false
+            }
+        );
+
+        drop(send_line);
+        assert!(matches!(next(), StratumEvent::Disconnected { .. }));
+        let requests = server.block_on(requests).unwrap();
+        assert_eq!(requests[0]["method"], "mining.subscribe");
+        assert_eq!(
+            requests[1]["params"],
+            json!(["tb1qpayout.pdm-monitor", "x"])
+        );
+    }
+
+    #[test]
+    fn monitor_reports_refused_worker() {
+        let server = Runtime::new().unwrap();
+        let (port, _send_line, _requests) = server.block_on(mock::pool("someone-else"));
+        let (sender, receiver) = mpsc::channel();
+        let target = Target {
+            address: format!("127.0.0.1:{}", port),
+            worker: WORKER.into(),
+        };
+        let _monitor = StratumMonitor::start(target, sender, Duration::from_secs(60)).unwrap();
+        let events: Vec<StratumEvent> = (0..4)
+            .map(
+                |_| match receiver.recv_timeout(Duration::from_secs(5)).unwrap() {
+                    AppEvent::Stratum { event, .. } => event,
+                    event => panic!("unexpected {:?}", event),
+                },
+            )
+            .collect();
+        assert!(matches!(
+            events[2],
+            StratumEvent::Authorized {
+                accepted: false,
+                ..
+            }
+        ));
+        assert_eq!(
+            events[3],
+            StratumEvent::Error("mining.authorize: Unauthorized worker (24)".into())
+        );
+    }
+}
@@ -0,0 +1,133 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Background RPC work for the UI.
+//!
+//! [`RpcTasks`] owns a small tokio runtime and a [`Client`] for the node
+//! configured by the loaded bitcoin.conf. Each task's outcome is sent to the
+//! event loop as an [`AppEvent::Rpc`], so screens ask for data and pick the
+//! reply up when it arrives.
+
+use crate::components::mempool_view::{FeeBucket, TxDetail};
+use crate::event::AppEvent;
+use crate::rpc::types::{BlockTemplate, MempoolInfo, PeerInfo};
+use crate::rpc::{Client, RpcConfig};
+use std::future::Future;
+use std::io;
+use std::sync::Arc;
+use std::sync::mpsc::Sender;
+use tokio::runtime::{Handle, Runtime};
+
+/// The outcome of a task started through [`RpcTasks::spawn`].
+#[derive(Debug)]
+pub enum RpcReply {
+    Peers(Result<Vec<PeerInfo>, String>),
+    /// A peer action finished; the text is for the status line.
+    PeerAction(Result<String, String>),
+    Mempool(Result<(MempoolInfo, Vec<FeeBucket>), String>),
+    Transaction(Result<TxDetail, String>),
+    Template(Result<BlockTemplate, String>),
+    /// Reply to the `stop` RPC.
+    Stop(Result<String, String>),
+}
+
+pub struct RpcTasks {
+    /// Always `Some` until dropped.
+    runtime: Option<Runtime>,
+    client: Arc<Client>,
+    events: Sender<AppEvent>,
+}
+
+impl RpcTasks {
+    pub fn start(config: RpcConfig, events: Sender<AppEvent>) -> io::Result<RpcTasks> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("pdm-rpc")
+            .enable_all()
+            .build()?;
+        Ok(RpcTasks {
+            runtime: Some(runtime),
+            client: Arc::new(Client::new(config)),
+            events,
+        })
+    }
+
+    pub fn client(&self) -> Arc<Client> {
+        Arc::clone(&self.client)
+    }
+
+    pub fn events(&self) -> Sender<AppEvent> {
+        self.events.clone()
+    }
+
+    pub fn handle(&self) -> &Handle {
+        self.runtime
+            .as_ref()
+            .expect("runtime until dropped")
+            .handle()
+    }
+
+    /// Runs `task` with the client and delivers its reply.
+    pub fn spawn<F, Fut>(&self, task: F)
+    where
+        F: FnOnce(Arc<Client>) -> Fut,
+        Fut: Future<Output = RpcReply> + Send + 'static,
+    {
+        let events = self.events();
+        let future = task(self.client());
+        self.handle().spawn(async move {
+            let _ = events.send(AppEvent::Rpc(future.await));
+        });
+    }
+}
+
+impl Drop for RpcTasks {
+    fn drop(&mut self) {
+        // Calls in flight are abandoned rather than waited for.
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::{Auth, mock};
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn spawned_tasks_reply_through_events() {
+        let server = Runtime::new().unwrap();
+        let port = server.block_on(mock::node(HashMap::from([(
+            "getpeerinfo",
+            json!([{
+                "id": 3, "addr": "203.0.113.5:8333", "inbound": false,
+                "version": 70016, "bytessent": 10, "bytesrecv": 20,
+            }]),
+        )])));
+        let config = RpcConfig {
+            host: "127.0.0.1".into(),
+            port,
+            auth: Auth::UserPass {
+                user: "pool".into(),
+                password: "secret".into(),
+            },
+            timeout: Duration::from_secs(5),
+        };
+        let (sender, receiver) = mpsc::channel();
+        let tasks = RpcTasks::start(config, sender).unwrap();
+
+        tasks.spawn(|client| async move {
+            RpcReply::Peers(client.get_peer_info().await.map_err(|e| e.to_string()))
+        });
+        match receiver.recv_timeout(Duration::from_secs(5)).unwrap() {
+            AppEvent::Rpc(RpcReply::Peers(Ok(peers))) => assert_eq!(peers[0].id, 3),
+            event => panic!("unexpected {:?}", event),
+        }
+    }
+}
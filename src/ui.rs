@@ -2,11 +2,37 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
-use crate::app::{App, CurrentScreen};
+use crate::app::{App, ConfigView, CurrentScreen, Discard, Focus, P2poolView, SIDEBAR_ITEMS};
+use crate::bitcoin_conf::options::OPTIONS;
+use crate::bitcoin_conf::readiness::{self, Status};
+use crate::bitcoin_conf::validate::{self, Diagnostic, Severity};
+use crate::components::config_editor::ConfigEditor;
+use crate::components::error_dialog::ErrorDialog;
+use crate::components::log_view::{Level, LogLine, LogView};
+use crate::components::mempool_view::{FeeBucket, MempoolView, TxDetail};
+use crate::components::miner_list::{self, MinerList, MinerSort};
+use crate::components::peer_list::{self, PeerList, PeerSort};
+use crate::components::rpcauth_dialog::RpcAuthDialog;
+use crate::components::save_preview::SavePreview;
+use crate::components::setup_wizard::{Field, SetupWizard, Step};
+use crate::components::template_view::{self, TemplateView};
+use crate::diff::{self, DiffKind, DiffLine};
+use crate::node_status::{self, NodeStatus};
+use crate::p2pool_conf::fields::FIELDS;
+use crate::process::{self, RunState, Supervisor};
+use crate::stratum::StratumStatus;
+use crate::zmq::{LinkState, ZmqActivity};
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    widgets::{
+        Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Table, Wrap,
+    },
 };
+use std::cmp::Reverse;
+use std::time::Instant;
+
+/// Rows of the bitcoin.conf Problems pane before it scrolls.
+const PROBLEM_ROWS: usize = 4;
 
 pub fn ui(f: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
@@ -18,14 +44,25 @@ pub fn ui(f: &mut Frame, app: &mut App) {
         .split(f.area());
 
     //  Sidebar
-    let items = vec![ListItem::new("Home"), ListItem::new("Bitcoin Config")];
+    let items: Vec<ListItem> = SIDEBAR_ITEMS
+        .iter()
+        .map(|item| ListItem::new(*item))
+        .collect();
 
     // Highlight the active one
     let mut state = ListState::default();
     state.select(Some(app.sidebar_index));
 
+    let mut block = Block::default().borders(Borders::ALL).title(" PDM ");
+    // A new block is announced whatever screen is shown
+    if let Some(hash) = app.zmq.recent_block(Instant::now()) {
+        block = block.title_bottom(Span::styled(
+            format!(" New block …{} ", &hash[hash.len().saturating_sub(8)..]),
+            Style::default().fg(Color::Black).bg(Color::Yellow),
+        ));
+    }
     let sidebar = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title(" PDM "))
+        .block(block)
         .highlight_style(Style::default().bg(Color::Gray).fg(Color::Black));
 
     f.render_stateful_widget(sidebar, chunks[0], &mut state);
@@ -35,33 +72,183 @@ pub fn ui(f: &mut Frame, app: &mut App) {
 
     match app.current_screen {
         CurrentScreen::Home => {
-            let config_status = match &app.bitcoin_conf_path {
-                Some(p) => format!("Loaded: {:?}", p),
-                None => "No config loaded".to_string(),
+            render_home(f, app, main_area);
+        }
+        CurrentScreen::BitcoinConfig if app.conf_picker.is_some() => {
+            render_conf_picker(f, app, main_area);
+        }
+        CurrentScreen::BitcoinConfig
+            if app.bitcoin_conf.is_some() && app.config_view == ConfigView::Readiness =>
+        {
+            render_readiness_report(f, app, main_area);
+        }
+        CurrentScreen::BitcoinConfig
+            if app.bitcoin_conf.is_some() && app.config_view == ConfigView::Effective =>
+        {
+            render_effective_config(f, app, main_area);
+        }
+        CurrentScreen::BitcoinConfig
+            if app.bitcoin_conf.is_some() && app.config_view == ConfigView::History =>
+        {
+            render_history(f, app, main_area);
+        }
+        CurrentScreen::BitcoinConfig if app.bitcoin_conf.is_some() => {
+            render_config_editor(f, app, main_area);
+        }
+        CurrentScreen::BitcoinConfig => {
+            let text = match &app.bitcoin_conf_path {
+                Some(path) => format!(
+                    "Could not read {:?}: {}\n\nPress [Enter] to select a bitcoin.conf file",
+                    path,
+                    app.bitcoin_conf_error.as_deref().unwrap_or("unknown error")
+                ),
+                None => "Press [Enter] to select a bitcoin.conf file".to_string(),
             };
-
-            let text = format!(
-                "Welcome to PDM.\n\n{}\n\n(Navigate to 'Bitcoin Config' to load)",
-                config_status
+            let p = Paragraph::new(text).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" Bitcoin Config "),
             );
-            let p = Paragraph::new(text)
-                .block(Block::default().borders(Borders::ALL).title(" Home "))
-                .wrap(Wrap { trim: true });
             f.render_widget(p, main_area);
         }
-        CurrentScreen::BitcoinConfig => {
-            let p = Paragraph::new("Press [Enter] to select a bitcoin.conf file").block(
+        CurrentScreen::P2poolConfig
+            if app.p2pool_conf.is_some() && app.p2pool_view == P2poolView::CrossCheck =>
+        {
+            render_crosscheck(f, app, main_area);
+        }
+        CurrentScreen::P2poolConfig
+            if app.p2pool_conf.is_some() && app.p2pool_view == P2poolView::History =>
+        {
+            render_history(f, app, main_area);
+        }
+        CurrentScreen::P2poolConfig if app.p2pool_conf.is_some() => {
+            render_p2pool_editor(f, app, main_area);
+        }
+        CurrentScreen::P2poolConfig => {
+            let text = match &app.p2pool_conf_path {
+                Some(path) => format!(
+                    "Could not read {:?}: {}\n\nPress [Enter] to select a p2poolv2 config file",
+                    path,
+                    app.p2pool_conf_error.as_deref().unwrap_or("unknown error")
+                ),
+                None => "Press [Enter] to select a p2poolv2 config file".to_string(),
+            };
+            let p = Paragraph::new(text).wrap(Wrap { trim: false }).block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title(" Bitcoin Config "),
+                    .title(" p2pool Config "),
             );
             f.render_widget(p, main_area);
         }
         CurrentScreen::FileExplorer => {
             render_file_explorer(f, app, main_area);
         }
+        CurrentScreen::Wizard => {
+            if let Some(wizard) = &app.wizard {
+                render_setup_wizard(f, wizard, main_area);
+            }
+        }
+        CurrentScreen::Peers => {
+            render_peers(
+                f,
+                &mut app.peer_list,
+                app.focus == Focus::Content,
+                main_area,
+            );
+        }
+        CurrentScreen::Mempool => {
+            render_mempool(
+                f,
+                &mut app.mempool_view,
+                app.focus == Focus::Content,
+                main_area,
+            );
+        }
+        CurrentScreen::Template => {
+            render_template(
+                f,
+                &mut app.template_view,
+                app.focus == Focus::Content,
+                main_area,
+            );
+        }
+        CurrentScreen::Log => {
+            render_log(f, &mut app.log_view, app.focus == Focus::Content, main_area);
+        }
+        CurrentScreen::Bitcoind => {
+            let command = match &app.bitcoind.command_line {
+                Some(line) => line.clone(),
+                None => app
+                    .bitcoind_command()
+                    .map(|c| process::command_line(&c))
+                    .unwrap_or_else(|| "load a bitcoin.conf first".to_string()),
+            };
+            render_process(
+                f,
+                &app.bitcoind,
+                command,
+                " [s] start  [x] stop  [R] restart  [Esc] back ",
+                app.focus == Focus::Content,
+                main_area,
+            );
+        }
+        CurrentScreen::P2pool => {
+            let command = match &app.p2pool.command_line {
+                Some(line) => line.clone(),
+                None => app
+                    .p2pool_command()
+                    .map(|c| process::command_line(&c))
+                    .unwrap_or_else(|| "select a p2poolv2 config first".to_string()),
+            };
+            render_process(
+                f,
+                &app.p2pool,
+                command,
+                " [s] start [x] stop [R] restart [a] auto [Esc] back ",
+                app.focus == Focus::Content,
+                main_area,
+            );
+        }
+        CurrentScreen::Stratum => {
+            render_stratum(f, app, main_area);
+        }
+        CurrentScreen::Miners => {
+            render_miners(
+                f,
+                &mut app.miner_list,
+                &app.p2pool,
+                app.focus == Focus::Content,
+                main_area,
+            );
+        }
         _ => {}
     }
+
+    // Dialogs
+    if let Some(dialog) = &app.rpcauth_dialog {
+        render_rpcauth_dialog(f, dialog, f.area());
+    }
+    if let Some(preview) = &app.save_preview {
+        render_save_preview(f, preview, f.area());
+    }
+    if let Some(action) = app.discard_prompt {
+        render_discard_prompt(f, action, &app.unsaved(action), f.area());
+    }
+    if let Some(dialog) = &app.error_dialog {
+        render_error_dialog(f, dialog, f.area());
+    }
+}
+
+/// A rectangle of `width` x `height` cells centred in `area`, clamped to fit.
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
 }
 
 fn render_file_explorer(f: &mut Frame, app: &mut App, area: Rect) {
@@ -92,3 +279,1803 @@ fn render_file_explorer(f: &mut Frame, app: &mut App, area: Rect) {
 
     f.render_stateful_widget(list, area, &mut state);
 }
+
+fn render_conf_picker(f: &mut Frame, app: &mut App, area: Rect) {
+    let Some(picker) = app.conf_picker.as_mut() else {
+        return;
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(3)])
+        .split(area);
+
+    let mut items: Vec<ListItem> = picker
+        .candidates
+        .iter()
+        .map(|candidate| {
+            ListItem::new(vec![
+                Line::from(candidate.path.display().to_string()),
+                Line::from(Span::styled(
+                    format!("  {}", candidate.source),
+                    Style::default().fg(Color::DarkGray),
+                )),
+            ])
+        })
+        .collect();
+    items.push(ListItem::new("Browse for another file..."));
+
+    picker.list_state.select(Some(picker.selected));
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Found bitcoin.conf files "),
+        )
+        .highlight_style(Style::default().bg(Color::Blue).fg(Color::White))
+        .highlight_symbol(">> ");
+    f.render_stateful_widget(list, chunks[0], &mut picker.list_state);
+
+    let footer =
+        Paragraph::new("[Enter] open  [Esc] back").block(Block::default().borders(Borders::ALL));
+    f.render_widget(footer, chunks[1]);
+}
+
+fn render_config_editor(f: &mut Frame, app: &mut App, area: Rect) {
+    let Some(conf) = &app.bitcoin_conf else {
+        return;
+    };
+    let editor = &mut app.config_editor;
+    let focused = app.focus == Focus::Content;
+    let mut diagnostics = validate::validate(conf);
+    // Errors block saving, so they come first in the Problems pane
+    diagnostics.sort_by_key(|d| (Reverse(d.severity), d.line));
+
+    let problems_height = if diagnostics.is_empty() {
+        0
+    } else {
+        diagnostics.len().min(PROBLEM_ROWS) as u16 + 2
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(problems_height),
+            Constraint::Length(5),
+        ])
+        .split(area);
+
+    let mut items = Vec::new();
+    let mut selected_row = 0;
+    let mut category = None;
+    for (i, spec) in OPTIONS.iter().enumerate() {
+        if category != Some(spec.category) {
+            category = Some(spec.category);
+            items.push(ListItem::new(Line::from(Span::styled(
+                spec.category.to_string(),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ))));
+        }
+
+        let value = if i == editor.selected
+            && let Some(input) = &editor.input
+        {
+            Span::styled(format!("{}_", input), Style::default().fg(Color::Cyan))
+        } else {
+            match ConfigEditor::display_value(conf, spec) {
+                Some(_) if spec.key == "rpcpassword" => Span::raw("********"),
+                Some(value) => Span::raw(value),
+                None => Span::styled("(unset)", Style::default().fg(Color::DarkGray)),
+            }
+        };
+
+        let mut spans = vec![Span::raw(format!("  {:<18} ", spec.key)), value];
+        if let Some(worst) = diagnostics
+            .iter()
+            .filter(|d| d.key.as_deref() == Some(spec.key))
+            .map(|d| d.severity)
+            .max()
+        {
+            spans.push(Span::raw(" "));
+            spans.push(severity_marker(worst));
+        }
+
+        if i == editor.selected {
+            selected_row = items.len();
+        }
+        items.push(ListItem::new(Line::from(spans)));
+    }
+
+    let file_name = app
+        .bitcoin_conf_path
+        .as_ref()
+        .and_then(|p| p.file_name())
+        .unwrap_or_default()
+        .to_string_lossy();
+    let title = format!(
+        " Bitcoin Config: {} ({}){} ",
+        file_name,
+        conf.chain(),
+        if editor.modified { " [modified]" } else { "" }
+    );
+
+    editor
+        .list_state
+        .select(if focused { Some(selected_row) } else { None });
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().bg(Color::Blue).fg(Color::White));
+    f.render_stateful_widget(list, chunks[0], &mut editor.list_state);
+
+    if !diagnostics.is_empty() {
+        let errors = validate::error_count(&diagnostics);
+        let title = format!(
+            " Problems: {} error(s), {} warning(s) ",
+            errors,
+            diagnostics.len() - errors
+        );
+        let mut block = Block::default().borders(Borders::ALL).title(title);
+        editor.problem_scroll = editor
+            .problem_scroll
+            .min(diagnostics.len().saturating_sub(PROBLEM_ROWS));
+        if diagnostics.len() > PROBLEM_ROWS {
+            let first = editor.problem_scroll + 1;
+            block = block.title_bottom(format!(
+                " {}-{} of {}  [PgUp/PgDn] scroll ",
+                first,
+                first + PROBLEM_ROWS - 1,
+                diagnostics.len()
+            ));
+        }
+        let lines: Vec<Line> = diagnostics.iter().map(diagnostic_line).collect();
+        let problems = Paragraph::new(lines)
+            .block(block)
+            .scroll((editor.problem_scroll as u16, 0));
+        f.render_widget(problems, chunks[1]);
+    }
+
+    let selected_problem = diagnostics
+        .iter()
+        .find(|d| d.key.as_deref() == Some(editor.selected_spec().key));
+    let status = match (&editor.message, selected_problem) {
+        (Some(message), _) => Line::from(Span::styled(
+            message.clone(),
+            Style::default().fg(Color::Yellow),
+        )),
+        (None, Some(diagnostic)) => diagnostic_line(diagnostic),
+        (None, None) => Line::from(editor.selected_spec().description),
+    };
+    let hints = if editor.is_editing() {
+        "[Enter] apply  [Esc] cancel  (empty = unset)"
+    } else if focused {
+        "[Enter] edit  [←/→] choose  [Del] unset  [s] save  [o] open  [p] p2pool check  [e] effective  [h] history  [a] rpcauth  [PgUp/PgDn] problems  [Esc] back"
+    } else {
+        "Press [Enter] to edit"
+    };
+    let footer = Paragraph::new(vec![status, Line::from(hints)])
+        .block(Block::default().borders(Borders::ALL))
+        .wrap(Wrap { trim: true });
+    f.render_widget(footer, chunks[2]);
+}
+
+fn render_p2pool_editor(f: &mut Frame, app: &mut App, area: Rect) {
+    let Some(conf) = &app.p2pool_conf else {
+        return;
+    };
+    let editor = &mut app.p2pool_editor;
+    let focused = app.focus == Focus::Content;
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(5)])
+        .split(area);
+
+    let mut items = Vec::new();
+    let mut selected_row = 0;
+    let mut table = None;
+    for (i, spec) in FIELDS.iter().enumerate() {
+        if table != Some(spec.table) {
+            table = Some(spec.table);
+            items.push(ListItem::new(Line::from(Span::styled(
+                format!("[{}]", spec.table),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ))));
+        }
+
+        let value = if i == editor.selected
+            && let Some(input) = &editor.input
+        {
+            Span::styled(format!("{}_", input), Style::default().fg(Color::Cyan))
+        } else {
+            match conf.get(spec) {
+                Some(_) if spec.key == "password" => Span::raw("********"),
+                Some(value) => Span::raw(value),
+                None => Span::styled("(unset)", Style::default().fg(Color::DarkGray)),
+            }
+        };
+
+        if i == editor.selected {
+            selected_row = items.len();
+        }
+        items.push(ListItem::new(Line::from(vec![
+            Span::raw(format!("  {:<18} ", spec.key)),
+            value,
+        ])));
+    }
+
+    let file_name = app
+        .p2pool_conf_path
+        .as_ref()
+        .and_then(|p| p.file_name())
+        .unwrap_or_default()
+        .to_string_lossy();
+    // Mismatches with bitcoin.conf, listed under [c]
+    let issues = app.p2pool_crosscheck().map_or(0, |checks| {
+        checks.iter().filter(|c| c.status != Status::Ok).count()
+    });
+    let editor = &mut app.p2pool_editor;
+    let title = format!(
+        " p2pool Config: {}{}{} ",
+        file_name,
+        if editor.modified { " [modified]" } else { "" },
+        if issues > 0 {
+            format!(" [{} issue(s)]", issues)
+        } else {
+            String::new()
+        }
+    );
+
+    editor
+        .list_state
+        .select(if focused { Some(selected_row) } else { None });
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().bg(Color::Blue).fg(Color::White));
+    f.render_stateful_widget(list, chunks[0], &mut editor.list_state);
+
+    let status = match &editor.message {
+        Some(message) => Line::from(Span::styled(
+            message.clone(),
+            Style::default().fg(Color::Yellow),
+        )),
+        None => Line::from(editor.selected_spec().description),
+    };
+    let hints = if editor.is_editing() {
+        "[Enter] apply  [Esc] cancel  (empty = unset)"
+    } else if focused {
+        "[Enter] edit  [←/→] choose  [Del] unset  [c] check  [s] save  [o] open  [h] history  [Esc] back"
+    } else {
+        "Press [Enter] to edit"
+    };
+    let footer = Paragraph::new(vec![status, Line::from(hints)])
+        .block(Block::default().borders(Borders::ALL))
+        .wrap(Wrap { trim: true });
+    f.render_widget(footer, chunks[1]);
+}
+
+/// A row of a checklist: a status marker, the check and what was found,
+/// with the fix on a second line.
+fn check_item(status: Status, name: &str, detail: &str, fix: Option<&str>) -> ListItem<'static> {
+    let marker = match status {
+        Status::Ok => Span::styled("✔", Style::default().fg(Color::Green)),
+        Status::Missing => Span::styled("✖", Style::default().fg(Color::Red)),
+        Status::Wrong => Span::styled("✖", Style::default().fg(Color::Yellow)),
+    };
+    let mut lines = vec![Line::from(vec![
+        marker,
+        Span::raw(format!(" {:<16} ", name)),
+        Span::raw(detail.to_string()),
+    ])];
+    if let Some(fix) = fix {
+        lines.push(Line::from(Span::styled(
+            format!("    fix: {}", fix),
+            Style::default().fg(Color::Cyan),
+        )));
+    }
+    ListItem::new(lines)
+}
+
+fn render_readiness_report(f: &mut Frame, app: &mut App, area: Rect) {
+    let Some(conf) = &app.bitcoin_conf else {
+        return;
+    };
+    let checks = readiness::check(conf, app.p2pool_network());
+    let report = &mut app.readiness_report;
+    let focused = app.focus == Focus::Content;
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(4)])
+        .split(area);
+
+    let items: Vec<ListItem> = checks
+        .iter()
+        .map(|check| {
+            check_item(
+                check.status,
+                check.name,
+                &check.detail,
+                check.fix.as_ref().map(|f| f.label.as_str()),
+            )
+        })
+        .collect();
+
+    let issues = checks.iter().filter(|c| c.status != Status::Ok).count();
+    let title = if issues == 0 {
+        " p2pool compatibility: ready ".to_string()
+    } else {
+        format!(" p2pool compatibility: {} issue(s) ", issues)
+    };
+
+    report
+        .list_state
+        .select(focused.then_some(report.selected.min(checks.len() - 1)));
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().bg(Color::Blue).fg(Color::White));
+    f.render_stateful_widget(list, chunks[0], &mut report.list_state);
+
+    let status = match &app.config_editor.message {
+        Some(message) => Line::from(Span::styled(
+            message.clone(),
+            Style::default().fg(Color::Yellow),
+        )),
+        None => Line::from("Fixes change the loaded config; save from the editor"),
+    };
+    let footer = Paragraph::new(vec![
+        status,
+        Line::from("[Enter] apply fix  [p/Esc] back to options"),
+    ])
+    .block(Block::default().borders(Borders::ALL));
+    f.render_widget(footer, chunks[1]);
+}
+
+fn render_crosscheck(f: &mut Frame, app: &mut App, area: Rect) {
+    let Some(checks) = app.p2pool_crosscheck() else {
+        return;
+    };
+    let report = &mut app.crosscheck_report;
+    let focused = app.focus == Focus::Content;
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(4)])
+        .split(area);
+
+    let items: Vec<ListItem> = checks
+        .iter()
+        .map(|check| {
+            check_item(
+                check.status,
+                check.name,
+                &check.detail,
+                check.fix.as_ref().map(|f| f.label.as_str()),
+            )
+        })
+        .collect();
+
+    let issues = checks.iter().filter(|c| c.status != Status::Ok).count();
+    let title = if issues == 0 {
+        " bitcoin.conf cross-check: consistent ".to_string()
+    } else {
+        format!(" bitcoin.conf cross-check: {} issue(s) ", issues)
+    };
+
+    report
+        .list_state
+        .select(focused.then_some(report.selected.min(checks.len() - 1)));
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().bg(Color::Blue).fg(Color::White));
+    f.render_stateful_widget(list, chunks[0], &mut report.list_state);
+
+    let status = match &app.p2pool_editor.message {
+        Some(message) => Line::from(Span::styled(
+            message.clone(),
+            Style::default().fg(Color::Yellow),
+        )),
+        None => Line::from("Fixes edit the p2poolv2 config; save from the editor"),
+    };
+    let footer = Paragraph::new(vec![
+        status,
+        Line::from("[Enter] apply fix  [c/Esc] back to settings"),
+    ])
+    .block(Block::default().borders(Borders::ALL));
+    f.render_widget(footer, chunks[1]);
+}
+
+fn render_effective_config(f: &mut Frame, app: &mut App, area: Rect) {
+    let (Some(path), Some(conf)) = (&app.bitcoin_conf_path, &app.bitcoin_conf) else {
+        return;
+    };
+    let includes = &app.bitcoin_includes;
+    let settings = includes.settings(path, conf);
+    let chain = includes.merged(path, conf).chain();
+    let view = &mut app.effective_view;
+    let focused = app.focus == Focus::Content;
+
+    let problems_height = if includes.problems.is_empty() {
+        0
+    } else {
+        includes.problems.len().min(4) as u16 + 2
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(problems_height),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    let dim = Style::default().fg(Color::DarkGray);
+    let shown = |key: &str, value: &str| {
+        if key == "rpcpassword" {
+            "********".to_string()
+        } else {
+            value.to_string()
+        }
+    };
+    let items: Vec<ListItem> = settings
+        .iter()
+        .map(|setting| {
+            let key = match &setting.entry.section {
+                Some(section) => format!("{}.{}", section, setting.key),
+                None => setting.key.clone(),
+            };
+            let mut lines = vec![Line::from(vec![
+                Span::raw(format!(
+                    "{} = {}",
+                    key,
+                    shown(&setting.key, &setting.entry.value)
+                )),
+                Span::styled(format!("  ({})", setting.entry.origin), dim),
+            ])];
+            for entry in &setting.ignored {
+                lines.push(Line::from(Span::styled(
+                    format!(
+                        "    ignored {} ({})",
+                        shown(&setting.key, &entry.value),
+                        entry.origin
+                    ),
+                    dim,
+                )));
+            }
+            ListItem::new(lines)
+        })
+        .collect();
+
+    let title = format!(
+        " Effective config ({}): {} file(s) ",
+        chain,
+        includes.files.len() + 1
+    );
+    view.list_state.select(
+        (focused && !settings.is_empty())
+            .then_some(view.selected.min(settings.len().saturating_sub(1))),
+    );
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().bg(Color::Blue).fg(Color::White));
+    f.render_stateful_widget(list, chunks[0], &mut view.list_state);
+
+    if !includes.problems.is_empty() {
+        let lines: Vec<Line> = includes
+            .problems
+            .iter()
+            .map(|problem| {
+                Line::from(vec![
+                    severity_marker(Severity::Error),
+                    Span::raw(format!(" {}", problem)),
+                ])
+            })
+            .collect();
+        let problems = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Include problems "),
+        );
+        f.render_widget(problems, chunks[1]);
+    }
+
+    let footer = Paragraph::new("[↑/↓] move  [e/Esc] back to options")
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(footer, chunks[2]);
+}
+
+fn render_history(f: &mut Frame, app: &mut App, area: Rect) {
+    let view = &mut app.history_view;
+    let focused = app.focus == Focus::Content;
+
+    let list_height = view.backups.len().clamp(1, 5) as u16 + 2;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(list_height),
+            Constraint::Min(0),
+            Constraint::Length(4),
+        ])
+        .split(area);
+
+    let items: Vec<ListItem> = if view.backups.is_empty() {
+        vec![ListItem::new(Span::styled(
+            "No backups yet; one is taken on every save",
+            Style::default().fg(Color::DarkGray),
+        ))]
+    } else {
+        view.backups
+            .iter()
+            .map(|backup| ListItem::new(backup.label()))
+            .collect()
+    };
+    let title = format!(
+        " History: {} backup(s), keeping {} ",
+        view.backups.len(),
+        app.backup_history
+    );
+    view.list_state
+        .select((focused && !view.backups.is_empty()).then_some(view.selected));
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().bg(Color::Blue).fg(Color::White));
+    f.render_stateful_widget(list, chunks[0], &mut view.list_state);
+
+    if let Some(backup) = view.selected_backup() {
+        let error = |text: String| {
+            vec![Line::from(Span::styled(
+                text,
+                Style::default().fg(Color::Red),
+            ))]
+        };
+        let lines = match (std::fs::read_to_string(&backup.path), &view.current) {
+            (Err(err), _) => error(format!("Could not read backup: {}", err)),
+            (_, Some(Err(err))) => error(format!("Could not read the current file: {}", err)),
+            (Ok(old), Some(Ok(current))) => {
+                let diff = diff::unified(
+                    &old,
+                    current,
+                    &format!("backup {}", backup.label()),
+                    "current",
+                );
+                if diff.is_empty() {
+                    vec![Line::from("Identical to the file on disk")]
+                } else {
+                    diff_lines(&diff)
+                }
+            }
+            (Ok(_), None) => Vec::new(),
+        };
+        let visible = chunks[1].height.saturating_sub(2) as usize;
+        view.scroll = view.scroll.min(lines.len().saturating_sub(visible));
+        let p = Paragraph::new(lines).scroll((view.scroll as u16, 0)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Changes on disk since this backup "),
+        );
+        f.render_widget(p, chunks[1]);
+    }
+
+    let status = match &view.message {
+        Some(message) => Line::from(Span::styled(
+            message.clone(),
+            Style::default().fg(Color::Yellow),
+        )),
+        None => Line::from("Restoring backs up the current file first"),
+    };
+    let footer = Paragraph::new(vec![
+        status,
+        Line::from("[r] restore  [PgUp/PgDn] diff  [+/-] kept  [Esc] back"),
+    ])
+    .block(Block::default().borders(Borders::ALL));
+    f.render_widget(footer, chunks[2]);
+}
+
+/// Colours the lines of a unified diff.
+fn diff_lines(diff: &[DiffLine]) -> Vec<Line<'static>> {
+    diff.iter()
+        .map(|line| {
+            let style = match line.kind {
+                DiffKind::Header => Style::default().add_modifier(Modifier::BOLD),
+                DiffKind::Hunk => Style::default().fg(Color::Cyan),
+                DiffKind::Context => Style::default(),
+                DiffKind::Added => Style::default().fg(Color::Green),
+                DiffKind::Removed => Style::default().fg(Color::Red),
+            };
+            Line::from(Span::styled(line.text.clone(), style))
+        })
+        .collect()
+}
+
+fn severity_marker(severity: Severity) -> Span<'static> {
+    match severity {
+        Severity::Error => Span::styled("✖", Style::default().fg(Color::Red)),
+        Severity::Warning => Span::styled("⚠", Style::default().fg(Color::Yellow)),
+    }
+}
+
+fn diagnostic_line(diagnostic: &Diagnostic) -> Line<'static> {
+    Line::from(vec![
+        severity_marker(diagnostic.severity),
+        Span::raw(format!(" {}", diagnostic)),
+    ])
+}
+
+fn render_rpcauth_dialog(f: &mut Frame, dialog: &RpcAuthDialog, area: Rect) {
+    let area = centered_rect(70, 12, area);
+    let highlight = Style::default().fg(Color::Cyan);
+
+    let lines = match &dialog.generated {
+        None => {
+            let mut lines = vec![
+                Line::from("Generate hashed credentials for bitcoind's RPC server."),
+                Line::from(""),
+                Line::from(vec![
+                    Span::raw("User: "),
+                    Span::styled(format!("{}_", dialog.user), highlight),
+                ]),
+                Line::from(""),
+            ];
+            if let Some(error) = &dialog.error {
+                lines.push(Line::from(Span::styled(
+                    error.clone(),
+                    Style::default().fg(Color::Red),
+                )));
+            }
+            lines.push(Line::from("[Enter] generate  [Esc] cancel"));
+            lines
+        }
+        Some(credentials) => vec![
+            Line::from("Added to the loaded bitcoin.conf:"),
+            Line::from(Span::styled(
+                format!("rpcauth={}", credentials.rpcauth()),
+                highlight,
+            )),
+            Line::from(""),
+            Line::from("Password for the p2poolv2 config (shown only once):"),
+            Line::from(Span::styled(
+                credentials.password.clone(),
+                highlight.add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from("Save the config to apply it.  [Enter] close"),
+        ],
+    };
+
+    let p = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Generate rpcauth "),
+        )
+        .wrap(Wrap { trim: false });
+    f.render_widget(Clear, area);
+    f.render_widget(p, area);
+}
+
+fn render_setup_wizard(f: &mut Frame, wizard: &SetupWizard, area: Rect) {
+    let step = wizard.current_step();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(4)])
+        .split(area);
+
+    let highlight = Style::default().fg(Color::Cyan);
+    let mut lines = vec![Line::from(step.help()), Line::from("")];
+
+    if step == Step::Review {
+        let heading = |text: String| {
+            Line::from(Span::styled(
+                text,
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ))
+        };
+        let warn_if_exists = |lines: &mut Vec<Line>, path: &std::path::Path| {
+            if path.exists() {
+                lines.push(Line::from(Span::styled(
+                    "  already exists and will not be overwritten",
+                    Style::default().fg(Color::Red),
+                )));
+            }
+        };
+
+        let bitcoin_path = wizard.bitcoin_conf_path();
+        lines.push(heading(format!("{}", bitcoin_path.display())));
+        warn_if_exists(&mut lines, &bitcoin_path);
+        let conf = wizard.bitcoin_conf().to_string();
+        lines.extend(conf.lines().map(|l| Line::from(format!("  {}", l))));
+        lines.push(Line::from(""));
+
+        let p2pool_path = wizard.p2pool_conf_path();
+        lines.push(heading(format!("{}", p2pool_path.display())));
+        warn_if_exists(&mut lines, &p2pool_path);
+        lines.push(Line::from(format!(
+            "  stratum port {}, payout to {}",
+            wizard.stratum_port, wizard.miner_address
+        )));
+        lines.push(Line::from(format!(
+            "  RPC as {} on port {}, password generated",
+            wizard.rpc_user,
+            wizard.network.default_rpc_port()
+        )));
+    } else {
+        for (i, field) in step.fields().iter().enumerate() {
+            let selected = i == wizard.field;
+            let value = match field {
+                Field::Network => format!("◀ {} ▶", wizard.network),
+                _ if selected => format!("{}_", wizard.value(*field)),
+                _ => wizard.value(*field),
+            };
+            lines.push(Line::from(format!(
+                "{} {}",
+                if selected { ">" } else { " " },
+                field.label()
+            )));
+            lines.push(Line::from(Span::styled(
+                format!("  {}", value),
+                if selected {
+                    highlight
+                } else {
+                    Style::default()
+                },
+            )));
+            lines.push(Line::from(""));
+        }
+    }
+
+    let title = format!(
+        " Setup {}/{}: {} ",
+        wizard.step + 1,
+        Step::ALL.len(),
+        step.title()
+    );
+    let body = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .wrap(Wrap { trim: false });
+    f.render_widget(body, chunks[0]);
+
+    let status = match &wizard.error {
+        Some(error) => Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red))),
+        None => Line::from("Nothing is written until the review is confirmed"),
+    };
+    let hints = match step {
+        Step::Network => "[←/→] choose  [Enter] next  [Esc] skip setup",
+        Step::Review => "[Enter] write files  [Esc] back",
+        _ => "[↑/↓] field  [Enter] next  [Esc] back",
+    };
+    let footer = Paragraph::new(vec![status, Line::from(hints)])
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(footer, chunks[1]);
+}
+
+fn render_save_preview(f: &mut Frame, preview: &SavePreview, area: Rect) {
+    let area = centered_rect(76, 21, area);
+    let (added, removed) = preview.stats();
+    let name = preview
+        .path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let title = format!(" Save {}? +{} -{} ", name, added, removed);
+
+    let p = Paragraph::new(diff_lines(&preview.diff))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_bottom(" [Enter] save  [Esc] cancel  [↑/↓] scroll "),
+        )
+        .scroll((preview.scroll as u16, 0));
+    f.render_widget(Clear, area);
+    f.render_widget(p, area);
+}
+
+fn render_discard_prompt(f: &mut Frame, action: Discard, unsaved: &[&str], area: Rect) {
+    let area = centered_rect(60, 6, area);
+    let question = match action {
+        Discard::Quit => "Quit anyway?",
+        Discard::Open(_) => "Open another file anyway?",
+        Discard::Restore(_) => "Restore anyway?",
+    };
+    let p = Paragraph::new(format!(
+        "{} has unsaved changes. {}",
+        unsaved.join(" and "),
+        question
+    ))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(" Unsaved changes ")
+            .title_bottom(" [y] discard changes  [n/Esc] cancel "),
+    )
+    .wrap(Wrap { trim: true });
+    f.render_widget(Clear, area);
+    f.render_widget(p, area);
+}
+
+fn render_error_dialog(f: &mut Frame, dialog: &ErrorDialog, area: Rect) {
+    let area = centered_rect(60, 8, area);
+    let p = Paragraph::new(dialog.message.clone())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Red))
+                .title(format!(" {} ", dialog.title))
+                .title_bottom(" [Enter] close "),
+        )
+        .wrap(Wrap { trim: true });
+    f.render_widget(Clear, area);
+    f.render_widget(p, area);
+}
+
+fn render_home(f: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title(" Home ");
+    let Some(path) = &app.bitcoin_conf_path else {
+        let p = Paragraph::new(
+            "Welcome to PDM.\n\nNo config loaded\n\n(Navigate to 'Bitcoin Config' to load)",
+        )
+        .block(block)
+        .wrap(Wrap { trim: true });
+        f.render_widget(p, area);
+        return;
+    };
+
+    let mut lines = vec![Line::from(format!("Config: {}", path.display()))];
+    if let Some(poller) = &app.status_poller {
+        lines.push(Line::from(format!("Node:   {}", poller.address)));
+    }
+    lines.push(Line::from(""));
+
+    match (&app.node_status, &app.node_error) {
+        (Some(status), error) => {
+            lines.extend(node_status_lines(status));
+            if let Some(err) = error {
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    format!("Last refresh failed: {}", err),
+                    Style::default().fg(Color::Yellow),
+                )));
+            }
+        }
+        (None, Some(err)) => lines.push(Line::from(Span::styled(
+            format!("Cannot reach bitcoind: {}", err),
+            Style::default().fg(Color::Red),
+        ))),
+        (None, None) if app.status_poller.is_some() => {
+            lines.push(Line::from("Connecting to bitcoind..."))
+        }
+        (None, None) => lines.push(Line::from("Node status unavailable")),
+    }
+    if !app.zmq.links.is_empty() {
+        lines.push(Line::from(""));
+        lines.extend(zmq_lines(&app.zmq));
+    }
+
+    let p = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+    f.render_widget(p, area);
+}
+
+fn node_status_lines(status: &NodeStatus) -> Vec<Line<'static>> {
+    let row = |label: &str, value: String, style: Style| {
+        Line::from(vec![
+            Span::styled(format!("{:<14}", label), Style::default().fg(Color::Gray)),
+            Span::styled(value, style),
+        ])
+    };
+    let plain = Style::default();
+    let behind = status.headers.saturating_sub(status.blocks);
+    let height_style = if behind > 0 {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default().fg(Color::Green)
+    };
+    let (ibd, ibd_style) = if status.initial_block_download {
+        ("yes", Style::default().fg(Color::Yellow))
+    } else {
+        ("no", Style::default().fg(Color::Green))
+    };
+    let peers_style = if status.connections == 0 {
+        Style::default().fg(Color::Red)
+    } else {
+        plain
+    };
+
+    vec![
+        row("Chain", status.chain.clone(), plain),
+        row(
+            "Height",
+            format!("{} / {} headers", status.blocks, status.headers),
+            height_style,
+        ),
+        row(
+            "Verification",
+            // Rounded down: 100% only once verification is complete
+            format!(
+                "{:.2}%",
+                (status.verification_progress * 10_000.0).floor() / 100.0
+            ),
+            plain,
+        ),
+        row("IBD", ibd.to_string(), ibd_style),
+        row(
+            "Peers",
+            format!(
+                "{} ({} out, {} in)",
+                status.connections, status.connections_out, status.connections_in
+            ),
+            peers_style,
+        ),
+        row(
+            "Mempool",
+            format!(
+                "{} txs, {}",
+                status.mempool_txs,
+                node_status::format_bytes(status.mempool_bytes)
+            ),
+            plain,
+        ),
+        row("Uptime", node_status::format_uptime(status.uptime), plain),
+        row("Version", status.subversion.clone(), plain),
+    ]
+}
+
+fn zmq_lines(zmq: &ZmqActivity) -> Vec<Line<'static>> {
+    let label =
+        |text: &str| Span::styled(format!("{:<14}", text), Style::default().fg(Color::Gray));
+    let down = zmq.links.iter().find_map(|(address, state)| match state {
+        LinkState::Down(err) => Some(format!("{} down: {}", address, err)),
+        _ => None,
+    });
+    let status = if let Some(down) = down {
+        Span::styled(down, Style::default().fg(Color::Yellow))
+    } else if zmq.links.values().all(|s| *s == LinkState::Connected) {
+        Span::styled(
+            format!(
+                "listening (blocks: {}, txs: {})",
+                zmq.blocks, zmq.transactions
+            ),
+            Style::default().fg(Color::Green),
+        )
+    } else {
+        Span::raw("connecting...")
+    };
+
+    let mut lines = vec![Line::from(vec![label("ZMQ"), status])];
+    if let Some(hash) = &zmq.last_block {
+        // Block hashes start with zeros; the tail tells them apart
+        let short = format!("{}…{}", &hash[..8], &hash[hash.len().saturating_sub(12)..]);
+        lines.push(Line::from(vec![label("New block"), Span::raw(short)]));
+    }
+    lines
+}
+
+fn render_peers(f: &mut Frame, peers: &mut PeerList, focused: bool, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(6)])
+        .split(area);
+
+    let header = Row::new(PeerSort::ALL.iter().map(|sort| {
+        let mut label = sort.label().to_string();
+        if *sort == peers.sort {
+            label.push(if peers.descending { '▼' } else { '▲' });
+        }
+        Cell::from(label)
+    }))
+    .style(Style::default().add_modifier(Modifier::BOLD));
+
+    let rows = peers.peers.iter().map(|peer| {
+        let ping = match peer.pingtime {
+            Some(secs) => format!("{}ms", (secs * 1000.0).round()),
+            None => "-".to_string(),
+        };
+        Row::new(vec![
+            Cell::from(peer.addr.clone()),
+            Cell::from(peer_list::connection_label(peer).to_string()),
+            Cell::from(ping),
+            Cell::from(peer.synced_blocks.to_string()),
+            Cell::from(node_status::format_bytes(peer.bytessent).replace(' ', "")),
+            Cell::from(node_status::format_bytes(peer.bytesrecv).replace(' ', "")),
+            Cell::from(peer.subver.trim_matches('/').to_string()),
+        ])
+    });
+
+    let title = format!(" Peers ({}) ", peers.peers.len());
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(16),
+            Constraint::Length(6),
+            Constraint::Length(5),
+            Constraint::Length(6),
+            Constraint::Length(7),
+            Constraint::Length(7),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(title))
+    .row_highlight_style(if focused {
+        Style::default().bg(Color::Gray).fg(Color::Black)
+    } else {
+        Style::default()
+    });
+
+    peers.table_state.select(if peers.peers.is_empty() {
+        None
+    } else {
+        Some(peers.selected)
+    });
+    f.render_stateful_widget(table, chunks[0], &mut peers.table_state);
+
+    // Selected peer, prompt and status
+    let mut lines = Vec::new();
+    if let Some(peer) = peers.selected_peer() {
+        lines.push(Line::from(format!(
+            "#{} {} {} v{}",
+            peer.id,
+            peer.addr,
+            peer.subver.trim_matches('/'),
+            peer.version
+        )));
+        lines.push(Line::from(format!(
+            "{}, synced {}/{}",
+            peer.connection_type, peer.synced_headers, peer.synced_blocks
+        )));
+    }
+    if let Some(input) = &peers.add_input {
+        lines.push(Line::from(Span::styled(
+            format!("addnode: {}_", input),
+            Style::default().fg(Color::Yellow),
+        )));
+    } else if let Some(action) = &peers.pending {
+        lines.push(Line::from(Span::styled(
+            action.prompt(),
+            Style::default().fg(Color::Yellow),
+        )));
+    } else if let Some(err) = &peers.error {
+        lines.push(Line::from(Span::styled(
+            format!("Refresh failed: {}", err),
+            Style::default().fg(Color::Red),
+        )));
+    } else if let Some(message) = &peers.message {
+        lines.push(Line::from(message.clone()));
+    }
+
+    let hints = if focused {
+        " [s/S] sort [d] drop [b] ban [a] add [r] refresh "
+    } else {
+        " [Enter] select peers "
+    };
+    let p = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title_bottom(hints))
+        .wrap(Wrap { trim: true });
+    f.render_widget(p, chunks[1]);
+}
+
+fn render_miners(
+    f: &mut Frame,
+    miners: &mut MinerList,
+    p2pool: &Supervisor,
+    focused: bool,
+    area: Rect,
+) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(6)])
+        .split(area);
+    let now = Instant::now();
+
+    let header = Row::new(MinerSort::ALL.iter().map(|sort| {
+        let mut label = sort.label().to_string();
+        if *sort == miners.sort {
+            label.push(if miners.descending { '▼' } else { '▲' });
+        }
+        Cell::from(label)
+    }))
+    .style(Style::default().add_modifier(Modifier::BOLD));
+
+    let visible = miners.visible(now);
+    let rows = visible.iter().map(|miner| {
+        let row = Row::new(vec![
+            Cell::from(miner.short_name().to_string()),
+            Cell::from(
+                miner
+                    .difficulty
+                    .map_or("-".to_string(), miner_list::format_difficulty),
+            ),
+            Cell::from(miner.accepted.to_string()),
+            Cell::from(miner.rejected.to_string()),
+            Cell::from(miner.stale.to_string()),
+            Cell::from(
+                miner
+                    .hashrate(now)
+                    .map_or("-".to_string(), miner_list::format_hashrate),
+            ),
+            Cell::from(
+                miner
+                    .last_share
+                    .map_or("-".to_string(), |at| miner_list::format_age(at, now)),
+            ),
+        ]);
+        if miner.connected {
+            row
+        } else {
+            row.style(Style::default().fg(Color::DarkGray))
+        }
+    });
+
+    let connected = miners.miners.iter().filter(|m| m.connected).count();
+    let title = match &miners.filter {
+        Some(filter) => format!(
+            " Miners matching \"{}\" ({} of {}) ",
+            filter,
+            visible.len(),
+            miners.miners.len()
+        ),
+        None => format!(" Miners ({} connected) ", connected),
+    };
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(10),
+            Constraint::Length(6),
+            Constraint::Length(6),
+            Constraint::Length(4),
+            Constraint::Length(5),
+            Constraint::Length(10),
+            Constraint::Length(5),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(title))
+    .row_highlight_style(if focused {
+        Style::default().bg(Color::Gray).fg(Color::Black)
+    } else {
+        Style::default()
+    });
+
+    let empty = visible.is_empty();
+    let selected = miners.selected_miner(now).cloned();
+    miners
+        .table_state
+        .select(if empty { None } else { Some(miners.selected) });
+    f.render_stateful_widget(table, chunks[0], &mut miners.table_state);
+
+    // Selected worker, totals and prompt
+    let mut lines = Vec::new();
+    if let Some(miner) = &selected {
+        lines.push(Line::from(format!(
+            "{} {}",
+            miner.worker,
+            miner.ip.as_deref().unwrap_or("")
+        )));
+    } else if miners.miners.is_empty() && p2pool.is_running() {
+        lines.push(Line::from("No worker activity in the p2poolv2 output yet"));
+    }
+    // A p2poolv2 started elsewhere is invisible here, however busy it is
+    if !p2pool.is_running() {
+        lines.push(Line::from(Span::styled(
+            "Only a p2poolv2 started from pdm fills this table",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    let hashrate: f64 = miners.miners.iter().filter_map(|m| m.hashrate(now)).sum();
+    let (accepted, rejected, stale) = miners.miners.iter().fold((0, 0, 0), |(a, r, s), m| {
+        (a + m.accepted, r + m.rejected, s + m.stale)
+    });
+    lines.push(Line::from(format!(
+        "Total {}: {} accepted, {} rejected, {} stale",
+        miner_list::format_hashrate(hashrate),
+        accepted,
+        rejected,
+        stale
+    )));
+    if let Some(input) = &miners.filter_input {
+        lines.push(Line::from(Span::styled(
+            format!("Filter by worker or IP: {}_", input),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+
+    let hints = if focused {
+        " [s/S] sort [/] filter [PgUp/PgDn] page [Esc] back "
+    } else {
+        " [Enter] select miners "
+    };
+    let p = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title_bottom(hints))
+        .wrap(Wrap { trim: true });
+    f.render_widget(p, chunks[1]);
+}
+
+fn render_mempool(f: &mut Frame, view: &mut MempoolView, focused: bool, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(5),
+            Constraint::Min(3),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    // Statistics from getmempoolinfo; fee rates come in BTC/kvB
+    let sat_per_vb = |btc_per_kvb: f64| btc_per_kvb * 100_000.0;
+    let stats = match &view.info {
+        Some(info) => vec![
+            Line::from(format!(
+                "Transactions {}   Size {}",
+                info.size,
+                format_vsize(info.bytes)
+            )),
+            Line::from(format!(
+                "Memory {} / {}   Fees {:.8} BTC",
+                node_status::format_bytes(info.usage),
+                node_status::format_bytes(info.maxmempool),
+                info.total_fee
+            )),
+            Line::from(format!(
+                "Min fee {:.2} sat/vB   Min relay {:.2} sat/vB",
+                sat_per_vb(info.mempoolminfee),
+                sat_per_vb(info.minrelaytxfee)
+            )),
+        ],
+        None => vec![Line::from("Loading mempool...")],
+    };
+    let p = Paragraph::new(stats).block(Block::default().borders(Borders::ALL).title(" Mempool "));
+    f.render_widget(p, chunks[0]);
+
+    match &view.detail {
+        Some(detail) => render_tx_detail(f, detail, view.selected, &mut view.list_state, chunks[1]),
+        None => render_fee_histogram(f, &view.histogram, chunks[1]),
+    }
+
+    // Prompt and status
+    let status = if let Some(input) = &view.txid_input {
+        Line::from(Span::styled(
+            format!("txid: {}_", input),
+            Style::default().fg(Color::Yellow),
+        ))
+    } else if let Some(err) = &view.error {
+        Line::from(Span::styled(
+            format!("Refresh failed: {}", err),
+            Style::default().fg(Color::Red),
+        ))
+    } else if let Some(message) = &view.message {
+        Line::from(message.clone())
+    } else {
+        Line::from("")
+    };
+    let hints = match (focused, view.detail.is_some()) {
+        (false, _) => " [Enter] explore ",
+        (true, false) => " [t] find txid  [r] refresh  [Esc] back ",
+        (true, true) => " [Enter] open related  [t] find txid  [Esc] back ",
+    };
+    let p =
+        Paragraph::new(status).block(Block::default().borders(Borders::ALL).title_bottom(hints));
+    f.render_widget(p, chunks[2]);
+}
+
+fn render_template(f: &mut Frame, view: &mut TemplateView, focused: bool, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(7), Constraint::Min(3)])
+        .split(area);
+
+    let mut lines = match (&view.template, view.summary()) {
+        (Some(template), Some(summary)) => {
+            let percent = |used: u64, limit: u64| used as f64 * 100.0 / limit.max(1) as f64;
+            let hash = &template.previousblockhash;
+            vec![
+                Line::from(format!(
+                    "Height {}   Previous {}…{}",
+                    template.height,
+                    &hash[..hash.len().min(8)],
+                    &hash[hash.len().saturating_sub(12)..]
+                )),
+                Line::from(format!(
+                    "Coinbase {}   Fees {}",
+                    format_btc(template.coinbasevalue),
+                    format_btc(summary.fees)
+                )),
+                Line::from(format!(
+                    "Weight {} / {} ({:.1}%)   Sigops {} / {}",
+                    summary.weight,
+                    summary.weight_limit,
+                    percent(summary.weight, summary.weight_limit),
+                    summary.sigops,
+                    summary.sigop_limit
+                )),
+                Line::from(format!("Transactions {}", template.transactions.len())),
+            ]
+        }
+        _ if view.error.is_none() => vec![Line::from("Loading block template...")],
+        _ => Vec::new(),
+    };
+    if let Some(err) = &view.error {
+        lines.push(Line::from(Span::styled(
+            format!("Refresh failed: {}", err),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    let p = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Block template "),
+        )
+        .wrap(Wrap { trim: true });
+    f.render_widget(p, chunks[0]);
+
+    let numeric = |title: &'static str| Cell::from(Line::from(title).right_aligned());
+    let header = Row::new([
+        Cell::from("#"),
+        Cell::from("Txid"),
+        numeric("sat/vB"),
+        numeric("Fee"),
+        numeric("Weight"),
+        numeric("Sigops"),
+    ])
+    .style(Style::default().add_modifier(Modifier::BOLD));
+    let rows: Vec<Row> = view
+        .transactions()
+        .map(|(position, tx)| {
+            let rate = template_view::fee_rate(tx);
+            Row::new([
+                Cell::from(position.to_string()),
+                Cell::from(tx.txid.clone()),
+                Cell::from(format!("{:>6.1}", rate))
+                    .style(Style::default().fg(fee_rate_color(rate))),
+                Cell::from(format!("{:>8}", tx.fee)),
+                Cell::from(format!("{:>6}", tx.weight)),
+                Cell::from(format!("{:>6}", tx.sigops)),
+            ])
+        })
+        .collect();
+    let hints = if focused {
+        " [↑/↓/PgUp/PgDn] scroll  [r] refresh  [Esc] back "
+    } else {
+        " [Enter] explore "
+    };
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(4),
+            Constraint::Min(8),
+            Constraint::Length(6),
+            Constraint::Length(8),
+            Constraint::Length(6),
+            Constraint::Length(6),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Transactions by fee rate ")
+            .title_bottom(hints),
+    )
+    .row_highlight_style(if focused {
+        Style::default().bg(Color::Gray).fg(Color::Black)
+    } else {
+        Style::default()
+    });
+
+    view.table_state.select(if view.order.is_empty() {
+        None
+    } else {
+        Some(view.selected)
+    });
+    f.render_stateful_widget(table, chunks[1], &mut view.table_state);
+}
+
+/// Satoshis as BTC, e.g. `3.12500000 BTC`.
+fn format_btc(sats: u64) -> String {
+    format!("{}.{:08} BTC", sats / 100_000_000, sats % 100_000_000)
+}
+
+fn render_log(f: &mut Frame, view: &mut LogView, focused: bool, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(3)])
+        .split(area);
+
+    view.height = chunks[0].height.saturating_sub(2) as usize;
+    let visible = view.visible();
+    let first = view.first_row(visible.len());
+    let lines: Vec<Line> = visible
+        .iter()
+        .skip(first)
+        .take(view.height)
+        .map(|line| log_line(line, view.search.as_ref()))
+        .collect();
+    let title = match &view.path {
+        Some(path) => format!(
+            " {} ",
+            path.file_name().unwrap_or_default().to_string_lossy()
+        ),
+        None => " Debug log ".to_string(),
+    };
+    let position = if view.follow {
+        " following ".to_string()
+    } else {
+        format!(
+            " {}/{} ",
+            (first + view.height).min(visible.len()),
+            visible.len()
+        )
+    };
+    let p = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title(Line::from(position).right_aligned()),
+    );
+    f.render_widget(p, chunks[0]);
+
+    // Filters, prompt and status
+    let status = if let Some(input) = &view.search_input {
+        Line::from(Span::styled(
+            format!("/{}_", input),
+            Style::default().fg(Color::Yellow),
+        ))
+    } else if let Some(err) = &view.error {
+        Line::from(Span::styled(err.clone(), Style::default().fg(Color::Red)))
+    } else if let Some(message) = &view.message {
+        Line::from(message.clone())
+    } else {
+        let mut filters = Vec::new();
+        if let Some(category) = &view.category {
+            filters.push(format!("category {}", category));
+        }
+        if let Some(search) = &view.search {
+            filters.push(format!("matching /{}/", search.as_str()));
+        }
+        if filters.is_empty() {
+            Line::from("All lines")
+        } else {
+            Line::from(filters.join(", "))
+        }
+    };
+    let hints = if focused {
+        " [f] follow  [c] category  [/] search  [Esc] back "
+    } else {
+        " [Enter] explore "
+    };
+    let p =
+        Paragraph::new(status).block(Block::default().borders(Borders::ALL).title_bottom(hints));
+    f.render_widget(p, chunks[1]);
+}
+
+/// The state, command line and latest output of a supervised program.
+fn render_stratum(f: &mut Frame, app: &App, area: Rect) {
+    let hints = if app.focus == Focus::Content {
+        " [r] reconnect  [x] disconnect  [Esc] back "
+    } else {
+        " [Enter] manage "
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Stratum ")
+        .title_bottom(hints);
+    let lines = match (&app.stratum, app.stratum_target()) {
+        (Some(status), _) => stratum_lines(status),
+        (None, Ok(target)) => vec![
+            Line::from(format!("Not connected to {}", target.address)),
+            Line::from(""),
+            Line::from("Press [r] to subscribe as a test miner"),
+        ],
+        (None, Err(err)) => vec![Line::from(format!("Nothing to monitor: {}", err))],
+    };
+    let p = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+    f.render_widget(p, area);
+}
+
+fn stratum_lines(status: &StratumStatus) -> Vec<Line<'static>> {
+    let row = |label: &str, value: String, style: Style| {
+        Line::from(vec![
+            Span::styled(format!("{:<12}", label), Style::default().fg(Color::Gray)),
+            Span::styled(value, style),
+        ])
+    };
+    let plain = Style::default();
+    let ms = |latency: std::time::Duration| format!("{:.1} ms", latency.as_secs_f64() * 1000.0);
+
+    let (link, link_style) = match &status.link {
+        LinkState::Connecting => ("connecting...".to_string(), plain),
+        LinkState::Connected => ("connected".to_string(), Style::default().fg(Color::Green)),
+        LinkState::Down(err) => (format!("down: {}", err), Style::default().fg(Color::Red)),
+    };
+    let subscribe = match (&status.subscribe_latency, &status.extranonce1) {
+        (Some(latency), Some(extranonce1)) => {
+            format!("{} (extranonce1 {})", ms(*latency), extranonce1)
+        }
+        _ => "waiting".to_string(),
+    };
+    let (authorize, authorize_style) = match (status.authorized, status.authorize_latency) {
+        (Some(true), Some(latency)) => (
+            format!("accepted in {}", ms(latency)),
+            Style::default().fg(Color::Green),
+        ),
+        (Some(false), _) => ("refused".to_string(), Style::default().fg(Color::Red)),
+        _ => ("waiting".to_string(), plain),
+    };
+    let difficulty = match status.difficulty {
+        Some(d) if d.fract() == 0.0 => format!("{:.0}", d),
+        Some(d) => d.to_string(),
+        None => "not set yet".to_string(),
+    };
+    let jobs = match &status.last_job {
+        Some((id, at)) => format!(
+            "{} ({} clean), last {} {}s ago",
+            status.jobs,
+            status.clean_jobs,
+            id,
+            at.elapsed().as_secs()
+        ),
+        None => "none yet".to_string(),
+    };
+
+    let mut lines = vec![
+        row("Server", status.target.address.clone(), plain),
+        row("Worker", status.target.worker.clone(), plain),
+        row("Connection", link, link_style),
+        row("Subscribe", subscribe, plain),
+        row("Authorize", authorize, authorize_style),
+        row("Difficulty", difficulty, plain),
+        row("Jobs", jobs, plain),
+    ];
+    if !status.errors.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "Recent errors",
+            Style::default().fg(Color::Gray),
+        )));
+        lines.extend(status.errors.iter().rev().map(|err| {
+            Line::from(Span::styled(
+                format!("  {}", err),
+                Style::default().fg(Color::Yellow),
+            ))
+        }));
+    }
+    lines
+}
+
+fn render_process(
+    f: &mut Frame,
+    supervisor: &Supervisor,
+    command: String,
+    hints: &str,
+    focused: bool,
+    area: Rect,
+) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(7), Constraint::Min(3)])
+        .split(area);
+
+    let (state, color) = match supervisor.state {
+        RunState::Idle => ("not started from pdm".to_string(), Color::DarkGray),
+        RunState::Running => match &supervisor.process {
+            Some(p) => (format!("running (pid {})", p.pid), Color::Green),
+            None => ("running".to_string(), Color::Green),
+        },
+        RunState::Stopping => ("stopping".to_string(), Color::Yellow),
+        RunState::Exited(report) => (format!("stopped, {}", report), Color::Reset),
+        RunState::Crashed(report) if supervisor.is_crash_looping() => {
+            (format!("crash looping, {}", report), Color::Red)
+        }
+        RunState::Crashed(report) => (format!("crashed, {}", report), Color::Red),
+    };
+    let restart = match supervisor.restart_at {
+        Some(at) => format!(
+            "  (restarting in {}s)",
+            at.saturating_duration_since(Instant::now())
+                .as_millis()
+                .div_ceil(1000)
+        ),
+        None if supervisor.auto_restart => "  (auto-restart on)".to_string(),
+        None => String::new(),
+    };
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(Color::Cyan));
+    let status = if let Some(action) = &supervisor.pending {
+        Line::from(Span::styled(
+            action.prompt(supervisor.name),
+            Style::default().fg(Color::Yellow),
+        ))
+    } else {
+        Line::from(supervisor.message.clone().unwrap_or_default())
+    };
+    let lines = vec![
+        Line::from(vec![
+            label("State    "),
+            Span::styled(state, Style::default().fg(color)),
+            Span::raw(restart),
+        ]),
+        status,
+        Line::from(vec![label("Command  "), Span::raw(command)]),
+    ];
+    let p = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" {} ", supervisor.name)),
+    );
+    f.render_widget(p, chunks[0]);
+
+    let height = chunks[1].height.saturating_sub(2) as usize;
+    let end = supervisor.output.len().saturating_sub(supervisor.scroll);
+    let output: Vec<Line> = supervisor
+        .output
+        .range(end.saturating_sub(height)..end)
+        .map(|line| Line::from(line.as_str()))
+        .collect();
+    let title = match supervisor.scroll {
+        0 if supervisor.output.len() > height && focused => " Output  [↑/↓] scroll ".to_string(),
+        0 => " Output ".to_string(),
+        n => format!(" Output: {} newer line(s) below, [End] to follow ", n),
+    };
+    let hints = if focused { hints } else { " [Enter] manage " };
+    let p = Paragraph::new(output).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title_bottom(hints),
+    );
+    f.render_widget(p, chunks[1]);
+}
+
+/// A debug.log line without its date, colored by level, with `search`
+/// matches highlighted.
+fn log_line<'a>(line: &'a LogLine, search: Option<&regex::Regex>) -> Line<'a> {
+    let style = match line.level {
+        _ if line.notice => Style::default().fg(Color::DarkGray),
+        Level::Error => Style::default().fg(Color::Red),
+        Level::Warning => Style::default().fg(Color::Yellow),
+        Level::Info => Style::default(),
+    };
+    // Every line of a session shares the date; the time is what tells
+    // them apart
+    let text = match line.text.split_once('T') {
+        Some((date, rest)) if date.len() == 10 && date.as_bytes()[4] == b'-' => rest,
+        _ => &line.text,
+    };
+    let Some(re) = search else {
+        return Line::from(Span::styled(text, style));
+    };
+    let highlight = Style::default().bg(Color::Yellow).fg(Color::Black);
+    let mut spans = Vec::new();
+    let mut end = 0;
+    for found in re.find_iter(text) {
+        spans.push(Span::styled(&text[end..found.start()], style));
+        spans.push(Span::styled(found.as_str(), highlight));
+        end = found.end();
+    }
+    spans.push(Span::styled(&text[end..], style));
+    Line::from(spans)
+}
+
+/// A virtual size with its unit, e.g. `1.2 MvB`.
+fn format_vsize(vbytes: u64) -> String {
+    node_status::format_bytes(vbytes).replace('B', "vB")
+}
+
+fn render_fee_histogram(f: &mut Frame, buckets: &[FeeBucket], area: Rect) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Fee rates (sat/vB) ");
+    // "  10-15 " + bar + "  123.4 kvB  12345"
+    let inner_width = area.width.saturating_sub(2) as usize;
+    let bar_width = inner_width.saturating_sub(8 + 18).max(1);
+    let max_vsize = buckets.iter().map(|b| b.vsize).max().unwrap_or(0).max(1);
+
+    let lines: Vec<Line> = buckets
+        .iter()
+        .rev()
+        .filter(|bucket| bucket.count > 0)
+        .map(|bucket| {
+            let filled =
+                (bucket.vsize as f64 / max_vsize as f64 * bar_width as f64).ceil() as usize;
+            Line::from(vec![
+                Span::raw(format!("{:>7} ", bucket.label())),
+                Span::styled(
+                    format!("{:<width$}", "█".repeat(filled), width = bar_width),
+                    Style::default().fg(fee_rate_color(bucket.min_rate)),
+                ),
+                Span::raw(format!(
+                    " {:>10} {:>6}",
+                    format_vsize(bucket.vsize),
+                    bucket.count
+                )),
+            ])
+        })
+        .collect();
+    let lines = if lines.is_empty() {
+        vec![Line::from("No transactions")]
+    } else {
+        lines
+    };
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn fee_rate_color(rate: f64) -> Color {
+    match rate {
+        r if r >= 50.0 => Color::Red,
+        r if r >= 10.0 => Color::Yellow,
+        r if r >= 2.0 => Color::Green,
+        _ => Color::Blue,
+    }
+}
+
+fn render_tx_detail(
+    f: &mut Frame,
+    detail: &TxDetail,
+    selected: usize,
+    list_state: &mut ListState,
+    area: Rect,
+) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(7), Constraint::Min(0)])
+        .split(area);
+
+    let entry = &detail.entry;
+    let lines = vec![
+        Line::from(Span::styled(
+            detail.txid.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(format!(
+            "Fee {} sat, {:.1} sat/vB ({:.1} with ancestors)",
+            entry.fee_sats(),
+            entry.fee_rate(),
+            entry.ancestor_fee_rate()
+        )),
+        Line::from(format!("Size {} vB, weight {}", entry.vsize, entry.weight)),
+        Line::from(format!(
+            "{} ancestor(s), {} descendant(s)",
+            detail.ancestors.len(),
+            detail.descendants.len()
+        )),
+    ];
+    let p = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Transaction "),
+        )
+        .wrap(Wrap { trim: false });
+    f.render_widget(p, chunks[0]);
+
+    let items: Vec<ListItem> = detail
+        .ancestors
+        .iter()
+        .map(|txid| ListItem::new(format!("↑ {}", txid)))
+        .chain(
+            detail
+                .descendants
+                .iter()
+                .map(|txid| ListItem::new(format!("↓ {}", txid))),
+        )
+        .collect();
+    list_state.select((!items.is_empty()).then_some(selected));
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Ancestors ↑ / Descendants ↓ "),
+        )
+        .highlight_style(Style::default().bg(Color::Gray).fg(Color::Black));
+    f.render_stateful_widget(list, chunks[1], list_state);
+}
@@ -0,0 +1,598 @@
+// SPDX-FileCopyrightText: 2024 PDM Authors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! A subscriber for bitcoind's ZMQ notifications.
+//!
+//! bitcoind publishes on the endpoints named by its `zmqpub*` options.
+//! [`ZmqSubscriber`] connects to the `hashblock` and `rawtx` ones and sends
+//! what arrives to the event loop, so a new block shows up on screen as
+//! soon as the node has it rather than on the next poll.
+//!
+//! Only as much of ZMTP 3.0 as a SUB socket needs against bitcoind's PUB
+//! socket is spoken: the NULL security mechanism, the READY handshake and
+//! plain message frames.
+
+use crate::bitcoin_conf::BitcoinConf;
+use crate::event::AppEvent;
+use crate::tasks::RpcTasks;
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+
+/// How long to wait before reconnecting to an endpoint that went away.
+pub const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long a new block stays announced on every screen.
+pub const BLOCK_NOTICE: Duration = Duration::from_secs(10);
+
+/// Frames larger than this are refused. `rawtx` bodies are bounded by
+/// the block weight limit.
+const MAX_FRAME: u64 = 8 * 1024 * 1024;
+
+/// The notifications pdm subscribes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Topic {
+    HashBlock,
+    RawTx,
+}
+
+impl Topic {
+    pub const ALL: [Topic; 2] = [Topic::HashBlock, Topic::RawTx];
+
+    /// The topic bitcoind tags messages with.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Topic::HashBlock => "hashblock",
+            Topic::RawTx => "rawtx",
+        }
+    }
+
+    /// The bitcoin.conf option that enables the topic.
+    pub fn option(&self) -> &'static str {
+        match self {
+            Topic::HashBlock => "zmqpubhashblock",
+            Topic::RawTx => "zmqpubrawtx",
+        }
+    }
+}
+
+/// One address to connect to and the topics published there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    /// `host:port`.
+    pub address: String,
+    pub topics: Vec<Topic>,
+}
+
+/// The endpoints `conf` publishes [`Topic::ALL`] on, one per address.
+///
+/// Endpoints that are not TCP, such as `ipc://`, are left out.
+pub fn endpoints(conf: &BitcoinConf) -> Vec<Endpoint> {
+    let mut by_address: BTreeMap<String, Vec<Topic>> = BTreeMap::new();
+    for topic in Topic::ALL {
+        for value in conf.effective_all(topic.option()) {
+            if let Some(address) = connect_address(value) {
+                let topics = by_address.entry(address).or_default();
+                if !topics.contains(&topic) {
+                    topics.push(topic);
+                }
+            }
+        }
+    }
+    by_address
+        .into_iter()
+        .map(|(address, topics)| Endpoint { address, topics })
+        .collect()
+}
+
+/// Where to connect for a `zmqpub*` value such as `tcp://0.0.0.0:28332`.
+/// Wildcard bind addresses are reached over loopback.
+pub(crate) fn connect_address(value: &str) -> Option<String> {
+    let rest = value.trim().strip_prefix("tcp://")?;
+    let (host, port) = rest.rsplit_once(':')?;
+    port.parse::<u16>().ok()?;
+    let host = match host {
+        "*" | "0.0.0.0" => "127.0.0.1",
+        "[::]" | "[*]" => "[::1]",
+        host => host,
+    };
+    Some(format!("{}:{}", host, port))
+}
+
+/// What a [`ZmqSubscriber`] reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZmqEvent {
+    Connected {
+        address: String,
+    },
+    Disconnected {
+        address: String,
+        error: String,
+    },
+    /// `hashblock`: the node has a new tip.
+    Block {
+        hash: String,
+        sequence: u32,
+    },
+    /// `rawtx`: a transaction entered the mempool or a block.
+    Transaction {
+        size: usize,
+        sequence: u32,
+    },
+}
+
+/// The state of the connection to one endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkState {
+    Connecting,
+    Connected,
+    Down(String),
+}
+
+/// What has been heard over ZMQ, for display.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ZmqActivity {
+    /// By address.
+    pub links: BTreeMap<String, LinkState>,
+    /// Hash of the latest block announced.
+    pub last_block: Option<String>,
+    /// When it was announced.
+    pub last_block_at: Option<Instant>,
+    pub blocks: u64,
+    pub transactions: u64,
+}
+
+impl ZmqActivity {
+    /// Starts out with every endpoint connecting.
+    pub fn new(endpoints: &[Endpoint]) -> Self {
+        Self {
+            links: endpoints
+                .iter()
+                .map(|e| (e.address.clone(), LinkState::Connecting))
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    pub fn record(&mut self, event: ZmqEvent) {
+        match event {
+            ZmqEvent::Connected { address } => {
+                self.links.insert(address, LinkState::Connected);
+            }
+            ZmqEvent::Disconnected { address, error } => {
+                self.links.insert(address, LinkState::Down(error));
+            }
+            ZmqEvent::Block { hash, .. } => {
+                self.last_block = Some(hash);
+                self.last_block_at = Some(Instant::now());
+                self.blocks += 1;
+            }
+            ZmqEvent::Transaction { .. } => self.transactions += 1,
+        }
+    }
+
+    /// The latest block's hash if it arrived within [`BLOCK_NOTICE`].
+    pub fn recent_block(&self, now: Instant) -> Option<&str> {
+        let at = self.last_block_at?;
+        (now.saturating_duration_since(at) < BLOCK_NOTICE)
+            .then_some(self.last_block.as_deref())
+            .flatten()
+    }
+}
+
+/// Subscribes to every endpoint in the background and sends what arrives
+/// to the event loop as [`AppEvent::Zmq`], reconnecting after
+/// [`RETRY_INTERVAL`] whenever an endpoint goes away.
+///
+/// Like [`StatusPoller`](crate::node_status::StatusPoller), events carry
+/// the subscriber's `id` and the tasks stop when it is dropped.
+pub struct ZmqSubscriber {
+    pub id: u64,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+static NEXT_SUBSCRIBER_ID: AtomicU64 = AtomicU64::new(1);
+
+impl ZmqSubscriber {
+    pub fn start(tasks: &RpcTasks, endpoints: Vec<Endpoint>, retry: Duration) -> ZmqSubscriber {
+        let id = NEXT_SUBSCRIBER_ID.fetch_add(1, Ordering::Relaxed);
+        let handles = endpoints
+            .into_iter()
+            .map(|endpoint| {
+                let events = tasks.events();
+                let send = move |event| {
+                    events
+                        .send(AppEvent::Zmq {
+                            subscriber: id,
+                            event,
+                        })
+                        .is_ok()
+                };
+                tasks.handle().spawn(async move {
+                    loop {
+                        let error = match subscribe(&endpoint, &send).await {
+                            Ok(()) => "closed by bitcoind".to_string(),
+                            Err(err) => err.to_string(),
+                        };
+                        let address = endpoint.address.clone();
+                        if !send(ZmqEvent::Disconnected { address, error }) {
+                            return;
+                        }
+                        tokio::time::sleep(retry).await;
+                    }
+                })
+            })
+            .collect();
+        ZmqSubscriber { id, tasks: handles }
+    }
+}
+
+impl Drop for ZmqSubscriber {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Connects to `endpoint` and forwards notifications until the connection
+/// ends or `send` reports the event loop has gone.
+async fn subscribe(endpoint: &Endpoint, send: &impl Fn(ZmqEvent) -> bool) -> io::Result<()> {
+    let mut stream = TcpStream::connect(&endpoint.address).await?;
+    handshake(&mut stream).await?;
+    for topic in &endpoint.topics {
+        write_frame(&mut stream, 0, &subscription(topic.name())).await?;
+    }
+    let address = endpoint.address.clone();
+    if !send(ZmqEvent::Connected { address }) {
+        return Ok(());
+    }
+    loop {
+        let Some(parts) = read_message(&mut stream).await? else {
+            return Ok(());
+        };
+        if let Some(event) = notification(&parts)
+            && !send(event)
+        {
+            return Ok(());
+        }
+    }
+}
+
+// Frame flags
+const MORE: u8 = 0x01;
+const LONG: u8 = 0x02;
+const COMMAND: u8 = 0x04;
+
+/// A ZMTP 3.0 greeting offering the NULL mechanism, as a client.
+fn greeting() -> [u8; 64] {
+    let mut greeting = [0u8; 64];
+    greeting[0] = 0xff;
+    greeting[9] = 0x7f;
+    greeting[10] = 3;
+    greeting[11] = 0;
+    greeting[12..16].copy_from_slice(b"NULL");
+    greeting
+}
+
+/// A READY command announcing `socket_type`.
+fn ready(socket_type: &str) -> Vec<u8> {
+    let mut body = vec![5];
+    body.extend_from_slice(b"READY");
+    body.push(11);
+    body.extend_from_slice(b"Socket-Type");
+    body.extend_from_slice(&(socket_type.len() as u32).to_be_bytes());
+    body.extend_from_slice(socket_type.as_bytes());
+    body
+}
+
+/// The message a ZMTP 3.0 SUB socket sends to subscribe to `topic`.
+fn subscription(topic: &str) -> Vec<u8> {
+    let mut body = vec![1];
+    body.extend_from_slice(topic.as_bytes());
+    body
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Exchanges greetings and READY commands with the publisher.
+async fn handshake<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> io::Result<()> {
+    stream.write_all(&greeting()).await?;
+    let mut theirs = [0u8; 64];
+    stream.read_exact(&mut theirs).await?;
+    if theirs[0] != 0xff || theirs[9] != 0x7f {
+        return Err(invalid("not a ZMQ endpoint"));
+    }
+    if theirs[10] < 3 {
+        return Err(invalid(format!("ZMTP version {} is too old", theirs[10])));
+    }
+    if !theirs[12..32].starts_with(b"NULL\0") {
+        return Err(invalid("publisher requires authentication"));
+    }
+
+    write_frame(stream, COMMAND, &ready("SUB")).await?;
+    let (flags, body) = read_frame(stream).await?;
+    if flags & COMMAND == 0 || !body.starts_with(b"\x05READY") {
+        return Err(invalid("publisher did not send READY"));
+    }
+    Ok(())
+}
+
+async fn write_frame<S: AsyncWrite + Unpin>(
+    stream: &mut S,
+    flags: u8,
+    body: &[u8],
+) -> io::Result<()> {
+    let mut frame = Vec::with_capacity(body.len() + 9);
+    if body.len() > u8::MAX as usize {
+        frame.push(flags | LONG);
+        frame.extend_from_slice(&(body.len() as u64).to_be_bytes());
+    } else {
+        frame.push(flags);
+        frame.push(body.len() as u8);
+    }
+    frame.extend_from_slice(body);
+    stream.write_all(&frame).await
+}
+
+/// Reads one frame, returning its flags and body.
+async fn read_frame<S: AsyncRead + Unpin>(stream: &mut S) -> io::Result<(u8, Vec<u8>)> {
+    let flags = stream.read_u8().await?;
+    let size = if flags & LONG != 0 {
+        stream.read_u64().await?
+    } else {
+        stream.read_u8().await? as u64
+    };
+    if size > MAX_FRAME {
+        return Err(invalid(format!("{} byte frame is too large", size)));
+    }
+    let mut body = vec![0; size as usize];
+    stream.read_exact(&mut body).await?;
+    Ok((flags, body))
+}
+
+/// Reads the parts of the next message, skipping commands. `None` once
+/// the publisher has closed the connection between messages.
+async fn read_message<S: AsyncRead + Unpin>(stream: &mut S) -> io::Result<Option<Vec<Vec<u8>>>> {
+    let mut parts = Vec::new();
+    loop {
+        let (flags, body) = match read_frame(stream).await {
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof && parts.is_empty() => {
+                return Ok(None);
+            }
+            frame => frame?,
+        };
+        if flags & COMMAND != 0 {
+            continue;
+        }
+        parts.push(body);
+        if flags & MORE == 0 {
+            return Ok(Some(parts));
+        }
+    }
+}
+
+/// The event for a bitcoind message: topic, body and a little-endian
+/// sequence number. `None` for topics pdm does not follow.
+fn notification(parts: &[Vec<u8>]) -> Option<ZmqEvent> {
+    let [topic, body, sequence] = parts else {
+        return None;
+    };
+    let sequence = u32::from_le_bytes(sequence.as_slice().try_into().ok()?);
+    match topic.as_slice() {
+        // Already in the byte order block hashes are displayed in
+        b"hashblock" if body.len() == 32 => Some(ZmqEvent::Block {
+            hash: body.iter().map(|b| format!("{:02x}", b)).collect(),
+            sequence,
+        }),
+        b"rawtx" => Some(ZmqEvent::Transaction {
+            size: body.len(),
+            sequence,
+        }),
+        _ => None,
+    }
+}
+
+/// A stand-in for bitcoind's publisher.
+#[cfg(test)]
+pub(crate) mod mock {
+    use super::*;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+
+    /// Accepts one subscriber, waits for its `subscriptions` and then
+    /// publishes each message sent through the returned channel, as
+    /// bitcoind would. The connection closes with the channel, and the
+    /// task returns the topics subscribed to.
+    pub async fn publisher(
+        subscriptions: usize,
+    ) -> (
+        u16,
+        mpsc::UnboundedSender<Vec<Vec<u8>>>,
+        JoinHandle<Vec<String>>,
+    ) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (sender, mut messages) = mpsc::unbounded_channel::<Vec<Vec<u8>>>();
+        let task = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut theirs = [0u8; 64];
+            stream.read_exact(&mut theirs).await.unwrap();
+            let mut ours = greeting();
+            ours[32] = 1; // as-server
+            stream.write_all(&ours).await.unwrap();
+            let (flags, body) = read_frame(&mut stream).await.unwrap();
+            assert_eq!((flags, body), (COMMAND, ready("SUB")));
+            write_frame(&mut stream, COMMAND, &ready("PUB"))
+                .await
+                .unwrap();
+
+            let mut topics = Vec::new();
+            for _ in 0..subscriptions {
+                let (_, body) = read_frame(&mut stream).await.unwrap();
+                assert_eq!(body[0], 1);
+                topics.push(String::from_utf8_lossy(&body[1..]).into_owned());
+            }
+            while let Some(message) = messages.recv().await {
+                let last = message.len() - 1;
+                for (i, part) in message.iter().enumerate() {
+                    let flags = if i < last { MORE } else { 0 };
+                    write_frame(&mut stream, flags, part).await.unwrap();
+                }
+            }
+            topics
+        });
+        (port, sender, task)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoints_come_from_zmqpub_options() {
+        let conf = BitcoinConf::parse(
+            "zmqpubhashblock=tcp://127.0.0.1:28332\n\
+             zmqpubrawtx=tcp://0.0.0.0:28333\n\
+             zmqpubrawblock=tcp://127.0.0.1:28334\n\
+             zmqpubhashblock=ipc:///tmp/bitcoind.sock\n\
+             [test]\n\
+             zmqpubrawtx=tcp://127.0.0.1:28332\n",
+        );
+        assert_eq!(
+            endpoints(&conf),
+            [
+                Endpoint {
+                    address: "127.0.0.1:28332".into(),
+                    topics: vec![Topic::HashBlock],
+                },
+                Endpoint {
+                    address: "127.0.0.1:28333".into(),
+                    topics: vec![Topic::RawTx],
+                },
+            ]
+        );
+        assert_eq!(
+            connect_address("tcp://[::]:28332").as_deref(),
+            Some("[::1]:28332")
+        );
+        assert_eq!(connect_address("tcp://node:bad"), None);
+    }
+
+    #[test]
+    fn parses_bitcoind_messages() {
+        let mut hash = vec![0u8; 32];
+        hash[31] = 0xab;
+        let block = notification(&[b"hashblock".to_vec(), hash, 7u32.to_le_bytes().to_vec()]);
+        assert_eq!(
+            block,
+            Some(ZmqEvent::Block {
+                hash: format!("{}ab", "00".repeat(31)),
+                sequence: 7,
+            })
+        );
+        let tx = notification(&[b"rawtx".to_vec(), vec![0; 300], 1u32.to_le_bytes().to_vec()]);
+        assert_eq!(
+            tx,
+            Some(ZmqEvent::Transaction {
+                size: 300,
+                sequence: 1
+            })
+        );
+        assert_eq!(notification(&[b"rawtx".to_vec(), vec![]]), None);
+    }
+
+    #[test]
+    fn new_blocks_are_announced_for_a_while() {
+        let mut activity = ZmqActivity::default();
+        assert_eq!(activity.recent_block(Instant::now()), None);
+        activity.record(ZmqEvent::Block {
+            hash: "ab".repeat(32),
+            sequence: 0,
+        });
+        let now = Instant::now();
+        assert_eq!(activity.recent_block(now), Some("ab".repeat(32).as_str()));
+        assert_eq!(activity.recent_block(now + BLOCK_NOTICE), None);
+    }
+
+    #[tokio::test]
+    async fn reads_long_frames() {
+        let (mut client, mut server) = tokio::io::duplex(4096);
+        write_frame(&mut client, MORE, b"rawtx").await.unwrap();
+        write_frame(&mut client, MORE, &[0x11; 1000]).await.unwrap();
+        write_frame(&mut client, 0, &[0, 0, 0, 0]).await.unwrap();
+        let parts = read_message(&mut server).await.unwrap().unwrap();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[1].len(), 1000);
+    }
+
+    #[test]
+    fn subscriber_reports_blocks_from_publisher() {
+        use crate::rpc::{Auth, RpcConfig};
+        use std::sync::mpsc;
+        use tokio::runtime::Runtime;
+
+        let server = Runtime::new().unwrap();
+        let (port, publish, published) = server.block_on(mock::publisher(2));
+        let config = RpcConfig {
+            host: "127.0.0.1".into(),
+            port: 1,
+            auth: Auth::Cookie("/nonexistent".into()),
+            timeout: Duration::from_secs(5),
+        };
+        let (sender, receiver) = mpsc::channel();
+        let tasks = RpcTasks::start(config, sender).unwrap();
+        let address = format!("127.0.0.1:{}", port);
+        let subscriber = ZmqSubscriber::start(
+            &tasks,
+            vec![Endpoint {
+                address: address.clone(),
+                topics: Topic::ALL.to_vec(),
+            }],
+            Duration::from_secs(60),
+        );
+        let next = || match receiver.recv_timeout(Duration::from_secs(5)).unwrap() {
+            AppEvent::Zmq {
+                subscriber: id,
+                event,
+            } => {
+                assert_eq!(id, subscriber.id);
+                event
+            }
+            event => panic!("unexpected {:?}", event),
+        };
+
+        assert_eq!(
+            next(),
+            ZmqEvent::Connected {
+                address: address.clone()
+            }
+        );
+        publish
+            .send(vec![
+                b"hashblock".to_vec(),
+                vec![0x42; 32],
+                vec![0, 0, 0, 0],
+            ])
+            .unwrap();
+        assert_eq!(
+            next(),
+            ZmqEvent::Block {
+                hash: "42".repeat(32),
+                sequence: 0
+            }
+        );
+
+        drop(publish);
+        assert!(matches!(next(), ZmqEvent::Disconnected { .. }));
+        assert_eq!(server.block_on(published).unwrap(), ["hashblock", "rawtx"]);
+    }
+}
@@ -2,9 +2,15 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
-use pdm::app::{App, CurrentScreen};
+use pdm::app::{App, ConfigView, CurrentScreen, Focus};
+use pdm::bitcoin_conf::BitcoinConf;
+use pdm::bitcoin_conf::discover::Candidate;
+use pdm::components::conf_picker::ConfPicker;
+use pdm::components::rpcauth_dialog::RpcAuthDialog;
+use pdm::components::setup_wizard::SetupWizard;
 use pdm::ui::ui;
 use ratatui::{Terminal, backend::TestBackend};
+use std::path::PathBuf;
 
 #[test]
 fn test_home_screen_render() {
@@ -38,3 +44,680 @@ fn test_config_screen_render() {
 
     insta::assert_debug_snapshot!(terminal.backend());
 }
+
+#[test]
+fn test_config_editor_render() {
+    let mut app = App::new();
+    app.sidebar_index = 1;
+    app.toggle_menu();
+    app.focus = Focus::Content;
+    app.bitcoin_conf_path = Some(PathBuf::from("/home/bitcoin/.bitcoin/bitcoin.conf"));
+    app.bitcoin_conf = Some(BitcoinConf::parse(
+        "server=1\nrpcpassword=hunter2\naddnode=10.0.0.1\naddnode=10.0.0.2\n",
+    ));
+    app.config_editor.next();
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_config_editor_diagnostics_render() {
+    let mut app = App::new();
+    app.sidebar_index = 1;
+    app.toggle_menu();
+    app.bitcoin_conf_path = Some(PathBuf::from("/home/bitcoin/.bitcoin/bitcoin.conf"));
+    app.bitcoin_conf = Some(BitcoinConf::parse(
+        "prune=1000\ntxindex=1\nrpcpasword=x\nlisten=yes\nrpcport=70000\nzzzzzzzz=1\n",
+    ));
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_readiness_report_render() {
+    let mut app = App::new();
+    app.sidebar_index = 1;
+    app.toggle_menu();
+    app.focus = Focus::Content;
+    app.config_view = ConfigView::Readiness;
+    app.bitcoin_conf_path = Some(PathBuf::from("/home/bitcoin/.bitcoin/bitcoin.conf"));
+    app.bitcoin_conf = Some(BitcoinConf::parse(
+        "server=1\nrpcuser=p2pool\nrpcpassword=secret\nprune=550\n",
+    ));
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_rpcauth_dialog_render() {
+    let mut app = App::new();
+    app.sidebar_index = 1;
+    app.toggle_menu();
+    app.focus = Focus::Content;
+    app.bitcoin_conf_path = Some(PathBuf::from("/home/bitcoin/.bitcoin/bitcoin.conf"));
+    app.bitcoin_conf = Some(BitcoinConf::parse("server=1\n"));
+    app.rpcauth_dialog = Some(RpcAuthDialog::new());
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_setup_wizard_render() {
+    let mut app = App::new();
+    let mut wizard = SetupWizard::new(std::path::Path::new("/home/pool"));
+    wizard.step = 5; // p2poolv2 settings
+    wizard.field = 1;
+    wizard.miner_address = "bc1qexample".to_string();
+    app.wizard = Some(wizard);
+    app.current_screen = CurrentScreen::Wizard;
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_conf_picker_render() {
+    let mut app = App::new();
+    app.sidebar_index = 1;
+    app.toggle_menu();
+    app.focus = Focus::Content;
+    app.conf_picker = Some(ConfPicker::new(vec![
+        Candidate {
+            path: PathBuf::from("/data/bitcoin/bitcoin.conf"),
+            source: "running bitcoind (pid 812)".to_string(),
+        },
+        Candidate {
+            path: PathBuf::from("/home/bitcoin/.bitcoin/bitcoin.conf"),
+            source: "default location".to_string(),
+        },
+    ]));
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_effective_config_render() {
+    use pdm::bitcoin_conf::include::{ConfFile, Includes, Origin, Problem};
+
+    let mut app = App::new();
+    app.sidebar_index = 1;
+    app.toggle_menu();
+    app.focus = Focus::Content;
+    app.config_view = ConfigView::Effective;
+    app.bitcoin_conf_path = Some(PathBuf::from("/home/bitcoin/.bitcoin/bitcoin.conf"));
+    app.bitcoin_conf = Some(BitcoinConf::parse(
+        "server=1\nprune=550\nincludeconf=p2pool.conf\nincludeconf=gone.conf\n",
+    ));
+    app.bitcoin_includes = Includes {
+        files: vec![ConfFile {
+            path: PathBuf::from("/home/bitcoin/.bitcoin/p2pool.conf"),
+            conf: BitcoinConf::parse("prune=10000\nrpcpassword=hunter2\n"),
+        }],
+        problems: vec![Problem {
+            origin: Origin {
+                path: PathBuf::from("/home/bitcoin/.bitcoin/bitcoin.conf"),
+                line: 3,
+            },
+            message: "cannot read gone.conf: No such file or directory".to_string(),
+        }],
+    };
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_history_render() {
+    use pdm::backup::Backup;
+    use std::fs;
+
+    let dir = std::env::temp_dir().join("pdm_history_render_test");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let backup_path = dir.join("bitcoin.conf.20261017-093000");
+    fs::write(&backup_path, "server=1\nprune=550\ntxindex=0\n").unwrap();
+
+    let mut app = App::new();
+    app.sidebar_index = 1;
+    app.toggle_menu();
+    app.focus = Focus::Content;
+    app.config_view = ConfigView::History;
+    app.bitcoin_conf = Some(BitcoinConf::parse("server=1\nprune=10000\ntxindex=0\n"));
+    app.history_view.backups = vec![Backup {
+        path: backup_path,
+        stamp: "20261017-093000".to_string(),
+    }];
+    app.history_view.current = Some(Ok("server=1\nprune=10000\ntxindex=0\n".to_string()));
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_save_preview_render() {
+    use pdm::components::save_preview::SavePreview;
+    use std::fs;
+
+    let dir = std::env::temp_dir().join("pdm_save_preview_render_test");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("bitcoin.conf");
+    fs::write(&path, "# node\nserver=0\nprune=550\n").unwrap();
+
+    let mut app = App::new();
+    app.sidebar_index = 1;
+    app.toggle_menu();
+    let mut preview = SavePreview::new(&path, "# node\nserver=1\nprune=550\nrpcuser=pool\n")
+        .unwrap()
+        .unwrap();
+    // Stable name regardless of the temp dir.
+    preview.path = PathBuf::from("bitcoin.conf");
+    for line in &mut preview.diff {
+        line.text = line.text.replace(&dir.display().to_string(), "~");
+    }
+    app.save_preview = Some(preview);
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_error_dialog_render() {
+    let mut app = App::new();
+    app.sidebar_index = 1;
+    app.toggle_menu();
+    app.show_error(
+        "Save failed",
+        "cannot keep owner 1001:1001 (Operation not permitted); run pdm as that user or root",
+    );
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_node_dashboard_render() {
+    use pdm::node_status::NodeStatus;
+    use pdm::zmq::{Endpoint, Topic, ZmqActivity, ZmqEvent};
+
+    let mut app = App::new();
+    app.bitcoin_conf_path = Some(PathBuf::from("/home/pool/.bitcoin/bitcoin.conf"));
+    app.node_status = Some(NodeStatus {
+        chain: "main".into(),
+        blocks: 850_000,
+        headers: 850_002,
+        verification_progress: 0.99998,
+        initial_block_download: false,
+        connections: 10,
+        connections_in: 2,
+        connections_out: 8,
+        mempool_txs: 41_234,
+        mempool_bytes: 25_300_000,
+        uptime: 273_600 + 720,
+        subversion: "/Satoshi:27.0.0/".into(),
+    });
+    app.node_error = Some("no reply after 10s".into());
+    app.zmq = ZmqActivity::new(&[Endpoint {
+        address: "127.0.0.1:28332".into(),
+        topics: vec![Topic::HashBlock],
+    }]);
+    app.zmq.record(ZmqEvent::Connected {
+        address: "127.0.0.1:28332".into(),
+    });
+    app.zmq.record(ZmqEvent::Block {
+        hash: "00000000000000000001a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f7".into(),
+        sequence: 0,
+    });
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_peers_render() {
+    use pdm::rpc::types::PeerInfo;
+
+    let peer = |id: u64, addr: &str, inbound: bool, kind: &str, ping: Option<f64>| PeerInfo {
+        id,
+        addr: addr.into(),
+        network: "ipv4".into(),
+        inbound,
+        connection_type: kind.into(),
+        version: 70016,
+        subver: "/Satoshi:27.0.0/".into(),
+        pingtime: ping,
+        bytessent: 1_200_000 * id,
+        bytesrecv: 34_000_000 * id,
+        conntime: 0,
+        synced_headers: 850_000,
+        synced_blocks: 850_000,
+    };
+
+    let mut app = App::new();
+    app.sidebar_index = 2;
+    app.toggle_menu();
+    app.focus = Focus::Content;
+    app.peer_list.set_peers(vec![
+        peer(
+            1,
+            "203.0.113.5:8333",
+            false,
+            "outbound-full-relay",
+            Some(0.045),
+        ),
+        peer(
+            2,
+            "198.51.100.20:8333",
+            false,
+            "block-relay-only",
+            Some(0.12),
+        ),
+        peer(5, "192.0.2.77:51234", true, "inbound", None),
+    ]);
+    app.peer_list.request_ban();
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_mempool_render() {
+    use pdm::components::mempool_view::FeeBucket;
+    use pdm::rpc::types::MempoolInfo;
+
+    let mut app = App::new();
+    app.sidebar_index = 3;
+    app.toggle_menu();
+    app.focus = Focus::Content;
+    let bucket = |min_rate: f64, max_rate: Option<f64>, count: u64, vsize: u64| FeeBucket {
+        min_rate,
+        max_rate,
+        count,
+        vsize,
+    };
+    app.mempool_view.set_stats(
+        MempoolInfo {
+            loaded: true,
+            size: 41_234,
+            bytes: 25_300_000,
+            usage: 120_500_000,
+            total_fee: 0.41234,
+            maxmempool: 300_000_000,
+            mempoolminfee: 0.00001,
+            minrelaytxfee: 0.00001,
+        },
+        vec![
+            bucket(1.0, Some(2.0), 20_000, 9_000_000),
+            bucket(2.0, Some(3.0), 12_000, 6_000_000),
+            bucket(10.0, Some(15.0), 8_000, 8_000_000),
+            bucket(50.0, Some(100.0), 1_234, 2_300_000),
+            bucket(500.0, None, 0, 0),
+        ],
+    );
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_block_template_render() {
+    use pdm::rpc::types::{BlockTemplate, TemplateTransaction};
+
+    let tx = |n: u8, fee: u64, weight: u64| TemplateTransaction {
+        txid: format!("{:02x}", n).repeat(32),
+        fee,
+        sigops: 4,
+        weight,
+        depends: Vec::new(),
+    };
+    let transactions = vec![
+        tx(1, 1_410, 561),
+        tx(2, 84_000, 900),
+        tx(3, 12_500, 1_000),
+        tx(4, 300, 1_200),
+    ];
+    let mut app = App::new();
+    app.sidebar_index = 4;
+    app.toggle_menu();
+    app.focus = Focus::Content;
+    app.template_view.set_template(BlockTemplate {
+        version: 0x2000_0000,
+        previousblockhash: "00000000000000000001a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f7"
+            .into(),
+        height: 850_001,
+        coinbasevalue: 312_500_000 + 98_210,
+        transactions,
+        sigoplimit: 80_000,
+        weightlimit: 4_000_000,
+        curtime: 1_700_000_000,
+        bits: "17034219".into(),
+        target: String::new(),
+    });
+    app.template_view.down(1);
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_debug_log_render() {
+    use pdm::components::log_view::LogLine;
+
+    let mut app = App::new();
+    app.sidebar_index = 5;
+    app.toggle_menu();
+    app.focus = Focus::Content;
+    let view = &mut app.log_view;
+    view.path = Some(PathBuf::from("/home/pool/.bitcoin/debug.log"));
+    view.follow = true;
+    view.error = None;
+    for line in [
+        "2024-05-01T12:00:00Z Bitcoin Core version v27.0.0 (release build)",
+        "2024-05-01T12:00:01Z [net] Added connection peer=3",
+        "2024-05-01T12:00:02Z UpdateTip: new best=0000000000000000000a1b2c height=850000",
+        "2024-05-01T12:00:03Z [net:warning] peer=3 sent bad headers",
+        "2024-05-01T12:00:04Z Error: Disk space is too low!",
+        "2024-05-01T12:00:05Z UpdateTip: new best=0000000000000000000d3e4f height=850001",
+    ] {
+        view.push(LogLine::parse(line.to_string()));
+    }
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_bitcoind_render() {
+    use pdm::process::{ExitReport, RunState};
+
+    let mut app = App::new();
+    app.sidebar_index = 6;
+    app.toggle_menu();
+    app.focus = Focus::Content;
+    let bitcoind = &mut app.bitcoind;
+    bitcoind.command_line =
+        Some("bitcoind -conf=/home/pool/.bitcoin/bitcoin.conf -datadir=/home/pool/.bitcoin".into());
+    bitcoind.state = RunState::Crashed(ExitReport {
+        code: Some(1),
+        signal: None,
+    });
+    bitcoind.message = Some("bitcoind crashed: exited with code 1".into());
+    bitcoind.output.extend([
+        "2024-05-01T12:00:00Z Bitcoin Core version v27.0.0 (release build)".to_string(),
+        "2024-05-01T12:00:00Z Using data directory /home/pool/.bitcoin".to_string(),
+        "Error: Cannot obtain a lock on data directory /home/pool/.bitcoin.".to_string(),
+    ]);
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_p2pool_process_render() {
+    use pdm::process::{Process, RunState};
+
+    let mut app = App::new();
+    app.sidebar_index = 8;
+    app.toggle_menu();
+    app.focus = Focus::Content;
+    let p2pool = &mut app.p2pool;
+    p2pool.command_line = Some("p2poolv2 --config /home/pool/.p2poolv2/config.toml".into());
+    p2pool.process = Some(Process { id: 1, pid: 4242 });
+    p2pool.state = RunState::Running;
+    p2pool.auto_restart = true;
+    p2pool.message = Some("Started p2poolv2 (pid 4242)".into());
+    p2pool
+        .output
+        .extend((1..=20).map(|n| format!("INFO p2poolv2: share {} accepted", n)));
+    p2pool.scroll = 4;
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_stratum_render() {
+    use pdm::stratum::{StratumEvent, StratumStatus, Target};
+    use std::time::Duration;
+
+    let mut app = App::new();
+    app.sidebar_index = 9;
+    app.toggle_menu();
+    app.focus = Focus::Content;
+    let mut status = StratumStatus::new(Target {
+        address: "127.0.0.1:3333".into(),
+        worker: "tb1qpayout.pdm-monitor".into(),
+    });
+    status.record(StratumEvent::Disconnected {
+        error: "Connection refused (os error 111)".into(),
+    });
+    for event in [
+        StratumEvent::Connected,
+        StratumEvent::Subscribed {
+            latency: Duration::from_micros(1250),
+            extranonce1: "2a000001".into(),
+            extranonce2_size: 4,
+        },
+        StratumEvent::Authorized {
+            latency: Duration::from_micros(800),
+            accepted: true,
+        },
+        StratumEvent::Difficulty(32.0),
+        StratumEvent::Job {
+            id: "b7".into(),
+            clean: true,
+        },
+    ] {
+        status.record(event);
+    }
+    app.stratum = Some(status);
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_miners_render() {
+    use std::time::Instant;
+
+    let mut app = App::new();
+    app.sidebar_index = 10;
+    app.toggle_menu();
+    app.focus = Focus::Content;
+    let now = Instant::now();
+    for (worker, ip, shares) in [
+        ("tb1qpayout.rack1-a", "10.0.1.1", 3),
+        ("tb1qpayout.rack1-b", "10.0.1.2", 1),
+        ("tb1qpayout.rack2-a", "10.0.2.1", 0),
+    ] {
+        let miners = &mut app.miner_list;
+        miners.observe(
+            &format!(
+                "INFO Miner authorized worker={} ip={}:4001 difficulty=65536",
+                worker, ip
+            ),
+            now,
+        );
+        for _ in 0..shares {
+            miners.observe(
+                &format!("INFO Share accepted worker={} difficulty=65536", worker),
+                now,
+            );
+        }
+    }
+    app.miner_list.observe(
+        "WARN Stale share worker=tb1qpayout.rack1-b difficulty=65536",
+        now,
+    );
+    app.miner_list
+        .observe("INFO Miner disconnected worker=tb1qpayout.rack2-a", now);
+    app.miner_list.filter = Some("rack1".into());
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_p2pool_config_render() {
+    use pdm::p2pool_conf::P2poolConf;
+
+    let mut app = App::new();
+    app.sidebar_index = 7;
+    app.toggle_menu();
+    app.focus = Focus::Content;
+    app.p2pool_conf_path = Some(PathBuf::from("/home/pool/.p2poolv2/config.toml"));
+    app.p2pool_conf = Some(
+        P2poolConf::parse(
+            r#"[stratum]
+hostname = "0.0.0.0"
+port = 3333
+start_difficulty = 10000
+minimum_difficulty = 100
+zmqpubhashblock = "tcp://127.0.0.1:28332"
+network = "signet"
+
+[bitcoinrpc]
+url = "http://127.0.0.1:38332"
+username = "p2pool"
+password = "secret"
+
+[store]
+path = "/home/pool/.p2poolv2/store.db"
+"#,
+        )
+        .unwrap(),
+    );
+    app.p2pool_editor.selected = 2;
+    app.p2pool_editor.modified = true;
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_p2pool_crosscheck_render() {
+    use pdm::app::P2poolView;
+    use pdm::bitcoin_conf::BitcoinConf;
+    use pdm::p2pool_conf::P2poolConf;
+
+    let mut app = App::new();
+    app.sidebar_index = 7;
+    app.toggle_menu();
+    app.focus = Focus::Content;
+    app.bitcoin_conf_path = Some(PathBuf::from("/nonexistent/pdm/bitcoin.conf"));
+    app.bitcoin_conf = Some(BitcoinConf::parse(
+        "chain=signet\nrpcuser=pool\nrpcpassword=secret\nzmqpubhashblock=tcp://0.0.0.0:28332\n",
+    ));
+    app.p2pool_conf_path = Some(PathBuf::from("/home/pool/.p2poolv2/config.toml"));
+    app.p2pool_conf = Some(
+        P2poolConf::parse(
+            r#"[stratum]
+network = "testnet4"
+zmqpubhashblock = "tcp://127.0.0.1:28332"
+
+[bitcoinrpc]
+url = "http://127.0.0.1:38332"
+username = "pool"
+password = "wrong"
+
+[miner]
+address = "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2"
+"#,
+        )
+        .unwrap(),
+    );
+    app.p2pool_view = P2poolView::CrossCheck;
+    app.crosscheck_report.selected = 2;
+
+    let backend = TestBackend::new(80, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+    insta::assert_debug_snapshot!(terminal.backend());
+}